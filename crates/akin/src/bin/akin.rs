@@ -6,7 +6,7 @@ use akin::{
 };
 use akin::hook::get_db_path;
 use clap::{Parser, Subcommand};
-use lsp::{LanguageAdapter, RustAdapter, SwiftAdapter, CodeUnit};
+use lsp::{LanguageAdapter, RustAdapter, SwiftAdapter, CodeUnit, QualifiedName};
 use sha2::{Sha256, Digest};
 use std::path::{Path, PathBuf};
 
@@ -144,7 +144,7 @@ fn ensure_store() -> anyhow::Result<Store> {
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    Ok(Store::open(&db_path)?)
+    Ok(Store::open_with_index(&db_path)?)
 }
 
 #[tokio::main]
@@ -261,6 +261,7 @@ async fn cmd_index(path: &str, lang: &str, model: &str, min_lines: u32) -> anyho
             structure_hash,
             embedding: Some(embedding),
             group_id: None,
+            signature: unit.signature.clone(),
         };
 
         // 使用 Store 写入，同时更新数据库和向量索引
@@ -402,18 +403,8 @@ async fn cmd_scan(paths: &[String], all: bool, cross_only: bool, threshold: f32)
     println!("\r完成: {} 对相似代码 (耗时 {:.2}s)", new_pairs.len(), t0.elapsed().as_secs_f32());
 
     // 显示结果
-    let pairs = db.get_similar_pairs(None, None, threshold)?;
-
-    // 过滤跨项目
-    let pairs: Vec<_> = if cross_only && project_ids.len() > 1 {
-        pairs.into_iter().filter(|p| {
-            let a_proj = units.iter().find(|u| u.qualified_name == p.unit_a).map(|u| u.project_id);
-            let b_proj = units.iter().find(|u| u.qualified_name == p.unit_b).map(|u| u.project_id);
-            a_proj != b_proj
-        }).collect()
-    } else {
-        pairs
-    };
+    // 跨项目过滤在 SQL 中完成
+    let pairs = db.get_similar_pairs(None, None, threshold, cross_only)?;
 
     println!("\n找到 {} 对相似代码 (阈值: {:.0}%)", pairs.len(), threshold * 100.0);
     println!("{}", "=".repeat(60));
@@ -509,6 +500,7 @@ async fn cmd_compare(
         connectivity: 16,
         expansion_add: 128,
         expansion_search: 64,
+        ..Default::default()
     };
     let index = VectorIndex::new(config)?;
     index.reserve(all_embeddings.len())?;
@@ -635,10 +627,10 @@ fn cmd_projects() -> anyhow::Result<()> {
 fn cmd_pairs(status: &str, limit: usize) -> anyhow::Result<()> {
     let db = ensure_db()?;
 
-    let pair_status = PairStatus::from_str(status)
+    let pair_status = PairStatus::parse(status)
         .ok_or_else(|| anyhow::anyhow!("无效状态: {}", status))?;
 
-    let pairs = db.get_similar_pairs(None, Some(pair_status), 0.0)?;
+    let pairs = db.get_similar_pairs(None, Some(pair_status), 0.0, false)?;
 
     println!("相似配对 (状态: {}):", status);
     println!();
@@ -648,8 +640,13 @@ fn cmd_pairs(status: &str, limit: usize) -> anyhow::Result<()> {
         let file_b = pair.file_b.as_ref().map(|f| Path::new(f).file_name().unwrap_or_default().to_string_lossy().to_string()).unwrap_or_default();
 
         println!("[{}] {:.2}%", pair.id, pair.similarity * 100.0);
-        println!("  A: {}:{} {}", file_a, pair.start_a.unwrap_or(0), short_name(&pair.unit_a));
-        println!("  B: {}:{} {}", file_b, pair.start_b.unwrap_or(0), short_name(&pair.unit_b));
+        let name_a = pair.signature_a.clone().unwrap_or_else(|| short_name(&pair.unit_a));
+        let name_b = pair.signature_b.clone().unwrap_or_else(|| short_name(&pair.unit_b));
+        println!("  A: {}:{} {}", file_a, pair.start_a.unwrap_or(0), name_a);
+        println!("  B: {}:{} {}", file_b, pair.start_b.unwrap_or(0), name_b);
+        if let Some(reason) = &pair.status_reason {
+            println!("  原因: {}", reason);
+        }
         println!();
     }
 
@@ -662,18 +659,18 @@ fn cmd_pairs(status: &str, limit: usize) -> anyhow::Result<()> {
 
 // ==================== Ignore ====================
 
-fn cmd_ignore(unit_a: &str, unit_b: &str, _reason: Option<&str>) -> anyhow::Result<()> {
+fn cmd_ignore(unit_a: &str, unit_b: &str, reason: Option<&str>) -> anyhow::Result<()> {
     let db = ensure_db()?;
 
     // 规范化顺序
     let (a, b) = if unit_a < unit_b { (unit_a, unit_b) } else { (unit_b, unit_a) };
 
-    let pairs = db.get_similar_pairs(None, None, 0.0)?;
+    let pairs = db.get_similar_pairs(None, None, 0.0, false)?;
     let pair = pairs.iter().find(|p| p.unit_a == a && p.unit_b == b);
 
     match pair {
         Some(p) => {
-            db.update_pair_status(p.id, PairStatus::Ignored)?;
+            db.update_pair_status_with_reason(p.id, PairStatus::Ignored, reason)?;
             println!("已忽略配对 (相似度: {:.2}%):", p.similarity * 100.0);
             println!("  A: {}", a);
             println!("  B: {}", b);
@@ -832,22 +829,9 @@ fn compute_structure_hash(content: &str) -> String {
 }
 
 fn short_name(name: &str) -> String {
-    name.split("::").last().unwrap_or(name).to_string()
+    QualifiedName::parse(name).short().to_string()
 }
 
 fn format_name(name: &str) -> String {
-    let parts: Vec<&str> = name.splitn(2, "::").collect();
-    if parts.len() == 2 {
-        let file_part = parts[0];
-        let func_part = parts[1];
-        let file_name = file_part
-            .rsplit('/')
-            .next()
-            .unwrap_or(file_part)
-            .trim_start_matches("swift:")
-            .trim_start_matches("rust:");
-        format!("{} ({})", func_part, file_name)
-    } else {
-        name.to_string()
-    }
+    QualifiedName::parse(name).display()
 }
@@ -0,0 +1,128 @@
+//! 函数体全文索引 (FTS5) 操作
+//!
+//! 只在 `akin index --store-bodies` 时写入；rowid 使用单元的 vector_id (稳定且唯一)，
+//! 查询时与 code_units 连接，已删除单元残留的函数体不会出现在结果中
+
+use rusqlite::{params, Result as SqliteResult};
+use super::types::BodyMatch;
+use super::Database;
+
+impl Database {
+    /// 写入或替换 CodeUnit 的函数体 (单元需已存在)
+    pub fn upsert_code_body(&self, qualified_name: &str, body: &str) -> SqliteResult<()> {
+        let vector_id = self.ensure_vector_id(qualified_name)?;
+        self.conn.execute("DELETE FROM code_bodies WHERE rowid = ?", [vector_id as i64])?;
+        self.conn.execute(
+            "INSERT INTO code_bodies (rowid, body) VALUES (?, ?)",
+            params![vector_id as i64, body],
+        )?;
+        Ok(())
+    }
+
+    /// 删除 CodeUnit 已存储的函数体
+    pub fn delete_code_body(&self, qualified_name: &str) -> SqliteResult<()> {
+        self.conn.execute(
+            "DELETE FROM code_bodies WHERE rowid = (SELECT vector_id FROM code_units WHERE qualified_name = ?)",
+            [qualified_name],
+        )?;
+        Ok(())
+    }
+
+    /// 已存储函数体的单元数
+    pub fn count_code_bodies(&self) -> SqliteResult<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM code_bodies JOIN code_units ON code_units.vector_id = code_bodies.rowid",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// 全文搜索函数体，按 BM25 相关度排序
+    ///
+    /// `query` 使用 FTS5 查询语法 (调用方负责对片段加引号)
+    pub fn search_code_bodies(
+        &self,
+        query: &str,
+        project_id: Option<i64>,
+        limit: usize,
+    ) -> SqliteResult<Vec<BodyMatch>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT cu.qualified_name, cu.file_path, cu.range_start, cu.range_end, -bm25(code_bodies)
+            FROM code_bodies
+            JOIN code_units cu ON cu.vector_id = code_bodies.rowid
+            WHERE code_bodies MATCH ?1 AND (?2 IS NULL OR cu.project_id = ?2)
+            ORDER BY bm25(code_bodies), cu.qualified_name
+            LIMIT ?3
+            "#,
+        )?;
+        let rows = stmt.query_map(params![query, project_id, limit as i64], |row| {
+            Ok(BodyMatch {
+                qualified_name: row.get(0)?,
+                file_path: row.get(1)?,
+                range_start: row.get(2)?,
+                range_end: row.get(3)?,
+                score: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::{Database, CodeUnitRecord};
+
+    #[test]
+    fn test_search_code_bodies() {
+        let db = Database::open_in_memory().unwrap();
+        let p1 = db.get_or_create_project("p1", "/p1", "rust").unwrap();
+        let p2 = db.get_or_create_project("p2", "/p2", "rust").unwrap();
+        for (name, project_id, body) in [
+            ("rust::retry", p1, "fn retry() { backoff(); backoff(); sleep(delay) }"),
+            ("rust::connect", p1, "fn connect() { backoff() }"),
+            ("rust::parse", p2, "fn parse() { tokens.next() }"),
+        ] {
+            db.upsert_code_unit(&CodeUnitRecord::fixture(name, project_id, "/path/src/lib.rs")).unwrap();
+            db.upsert_code_body(name, body).unwrap();
+        }
+        assert_eq!(db.count_code_bodies().unwrap(), 3);
+
+        let names: Vec<String> = db.search_code_bodies("backoff", None, 10).unwrap()
+            .into_iter()
+            .map(|m| m.qualified_name)
+            .collect();
+        assert_eq!(names, vec!["rust::retry", "rust::connect"]);
+
+        // 按项目过滤与数量限制
+        assert!(db.search_code_bodies("backoff", Some(p2), 10).unwrap().is_empty());
+        assert_eq!(db.search_code_bodies("backoff", None, 1).unwrap().len(), 1);
+
+        // 短语查询
+        let matches = db.search_code_bodies("\"tokens.next\"", None, 10).unwrap();
+        assert_eq!(matches[0].qualified_name, "rust::parse");
+        assert!(matches[0].score > 0.0);
+    }
+
+    #[test]
+    fn test_code_body_replaced_and_deleted() {
+        let db = Database::open_in_memory().unwrap();
+        let project_id = db.get_or_create_project("p", "/p", "rust").unwrap();
+        db.upsert_code_unit(&CodeUnitRecord::fixture("rust::a", project_id, "/path/src/lib.rs")).unwrap();
+        db.upsert_code_body("rust::a", "fn a() { old_call() }").unwrap();
+        db.upsert_code_body("rust::a", "fn a() { new_call() }").unwrap();
+
+        assert!(db.search_code_bodies("old_call", None, 10).unwrap().is_empty());
+        assert_eq!(db.search_code_bodies("new_call", None, 10).unwrap().len(), 1);
+
+        db.delete_code_body("rust::a").unwrap();
+        assert_eq!(db.count_code_bodies().unwrap(), 0);
+
+        // 删除单元时一并删除函数体
+        db.upsert_code_body("rust::a", "fn a() { new_call() }").unwrap();
+        db.delete_code_units_by_file("/path/src/lib.rs").unwrap();
+        let orphans: i64 = db.conn.query_row("SELECT COUNT(*) FROM code_bodies", [], |row| row.get(0)).unwrap();
+        assert_eq!(orphans, 0);
+    }
+}
@@ -18,8 +18,9 @@ impl Database {
             r#"
             INSERT INTO code_units
                 (qualified_name, project_id, file_path, kind, range_start, range_end,
-                 content_hash, structure_hash, embedding, group_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 content_hash, structure_hash, embedding, group_id, signature,
+                 created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
             ON CONFLICT(qualified_name) DO UPDATE SET
                 file_path = excluded.file_path,
                 kind = excluded.kind,
@@ -28,7 +29,9 @@ impl Database {
                 content_hash = excluded.content_hash,
                 structure_hash = excluded.structure_hash,
                 embedding = COALESCE(excluded.embedding, code_units.embedding),
-                group_id = COALESCE(code_units.group_id, excluded.group_id)
+                group_id = COALESCE(code_units.group_id, excluded.group_id),
+                signature = excluded.signature,
+                updated_at = excluded.updated_at
             "#,
             params![
                 &record.qualified_name,
@@ -41,11 +44,149 @@ impl Database {
                 &record.structure_hash,
                 &record.embedding,
                 inherited_group_id.or(record.group_id),
+                &record.signature,
             ],
         )?;
+        self.ensure_vector_id(&record.qualified_name)?;
         Ok(())
     }
 
+    /// 插入来自其他数据库的 CodeUnit，使用指定的向量 ID (单事务)
+    ///
+    /// 序列推进到最大的导入 ID，之后分配的 ID 不会与之冲突；同名单元已存在时整体回滚
+    pub fn import_code_units(&self, units: &[(CodeUnitRecord, u64)]) -> SqliteResult<usize> {
+        self.conn.execute("BEGIN TRANSACTION", [])?;
+
+        let result = (|| {
+            let mut stmt = self.conn.prepare(
+                r#"
+                INSERT INTO code_units
+                    (qualified_name, project_id, file_path, kind, range_start, range_end,
+                     content_hash, structure_hash, embedding, group_id, vector_id, signature,
+                     created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
+                "#,
+            )?;
+            for (record, vector_id) in units {
+                stmt.execute(params![
+                    &record.qualified_name,
+                    record.project_id,
+                    &record.file_path,
+                    &record.kind,
+                    record.range_start,
+                    record.range_end,
+                    &record.content_hash,
+                    &record.structure_hash,
+                    &record.embedding,
+                    record.group_id,
+                    *vector_id as i64,
+                    &record.signature,
+                ])?;
+            }
+
+            if let Some(max_id) = units.iter().map(|(_, id)| *id).max() {
+                self.conn.execute(
+                    r#"
+                    INSERT INTO sequences (name, value) VALUES ('vector_id', ?)
+                    ON CONFLICT(name) DO UPDATE SET value = MAX(value, excluded.value)
+                    "#,
+                    [max_id as i64],
+                )?;
+            }
+            Ok::<usize, rusqlite::Error>(units.len())
+        })();
+
+        match result {
+            Ok(count) => {
+                self.conn.execute("COMMIT", [])?;
+                Ok(count)
+            }
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
+    /// 获取 CodeUnit 的向量 ID，没有时分配一个
+    ///
+    /// ID 只分配一次且单调递增 (删除后不复用)，重建索引时保持不变
+    pub fn ensure_vector_id(&self, qualified_name: &str) -> SqliteResult<u64> {
+        if let Some(id) = self.get_vector_id(qualified_name)? {
+            return Ok(id);
+        }
+
+        self.conn.execute(
+            r#"
+            INSERT INTO sequences (name, value) VALUES ('vector_id', 1)
+            ON CONFLICT(name) DO UPDATE SET value = value + 1
+            "#,
+            [],
+        )?;
+        let id: i64 = self.conn.query_row(
+            "SELECT value FROM sequences WHERE name = 'vector_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "UPDATE code_units SET vector_id = ? WHERE qualified_name = ?",
+            params![id, qualified_name],
+        )?;
+        Ok(id as u64)
+    }
+
+    /// 已分配的最大向量 ID (ID 从 1 开始连续递增，未分配过时为 0)
+    pub fn max_vector_id(&self) -> SqliteResult<u64> {
+        let result = self.conn.query_row(
+            "SELECT value FROM sequences WHERE name = 'vector_id'",
+            [],
+            |row| row.get::<_, i64>(0),
+        );
+
+        match result {
+            Ok(id) => Ok(id as u64),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 为所有尚无向量 ID 的单元分配 ID，返回新分配的数量
+    pub fn assign_missing_vector_ids(&self) -> SqliteResult<usize> {
+        let names: Vec<String> = {
+            let mut stmt = self.conn.prepare("SELECT qualified_name FROM code_units WHERE vector_id IS NULL")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        for name in &names {
+            self.ensure_vector_id(name)?;
+        }
+        Ok(names.len())
+    }
+
+    /// 获取 CodeUnit 的向量 ID
+    pub fn get_vector_id(&self, qualified_name: &str) -> SqliteResult<Option<u64>> {
+        let result = self.conn.query_row(
+            "SELECT vector_id FROM code_units WHERE qualified_name = ?",
+            [qualified_name],
+            |row| row.get::<_, Option<i64>>(0),
+        );
+
+        match result {
+            Ok(id) => Ok(id.map(|id| id as u64)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 获取所有 (qualified_name, vector_id)
+    pub fn get_vector_ids(&self) -> SqliteResult<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT qualified_name, vector_id FROM code_units WHERE vector_id IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?;
+        rows.collect()
+    }
+
     /// 获取单个 CodeUnit
     pub fn get_code_unit(&self, qualified_name: &str) -> SqliteResult<Option<CodeUnitRecord>> {
         let mut stmt = self.conn.prepare("SELECT * FROM code_units WHERE qualified_name = ?")?;
@@ -73,7 +214,7 @@ impl Database {
                 let rows = stmt.query_map([], Self::row_to_code_unit)?;
                 rows.collect()
             }
-            Some(ids) if ids.is_empty() => Ok(vec![]),
+            Some([]) => Ok(vec![]),
             Some(ids) => {
                 let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
                 let query = format!("SELECT * FROM code_units WHERE project_id IN ({})", placeholders);
@@ -84,6 +225,88 @@ impl Database {
         }
     }
 
+    /// 同 [`Database::get_code_units_by_projects`]，但不读取 embedding 字段 (始终为 None)
+    pub fn get_code_unit_metadata_by_projects(&self, project_ids: Option<&[i64]>) -> SqliteResult<Vec<CodeUnitRecord>> {
+        const COLUMNS: &str = "qualified_name, project_id, file_path, kind, range_start, range_end, \
+            content_hash, structure_hash, NULL, group_id, vector_id, signature";
+        match project_ids {
+            None => {
+                let mut stmt = self.conn.prepare(&format!("SELECT {} FROM code_units", COLUMNS))?;
+                let rows = stmt.query_map([], Self::row_to_code_unit)?;
+                rows.collect()
+            }
+            Some([]) => Ok(vec![]),
+            Some(ids) => {
+                let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+                let query = format!("SELECT {} FROM code_units WHERE project_id IN ({})", COLUMNS, placeholders);
+                let mut stmt = self.conn.prepare(&query)?;
+                let rows = stmt.query_map(rusqlite::params_from_iter(ids.iter()), Self::row_to_code_unit)?;
+                rows.collect()
+            }
+        }
+    }
+
+    /// 按 qualified_name 顺序分页读取 (qualified_name, embedding)，只包含有 embedding 的单元
+    ///
+    /// 返回 `after` 之后的至多 `limit` 行；以上一页最后的名称作为下一页的 `after`，返回空时结束
+    pub fn get_embeddings_page(
+        &self,
+        project_ids: Option<&[i64]>,
+        after: Option<&str>,
+        limit: usize,
+    ) -> SqliteResult<Vec<(String, Vec<u8>)>> {
+        use rusqlite::types::Value;
+
+        let mut conditions = vec!["embedding IS NOT NULL".to_string()];
+        let mut params: Vec<Value> = Vec::new();
+        match project_ids {
+            None => {}
+            Some([]) => return Ok(vec![]),
+            Some(ids) => {
+                let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+                conditions.push(format!("project_id IN ({})", placeholders));
+                params.extend(ids.iter().map(|&id| Value::Integer(id)));
+            }
+        }
+        if let Some(after) = after {
+            conditions.push("qualified_name > ?".to_string());
+            params.push(Value::Text(after.to_string()));
+        }
+        params.push(Value::Integer(limit as i64));
+
+        let query = format!(
+            "SELECT qualified_name, embedding FROM code_units WHERE {} ORDER BY qualified_name LIMIT ?",
+            conditions.join(" AND "),
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 统计 CodeUnit 数量 (None = 所有项目)，不加载行数据
+    pub fn count_code_units(&self, project_ids: Option<&[i64]>) -> SqliteResult<usize> {
+        let count: i64 = match project_ids {
+            None => self.conn.query_row("SELECT COUNT(*) FROM code_units", [], |row| row.get(0))?,
+            Some([]) => 0,
+            Some(ids) => {
+                let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+                let query = format!("SELECT COUNT(*) FROM code_units WHERE project_id IN ({})", placeholders);
+                self.conn.query_row(&query, rusqlite::params_from_iter(ids.iter()), |row| row.get(0))?
+            }
+        };
+        Ok(count as usize)
+    }
+
+    /// 统计带 embedding 的 CodeUnit 数量 (即向量索引应有的条目数)
+    pub fn count_embeddings(&self) -> SqliteResult<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM code_units WHERE embedding IS NOT NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
     /// 获取文件的所有 CodeUnits
     pub fn get_code_units_by_file(&self, file_path: &str) -> SqliteResult<Vec<CodeUnitRecord>> {
         let mut stmt = self.conn.prepare("SELECT * FROM code_units WHERE file_path = ?")?;
@@ -91,12 +314,38 @@ impl Database {
         rows.collect()
     }
 
+    /// 删除单个 CodeUnit，返回是否存在 (引用它的配对需先删除)
+    pub fn delete_code_unit(&self, qualified_name: &str) -> SqliteResult<bool> {
+        self.delete_code_body(qualified_name)?;
+        let deleted = self.conn.execute("DELETE FROM code_units WHERE qualified_name = ?", [qualified_name])?;
+        Ok(deleted > 0)
+    }
+
     /// 删除文件的所有 CodeUnits
     pub fn delete_code_units_by_file(&self, file_path: &str) -> SqliteResult<()> {
+        self.conn.execute(
+            "DELETE FROM code_bodies WHERE rowid IN (SELECT vector_id FROM code_units WHERE file_path = ?)",
+            [file_path],
+        )?;
         self.conn.execute("DELETE FROM code_units WHERE file_path = ?", [file_path])?;
         Ok(())
     }
 
+    /// 删除文件的 CodeUnit 并清理随之失效的配对，返回清理的配对数量
+    pub fn delete_code_units_by_file_and_prune(&self, file_path: &str) -> SqliteResult<usize> {
+        // similar_pairs 外键引用 code_units，配对需先于单元删除
+        let deleted = self.conn.execute(
+            r#"
+            DELETE FROM similar_pairs
+            WHERE unit_a IN (SELECT qualified_name FROM code_units WHERE file_path = ?1)
+               OR unit_b IN (SELECT qualified_name FROM code_units WHERE file_path = ?1)
+            "#,
+            [file_path],
+        )?;
+        self.delete_code_units_by_file(file_path)?;
+        Ok(deleted + self.prune_orphan_pairs()?)
+    }
+
     /// 按 content_hash 获取已缓存的 embedding
     pub fn get_embedding_by_content_hash(&self, content_hash: &str) -> SqliteResult<Option<Vec<u8>>> {
         let mut stmt = self.conn.prepare(
@@ -123,6 +372,7 @@ impl Database {
             structure_hash: row.get(7)?,
             embedding: row.get(8)?,
             group_id: row.get(9)?,
+            signature: row.get(11)?,
         })
     }
 }
@@ -147,6 +397,7 @@ mod tests {
             structure_hash: "def456".to_string(),
             embedding: Some(vec![1, 2, 3, 4]),
             group_id: None,
+            signature: Some("fn foo() -> i32".to_string()),
         };
 
         // 插入
@@ -156,6 +407,7 @@ mod tests {
         let loaded = db.get_code_unit("rust::test::foo").unwrap().unwrap();
         assert_eq!(loaded.file_path, "/path/src/lib.rs");
         assert_eq!(loaded.embedding, Some(vec![1, 2, 3, 4]));
+        assert_eq!(loaded.signature.as_deref(), Some("fn foo() -> i32"));
 
         // 更新
         let updated = CodeUnitRecord {
@@ -170,6 +422,13 @@ mod tests {
         let units = db.get_code_units_by_project(project_id).unwrap();
         assert_eq!(units.len(), 1);
 
+        // 计数
+        assert_eq!(db.count_code_units(None).unwrap(), 1);
+        assert_eq!(db.count_code_units(Some(&[project_id])).unwrap(), 1);
+        assert_eq!(db.count_code_units(Some(&[project_id + 1])).unwrap(), 0);
+        assert_eq!(db.count_code_units(Some(&[])).unwrap(), 0);
+        assert_eq!(db.count_embeddings().unwrap(), 1);
+
         // 按文件查询
         let units = db.get_code_units_by_file("/path/src/lib.rs").unwrap();
         assert_eq!(units.len(), 1);
@@ -180,6 +439,43 @@ mod tests {
         assert_eq!(units.len(), 0);
     }
 
+    #[test]
+    fn test_embeddings_page_and_metadata() {
+        let db = Database::open_in_memory().unwrap();
+        let project_id = db.get_or_create_project("test", "/path", "rust").unwrap();
+        let other_id = db.get_or_create_project("other", "/other", "rust").unwrap();
+        for (name, project, embedding) in [
+            ("rust::d", project_id, Some(vec![4])),
+            ("rust::a", project_id, Some(vec![1])),
+            ("rust::c", project_id, None),
+            ("rust::b", project_id, Some(vec![2])),
+            ("rust::e", other_id, Some(vec![5])),
+        ] {
+            db.upsert_code_unit(&CodeUnitRecord { embedding, ..CodeUnitRecord::fixture(name, project, "/path/lib.rs") }).unwrap();
+        }
+
+        // 按名称顺序分页，跳过没有 embedding 的单元
+        let mut pages = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let page = db.get_embeddings_page(Some(&[project_id]), after.as_deref(), 2).unwrap();
+            let Some((last, _)) = page.last() else { break };
+            after = Some(last.clone());
+            pages.push(page);
+        }
+        assert_eq!(pages, vec![
+            vec![("rust::a".to_string(), vec![1]), ("rust::b".to_string(), vec![2])],
+            vec![("rust::d".to_string(), vec![4])],
+        ]);
+        assert_eq!(db.get_embeddings_page(None, Some("rust::d"), 10).unwrap().len(), 1);
+        assert!(db.get_embeddings_page(Some(&[]), None, 10).unwrap().is_empty());
+
+        let metadata = db.get_code_unit_metadata_by_projects(Some(&[project_id])).unwrap();
+        assert_eq!(metadata.len(), 4);
+        assert!(metadata.iter().all(|u| u.embedding.is_none() && u.file_path == "/path/lib.rs"));
+        assert_eq!(db.get_code_unit_metadata_by_projects(None).unwrap().len(), 5);
+    }
+
     #[test]
     fn test_embedding_cache() {
         let db = Database::open_in_memory().unwrap();
@@ -196,6 +492,7 @@ mod tests {
             structure_hash: "struct_hash".to_string(),
             embedding: Some(vec![1, 2, 3, 4]),
             group_id: None,
+            signature: None,
         };
         db.upsert_code_unit(&record).unwrap();
 
@@ -207,4 +504,93 @@ mod tests {
         let none = db.get_embedding_by_content_hash("other_hash").unwrap();
         assert!(none.is_none());
     }
+
+    #[test]
+    fn test_vector_ids_are_stable_and_not_reused() {
+        let db = Database::open_in_memory().unwrap();
+        let project_id = db.get_or_create_project("test", "/path", "rust").unwrap();
+
+        db.upsert_code_unit(&CodeUnitRecord::fixture("rust::a", project_id, "/path/a.rs")).unwrap();
+        db.upsert_code_unit(&CodeUnitRecord::fixture("rust::b", project_id, "/path/b.rs")).unwrap();
+        let a = db.get_vector_id("rust::a").unwrap().unwrap();
+        let b = db.get_vector_id("rust::b").unwrap().unwrap();
+        assert_ne!(a, b);
+
+        // 更新不改变 ID
+        db.upsert_code_unit(&CodeUnitRecord { range_end: 30, ..CodeUnitRecord::fixture("rust::a", project_id, "/path/a.rs") }).unwrap();
+        assert_eq!(db.get_vector_id("rust::a").unwrap(), Some(a));
+
+        // 删除后新单元拿到更大的 ID
+        db.delete_code_units_by_file("/path/b.rs").unwrap();
+        db.upsert_code_unit(&CodeUnitRecord::fixture("rust::c", project_id, "/path/c.rs")).unwrap();
+        let c = db.get_vector_id("rust::c").unwrap().unwrap();
+        assert!(c > b);
+        assert_eq!(db.get_vector_ids().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_import_code_units_keeps_ids_and_rolls_back() {
+        let db = Database::open_in_memory().unwrap();
+        let project_id = db.get_or_create_project("test", "/path", "rust").unwrap();
+        db.upsert_code_unit(&CodeUnitRecord::fixture("rust::a", project_id, "/path/a.rs")).unwrap();
+
+        let imported = vec![
+            (CodeUnitRecord::fixture("rust::b", project_id, "/path/b.rs"), 10),
+            (CodeUnitRecord::fixture("rust::c", project_id, "/path/c.rs"), 12),
+        ];
+        assert_eq!(db.import_code_units(&imported).unwrap(), 2);
+        assert_eq!(db.get_vector_id("rust::b").unwrap(), Some(10));
+        assert_eq!(db.get_vector_id("rust::c").unwrap(), Some(12));
+
+        // 序列已推进，新单元不会与导入的 ID 冲突
+        db.upsert_code_unit(&CodeUnitRecord::fixture("rust::d", project_id, "/path/d.rs")).unwrap();
+        assert_eq!(db.get_vector_id("rust::d").unwrap(), Some(13));
+
+        // 同名单元导致整体回滚
+        let conflicting = vec![
+            (CodeUnitRecord::fixture("rust::e", project_id, "/path/e.rs"), 20),
+            (CodeUnitRecord::fixture("rust::a", project_id, "/path/a.rs"), 21),
+        ];
+        assert!(db.import_code_units(&conflicting).is_err());
+        assert!(db.get_code_unit("rust::e").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migrate_vector_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.db");
+
+        // 旧版本的表结构 (没有 vector_id 列)
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE code_units (
+                qualified_name TEXT PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                range_start INTEGER NOT NULL,
+                range_end INTEGER NOT NULL,
+                content_hash TEXT NOT NULL,
+                structure_hash TEXT NOT NULL,
+                embedding BLOB,
+                group_id INTEGER
+            );
+            INSERT INTO code_units VALUES ('rust::a', 1, '/p/a.rs', 'function', 1, 5, 'h1', 's1', NULL, NULL);
+            INSERT INTO code_units VALUES ('rust::b', 1, '/p/b.rs', 'function', 1, 5, 'h2', 's2', NULL, NULL);
+            "#,
+        ).unwrap();
+        drop(conn);
+
+        let db = Database::open(&path).unwrap();
+        let mut ids = db.get_vector_ids().unwrap();
+        ids.sort();
+        assert_eq!(ids, vec![("rust::a".to_string(), 1), ("rust::b".to_string(), 2)]);
+        assert!(db.get_code_unit("rust::a").unwrap().is_some());
+
+        // 重新打开不会重新分配
+        drop(db);
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.get_vector_id("rust::b").unwrap(), Some(2));
+    }
 }
@@ -110,6 +110,7 @@ mod tests {
             structure_hash: "def".to_string(),
             embedding: None,
             group_id: None,
+            signature: None,
         };
         db.upsert_code_unit(&record).unwrap();
         db.add_to_group("rust::test::foo", group_id).unwrap();
@@ -142,6 +143,7 @@ mod tests {
                 structure_hash: format!("struct_{}", i),
                 embedding: None,
                 group_id: None,
+                signature: None,
             };
             db.upsert_code_unit(&record).unwrap();
         }
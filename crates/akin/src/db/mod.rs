@@ -5,11 +5,27 @@ mod project;
 mod code_unit;
 mod pairs;
 mod groups;
+mod bodies;
 
 pub use types::*;
 
-use rusqlite::{Connection, Result as SqliteResult};
+use rusqlite::{Connection, OpenFlags, Result as SqliteResult};
 use std::path::Path;
+use std::time::Duration;
+
+/// 迁移步骤: 第 i 项把表结构从版本 i 升级到 i + 1
+///
+/// 新增列/表时在末尾追加一步，不要修改已发布的步骤
+const MIGRATIONS: &[fn(&Database) -> SqliteResult<()>] = &[
+    Database::migrate_v1,
+    Database::migrate_v2,
+    Database::migrate_v3,
+    Database::migrate_v4,
+    Database::migrate_v5,
+];
+
+/// 当前表结构版本 (没有 `schema_version` 表的旧数据库视为版本 0)
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
 
 /// 数据库管理
 pub struct Database {
@@ -19,19 +35,73 @@ pub struct Database {
 impl Database {
     pub fn open(path: &Path) -> SqliteResult<Self> {
         let conn = Connection::open(path)?;
+        // WAL 模式下读连接不会被写事务阻塞
+        conn.pragma_update(None, "journal_mode", "WAL")?;
         let db = Self { conn };
-        db.init_schema()?;
+        db.migrate()?;
         Ok(db)
     }
 
+    /// 以只读方式打开已有数据库
+    ///
+    /// 不初始化/迁移表结构，不争抢写锁，可在后台 `akin index` 运行时安全查询；
+    /// 表结构可能落后于 `SCHEMA_VERSION`，需要时先用 `open` 迁移
+    pub fn open_readonly(path: &Path) -> SqliteResult<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        Ok(Self { conn })
+    }
+
     pub fn open_in_memory() -> SqliteResult<Self> {
         let conn = Connection::open_in_memory()?;
         let db = Self { conn };
-        db.init_schema()?;
+        db.migrate()?;
         Ok(db)
     }
 
-    fn init_schema(&self) -> SqliteResult<()> {
+    /// 按 `schema_version` 中记录的版本依次执行未应用的迁移步骤
+    ///
+    /// 每一步与版本号更新在同一事务中提交，中途失败不会留下半升级的表结构
+    pub fn migrate(&self) -> SqliteResult<()> {
+        self.conn.execute("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)", [])?;
+        let current = self.schema_version()?;
+        if current > SCHEMA_VERSION {
+            tracing::warn!("Database schema version {} is newer than supported ({}), skipping migrations", current, SCHEMA_VERSION);
+            return Ok(());
+        }
+
+        for (from, step) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+            let tx = self.conn.unchecked_transaction()?;
+            step(self)?;
+            tx.execute("DELETE FROM schema_version", [])?;
+            tx.execute("INSERT INTO schema_version (version) VALUES (?)", [from as u32 + 1])?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// 已应用的表结构版本 (未记录时为 0)
+    pub fn schema_version(&self) -> SqliteResult<u32> {
+        let has_table: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_table {
+            return Ok(0);
+        }
+        let version: Option<u32> = self.conn
+            .query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0))?;
+        Ok(version.unwrap_or(0))
+    }
+
+    /// 版本 1: 基础表结构
+    ///
+    /// 版本化之前创建的数据库可能缺少后来补上的列，按列检测补齐
+    fn migrate_v1(&self) -> SqliteResult<()> {
         self.conn.execute_batch(
             r#"
             CREATE TABLE IF NOT EXISTS projects (
@@ -39,7 +109,9 @@ impl Database {
                 name TEXT NOT NULL,
                 root_path TEXT NOT NULL UNIQUE,
                 language TEXT NOT NULL,
-                last_indexed_at TEXT
+                last_indexed_at TEXT,
+                embedding_model TEXT,
+                embedding_dimensions INTEGER
             );
 
             CREATE TABLE IF NOT EXISTS code_units (
@@ -53,6 +125,8 @@ impl Database {
                 structure_hash TEXT NOT NULL,
                 embedding BLOB,
                 group_id INTEGER,
+                vector_id INTEGER,
+                signature TEXT,
                 FOREIGN KEY (project_id) REFERENCES projects(id)
             );
 
@@ -77,11 +151,246 @@ impl Database {
                 FOREIGN KEY (project_id) REFERENCES projects(id)
             );
 
+            CREATE TABLE IF NOT EXISTS sequences (
+                name TEXT PRIMARY KEY,
+                value INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS index_progress (
+                project_id INTEGER PRIMARY KEY,
+                done INTEGER NOT NULL,
+                total INTEGER NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (project_id) REFERENCES projects(id)
+            );
+
             CREATE INDEX IF NOT EXISTS idx_units_project ON code_units(project_id);
             CREATE INDEX IF NOT EXISTS idx_units_hash ON code_units(content_hash);
             CREATE INDEX IF NOT EXISTS idx_pairs_status ON similar_pairs(status);
             "#,
         )?;
+        self.migrate_vector_ids()?;
+        self.migrate_signatures()?;
+        self.migrate_project_models()
+    }
+
+    /// 版本 2: 代码单元与配对的创建/更新时间 (迁移前的行为 NULL)
+    fn migrate_v2(&self) -> SqliteResult<()> {
+        self.conn.execute_batch(
+            r#"
+            ALTER TABLE code_units ADD COLUMN created_at TEXT;
+            ALTER TABLE code_units ADD COLUMN updated_at TEXT;
+            ALTER TABLE similar_pairs ADD COLUMN created_at TEXT;
+            ALTER TABLE similar_pairs ADD COLUMN updated_at TEXT;
+            "#,
+        )
+    }
+
+    /// 版本 3: 配对状态变更的原因 (如忽略理由)
+    fn migrate_v3(&self) -> SqliteResult<()> {
+        self.conn.execute("ALTER TABLE similar_pairs ADD COLUMN status_reason TEXT", [])?;
+        Ok(())
+    }
+
+    /// 版本 4: 函数体全文索引 (rowid = code_units.vector_id)，仅 `--store-bodies` 时写入
+    fn migrate_v4(&self) -> SqliteResult<()> {
+        self.conn.execute("CREATE VIRTUAL TABLE IF NOT EXISTS code_bodies USING fts5(body)", [])?;
+        Ok(())
+    }
+
+    /// 版本 5: 索引时被索引文本使用的嵌入前缀
+    ///
+    /// 之前已记录模型的项目是在引入前缀之前索引的，向量不带前缀
+    fn migrate_v5(&self) -> SqliteResult<()> {
+        self.conn.execute_batch(
+            r#"
+            ALTER TABLE projects ADD COLUMN embedding_prefix TEXT;
+            UPDATE projects SET embedding_prefix = '' WHERE embedding_model IS NOT NULL;
+            "#,
+        )
+    }
+
+    /// 迁移: 旧数据库没有 vector_id 列，补列并为已有单元分配 ID
+    fn migrate_vector_ids(&self) -> SqliteResult<()> {
+        if self.conn.prepare("SELECT vector_id FROM code_units LIMIT 0").is_err() {
+            self.conn.execute("ALTER TABLE code_units ADD COLUMN vector_id INTEGER", [])?;
+        }
+        self.conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_units_vector_id ON code_units(vector_id)",
+            [],
+        )?;
+
+        let missing: Vec<String> = self.conn
+            .prepare("SELECT qualified_name FROM code_units WHERE vector_id IS NULL ORDER BY rowid")?
+            .query_map([], |row| row.get(0))?
+            .collect::<SqliteResult<_>>()?;
+        for name in missing {
+            self.ensure_vector_id(&name)?;
+        }
+        Ok(())
+    }
+
+    /// 迁移: 旧数据库没有 signature 列，补列 (已有单元在重新索引时填充)
+    fn migrate_signatures(&self) -> SqliteResult<()> {
+        if self.conn.prepare("SELECT signature FROM code_units LIMIT 0").is_err() {
+            self.conn.execute("ALTER TABLE code_units ADD COLUMN signature TEXT", [])?;
+        }
         Ok(())
     }
+
+    /// 迁移: 旧数据库的 projects 没有嵌入模型列 (下次索引时记录)
+    fn migrate_project_models(&self) -> SqliteResult<()> {
+        if self.conn.prepare("SELECT embedding_model FROM projects LIMIT 0").is_err() {
+            self.conn.execute_batch(
+                r#"
+                ALTER TABLE projects ADD COLUMN embedding_model TEXT;
+                ALTER TABLE projects ADD COLUMN embedding_dimensions INTEGER;
+                "#,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 回收已删除数据占用的空间
+    pub fn vacuum(&self) -> SqliteResult<()> {
+        self.conn.execute_batch("VACUUM")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_readonly() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akin.db");
+
+        let writer = Database::open(&path).unwrap();
+        writer.get_or_create_project("test", "/path/to/test", "rust").unwrap();
+
+        // 写连接仍然打开时可以读取
+        let reader = Database::open_readonly(&path).unwrap();
+        assert_eq!(reader.get_all_projects().unwrap().len(), 1);
+
+        // 只读连接不能写入
+        assert!(reader.get_or_create_project("other", "/other", "rust").is_err());
+
+        // 不存在的数据库不会被创建
+        assert!(Database::open_readonly(&dir.path().join("missing.db")).is_err());
+    }
+
+    #[test]
+    fn test_open_readonly_special_path() {
+        // 路径按文件名打开，不作为 URI 解析
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a?mode=rwc#b.db");
+        Database::open(&path).unwrap();
+
+        let reader = Database::open_readonly(&path).unwrap();
+        assert_eq!(reader.schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_old_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akin.db");
+
+        // 没有 vector_id / signature / 嵌入模型列的旧表
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE projects (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                root_path TEXT NOT NULL UNIQUE,
+                language TEXT NOT NULL,
+                last_indexed_at TEXT
+            );
+            INSERT INTO projects (name, root_path, language) VALUES ('p', '/p', 'rust');
+            CREATE TABLE code_units (
+                qualified_name TEXT PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                range_start INTEGER NOT NULL,
+                range_end INTEGER NOT NULL,
+                content_hash TEXT NOT NULL,
+                structure_hash TEXT NOT NULL,
+                embedding BLOB,
+                group_id INTEGER
+            );
+            INSERT INTO code_units VALUES ('rust::a', 1, '/p/a.rs', 'function', 1, 5, 'h', 's', NULL, NULL);
+            "#,
+        ).unwrap();
+        drop(conn);
+
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        let unit = db.get_code_unit("rust::a").unwrap().unwrap();
+        assert_eq!(unit.signature, None);
+        assert_eq!(db.get_vector_id("rust::a").unwrap(), Some(1));
+
+        let project = db.get_project_by_path("/p").unwrap().unwrap();
+        assert_eq!(project.embedding_model, None);
+        assert_eq!(project.embedding_prefix, None);
+        db.set_project_model(project.id, "nomic-embed-text", 768, "search_document: ").unwrap();
+        let project = db.get_project_by_path("/p").unwrap().unwrap();
+        assert_eq!(project.embedding_dimensions, Some(768));
+        assert_eq!(project.embedding_prefix.as_deref(), Some("search_document: "));
+    }
+
+    #[test]
+    fn test_migrate_records_legacy_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akin.db");
+
+        // 版本 4 的数据库：已记录模型，但还没有前缀列
+        let db = Database::open(&path).unwrap();
+        let indexed = db.get_or_create_project("indexed", "/indexed", "rust").unwrap();
+        db.set_project_model(indexed, "bge-m3", 1024, "passage: ").unwrap();
+        db.get_or_create_project("fresh", "/fresh", "rust").unwrap();
+        db.conn.execute_batch(
+            "ALTER TABLE projects DROP COLUMN embedding_prefix; UPDATE schema_version SET version = 4;",
+        ).unwrap();
+        drop(db);
+
+        let db = Database::open(&path).unwrap();
+        let indexed = db.get_project_by_path("/indexed").unwrap().unwrap();
+        assert_eq!(indexed.embedding_prefix.as_deref(), Some(""));
+        assert!(indexed.check_model("bge-m3", 1024, "passage: ").is_err());
+        assert_eq!(db.get_project_by_path("/fresh").unwrap().unwrap().embedding_prefix, None);
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+
+        // 重复迁移是空操作，版本只记录一行
+        db.migrate().unwrap();
+        let rows: u32 = db.conn.query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn test_migrate_skips_applied_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akin.db");
+        Database::open(&path).unwrap().get_or_create_project("p", "/p", "rust").unwrap();
+
+        // 版本已是最新时不会重新执行建表步骤
+        let conn = Connection::open(&path).unwrap();
+        conn.execute("DROP INDEX idx_units_hash", []).unwrap();
+        drop(conn);
+
+        let db = Database::open(&path).unwrap();
+        let has_index: bool = db.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'idx_units_hash')",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert!(!has_index);
+        assert_eq!(db.get_all_projects().unwrap().len(), 1);
+    }
 }
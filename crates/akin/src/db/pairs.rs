@@ -4,6 +4,20 @@ use rusqlite::{params, Result as SqliteResult};
 use super::types::{PairStatus, SimilarPairRecord};
 use super::Database;
 
+/// 插入新配对；已存在时只更新相似度与 `updated_at`，`trigger_reason` 仅在仍为 `new` 时覆盖，
+/// 已审查 (ignored/confirmed/redundant) 的配对保留原有状态、上下文与 `created_at`
+const UPSERT_PAIR_SQL: &str = r#"
+    INSERT INTO similar_pairs (unit_a, unit_b, similarity, status, trigger_reason, created_at, updated_at)
+    VALUES (?, ?, ?, 'new', ?, datetime('now'), datetime('now'))
+    ON CONFLICT(unit_a, unit_b) DO UPDATE SET
+        similarity = excluded.similarity,
+        updated_at = excluded.updated_at,
+        trigger_reason = CASE
+            WHEN similar_pairs.status = 'new' THEN excluded.trigger_reason
+            ELSE similar_pairs.trigger_reason
+        END
+"#;
+
 impl Database {
     /// 插入或更新相似配对
     pub fn upsert_similar_pair(
@@ -17,30 +31,48 @@ impl Database {
         let (a, b) = if unit_a < unit_b { (unit_a, unit_b) } else { (unit_b, unit_a) };
 
         self.conn.execute(
-            r#"
-            INSERT INTO similar_pairs (unit_a, unit_b, similarity, status, trigger_reason)
-            VALUES (?, ?, ?, 'new', ?)
-            ON CONFLICT(unit_a, unit_b) DO UPDATE SET
-                similarity = excluded.similarity,
-                trigger_reason = excluded.trigger_reason
-            "#,
+            UPSERT_PAIR_SQL,
             params![a, b, similarity, trigger_reason],
         )?;
         Ok(())
     }
 
-    /// 获取相似配对列表
+    /// 统计配对数量 (两端单元都存在，与 `get_similar_pairs` 一致)，不加载行数据
+    pub fn count_pairs(&self, status: Option<PairStatus>) -> SqliteResult<usize> {
+        let mut query = String::from(
+            r#"
+            SELECT COUNT(*)
+            FROM similar_pairs sp
+            JOIN code_units ua ON sp.unit_a = ua.qualified_name
+            JOIN code_units ub ON sp.unit_b = ub.qualified_name
+            "#
+        );
+
+        let count: i64 = match status {
+            Some(s) => {
+                query.push_str(" WHERE sp.status = ?");
+                self.conn.query_row(&query, [s.as_str()], |row| row.get(0))?
+            }
+            None => self.conn.query_row(&query, [], |row| row.get(0))?,
+        };
+        Ok(count as usize)
+    }
+
+    /// 获取相似配对列表，`cross_only` 时只返回两端属于不同项目的配对
     pub fn get_similar_pairs(
         &self,
         project_id: Option<i64>,
         status: Option<PairStatus>,
         min_similarity: f32,
+        cross_only: bool,
     ) -> SqliteResult<Vec<SimilarPairRecord>> {
         let mut query = String::from(
             r#"
             SELECT sp.id, sp.unit_a, sp.unit_b, sp.similarity, sp.status, sp.trigger_reason,
                    ua.file_path, ua.range_start, ua.range_end,
-                   ub.file_path, ub.range_start, ub.range_end
+                   ub.file_path, ub.range_start, ub.range_end,
+                   ua.kind, ub.kind, ua.signature, ub.signature,
+                   sp.created_at, sp.updated_at, sp.status_reason
             FROM similar_pairs sp
             JOIN code_units ua ON sp.unit_a = ua.qualified_name
             JOIN code_units ub ON sp.unit_b = ub.qualified_name
@@ -50,8 +82,10 @@ impl Database {
 
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(min_similarity)];
 
+        // 任一端属于该项目即可（包含跨项目配对）
         if let Some(pid) = project_id {
-            query.push_str(" AND ua.project_id = ?");
+            query.push_str(" AND (ua.project_id = ? OR ub.project_id = ?)");
+            params_vec.push(Box::new(pid));
             params_vec.push(Box::new(pid));
         }
 
@@ -60,7 +94,12 @@ impl Database {
             params_vec.push(Box::new(s.as_str().to_string()));
         }
 
-        query.push_str(" ORDER BY sp.similarity DESC");
+        if cross_only {
+            query.push_str(" AND ua.project_id != ub.project_id");
+        }
+
+        // 相似度相同时按单元名排序，保证输出稳定
+        query.push_str(" ORDER BY sp.similarity DESC, sp.unit_a, sp.unit_b");
 
         let mut stmt = self.conn.prepare(&query)?;
         let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
@@ -71,7 +110,7 @@ impl Database {
                 unit_a: row.get(1)?,
                 unit_b: row.get(2)?,
                 similarity: row.get(3)?,
-                status: PairStatus::from_str(&status_str).unwrap_or(PairStatus::New),
+                status: PairStatus::parse(&status_str).unwrap_or(PairStatus::New),
                 trigger_reason: row.get(5)?,
                 file_a: row.get(6)?,
                 start_a: row.get(7)?,
@@ -79,20 +118,64 @@ impl Database {
                 file_b: row.get(9)?,
                 start_b: row.get(10)?,
                 end_b: row.get(11)?,
+                kind_a: row.get(12)?,
+                kind_b: row.get(13)?,
+                signature_a: row.get(14)?,
+                signature_b: row.get(15)?,
+                created_at: row.get(16)?,
+                updated_at: row.get(17)?,
+                status_reason: row.get(18)?,
             })
         })?;
         rows.collect()
     }
 
-    /// 更新配对状态
+    /// 更新配对状态 (清除之前记录的原因)
     pub fn update_pair_status(&self, pair_id: i64, status: PairStatus) -> SqliteResult<()> {
+        self.update_pair_status_with_reason(pair_id, status, None)
+    }
+
+    /// 更新配对状态并记录原因 (如为什么忽略)，`akin pairs` 中显示
+    pub fn update_pair_status_with_reason(
+        &self,
+        pair_id: i64,
+        status: PairStatus,
+        reason: Option<&str>,
+    ) -> SqliteResult<()> {
         self.conn.execute(
-            "UPDATE similar_pairs SET status = ? WHERE id = ?",
-            params![status.as_str(), pair_id],
+            "UPDATE similar_pairs SET status = ?, status_reason = ? WHERE id = ?",
+            params![status.as_str(), reason, pair_id],
         )?;
         Ok(())
     }
 
+    /// 按文件路径 glob 批量更新 `new` 状态的配对，返回更新数量
+    ///
+    /// 使用 SQLite GLOB 匹配完整文件路径 (`*` 可跨越 `/`)；
+    /// `match_either` 为 false 时要求两端都匹配，否则任一端匹配即可；`reason` 记录到每个配对
+    pub fn bulk_update_pair_status_by_file(
+        &self,
+        glob: &str,
+        status: PairStatus,
+        match_either: bool,
+        reason: Option<&str>,
+    ) -> SqliteResult<usize> {
+        let op = if match_either { "OR" } else { "AND" };
+        let sql = format!(
+            r#"
+            UPDATE similar_pairs SET status = ?1, status_reason = ?3
+            WHERE status = 'new' AND id IN (
+                SELECT sp.id FROM similar_pairs sp
+                JOIN code_units ua ON sp.unit_a = ua.qualified_name
+                JOIN code_units ub ON sp.unit_b = ub.qualified_name
+                WHERE ua.file_path GLOB ?2 {} ub.file_path GLOB ?2
+            )
+            "#,
+            op
+        );
+        self.conn.execute(&sql, params![status.as_str(), glob, reason])
+    }
+
     /// 删除涉及某 CodeUnit 的所有配对
     pub fn delete_pairs_involving(&self, qualified_name: &str) -> SqliteResult<()> {
         self.conn.execute(
@@ -102,6 +185,18 @@ impl Database {
         Ok(())
     }
 
+    /// 删除引用了已不存在 CodeUnit 的配对，返回删除数量
+    pub fn prune_orphan_pairs(&self) -> SqliteResult<usize> {
+        self.conn.execute(
+            r#"
+            DELETE FROM similar_pairs
+            WHERE unit_a NOT IN (SELECT qualified_name FROM code_units)
+               OR unit_b NOT IN (SELECT qualified_name FROM code_units)
+            "#,
+            [],
+        )
+    }
+
     /// 批量插入相似配对（单事务，高效）
     pub fn batch_upsert_similar_pairs(
         &self,
@@ -112,15 +207,7 @@ impl Database {
         self.conn.execute("BEGIN TRANSACTION", [])?;
 
         let result = (|| {
-            let mut stmt = self.conn.prepare(
-                r#"
-                INSERT INTO similar_pairs (unit_a, unit_b, similarity, status, trigger_reason)
-                VALUES (?, ?, ?, 'new', ?)
-                ON CONFLICT(unit_a, unit_b) DO UPDATE SET
-                    similarity = excluded.similarity,
-                    trigger_reason = excluded.trigger_reason
-                "#,
-            )?;
+            let mut stmt = self.conn.prepare(UPSERT_PAIR_SQL)?;
 
             let mut count = 0;
             for (unit_a, unit_b, similarity) in pairs {
@@ -149,30 +236,23 @@ impl Database {
 #[cfg(test)]
 mod tests {
     use crate::db::{Database, CodeUnitRecord, PairStatus};
+    use crate::vector_index::Metric;
 
     fn setup_db_with_units() -> (Database, i64) {
         let db = Database::open_in_memory().unwrap();
-        let project_id = db.get_or_create_project("test", "/path", "rust").unwrap();
-
-        for name in ["rust::a", "rust::b"] {
-            let record = CodeUnitRecord {
-                qualified_name: name.to_string(),
-                project_id,
-                file_path: "/path/src/lib.rs".to_string(),
-                kind: "function".to_string(),
-                range_start: 10,
-                range_end: 20,
-                content_hash: format!("hash_{}", name),
-                structure_hash: format!("struct_{}", name),
-                embedding: None,
-                group_id: None,
-            };
-            db.upsert_code_unit(&record).unwrap();
-        }
-
+        let project_id = add_units(&db, "/path", "/path/src/lib.rs", &["rust::a", "rust::b"]);
         (db, project_id)
     }
 
+    /// 在 `root` 项目的 `file_path` 中插入 `names`，返回项目 ID
+    fn add_units(db: &Database, root: &str, file_path: &str, names: &[&str]) -> i64 {
+        let project_id = db.get_or_create_project(root.trim_start_matches('/'), root, "rust").unwrap();
+        for name in names {
+            db.upsert_code_unit(&CodeUnitRecord::fixture(name, project_id, file_path)).unwrap();
+        }
+        project_id
+    }
+
     #[test]
     fn test_similar_pair_crud() {
         let (db, _) = setup_db_with_units();
@@ -181,26 +261,96 @@ mod tests {
         db.upsert_similar_pair("rust::a", "rust::b", 0.95, Some("test")).unwrap();
 
         // 查询
-        let pairs = db.get_similar_pairs(None, None, 0.0).unwrap();
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
         assert_eq!(pairs.len(), 1);
         assert_eq!(pairs[0].similarity, 0.95);
         assert_eq!(pairs[0].status, PairStatus::New);
+        assert_eq!(pairs[0].kind_a.as_deref(), Some("function"));
+        assert!(pairs[0].both_kind("function"));
+        assert!(!pairs[0].both_kind("type"));
 
         // 更新状态
         db.update_pair_status(pairs[0].id, PairStatus::Confirmed).unwrap();
-        let pairs = db.get_similar_pairs(None, Some(PairStatus::Confirmed), 0.0).unwrap();
+        let pairs = db.get_similar_pairs(None, Some(PairStatus::Confirmed), 0.0, false).unwrap();
         assert_eq!(pairs.len(), 1);
 
         // 过滤相似度
-        let pairs = db.get_similar_pairs(None, None, 0.99).unwrap();
+        let pairs = db.get_similar_pairs(None, None, 0.99, false).unwrap();
         assert_eq!(pairs.len(), 0);
 
+        // 计数
+        assert_eq!(db.count_pairs(None).unwrap(), 1);
+        assert_eq!(db.count_pairs(Some(PairStatus::Confirmed)).unwrap(), 1);
+        assert_eq!(db.count_pairs(Some(PairStatus::New)).unwrap(), 0);
+
         // 删除
         db.delete_pairs_involving("rust::a").unwrap();
-        let pairs = db.get_similar_pairs(None, None, 0.0).unwrap();
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
         assert_eq!(pairs.len(), 0);
     }
 
+    #[test]
+    fn test_rescan_keeps_reviewed_pair_context() {
+        let (db, _) = setup_db_with_units();
+
+        // 未审查的配对照常更新来源
+        db.upsert_similar_pair("rust::a", "rust::b", 0.90, Some("hook")).unwrap();
+        db.upsert_similar_pair("rust::a", "rust::b", 0.91, Some("compare")).unwrap();
+        let pair = db.get_similar_pairs(None, None, 0.0, false).unwrap().remove(0);
+        assert_eq!(pair.trigger_reason.as_deref(), Some("compare"));
+
+        db.update_pair_status(pair.id, PairStatus::Ignored).unwrap();
+
+        // 再次扫描命中已忽略的配对：只更新相似度
+        db.batch_upsert_similar_pairs(&[("rust::b".to_string(), "rust::a".to_string(), 0.93)], Some("scan")).unwrap();
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].status, PairStatus::Ignored);
+        assert_eq!(pairs[0].trigger_reason.as_deref(), Some("compare"));
+        assert_eq!(pairs[0].similarity, 0.93);
+        assert!((pairs[0].distance(Metric::Cosine) - 0.07).abs() < 1e-6);
+        assert!((pairs[0].distance(Metric::L2) - 0.14).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pair_timestamps() {
+        let (db, _) = setup_db_with_units();
+        db.batch_upsert_similar_pairs(&[("rust::a".to_string(), "rust::b".to_string(), 0.91)], Some("scan")).unwrap();
+
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
+        assert!(pairs[0].created_at.is_some());
+        assert_eq!(pairs[0].created_at, pairs[0].updated_at);
+
+        // 再次检测到只刷新 updated_at，首次发现时间不变
+        db.conn.execute(
+            "UPDATE similar_pairs SET created_at = '2020-01-01 00:00:00', updated_at = '2020-01-01 00:00:00'",
+            [],
+        ).unwrap();
+        db.batch_upsert_similar_pairs(&[("rust::b".to_string(), "rust::a".to_string(), 0.92)], Some("scan")).unwrap();
+
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
+        assert_eq!(pairs[0].created_at.as_deref(), Some("2020-01-01 00:00:00"));
+        assert!(pairs[0].updated_at.as_deref().unwrap() > "2020-01-01 00:00:00");
+    }
+
+    #[test]
+    fn test_update_pair_status_with_reason() {
+        let (db, _) = setup_db_with_units();
+        db.upsert_similar_pair("rust::a", "rust::b", 0.95, Some("scan")).unwrap();
+        let id = db.get_similar_pairs(None, None, 0.0, false).unwrap()[0].id;
+
+        db.update_pair_status_with_reason(id, PairStatus::Ignored, Some("intentional mirror")).unwrap();
+        let pair = &db.get_similar_pairs(None, Some(PairStatus::Ignored), 0.0, false).unwrap()[0];
+        assert_eq!(pair.status_reason.as_deref(), Some("intentional mirror"));
+        assert_eq!(pair.trigger_reason.as_deref(), Some("scan"));
+
+        // 不带原因的状态变更清除旧原因
+        db.update_pair_status(id, PairStatus::New).unwrap();
+        let pair = &db.get_similar_pairs(None, None, 0.0, false).unwrap()[0];
+        assert_eq!(pair.status, PairStatus::New);
+        assert_eq!(pair.status_reason, None);
+    }
+
     #[test]
     fn test_pair_ordering_consistency() {
         let (db, _) = setup_db_with_units();
@@ -209,8 +359,108 @@ mod tests {
         db.upsert_similar_pair("rust::b", "rust::a", 0.90, None).unwrap();
         db.upsert_similar_pair("rust::a", "rust::b", 0.95, None).unwrap();
 
-        let pairs = db.get_similar_pairs(None, None, 0.0).unwrap();
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
         assert_eq!(pairs.len(), 1);
         assert_eq!(pairs[0].similarity, 0.95); // 更新后的值
     }
+
+    #[test]
+    fn test_prune_orphan_pairs() {
+        let (db, _) = setup_db_with_units();
+        db.upsert_similar_pair("rust::a", "rust::b", 0.95, None).unwrap();
+
+        // 没有孤立配对时不删除
+        assert_eq!(db.prune_orphan_pairs().unwrap(), 0);
+
+        // 模拟未启用外键约束时遗留的孤立配对
+        db.conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        db.delete_code_units_by_file("/path/src/lib.rs").unwrap();
+        db.conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        assert_eq!(db.prune_orphan_pairs().unwrap(), 1);
+
+        let count: i64 = db.conn
+            .query_row("SELECT COUNT(*) FROM similar_pairs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_delete_code_units_by_file_and_prune() {
+        let (db, _) = setup_db_with_units();
+        db.upsert_similar_pair("rust::a", "rust::b", 0.95, None).unwrap();
+
+        assert_eq!(db.delete_code_units_by_file_and_prune("/path/src/lib.rs").unwrap(), 1);
+        assert!(db.get_code_units_by_file("/path/src/lib.rs").unwrap().is_empty());
+        assert!(db.get_similar_pairs(None, None, 0.0, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_similar_pairs_project_filter() {
+        let (db, project_id) = setup_db_with_units();
+        let other_id = add_units(&db, "/other", "/other/src/lib.rs", &["rust::c", "rust::d"]);
+
+        db.upsert_similar_pair("rust::a", "rust::b", 0.95, None).unwrap();
+        db.upsert_similar_pair("rust::c", "rust::d", 0.93, None).unwrap();
+        db.upsert_similar_pair("rust::b", "rust::c", 0.90, None).unwrap(); // 跨项目
+
+        let pairs = db.get_similar_pairs(Some(project_id), None, 0.0, false).unwrap();
+        let names: Vec<_> = pairs.iter().map(|p| (p.unit_a.as_str(), p.unit_b.as_str())).collect();
+        assert_eq!(names, vec![("rust::a", "rust::b"), ("rust::b", "rust::c")]);
+
+        let pairs = db.get_similar_pairs(Some(other_id), None, 0.0, false).unwrap();
+        assert!(pairs.iter().all(|p| p.unit_a != "rust::a"));
+        assert_eq!(pairs.len(), 2);
+
+        // 只保留跨项目配对
+        let pairs = db.get_similar_pairs(None, None, 0.0, true).unwrap();
+        let names: Vec<_> = pairs.iter().map(|p| (p.unit_a.as_str(), p.unit_b.as_str())).collect();
+        assert_eq!(names, vec![("rust::b", "rust::c")]);
+
+        let pairs = db.get_similar_pairs(Some(other_id), None, 0.0, true).unwrap();
+        assert_eq!(pairs.len(), 1);
+    }
+
+    #[test]
+    fn test_similar_pairs_tie_order() {
+        let (db, _) = setup_db_with_units();
+        add_units(&db, "/path", "/path/src/lib.rs", &["rust::c", "rust::d"]);
+
+        db.upsert_similar_pair("rust::c", "rust::d", 1.0, None).unwrap();
+        db.upsert_similar_pair("rust::b", "rust::d", 0.9, None).unwrap();
+        db.upsert_similar_pair("rust::c", "rust::a", 1.0, None).unwrap();
+        db.upsert_similar_pair("rust::a", "rust::b", 1.0, None).unwrap();
+
+        let pairs = db.get_similar_pairs(None, None, 0.0, false).unwrap();
+        let names: Vec<_> = pairs.iter().map(|p| (p.unit_a.as_str(), p.unit_b.as_str())).collect();
+        assert_eq!(names, vec![
+            ("rust::a", "rust::b"),
+            ("rust::a", "rust::c"),
+            ("rust::c", "rust::d"),
+            ("rust::b", "rust::d"),
+        ]);
+    }
+
+    #[test]
+    fn test_bulk_update_pair_status_by_file() {
+        let (db, _) = setup_db_with_units();
+        add_units(&db, "/path", "/path/src/generated/api.rs", &["rust::gen"]);
+
+        db.upsert_similar_pair("rust::a", "rust::b", 0.95, None).unwrap();
+        db.upsert_similar_pair("rust::a", "rust::gen", 0.90, None).unwrap();
+
+        // 两端都需匹配：只有 lib.rs 内部的配对
+        let n = db.bulk_update_pair_status_by_file("*/src/lib.rs", PairStatus::Ignored, false, Some("vendored")).unwrap();
+        assert_eq!(n, 1);
+
+        // 已忽略的配对不会重复计数
+        let n = db.bulk_update_pair_status_by_file("*/generated/*", PairStatus::Ignored, true, None).unwrap();
+        assert_eq!(n, 1);
+        let n = db.bulk_update_pair_status_by_file("*", PairStatus::Ignored, false, None).unwrap();
+        assert_eq!(n, 0);
+
+        let ignored = db.get_similar_pairs(None, Some(PairStatus::Ignored), 0.0, false).unwrap();
+        assert_eq!(ignored.len(), 2);
+        assert_eq!(ignored[0].status_reason.as_deref(), Some("vendored"));
+        assert_eq!(ignored[1].status_reason, None);
+    }
 }
@@ -32,33 +32,105 @@ impl Database {
         Ok(())
     }
 
+    /// 记录索引进度 (用于中断后恢复)
+    pub fn set_index_progress(&self, project_id: i64, done: usize, total: usize) -> SqliteResult<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO index_progress (project_id, done, total, updated_at)
+            VALUES (?, ?, ?, datetime('now'))
+            ON CONFLICT(project_id) DO UPDATE SET
+                done = excluded.done,
+                total = excluded.total,
+                updated_at = excluded.updated_at
+            "#,
+            params![project_id, done as i64, total as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 获取未完成的索引进度 (done, total)，没有记录表示上次索引已正常结束
+    pub fn get_index_progress(&self, project_id: i64) -> SqliteResult<Option<(usize, usize)>> {
+        let result = self.conn.query_row(
+            "SELECT done, total FROM index_progress WHERE project_id = ?",
+            [project_id],
+            |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, i64>(1)? as usize)),
+        );
+
+        match result {
+            Ok(progress) => Ok(Some(progress)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 清除索引进度 (索引完成时调用)
+    pub fn clear_index_progress(&self, project_id: i64) -> SqliteResult<()> {
+        self.conn.execute("DELETE FROM index_progress WHERE project_id = ?", [project_id])?;
+        Ok(())
+    }
+
+    /// 记录项目使用的嵌入模型、维度与输入前缀
+    pub fn set_project_model(&self, project_id: i64, model: &str, dimensions: usize, prefix: &str) -> SqliteResult<()> {
+        self.conn.execute(
+            "UPDATE projects SET embedding_model = ?, embedding_dimensions = ?, embedding_prefix = ? WHERE id = ?",
+            params![model, dimensions as i64, prefix, project_id],
+        )?;
+        Ok(())
+    }
+
+    /// 删除项目及其代码单元、相似配对、分组与索引进度 (单事务)，返回删除的单元数
+    ///
+    /// 项目不存在时返回 0；其他项目中引用了被删分组的单元会移出分组
+    pub fn delete_project(&self, project_id: i64) -> SqliteResult<usize> {
+        self.conn.execute("BEGIN TRANSACTION", [])?;
+
+        let result = (|| {
+            self.conn.execute(
+                r#"
+                DELETE FROM similar_pairs
+                WHERE unit_a IN (SELECT qualified_name FROM code_units WHERE project_id = ?1)
+                   OR unit_b IN (SELECT qualified_name FROM code_units WHERE project_id = ?1)
+                "#,
+                [project_id],
+            )?;
+            self.conn.execute(
+                "UPDATE code_units SET group_id = NULL WHERE group_id IN (SELECT id FROM similarity_groups WHERE project_id = ?)",
+                [project_id],
+            )?;
+            self.conn.execute("DELETE FROM similarity_groups WHERE project_id = ?", [project_id])?;
+            self.conn.execute("DELETE FROM index_progress WHERE project_id = ?", [project_id])?;
+            self.conn.execute(
+                "DELETE FROM code_bodies WHERE rowid IN (SELECT vector_id FROM code_units WHERE project_id = ?)",
+                [project_id],
+            )?;
+            let units = self.conn.execute("DELETE FROM code_units WHERE project_id = ?", [project_id])?;
+            self.conn.execute("DELETE FROM projects WHERE id = ?", [project_id])?;
+            Ok::<usize, rusqlite::Error>(units)
+        })();
+
+        match result {
+            Ok(units) => {
+                self.conn.execute("COMMIT", [])?;
+                Ok(units)
+            }
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
     /// 获取所有项目
     pub fn get_all_projects(&self) -> SqliteResult<Vec<ProjectRecord>> {
         let mut stmt = self.conn.prepare("SELECT * FROM projects ORDER BY name")?;
-        let rows = stmt.query_map([], |row| {
-            Ok(ProjectRecord {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                root_path: row.get(2)?,
-                language: row.get(3)?,
-                last_indexed_at: row.get(4)?,
-            })
-        })?;
+        let rows = stmt.query_map([], Self::row_to_project)?;
         rows.collect()
     }
 
     /// 按路径获取项目
     pub fn get_project_by_path(&self, root_path: &str) -> SqliteResult<Option<ProjectRecord>> {
         let mut stmt = self.conn.prepare("SELECT * FROM projects WHERE root_path = ?")?;
-        let result = stmt.query_row([root_path], |row| {
-            Ok(ProjectRecord {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                root_path: row.get(2)?,
-                language: row.get(3)?,
-                last_indexed_at: row.get(4)?,
-            })
-        });
+        let result = stmt.query_row([root_path], Self::row_to_project);
 
         match result {
             Ok(record) => Ok(Some(record)),
@@ -66,11 +138,24 @@ impl Database {
             Err(e) => Err(e),
         }
     }
+
+    fn row_to_project(row: &rusqlite::Row) -> rusqlite::Result<ProjectRecord> {
+        Ok(ProjectRecord {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            root_path: row.get(2)?,
+            language: row.get(3)?,
+            last_indexed_at: row.get(4)?,
+            embedding_model: row.get(5)?,
+            embedding_dimensions: row.get::<_, Option<i64>>(6)?.map(|d| d as usize),
+            embedding_prefix: row.get(7)?,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::db::Database;
+    use crate::db::{CodeUnitRecord, Database};
 
     #[test]
     fn test_project_crud() {
@@ -98,4 +183,84 @@ mod tests {
         let projects = db.get_all_projects().unwrap();
         assert_eq!(projects.len(), 1);
     }
+
+    #[test]
+    fn test_project_model_guard() {
+        let db = Database::open_in_memory().unwrap();
+        let id = db.get_or_create_project("test", "/path/to/test", "rust").unwrap();
+
+        // 未记录过模型时任何维度都可以
+        let project = db.get_project_by_path("/path/to/test").unwrap().unwrap();
+        assert_eq!(project.embedding_dimensions, None);
+        assert!(project.check_model("bge-m3", 1024, "passage: ").is_ok());
+
+        db.set_project_model(id, "bge-m3", 1024, "passage: ").unwrap();
+        let project = db.get_project_by_path("/path/to/test").unwrap().unwrap();
+        assert_eq!(project.embedding_model.as_deref(), Some("bge-m3"));
+        assert!(project.check_model("bge-m3", 1024, "passage: ").is_ok());
+        // 同维度、同前缀换模型允许
+        assert!(project.check_model("multilingual-e5-large", 1024, "passage: ").is_ok());
+
+        let err = project.check_model("nomic-embed-text", 768, "search_document: ").unwrap_err();
+        assert_eq!(err.recorded_dimensions, 1024);
+        assert_eq!(err.dimensions, 768);
+        assert!(err.to_string().contains("bge-m3 (1024 dimensions)"));
+
+        // 前缀变化 (如不带前缀索引的旧向量) 与换维度同样处理
+        let err = project.check_model("bge-m3", 1024, "").unwrap_err();
+        assert_eq!(err.recorded_prefix, "passage: ");
+        assert!(err.to_string().contains("input prefix \"passage: \""));
+    }
+
+    #[test]
+    fn test_index_progress() {
+        let db = Database::open_in_memory().unwrap();
+        let id = db.get_or_create_project("test", "/path/to/test", "rust").unwrap();
+
+        assert_eq!(db.get_index_progress(id).unwrap(), None);
+
+        db.set_index_progress(id, 100, 250).unwrap();
+        db.set_index_progress(id, 200, 250).unwrap();
+        assert_eq!(db.get_index_progress(id).unwrap(), Some((200, 250)));
+
+        db.clear_index_progress(id).unwrap();
+        assert_eq!(db.get_index_progress(id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_delete_project_cascades() {
+        let db = Database::open_in_memory().unwrap();
+        let gone = db.get_or_create_project("gone", "/gone", "rust").unwrap();
+        let kept = db.get_or_create_project("kept", "/kept", "rust").unwrap();
+        for (name, project_id) in [("rust::gone::a", gone), ("rust::gone::b", gone), ("rust::kept::c", kept)] {
+            db.upsert_code_unit(&CodeUnitRecord {
+                qualified_name: name.to_string(),
+                project_id,
+                file_path: format!("/{}.rs", name),
+                kind: "function".to_string(),
+                range_start: 1,
+                range_end: 9,
+                content_hash: format!("hash_{}", name),
+                structure_hash: format!("struct_{}", name),
+                embedding: None,
+                group_id: None,
+                signature: None,
+            }).unwrap();
+        }
+        db.upsert_similar_pair("rust::gone::a", "rust::kept::c", 0.9, None).unwrap();
+        let group = db.create_group(gone, "dup", None, None).unwrap();
+        db.add_to_group("rust::kept::c", group).unwrap();
+        db.set_index_progress(gone, 1, 2).unwrap();
+
+        assert_eq!(db.delete_project(gone).unwrap(), 2);
+        assert!(db.get_project_by_path("/gone").unwrap().is_none());
+        assert!(db.get_code_unit("rust::gone::a").unwrap().is_none());
+        assert!(db.get_similar_pairs(None, None, 0.0, false).unwrap().is_empty());
+        assert!(db.get_groups(gone).unwrap().is_empty());
+        assert_eq!(db.get_code_unit("rust::kept::c").unwrap().unwrap().group_id, None);
+
+        // 已删除的项目再删一次不报错
+        assert_eq!(db.delete_project(gone).unwrap(), 0);
+        assert_eq!(db.get_all_projects().unwrap().len(), 1);
+    }
 }
@@ -2,6 +2,8 @@
 
 use std::collections::HashMap;
 
+use crate::vector_index::Metric;
+
 /// 配对状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PairStatus {
@@ -21,7 +23,7 @@ impl PairStatus {
         }
     }
 
-    pub fn from_str(s: &str) -> Option<Self> {
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
             "new" => Some(Self::New),
             "confirmed" => Some(Self::Confirmed),
@@ -40,6 +42,65 @@ pub struct ProjectRecord {
     pub root_path: String,
     pub language: String,
     pub last_indexed_at: Option<String>,
+    /// 上次索引使用的嵌入模型 (旧数据库为 None)
+    pub embedding_model: Option<String>,
+    /// 上次索引使用的嵌入维度
+    pub embedding_dimensions: Option<usize>,
+    /// 上次索引时被索引文本使用的前缀 (没有前缀为空字符串)
+    pub embedding_prefix: Option<String>,
+}
+
+impl ProjectRecord {
+    /// 检查本次嵌入维度与输入前缀是否与记录一致，未记录过时视为一致
+    ///
+    /// 同维度换模型不会破坏向量索引，仍可比较 (结果质量另当别论)；
+    /// 前缀不同的向量混在一起则没有可比性
+    pub fn check_model(&self, model: &str, dimensions: usize, prefix: &str) -> Result<(), Box<ModelMismatch>> {
+        let dimensions_differ = self.embedding_dimensions.is_some_and(|recorded| recorded != dimensions);
+        let prefix_differs = self.embedding_prefix.as_deref().is_some_and(|recorded| recorded != prefix);
+        if !dimensions_differ && !prefix_differs {
+            return Ok(());
+        }
+        Err(Box::new(ModelMismatch {
+            project: self.name.clone(),
+            recorded_model: self.embedding_model.clone().unwrap_or_default(),
+            recorded_dimensions: self.embedding_dimensions.unwrap_or(dimensions),
+            recorded_prefix: self.embedding_prefix.clone().unwrap_or_else(|| prefix.to_string()),
+            model: model.to_string(),
+            dimensions,
+            prefix: prefix.to_string(),
+        }))
+    }
+}
+
+/// 嵌入维度或输入前缀与项目记录不一致 (混用会让向量比较失去意义)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelMismatch {
+    pub project: String,
+    pub recorded_model: String,
+    pub recorded_dimensions: usize,
+    pub recorded_prefix: String,
+    pub model: String,
+    pub dimensions: usize,
+    pub prefix: String,
+}
+
+impl std::fmt::Display for ModelMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.recorded_dimensions != self.dimensions {
+            write!(
+                f,
+                "project '{}' was indexed with {} ({} dimensions), but {} produces {} dimensions",
+                self.project, self.recorded_model, self.recorded_dimensions, self.model, self.dimensions
+            )
+        } else {
+            write!(
+                f,
+                "project '{}' was indexed with {} using input prefix {:?}, but {} now uses {:?}",
+                self.project, self.recorded_model, self.recorded_prefix, self.model, self.prefix
+            )
+        }
+    }
 }
 
 /// CodeUnit 数据库记录
@@ -55,6 +116,28 @@ pub struct CodeUnitRecord {
     pub structure_hash: String,
     pub embedding: Option<Vec<u8>>,
     pub group_id: Option<i64>,
+    /// 函数签名 (用于展示)
+    pub signature: Option<String>,
+}
+
+#[cfg(test)]
+impl CodeUnitRecord {
+    /// 测试用记录：hash 由名称派生，没有 embedding，其余字段按需用结构体更新语法覆盖
+    pub(crate) fn fixture(name: &str, project_id: i64, file_path: &str) -> Self {
+        Self {
+            qualified_name: name.to_string(),
+            project_id,
+            file_path: file_path.to_string(),
+            kind: "function".to_string(),
+            range_start: 1,
+            range_end: 9,
+            content_hash: format!("hash_{}", name),
+            structure_hash: format!("struct_{}", name),
+            embedding: None,
+            group_id: None,
+            signature: None,
+        }
+    }
 }
 
 /// 相似配对记录
@@ -73,6 +156,30 @@ pub struct SimilarPairRecord {
     pub file_b: Option<String>,
     pub start_b: Option<u32>,
     pub end_b: Option<u32>,
+    pub kind_a: Option<String>,
+    pub kind_b: Option<String>,
+    pub signature_a: Option<String>,
+    pub signature_b: Option<String>,
+    /// 首次检测到的时间 (UTC, `YYYY-MM-DD HH:MM:SS`)，迁移前已存在的配对为 None
+    pub created_at: Option<String>,
+    /// 最近一次扫描再次检测到的时间
+    pub updated_at: Option<String>,
+    /// 最近一次状态变更的原因 (如忽略理由)
+    pub status_reason: Option<String>,
+}
+
+impl SimilarPairRecord {
+    /// 把存储的相似度换算回 `metric` 下的索引距离 (0 = 相同)
+    ///
+    /// 扫描时启用了 `--length-penalty` 的配对，换算结果包含该惩罚
+    pub fn distance(&self, metric: Metric) -> f32 {
+        metric.distance(self.similarity)
+    }
+
+    /// 两端单元是否都是指定 kind (如 "type")
+    pub fn both_kind(&self, kind: &str) -> bool {
+        self.kind_a.as_deref() == Some(kind) && self.kind_b.as_deref() == Some(kind)
+    }
 }
 
 /// 相似度分组记录
@@ -85,6 +192,17 @@ pub struct SimilarityGroupRecord {
     pub pattern: Option<String>,
 }
 
+/// 函数体全文搜索结果
+#[derive(Debug, Clone)]
+pub struct BodyMatch {
+    pub qualified_name: String,
+    pub file_path: String,
+    pub range_start: u32,
+    pub range_end: u32,
+    /// 相关度 (BM25 取负，越大越相关)
+    pub score: f64,
+}
+
 /// 项目统计信息
 #[derive(Debug)]
 pub struct ProjectStats {
@@ -104,8 +222,8 @@ mod tests {
         assert_eq!(PairStatus::Redundant.as_str(), "redundant");
         assert_eq!(PairStatus::Ignored.as_str(), "ignored");
 
-        assert_eq!(PairStatus::from_str("new"), Some(PairStatus::New));
-        assert_eq!(PairStatus::from_str("confirmed"), Some(PairStatus::Confirmed));
-        assert_eq!(PairStatus::from_str("invalid"), None);
+        assert_eq!(PairStatus::parse("new"), Some(PairStatus::New));
+        assert_eq!(PairStatus::parse("confirmed"), Some(PairStatus::Confirmed));
+        assert_eq!(PairStatus::parse("invalid"), None);
     }
 }
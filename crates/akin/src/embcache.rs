@@ -0,0 +1,258 @@
+//! 磁盘嵌入缓存 - 跨数据库、跨项目复用 embedding
+//!
+//! 按 `(模型, 维度, 内容哈希)` 寻址，存放在 `~/.vimo/akin/embcache/<模型>-<维度>/<哈希前两位>/<哈希>`，
+//! 文件内容为原始 embedding 字节 (与数据库中的格式一致)
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use ndarray::Array1;
+use sha2::{Digest, Sha256};
+
+use crate::embedding::{Embedder, Result, bytes_to_embedding, embedding_to_bytes};
+
+/// 内容寻址的嵌入缓存
+#[derive(Debug, Clone)]
+pub struct EmbeddingCache {
+    dir: PathBuf,
+}
+
+impl EmbeddingCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 默认缓存目录: `~/.vimo/akin/embcache`
+    pub fn default_dir() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".vimo")
+            .join("akin")
+            .join("embcache")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 读取缓存，字节数与维度不符时视为未命中
+    pub fn get(&self, model: &str, dimensions: usize, text: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.entry_path(model, dimensions, text))
+            .ok()
+            .filter(|bytes| bytes.len() == dimensions * 4)
+    }
+
+    /// 写入缓存 (先写临时文件再改名，并发写入同一条目也不会读到半截内容)
+    pub fn put(&self, model: &str, dimensions: usize, text: &str, bytes: &[u8]) -> std::io::Result<()> {
+        let path = self.entry_path(model, dimensions, text);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &path)
+    }
+
+    /// 删除全部缓存，返回删除的条目数
+    pub fn clear(&self) -> std::io::Result<usize> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+        let count = count_files(&self.dir)?;
+        std::fs::remove_dir_all(&self.dir)?;
+        Ok(count)
+    }
+
+    fn entry_path(&self, model: &str, dimensions: usize, text: &str) -> PathBuf {
+        let hash = format!("{:x}", Sha256::digest(text.as_bytes()));
+        self.dir
+            .join(format!("{}-{}", model_slug(model), dimensions))
+            .join(&hash[..2])
+            .join(hash)
+    }
+}
+
+impl Default for EmbeddingCache {
+    fn default() -> Self {
+        Self::new(Self::default_dir())
+    }
+}
+
+/// 模型名转为目录名 (`nomic-embed-text:latest` -> `nomic-embed-text_latest`)
+fn model_slug(model: &str) -> String {
+    model
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '.') { c } else { '_' })
+        .collect()
+}
+
+fn count_files(dir: &Path) -> std::io::Result<usize> {
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            count += count_files(&entry.path())?;
+        } else {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// 带磁盘缓存的嵌入生成器：命中缓存时不调用底层后端
+///
+/// 维度未知时 (未调用 `with_dimensions`) 第一次嵌入必然未命中，之后按其结果确定维度
+pub struct CachedEmbedder {
+    inner: Box<dyn Embedder>,
+    cache: EmbeddingCache,
+    dimensions: Option<usize>,
+    hits: usize,
+}
+
+impl CachedEmbedder {
+    pub fn new(inner: Box<dyn Embedder>, cache: EmbeddingCache) -> Self {
+        Self { inner, cache, dimensions: None, hits: 0 }
+    }
+
+    /// 已知的模型维度 (如探测结果或项目记录)
+    pub fn with_dimensions(mut self, dimensions: Option<usize>) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+
+    /// 缓存命中次数
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// 缓存键文本：带上后端的输入前缀，query/passage 嵌入互不复用
+    fn key<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.inner.input_prefix() {
+            "" => Cow::Borrowed(text),
+            prefix => Cow::Owned(format!("{}{}", prefix, text)),
+        }
+    }
+
+    fn lookup(&mut self, text: &str) -> Option<Array1<f32>> {
+        let dimensions = self.dimensions?;
+        let embedding = self.cache.get(self.inner.model_name(), dimensions, &self.key(text))
+            .and_then(|bytes| bytes_to_embedding(&bytes))?;
+        self.hits += 1;
+        Some(embedding)
+    }
+
+    fn store(&mut self, text: &str, embedding: &Array1<f32>) {
+        let dimensions = *self.dimensions.get_or_insert(embedding.len());
+        if embedding.len() != dimensions {
+            return;
+        }
+        if let Err(e) = self.cache.put(self.inner.model_name(), dimensions, &self.key(text), &embedding_to_bytes(embedding)) {
+            tracing::warn!("Failed to write embedding cache {}: {}", self.cache.dir().display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl Embedder for CachedEmbedder {
+    async fn embed(&mut self, text: &str) -> Result<Array1<f32>> {
+        if let Some(embedding) = self.lookup(text) {
+            return Ok(embedding);
+        }
+        let embedding = self.inner.embed(text).await?;
+        self.store(text, &embedding);
+        Ok(embedding)
+    }
+
+    async fn embed_each(&mut self, texts: &[&str], concurrency: usize) -> Vec<Result<Array1<f32>>> {
+        let mut results: Vec<Option<Result<Array1<f32>>>> = texts.iter()
+            .map(|text| self.lookup(text).map(Ok))
+            .collect();
+
+        let misses: Vec<&str> = texts.iter()
+            .zip(&results)
+            .filter(|(_, cached)| cached.is_none())
+            .map(|(text, _)| *text)
+            .collect();
+        let mut fresh = self.inner.embed_each(&misses, concurrency).await.into_iter();
+
+        for (text, slot) in texts.iter().zip(results.iter_mut()) {
+            if slot.is_none() {
+                let result = fresh.next().unwrap_or_else(|| {
+                    Err(crate::embedding::EmbeddingError::Model("missing batch result".into()))
+                });
+                if let Ok(embedding) = &result {
+                    self.store(text, embedding);
+                }
+                *slot = Some(result);
+            }
+        }
+
+        results.into_iter().flatten().collect()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn input_prefix(&self) -> &str {
+        self.inner.input_prefix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockEmbedding;
+
+    #[test]
+    fn test_put_get_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = EmbeddingCache::new(dir.path());
+        let bytes = embedding_to_bytes(&Array1::from_vec(vec![0.5_f32, -1.0]));
+
+        assert!(cache.get("bge-m3", 2, "fn a() {}").is_none());
+        cache.put("bge-m3", 2, "fn a() {}", &bytes).unwrap();
+        assert_eq!(cache.get("bge-m3", 2, "fn a() {}"), Some(bytes.clone()));
+
+        // 模型或维度不同都不会命中
+        assert!(cache.get("nomic-embed-text:latest", 2, "fn a() {}").is_none());
+        assert!(cache.get("bge-m3", 4, "fn a() {}").is_none());
+        assert!(cache.get("bge-m3", 2, "fn b() {}").is_none());
+
+        cache.put("nomic-embed-text:latest", 2, "fn a() {}", &bytes).unwrap();
+        assert!(dir.path().join("nomic-embed-text_latest-2").is_dir());
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert!(cache.get("bge-m3", 2, "fn a() {}").is_none());
+        assert_eq!(cache.clear().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cached_embedder_hits() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = EmbeddingCache::new(dir.path());
+        let texts = ["fn a() { 1 }", "fn b() { 2 }"];
+
+        let mut first = CachedEmbedder::new(Box::new(MockEmbedding::new(8)), cache.clone());
+        let embedded = first.embed_each(&texts, 2).await;
+        assert!(embedded.iter().all(|r| r.is_ok()));
+        assert_eq!(first.hits(), 0);
+
+        // 新实例 (如另一个项目的索引) 直接命中磁盘缓存，结果与顺序不变
+        let mut second = CachedEmbedder::new(Box::new(MockEmbedding::new(8)), cache.clone())
+            .with_dimensions(Some(8));
+        let cached = second.embed_each(&["fn c() { 3 }", texts[1], texts[0]], 2).await;
+        assert_eq!(second.hits(), 2);
+        assert_eq!(cached[1].as_ref().unwrap(), embedded[1].as_ref().unwrap());
+        assert_eq!(cached[2].as_ref().unwrap(), embedded[0].as_ref().unwrap());
+        assert_eq!(second.embed(texts[0]).await.unwrap(), *embedded[0].as_ref().unwrap());
+        assert_eq!(second.hits(), 3);
+
+        // 维度不同的模型不复用
+        let mut other = CachedEmbedder::new(Box::new(MockEmbedding::new(16)), cache)
+            .with_dimensions(Some(16));
+        assert_eq!(other.embed(texts[0]).await.unwrap().len(), 16);
+        assert_eq!(other.hits(), 0);
+    }
+}
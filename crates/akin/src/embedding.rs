@@ -1,25 +1,145 @@
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use ndarray::Array1;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum EmbeddingError {
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
+    #[error("Ollama returned status {0}")]
+    Status(StatusCode),
     #[error("API error: {0}")]
     Api(String),
+    #[error("Model error: {0}")]
+    Model(String),
+}
+
+impl EmbeddingError {
+    /// 是否是暂时性错误 (超时、连接失败、5xx)，重试可能成功；4xx 与模型错误重试无意义
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            Self::Status(status) => status.is_server_error(),
+            Self::Api(_) | Self::Model(_) => false,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, EmbeddingError>;
 
+/// 内置默认模型
+pub const DEFAULT_MODEL: &str = "bge-m3";
+
+/// 全局模型覆盖的环境变量
+pub const MODEL_ENV: &str = "IRIS_EMBED_MODEL";
+
+/// 默认模型：`IRIS_EMBED_MODEL` 优先，否则为内置默认值
+pub fn default_model() -> String {
+    std::env::var(MODEL_ENV)
+        .ok()
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string())
+}
+
+/// 嵌入用途：非对称模型对被索引文本与检索文本使用不同前缀
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmbedRole {
+    /// 被索引的文本 (`akin index`)
+    #[default]
+    Passage,
+    /// 检索用的文本 (hook 中新写的代码)
+    Query,
+}
+
+impl EmbedRole {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "passage" => Some(Self::Passage),
+            "query" => Some(Self::Query),
+            _ => None,
+        }
+    }
+}
+
+/// 需要任务前缀的模型：(模型名前缀, passage 前缀, query 前缀)
+const MODEL_PREFIXES: &[(&str, &str, &str)] = &[
+    ("bge-m3", "passage: ", "query: "),
+    ("multilingual-e5", "passage: ", "query: "),
+    ("e5-", "passage: ", "query: "),
+    ("nomic-embed-text", "search_document: ", "search_query: "),
+];
+
+/// 模型的默认 (passage, query) 前缀，不需要前缀的模型返回 None
+///
+/// 忽略 Ollama 的命名空间与标签 (`library/bge-m3:latest` 按 `bge-m3` 匹配)
+pub fn default_prefixes(model: &str) -> Option<(&'static str, &'static str)> {
+    let name = model.split(':').next().unwrap_or(model);
+    let name = name.rsplit('/').next().unwrap_or(name);
+    MODEL_PREFIXES
+        .iter()
+        .find(|(prefix, _, _)| name.starts_with(prefix))
+        .map(|&(_, passage, query)| (passage, query))
+}
+
+/// 嵌入生成后端
+#[async_trait]
+pub trait Embedder: Send {
+    /// 生成单个文本的嵌入
+    async fn embed(&mut self, text: &str) -> Result<Array1<f32>>;
+
+    /// 批量生成嵌入，结果与输入顺序一致，单个失败不影响其他文本
+    ///
+    /// 默认逐个生成；支持并发的后端最多同时处理 `concurrency` 个文本
+    async fn embed_each(&mut self, texts: &[&str], concurrency: usize) -> Vec<Result<Array1<f32>>> {
+        let _ = concurrency;
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.embed(text).await);
+        }
+        results
+    }
+
+    /// 模型名称 (用于日志输出)
+    fn model_name(&self) -> &str;
+
+    /// 嵌入前加在文本前面的前缀 (缓存键需要包含它)
+    fn input_prefix(&self) -> &str {
+        ""
+    }
+}
+
 /// Ollama 嵌入生成器
+#[derive(Clone)]
 pub struct OllamaEmbedding {
     client: Option<Client>,
     base_url: String,
     model: String,
+    /// 暂时性错误的最大重试次数
+    retries: u32,
+    /// 被索引文本的前缀
+    prefix_passage: Option<String>,
+    /// 检索文本的前缀
+    prefix_query: Option<String>,
+    role: EmbedRole,
 }
 
+/// 默认重试次数
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// 单次请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 首次重试前的等待，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// 单次重试等待的上限，保证总耗时有界: `(retries + 1) * REQUEST_TIMEOUT + retries * MAX_RETRY_DELAY`
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
 #[derive(Serialize)]
 struct EmbedRequest {
     model: String,
@@ -37,6 +157,10 @@ impl OllamaEmbedding {
             client: None, // Lazy init
             base_url: "http://localhost:11434".to_string(),
             model: model.to_string(),
+            retries: DEFAULT_RETRIES,
+            prefix_passage: default_prefixes(model).map(|(p, _)| p.to_string()),
+            prefix_query: default_prefixes(model).map(|(_, q)| q.to_string()),
+            role: EmbedRole::default(),
         }
     }
 
@@ -44,9 +168,9 @@ impl OllamaEmbedding {
     fn get_client(&mut self) -> Result<&Client> {
         if self.client.is_none() {
             let client = Client::builder()
-                .timeout(std::time::Duration::from_secs(60))
+                .timeout(REQUEST_TIMEOUT)
                 .build()
-                .map_err(|e| EmbeddingError::Http(e))?;
+                .map_err(EmbeddingError::Http)?;
             self.client = Some(client);
         }
         Ok(self.client.as_ref().unwrap())
@@ -57,46 +181,162 @@ impl OllamaEmbedding {
         self
     }
 
-    /// 生成单个文本的嵌入
-    pub async fn embed(&mut self, text: &str) -> Result<Array1<f32>> {
-        // Clone values before mutable borrow
-        let url = format!("{}/api/embed", self.base_url);
-        let request = EmbedRequest {
-            model: self.model.clone(),
-            input: text.to_string(),
+    /// 暂时性错误 (超时、连接失败、5xx) 的最大重试次数，0 表示不重试
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// 覆盖模型的默认前缀，None 表示不加前缀
+    pub fn with_prefixes(mut self, passage: Option<&str>, query: Option<&str>) -> Self {
+        self.prefix_passage = passage.map(str::to_string);
+        self.prefix_query = query.map(str::to_string);
+        self
+    }
+
+    /// 嵌入用途，决定使用哪个前缀 (默认 passage)
+    pub fn with_role(mut self, role: EmbedRole) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// 当前用途对应的前缀
+    pub fn prefix(&self) -> &str {
+        let prefix = match self.role {
+            EmbedRole::Passage => &self.prefix_passage,
+            EmbedRole::Query => &self.prefix_query,
         };
+        prefix.as_deref().unwrap_or("")
+    }
 
-        let client = self.get_client()?;
-        let response = client
-            .post(url)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(EmbeddingError::Api(format!(
-                "Ollama returned status {}",
-                response.status()
-            )));
+    fn prefixed<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.prefix() {
+            "" => Cow::Borrowed(text),
+            prefix => Cow::Owned(format!("{}{}", prefix, text)),
         }
+    }
 
-        let data: EmbedResponse = response.json().await?;
-        let embedding = data
-            .embeddings
-            .into_iter()
-            .next()
-            .ok_or_else(|| EmbeddingError::Api("No embedding returned".into()))?;
+    fn embed_url(&self) -> String {
+        format!("{}/api/embed", self.base_url)
+    }
 
-        Ok(Array1::from_vec(embedding))
+    /// 生成单个文本的嵌入
+    pub async fn embed(&mut self, text: &str) -> Result<Array1<f32>> {
+        let client = self.get_client()?.clone();
+        let input = self.prefixed(text);
+        request_embedding(&client, &self.embed_url(), &self.model, &input, self.retries).await
     }
 
-    /// 批量生成嵌入
+    /// 批量生成嵌入 (逐个请求)
     pub async fn embed_batch(&mut self, texts: &[&str]) -> Result<Vec<Array1<f32>>> {
-        let mut results = Vec::with_capacity(texts.len());
-        for text in texts {
-            results.push(self.embed(text).await?);
+        self.embed_batch_concurrent(texts, 1).await
+    }
+
+    /// 并发批量生成嵌入：最多同时 `concurrency` 个请求，结果与输入顺序一致
+    ///
+    /// 按输入顺序遇到第一个错误即返回；需要跳过失败项时使用 `embed_batch_concurrent_each`
+    pub async fn embed_batch_concurrent(&mut self, texts: &[&str], concurrency: usize) -> Result<Vec<Array1<f32>>> {
+        let client = self.get_client()?.clone();
+        let url = self.embed_url();
+        let inputs: Vec<Cow<str>> = texts.iter().map(|text| self.prefixed(text)).collect();
+        // 先收集成 Vec：直接在 stream 的闭包里借用会让 future 无法满足 Send 的高阶生命周期约束
+        let requests: Vec<_> = inputs.iter()
+            .map(|input| request_embedding(&client, &url, &self.model, input, self.retries))
+            .collect();
+        stream::iter(requests)
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// 同 `embed_batch_concurrent`，但逐项返回结果，失败的文本不影响其他文本
+    pub async fn embed_batch_concurrent_each(&mut self, texts: &[&str], concurrency: usize) -> Vec<Result<Array1<f32>>> {
+        let client = match self.get_client() {
+            Ok(client) => client.clone(),
+            Err(e) => {
+                let message = e.to_string();
+                return texts.iter().map(|_| Err(EmbeddingError::Api(message.clone()))).collect();
+            }
+        };
+        let url = self.embed_url();
+        let inputs: Vec<Cow<str>> = texts.iter().map(|text| self.prefixed(text)).collect();
+        let requests: Vec<_> = inputs.iter()
+            .map(|input| request_embedding(&client, &url, &self.model, input, self.retries))
+            .collect();
+        stream::iter(requests)
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+/// 向 Ollama `/api/embed` 请求嵌入 (client 可在并发请求间共享)
+///
+/// 暂时性错误最多重试 `retries` 次，等待时间 200ms 起指数增长
+async fn request_embedding(client: &Client, url: &str, model: &str, text: &str, retries: u32) -> Result<Array1<f32>> {
+    let mut attempt = 0;
+    loop {
+        match request_once(client, url, model, text).await {
+            Err(e) if attempt < retries && e.is_retryable() => {
+                let delay = retry_delay(attempt);
+                tracing::debug!("Embedding request failed ({}), retrying in {:?}", e, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
         }
-        Ok(results)
+    }
+}
+
+/// 第 `attempt` 次重试前的等待 (从 0 开始): 200ms、400ms、800ms…，不超过 `MAX_RETRY_DELAY`
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY)
+}
+
+async fn request_once(client: &Client, url: &str, model: &str, text: &str) -> Result<Array1<f32>> {
+    let request = EmbedRequest {
+        model: model.to_string(),
+        input: text.to_string(),
+    };
+
+    let response = client
+        .post(url)
+        .json(&request)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(EmbeddingError::Status(response.status()));
+    }
+
+    let data: EmbedResponse = response.json().await?;
+    let embedding = data
+        .embeddings
+        .into_iter()
+        .next()
+        .ok_or_else(|| EmbeddingError::Api("No embedding returned".into()))?;
+
+    Ok(Array1::from_vec(embedding))
+}
+
+#[async_trait]
+impl Embedder for OllamaEmbedding {
+    async fn embed(&mut self, text: &str) -> Result<Array1<f32>> {
+        OllamaEmbedding::embed(self, text).await
+    }
+
+    async fn embed_each(&mut self, texts: &[&str], concurrency: usize) -> Vec<Result<Array1<f32>>> {
+        self.embed_batch_concurrent_each(texts, concurrency).await
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn input_prefix(&self) -> &str {
+        self.prefix()
     }
 }
 
@@ -111,6 +351,23 @@ pub fn cosine_similarity(a: &Array1<f32>, b: &Array1<f32>) -> f32 {
     dot / (norm_a * norm_b)
 }
 
+/// 函数行数 (range 为闭区间)
+pub fn line_count(range_start: u32, range_end: u32) -> u32 {
+    range_end.saturating_sub(range_start) + 1
+}
+
+/// 按函数长度差异惩罚相似度: `similarity * (1 - weight * |a - b| / max(a, b))`
+///
+/// `weight` 为 0 时关闭，最大按 1 处理；长度相同的配对不受影响
+pub fn apply_length_penalty(similarity: f32, lines_a: u32, lines_b: u32, weight: f32) -> f32 {
+    let max = lines_a.max(lines_b);
+    if weight <= 0.0 || max == 0 {
+        return similarity;
+    }
+    let diff = lines_a.abs_diff(lines_b) as f32 / max as f32;
+    similarity * (1.0 - weight.min(1.0) * diff)
+}
+
 /// 嵌入转字节 (用于数据库存储)
 pub fn embedding_to_bytes(embedding: &Array1<f32>) -> Vec<u8> {
     embedding
@@ -121,7 +378,7 @@ pub fn embedding_to_bytes(embedding: &Array1<f32>) -> Vec<u8> {
 
 /// 字节转嵌入 (返回 None 如果字节数不是 4 的倍数)
 pub fn bytes_to_embedding(bytes: &[u8]) -> Option<Array1<f32>> {
-    if bytes.len() % 4 != 0 {
+    if !bytes.len().is_multiple_of(4) {
         return None;
     }
     let floats: Vec<f32> = bytes
@@ -138,6 +395,8 @@ pub fn bytes_to_embedding(bytes: &[u8]) -> Option<Array1<f32>> {
 mod tests {
     use super::*;
     use ndarray::array;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn test_cosine_similarity_identical_vectors() {
@@ -179,9 +438,33 @@ mod tests {
         assert!(sim > 0.99); // Very similar
     }
 
+    #[test]
+    fn test_length_penalty_equal_lengths_unchanged() {
+        assert_eq!(apply_length_penalty(0.9, 20, 20, 1.0), 0.9);
+        assert_eq!(apply_length_penalty(0.9, 0, 0, 1.0), 0.9);
+    }
+
+    #[test]
+    fn test_length_penalty_reduces_mismatched() {
+        // 差异 75%，权重 0.5 -> 乘以 0.625
+        let sim = apply_length_penalty(0.88, 10, 40, 0.5);
+        assert!((sim - 0.55).abs() < 1e-6);
+        assert!(apply_length_penalty(0.88, 10, 40, 1.0) < sim);
+
+        // 关闭时不变
+        assert_eq!(apply_length_penalty(0.88, 10, 40, 0.0), 0.88);
+    }
+
+    #[test]
+    fn test_line_count() {
+        assert_eq!(line_count(10, 20), 11);
+        assert_eq!(line_count(5, 5), 1);
+        assert_eq!(line_count(7, 3), 1);
+    }
+
     #[test]
     fn test_embedding_to_bytes_roundtrip() {
-        let original = array![1.0_f32, 2.5, -3.14, 0.0];
+        let original = array![1.0_f32, 2.5, -1.75, 0.0];
         let bytes = embedding_to_bytes(&original);
         let recovered = bytes_to_embedding(&bytes).unwrap();
 
@@ -352,4 +635,173 @@ func establish(path: String) {
         println!("假阳性 (Session vs FileManager): {:.2}% → {}", sim_fp_with_ctx * 100.0,
             if sim_fp_with_ctx < 0.85 { "✅ 正确排除" } else { "❌ 误报" });
     }
+
+    /// 最小的 Ollama `/api/embed` 模拟：返回 `[输入长度]`，输入越短响应越慢 (打乱完成顺序)
+    ///
+    /// 输入 "fail" 返回 500，"missing" 返回 404，"flaky" 的前两次请求返回 503；
+    /// 同时返回收到的请求总数
+    async fn spawn_mock_ollama() -> (String, Arc<AtomicUsize>) {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let flaky = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let counter = Arc::clone(&counter);
+                let flaky = Arc::clone(&flaky);
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(socket);
+                    loop {
+                        // 请求行与头部
+                        let mut content_length = 0;
+                        let mut line = String::new();
+                        loop {
+                            line.clear();
+                            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                                return;
+                            }
+                            let header = line.trim_end();
+                            if header.is_empty() {
+                                break;
+                            }
+                            if let Some((name, value)) = header.split_once(':') {
+                                if name.eq_ignore_ascii_case("content-length") {
+                                    content_length = value.trim().parse().unwrap_or(0);
+                                }
+                            }
+                        }
+
+                        let mut body = vec![0; content_length];
+                        if reader.read_exact(&mut body).await.is_err() {
+                            return;
+                        }
+                        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                        let input = request["input"].as_str().unwrap_or_default().to_string();
+                        counter.fetch_add(1, Ordering::SeqCst);
+
+                        let delay = 50u64.saturating_sub(input.len() as u64 * 10);
+                        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+
+                        let status = match input.as_str() {
+                            "fail" => "500 Internal Server Error",
+                            "missing" => "404 Not Found",
+                            "flaky" if flaky.fetch_add(1, Ordering::SeqCst) < 2 => "503 Service Unavailable",
+                            _ => "200 OK",
+                        };
+                        let payload = serde_json::json!({ "embeddings": [[input.len() as f32]] }).to_string();
+                        let response = format!(
+                            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            status,
+                            payload.len(),
+                            payload
+                        );
+                        if reader.get_mut().write_all(response.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        (format!("http://{}", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn test_embed_batch_concurrent_preserves_order() {
+        let (url, _) = spawn_mock_ollama().await;
+        let mut embedder = OllamaEmbedding::new("mock").with_url(&url);
+
+        let texts = ["a", "bbbb", "cc", "ddd"];
+        let embeddings = embedder.embed_batch_concurrent(&texts, 4).await.unwrap();
+        let lengths: Vec<f32> = embeddings.iter().map(|e| e[0]).collect();
+        assert_eq!(lengths, vec![1.0, 4.0, 2.0, 3.0]);
+
+        let sequential = embedder.embed_batch(&texts).await.unwrap();
+        assert_eq!(sequential, embeddings);
+    }
+
+    #[tokio::test]
+    async fn test_embed_batch_concurrent_failures() {
+        let (url, _) = spawn_mock_ollama().await;
+        let mut embedder = OllamaEmbedding::new("mock").with_url(&url).with_retries(0);
+        let texts = ["a", "fail", "ccc"];
+
+        // 严格版本返回第一个错误
+        let result = embedder.embed_batch_concurrent(&texts, 2).await;
+        assert!(matches!(result, Err(EmbeddingError::Status(status)) if status.as_u16() == 500));
+
+        // 逐项版本跳过失败项，其余结果不受影响
+        let results = embedder.embed_each(&texts, 2).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap()[0], 1.0);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap()[0], 3.0);
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_and_is_capped() {
+        assert_eq!(retry_delay(0), Duration::from_millis(200));
+        assert_eq!(retry_delay(1), Duration::from_millis(400));
+        assert_eq!(retry_delay(2), Duration::from_millis(800));
+        assert_eq!(retry_delay(10), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(EmbeddingError::Status(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        assert!(!EmbeddingError::Status(StatusCode::NOT_FOUND).is_retryable());
+        assert!(!EmbeddingError::Api("No embedding returned".into()).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_embed_retries_transient_errors() {
+        let (url, requests) = spawn_mock_ollama().await;
+
+        // 前两次 503，第三次成功
+        let mut embedder = OllamaEmbedding::new("mock").with_url(&url);
+        assert_eq!(embedder.embed("flaky").await.unwrap()[0], 5.0);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // 4xx 不重试
+        let result = embedder.embed("missing").await;
+        assert!(matches!(result, Err(EmbeddingError::Status(status)) if status.as_u16() == 404));
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+
+        // 重试次数用尽后返回最后一次错误
+        let mut embedder = OllamaEmbedding::new("mock").with_url(&url).with_retries(1);
+        assert!(embedder.embed("fail").await.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn test_default_prefixes() {
+        assert_eq!(default_prefixes("bge-m3"), Some(("passage: ", "query: ")));
+        assert_eq!(default_prefixes("library/bge-m3:latest"), Some(("passage: ", "query: ")));
+        assert_eq!(default_prefixes("nomic-embed-text:v1.5"), Some(("search_document: ", "search_query: ")));
+        assert_eq!(default_prefixes("mxbai-embed-large"), None);
+        assert_eq!(EmbedRole::parse("Query"), Some(EmbedRole::Query));
+        assert_eq!(EmbedRole::parse("document"), None);
+    }
+
+    #[tokio::test]
+    async fn test_embed_applies_role_prefix() {
+        // mock 返回输入长度，可据此判断实际发送的前缀
+        let (url, _) = spawn_mock_ollama().await;
+
+        let mut passage = OllamaEmbedding::new("bge-m3").with_url(&url);
+        assert_eq!(passage.input_prefix(), "passage: ");
+        assert_eq!(passage.embed("abc").await.unwrap()[0], 12.0);
+
+        let mut query = OllamaEmbedding::new("bge-m3").with_url(&url).with_role(EmbedRole::Query);
+        assert_eq!(query.embed("abc").await.unwrap()[0], 10.0);
+        let batch = query.embed_batch_concurrent(&["abc", "de"], 2).await.unwrap();
+        assert_eq!(batch[1][0], 9.0);
+
+        let mut plain = OllamaEmbedding::new("bge-m3").with_url(&url).with_prefixes(None, None);
+        assert_eq!(plain.embed("abc").await.unwrap()[0], 3.0);
+    }
 }
@@ -0,0 +1,108 @@
+//! 相似度分布直方图 - 辅助选择阈值
+//!
+//! 若分布呈双峰，两峰之间的低谷通常就是"偶然相似"与"真正重复"的分界
+
+/// 默认统计范围下限
+pub const HISTOGRAM_MIN: f32 = 0.70;
+
+/// 相似度直方图
+#[derive(Debug, Clone)]
+pub struct SimilarityHistogram {
+    min: f32,
+    bucket_width: f32,
+    counts: Vec<usize>,
+    /// 低于下限的数量
+    below: usize,
+}
+
+impl SimilarityHistogram {
+    /// 将 [min, max] 等分为 `buckets` 个区间
+    pub fn new(min: f32, max: f32, buckets: usize) -> Self {
+        let buckets = buckets.max(1);
+        Self {
+            min,
+            bucket_width: (max - min) / buckets as f32,
+            counts: vec![0; buckets],
+            below: 0,
+        }
+    }
+
+    pub fn add(&mut self, similarity: f32) {
+        if similarity.is_nan() || similarity < self.min {
+            self.below += 1;
+            return;
+        }
+        // 上限 (含 1.0 及浮点误差) 归入最后一个区间
+        let idx = ((similarity - self.min) / self.bucket_width) as usize;
+        let last = self.counts.len() - 1;
+        self.counts[idx.min(last)] += 1;
+    }
+
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    pub fn below(&self) -> usize {
+        self.below
+    }
+
+    /// 范围内的总数
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// 渲染为 ASCII 直方图，最长的条宽度为 `width`
+    pub fn render(&self, width: usize) -> String {
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut lines = Vec::with_capacity(self.counts.len());
+
+        // 高相似度在上
+        for (i, &count) in self.counts.iter().enumerate().rev() {
+            let lo = self.min + i as f32 * self.bucket_width;
+            let hi = lo + self.bucket_width;
+            let bar_len = if count > 0 { (count * width).div_ceil(max) } else { 0 };
+            lines.push(format!("{:.2}-{:.2} | {:<width$} {}", lo, hi, "#".repeat(bar_len), count, width = width));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Default for SimilarityHistogram {
+    /// 0.70–1.00，每 0.02 一个区间
+    fn default() -> Self {
+        Self::new(HISTOGRAM_MIN, 1.0, 15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut h = SimilarityHistogram::new(0.7, 1.0, 3);
+        for s in [0.5, 0.71, 0.79, 0.85, 0.95, 1.0, 1.0000001] {
+            h.add(s);
+        }
+
+        assert_eq!(h.counts(), &[2, 1, 3]);
+        assert_eq!(h.below(), 1);
+        assert_eq!(h.total(), 6);
+    }
+
+    #[test]
+    fn test_histogram_render() {
+        let mut h = SimilarityHistogram::new(0.7, 1.0, 3);
+        for s in [0.75, 0.95, 0.96, 0.97, 0.98] {
+            h.add(s);
+        }
+
+        let out = h.render(8);
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("0.90-1.00 | ########"));
+        assert!(lines[0].ends_with(" 4"));
+        assert!(lines[1].ends_with(" 0"));
+        assert!(lines[2].starts_with("0.70-0.80 | ## "));
+    }
+}
@@ -1,25 +1,50 @@
 //! Hook 配置
 
+use super::parser::get_language;
+use crate::store::DEFAULT_CANDIDATES;
+use lsp::CodeUnit;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 /// Hook 配置
 #[derive(Debug, Clone)]
 pub struct HookConfig {
     pub threshold: f32,
-    pub min_lines: u32,
+    /// 最小行数，可按语言覆盖
+    pub min_lines: MinLines,
     pub scope: HookScope,
     pub max_results: usize,
+    /// 一个新函数匹配多个已有函数时的报告方式
+    pub report_mode: ReportMode,
+    /// 长度差异惩罚权重 (0 = 关闭)
+    pub length_penalty: f32,
+    /// ANN 每次查询的候选数 (k)，越大召回越高
+    pub candidates: usize,
+    /// 嵌入前移除注释 (需与索引时的设置一致)
+    pub strip_comments: bool,
+    /// 同时检查类型声明 (需与索引时的 `--include-types` 一致)
+    pub include_types: bool,
     pub notify: NotifyMode,
     pub model: String,
+    /// 数据库路径，None 时使用默认位置 (`get_db_path`)
+    pub db_path: Option<PathBuf>,
 }
 
 impl Default for HookConfig {
     fn default() -> Self {
         Self {
             threshold: 0.85,
-            min_lines: 5,
+            min_lines: MinLines::new(5),
             scope: HookScope::All,
             max_results: 3,
+            report_mode: ReportMode::default(),
+            length_penalty: 0.0,
+            candidates: DEFAULT_CANDIDATES,
+            strip_comments: false,
+            include_types: false,
             notify: NotifyMode::Block,
-            model: "bge-m3".to_string(),
+            model: crate::embedding::DEFAULT_MODEL.to_string(),
+            db_path: None,
         }
     }
 }
@@ -35,8 +60,9 @@ impl HookConfig {
             }
         }
 
+        // 支持 "5" 或 "rust=5,ts=8" 或 "5,ts=8"
         if let Ok(v) = std::env::var("AKIN_MIN_LINES") {
-            if let Ok(m) = v.parse() {
+            if let Ok(m) = MinLines::parse(&v, config.min_lines.default) {
                 config.min_lines = m;
             }
         }
@@ -55,6 +81,32 @@ impl HookConfig {
             }
         }
 
+        if let Ok(v) = std::env::var("AKIN_REPORT_MODE") {
+            if let Some(mode) = ReportMode::parse(&v) {
+                config.report_mode = mode;
+            }
+        }
+
+        if let Ok(v) = std::env::var("AKIN_CANDIDATES") {
+            if let Ok(k) = v.parse() {
+                config.candidates = k;
+            }
+        }
+
+        if let Ok(v) = std::env::var("AKIN_LENGTH_PENALTY") {
+            if let Ok(w) = v.parse() {
+                config.length_penalty = w;
+            }
+        }
+
+        if let Ok(v) = std::env::var("AKIN_STRIP_COMMENTS") {
+            config.strip_comments = matches!(v.as_str(), "1" | "true");
+        }
+
+        if let Ok(v) = std::env::var("AKIN_INCLUDE_TYPES") {
+            config.include_types = matches!(v.as_str(), "1" | "true");
+        }
+
         if let Ok(v) = std::env::var("AKIN_NOTIFY") {
             config.notify = match v.as_str() {
                 "user" => NotifyMode::User,
@@ -62,12 +114,88 @@ impl HookConfig {
             };
         }
 
+        // AKIN_MODEL > IRIS_EMBED_MODEL > 内置默认值
+        config.model = crate::embedding::default_model();
         if let Ok(v) = std::env::var("AKIN_MODEL") {
             config.model = v;
         }
 
         config
     }
+
+    /// 获取指定语言的最小行数，未知语言使用全局默认值
+    pub fn min_lines_for(&self, language: Option<&str>) -> u32 {
+        language.map_or(self.min_lines.default, |l| self.min_lines.for_language(l))
+    }
+}
+
+/// 最小行数配置：全局默认值 + 按语言覆盖
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinLines {
+    pub default: u32,
+    pub by_language: HashMap<String, u32>,
+}
+
+impl MinLines {
+    pub fn new(default: u32) -> Self {
+        Self { default, by_language: HashMap::new() }
+    }
+
+    /// 解析 "5"、"rust=5,ts=8" 或 "5,ts=8"，未给出全局值时使用 `default`
+    pub fn parse(spec: &str, default: u32) -> Result<Self, String> {
+        let mut result = Self::new(default);
+
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((lang, value)) => {
+                    let lang = lang.trim();
+                    if lang.is_empty() {
+                        return Err(format!("missing language in '{}'", part));
+                    }
+                    let value = value.trim().parse()
+                        .map_err(|_| format!("invalid line count in '{}'", part))?;
+                    result.by_language.insert(normalize_language(lang).to_string(), value);
+                }
+                None => {
+                    result.default = part.parse()
+                        .map_err(|_| format!("invalid line count '{}'", part))?;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// 获取指定语言的最小行数
+    pub fn for_language(&self, language: &str) -> u32 {
+        self.by_language
+            .get(normalize_language(language))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// 按文件语言过滤代码单元，无法识别扩展名时使用 `fallback_language`
+    pub fn filter_units(&self, units: Vec<CodeUnit>, fallback_language: &str) -> Vec<CodeUnit> {
+        units
+            .into_iter()
+            .filter(|u| {
+                let lang = get_language(&u.file_path).unwrap_or(fallback_language);
+                (u.range_end - u.range_start) >= self.for_language(lang)
+            })
+            .collect()
+    }
+}
+
+/// 统一语言别名 (ts -> typescript 等)
+fn normalize_language(lang: &str) -> &str {
+    match lang {
+        "rs" => "rust",
+        "ts" | "tsx" => "typescript",
+        "js" | "jsx" => "javascript",
+        "py" => "python",
+        "kt" | "kts" => "kotlin",
+        other => other,
+    }
 }
 
 /// 检查范围
@@ -81,6 +209,26 @@ pub enum HookScope {
     CrossOnly,
 }
 
+/// 多匹配报告方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportMode {
+    /// 每个新函数只报告相似度最高的匹配
+    #[default]
+    Best,
+    /// 报告所有匹配 (每个新函数最多 `max_results` 个)
+    All,
+}
+
+impl ReportMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "best" => Some(Self::Best),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+}
+
 /// 通知模式
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NotifyMode {
@@ -98,7 +246,78 @@ mod tests {
     fn test_hook_config_default() {
         let config = HookConfig::default();
         assert_eq!(config.threshold, 0.85);
-        assert_eq!(config.min_lines, 5);
+        assert_eq!(config.min_lines, MinLines::new(5));
         assert_eq!(config.max_results, 3);
+        assert_eq!(config.report_mode, ReportMode::Best);
+    }
+
+    #[test]
+    fn test_report_mode_parse() {
+        assert_eq!(ReportMode::parse("best"), Some(ReportMode::Best));
+        assert_eq!(ReportMode::parse("all"), Some(ReportMode::All));
+        assert_eq!(ReportMode::parse("top"), None);
+    }
+
+    #[test]
+    fn test_min_lines_parse() {
+        let m = MinLines::parse("rust=5,ts=8", 3).unwrap();
+        assert_eq!(m.default, 3);
+        assert_eq!(m.for_language("rust"), 5);
+        assert_eq!(m.for_language("typescript"), 8);
+        assert_eq!(m.for_language("ts"), 8);
+        assert_eq!(m.for_language("swift"), 3);
+
+        let m = MinLines::parse("4, swift = 6", 3).unwrap();
+        assert_eq!(m.default, 4);
+        assert_eq!(m.for_language("swift"), 6);
+
+        assert_eq!(MinLines::parse("7", 3).unwrap(), MinLines::new(7));
+        assert!(MinLines::parse("rust=abc", 3).is_err());
+        assert!(MinLines::parse("=5", 3).is_err());
+        assert!(MinLines::parse("many", 3).is_err());
+    }
+
+    fn make_unit(file_path: &str, lines: u32) -> CodeUnit {
+        CodeUnit {
+            qualified_name: format!("{}::f{}", file_path, lines),
+            file_path: file_path.to_string(),
+            kind: "function".to_string(),
+            range_start: 10,
+            range_end: 10 + lines,
+            body: String::new(),
+            selection_line: 10,
+            selection_column: 0,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_min_lines_filter_units() {
+        let m = MinLines::parse("rust=5,ts=8", 3).unwrap();
+        let units = vec![
+            make_unit("a.rs", 4),
+            make_unit("b.rs", 5),
+            make_unit("c.ts", 6),
+            make_unit("d.tsx", 8),
+            make_unit("e.swift", 3),
+            make_unit("F.java", 2),
+        ];
+
+        let kept: Vec<_> = m.filter_units(units, "java")
+            .into_iter()
+            .map(|u| u.file_path)
+            .collect();
+        assert_eq!(kept, vec!["b.rs", "d.tsx", "e.swift"]);
+    }
+
+    #[test]
+    fn test_hook_config_min_lines_for() {
+        let mut config = HookConfig::default();
+        config.min_lines.by_language.insert("typescript".to_string(), 8);
+
+        assert_eq!(config.min_lines_for(Some("typescript")), 8);
+        assert_eq!(config.min_lines_for(Some("ts")), 8);
+        assert_eq!(config.min_lines_for(Some("rust")), 5);
+        assert_eq!(config.min_lines_for(None), 5);
     }
 }
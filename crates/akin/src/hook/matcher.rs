@@ -1,22 +1,23 @@
 //! 相似度匹配器
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use lsp::CodeUnit;
+use lsp::{CodeUnit, IgnoreSet, QualifiedName};
 
 use crate::db::{Database, CodeUnitRecord, PairStatus};
-use crate::embedding::{OllamaEmbedding, cosine_similarity, bytes_to_embedding};
+use crate::embedding::{Embedder, apply_length_penalty, bytes_to_embedding, cosine_similarity, line_count};
 use crate::store::Store;
-use super::config::{HookConfig, HookScope};
+use super::config::{HookConfig, HookScope, ReportMode};
 use super::types::{Result, SimilarityMatch};
 
-/// 查找相似代码
+/// 查找相似代码，`ignore` 匹配的新单元和已索引单元都会跳过
 pub async fn find_similar_units(
     db: &Database,
-    embedder: &mut OllamaEmbedding,
+    embedder: &mut dyn Embedder,
     units: &[CodeUnit],
     current_project_path: Option<&str>,
     config: &HookConfig,
+    ignore: &IgnoreSet,
 ) -> Result<Vec<SimilarityMatch>> {
     let mut results = Vec::new();
 
@@ -35,7 +36,7 @@ pub async fn find_similar_units(
 
     // 加载已忽略的配对
     let ignored_pairs: HashSet<(String, String)> = db
-        .get_similar_pairs(None, Some(PairStatus::Ignored), 0.0)?
+        .get_similar_pairs(None, Some(PairStatus::Ignored), 0.0, false)?
         .into_iter()
         .flat_map(|p| {
             vec![
@@ -48,6 +49,7 @@ pub async fn find_similar_units(
     // 加载 embeddings
     let db_embeddings: Vec<(CodeUnitRecord, ndarray::Array1<f32>)> = db_units
         .into_iter()
+        .filter(|unit| !ignore.is_ignored(&unit.file_path, &unit.qualified_name))
         .filter_map(|unit| {
             unit.embedding.as_ref()
                 .and_then(|e| bytes_to_embedding(e))
@@ -60,7 +62,7 @@ pub async fn find_similar_units(
     }
 
     // 对每个新 unit 生成 embedding 并比较
-    for unit in units {
+    for unit in units.iter().filter(|u| !ignore.is_ignored(&u.file_path, &u.qualified_name)) {
         let new_embedding = match embedder.embed(&unit.body).await {
             Ok(e) => e,
             Err(_) => continue,
@@ -88,7 +90,12 @@ pub async fn find_similar_units(
                 }
             }
 
-            let sim = cosine_similarity(&new_embedding, db_emb);
+            let sim = apply_length_penalty(
+                cosine_similarity(&new_embedding, db_emb),
+                line_count(unit.range_start, unit.range_end),
+                line_count(db_unit.range_start, db_unit.range_end),
+                config.length_penalty,
+            );
             if sim >= config.threshold {
                 let is_cross = current_project_id
                     .map(|pid| db_unit.project_id != pid)
@@ -112,16 +119,17 @@ pub async fn find_similar_units(
         results.extend(similarities.into_iter().take(config.max_results));
     }
 
-    Ok(results)
+    Ok(collapse_matches(results, config.report_mode))
 }
 
 /// 使用 ANN 索引查找相似代码（O(log n) 复杂度）
 pub async fn find_similar_units_ann(
     store: &Store,
-    embedder: &mut OllamaEmbedding,
+    embedder: &mut dyn Embedder,
     units: &[CodeUnit],
     current_project_path: Option<&str>,
     config: &HookConfig,
+    ignore: &IgnoreSet,
 ) -> Result<Vec<SimilarityMatch>> {
     let mut results = Vec::new();
     let db = store.db();
@@ -133,7 +141,7 @@ pub async fn find_similar_units_ann(
 
     // 加载已忽略的配对
     let ignored_pairs: HashSet<(String, String)> = db
-        .get_similar_pairs(None, Some(PairStatus::Ignored), 0.0)?
+        .get_similar_pairs(None, Some(PairStatus::Ignored), 0.0, false)?
         .into_iter()
         .flat_map(|p| {
             vec![
@@ -144,14 +152,14 @@ pub async fn find_similar_units_ann(
         .collect();
 
     // 对每个新 unit 生成 embedding 并使用 ANN 搜索
-    for unit in units {
+    for unit in units.iter().filter(|u| !ignore.is_ignored(&u.file_path, &u.qualified_name)) {
         let new_embedding = match embedder.embed(&unit.body).await {
             Ok(e) => e,
             Err(_) => continue,
         };
 
-        // 使用 ANN 搜索，多取一些结果用于后续过滤
-        let k = (config.max_results * 3).max(50); // 多取一些，因为要过滤
+        // 多取一些候选，因为后续还要按范围过滤
+        let k = config.candidates.max(config.max_results);
 
         // 构建过滤器
         let search_results = store.search_similar_filtered(
@@ -177,8 +185,13 @@ pub async fn find_similar_units_ann(
         };
 
         let mut similarities: Vec<SimilarityMatch> = Vec::new();
+        let unit_lines = line_count(unit.range_start, unit.range_end);
 
         for su in similar_units {
+            if ignore.is_ignored(&su.file_path, &su.qualified_name) {
+                continue;
+            }
+
             // cross_only 模式：跳过同项目
             if config.scope == HookScope::CrossOnly {
                 if let Some(pid) = current_project_id {
@@ -197,6 +210,17 @@ pub async fn find_similar_units_ann(
                 }
             }
 
+            // 长度惩罚后可能低于阈值
+            let sim = apply_length_penalty(
+                su.similarity,
+                unit_lines,
+                line_count(su.range_start, su.range_end),
+                config.length_penalty,
+            );
+            if sim < config.threshold {
+                continue;
+            }
+
             let is_cross = current_project_id
                 .map(|pid| su.project_id != pid)
                 .unwrap_or(true);
@@ -208,36 +232,65 @@ pub async fn find_similar_units_ann(
                 similar_name: su.qualified_name,
                 similar_file: su.file_path,
                 similar_line: su.range_start,
-                similarity: su.similarity,
+                similarity: sim,
                 is_cross_project: is_cross,
             });
-
-            if similarities.len() >= config.max_results {
-                break;
-            }
         }
 
-        results.extend(similarities);
+        // 惩罚会改变排序，重新排序后取 top N
+        similarities.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+        results.extend(similarities.into_iter().take(config.max_results));
+    }
+
+    Ok(collapse_matches(results, config.report_mode))
+}
+
+/// 按 `current_name` 合并匹配，`Best` 时每个新函数只保留相似度最高的一个 (保持首次出现顺序)
+fn collapse_matches(results: Vec<SimilarityMatch>, mode: ReportMode) -> Vec<SimilarityMatch> {
+    if mode == ReportMode::All {
+        return results;
     }
 
-    Ok(results)
+    let mut best: Vec<SimilarityMatch> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for m in results {
+        match index.get(&m.current_name) {
+            Some(&i) => {
+                if m.similarity > best[i].similarity {
+                    best[i] = m;
+                }
+            }
+            None => {
+                index.insert(m.current_name.clone(), best.len());
+                best.push(m);
+            }
+        }
+    }
+    best
 }
 
 /// 格式化结果输出
-pub fn format_result(results: &[SimilarityMatch]) -> String {
+///
+/// `total_units` 为本次检查的单元数，用于首行汇总 (有匹配的单元数 / 总数)
+pub fn format_result(results: &[SimilarityMatch], total_units: usize) -> String {
     if results.is_empty() {
         return String::new();
     }
 
-    let mut lines = vec!["⚠️ 检测到相似代码:".to_string()];
+    let matched = results.iter().map(|r| r.current_name.as_str()).collect::<HashSet<_>>().len();
+    let mut lines = vec![format!(
+        "⚠️ 检测到相似代码: {} 个函数中有 {} 个与已有代码相似",
+        total_units.max(matched),
+        matched,
+    )];
 
     for r in results {
         let sim_pct = (r.similarity * 100.0) as i32;
         let cross_mark = if r.is_cross_project { " [跨项目]" } else { "" };
 
         // 提取简短的名称
-        let current_short = r.current_name.split("::").last().unwrap_or(&r.current_name);
-        let similar_short = r.similar_name.split("::").last().unwrap_or(&r.similar_name);
+        let current_short = QualifiedName::parse(&r.current_name).short();
+        let similar_short = QualifiedName::parse(&r.similar_name).short();
 
         // 提取文件名
         let current_file = Path::new(&r.current_file)
@@ -266,10 +319,12 @@ pub fn format_result(results: &[SimilarityMatch]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::embedding_to_bytes;
+    use crate::mock::MockEmbedding;
 
     #[test]
     fn test_format_result_empty() {
-        let result = format_result(&[]);
+        let result = format_result(&[], 3);
         assert!(result.is_empty());
     }
 
@@ -285,7 +340,7 @@ mod tests {
             similarity: 0.95,
             is_cross_project: false,
         }];
-        let result = format_result(&matches);
+        let result = format_result(&matches, 1);
         assert!(result.contains("检测到相似代码"));
         assert!(result.contains("95%"));
         assert!(result.contains("foo"));
@@ -304,10 +359,53 @@ mod tests {
             similarity: 0.90,
             is_cross_project: true,
         }];
-        let result = format_result(&matches);
+        let result = format_result(&matches, 1);
         assert!(result.contains("[跨项目]"));
     }
 
+    #[test]
+    fn test_format_result_summary_line() {
+        let make = |current: &str, similar: &str| SimilarityMatch {
+            current_name: format!("rust:/p/new.rs::{}", current),
+            current_file: "/p/new.rs".to_string(),
+            current_line: 1,
+            similar_name: format!("rust:/p/old.rs::{}", similar),
+            similar_file: "/p/old.rs".to_string(),
+            similar_line: 1,
+            similarity: 0.9,
+            is_cross_project: false,
+        };
+        // foo 有两个匹配，只计一次
+        let matches = vec![make("foo", "a"), make("foo", "b"), make("bar", "c")];
+        let result = format_result(&matches, 5);
+
+        let mut lines = result.lines();
+        assert_eq!(lines.next(), Some("⚠️ 检测到相似代码: 5 个函数中有 2 个与已有代码相似"));
+        // 详细列表在汇总之后
+        assert!(lines.any(|l| l.contains("new.rs:1 foo()")));
+    }
+
+    #[test]
+    fn test_collapse_matches_keeps_best() {
+        let make = |current: &str, similar: &str, similarity: f32| SimilarityMatch {
+            current_name: current.to_string(),
+            current_file: "new.rs".to_string(),
+            current_line: 1,
+            similar_name: similar.to_string(),
+            similar_file: "old.rs".to_string(),
+            similar_line: 1,
+            similarity,
+            is_cross_project: false,
+        };
+        let matches = vec![make("foo", "a", 0.88), make("bar", "c", 0.9), make("foo", "b", 0.95)];
+
+        let best = collapse_matches(matches.clone(), ReportMode::Best);
+        let pairs: Vec<_> = best.iter().map(|m| (m.current_name.as_str(), m.similar_name.as_str())).collect();
+        assert_eq!(pairs, vec![("foo", "b"), ("bar", "c")]);
+
+        assert_eq!(collapse_matches(matches, ReportMode::All).len(), 3);
+    }
+
     #[test]
     fn test_similarity_match_struct() {
         let m = SimilarityMatch {
@@ -332,7 +430,7 @@ mod tests {
 
         assert!(0.90 >= threshold); // 应该通过
         assert!(0.85 >= threshold); // 边界值应该通过
-        assert!(!(0.84 >= threshold)); // 应该被过滤
+        assert!(0.84 < threshold); // 应该被过滤
     }
 
     // 测试 ignored_pairs HashSet 逻辑
@@ -372,11 +470,130 @@ mod tests {
         assert!(limited[1].similarity > limited[2].similarity);
     }
 
-    // 集成测试 - 需要 Ollama 服务
-    #[test]
-    #[ignore = "需要 Ollama 服务运行"]
-    fn test_find_similar_units_integration() {
-        // 此测试需要 Ollama 服务
-        // 运行: cargo test test_find_similar_units_integration -- --ignored
+    fn new_unit(name: &str, file: &str, body: &str) -> CodeUnit {
+        CodeUnit {
+            qualified_name: name.to_string(),
+            file_path: file.to_string(),
+            kind: "function".to_string(),
+            range_start: 0,
+            range_end: 4,
+            body: body.to_string(),
+            selection_line: 0,
+            selection_column: 0,
+            signature: None,
+        }
+    }
+
+    /// 用确定性嵌入写入数据库，返回项目 ID
+    fn seed_db(db: &Database, embedder: &MockEmbedding, units: &[CodeUnit]) -> i64 {
+        let project_id = db.get_or_create_project("p", "/p", "rust").unwrap();
+        for unit in units {
+            db.upsert_code_unit(&CodeUnitRecord {
+                qualified_name: unit.qualified_name.clone(),
+                project_id,
+                file_path: unit.file_path.clone(),
+                kind: unit.kind.clone(),
+                range_start: unit.range_start,
+                range_end: unit.range_end,
+                content_hash: unit.qualified_name.clone(),
+                structure_hash: String::new(),
+                embedding: Some(embedding_to_bytes(&embedder.embed_sync(&unit.body))),
+                group_id: None,
+                signature: None,
+            }).unwrap();
+        }
+        project_id
+    }
+
+    const SUM_BODY: &str = "fn sum(items: &[i32]) -> i32 { let mut total = 0; for item in items { total += item; } total }";
+    const LOAD_BODY: &str = "fn load(path: &str) -> String { std::fs::read_to_string(path).unwrap() }";
+
+    #[tokio::test]
+    async fn test_find_similar_units_with_mock_embedder() {
+        let db = Database::open_in_memory().unwrap();
+        let mut embedder = MockEmbedding::default();
+        seed_db(&db, &embedder, &[
+            new_unit("rust:a.rs::sum", "a.rs", SUM_BODY),
+            new_unit("rust:b.rs::load", "b.rs", LOAD_BODY),
+        ]);
+
+        let new_units = vec![
+            new_unit("rust:c.rs::total", "c.rs", &SUM_BODY.replace("fn sum", "fn total")),
+            // 与库中同名的单元不和自己比较
+            new_unit("rust:b.rs::load", "b.rs", LOAD_BODY),
+        ];
+        let results = find_similar_units(&db, &mut embedder, &new_units, None, &HookConfig::default(), &IgnoreSet::default())
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].current_name, "rust:c.rs::total");
+        assert_eq!(results[0].similar_name, "rust:a.rs::sum");
+        assert!(results[0].similarity > 0.9);
+        assert!(results[0].is_cross_project);
+    }
+
+    #[tokio::test]
+    async fn test_find_similar_units_report_mode() {
+        let db = Database::open_in_memory().unwrap();
+        let mut embedder = MockEmbedding::default();
+        seed_db(&db, &embedder, &[
+            new_unit("rust:a.rs::sum", "a.rs", SUM_BODY),
+            new_unit("rust:b.rs::sum", "b.rs", SUM_BODY),
+        ]);
+        let new_units = vec![new_unit("rust:c.rs::total", "c.rs", SUM_BODY)];
+
+        let results = find_similar_units(&db, &mut embedder, &new_units, None, &HookConfig::default(), &IgnoreSet::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let config = HookConfig { report_mode: ReportMode::All, ..HookConfig::default() };
+        let results = find_similar_units(&db, &mut embedder, &new_units, None, &config, &IgnoreSet::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_similar_units_skips_ignored_pairs() {
+        let db = Database::open_in_memory().unwrap();
+        let mut embedder = MockEmbedding::default();
+        seed_db(&db, &embedder, &[
+            new_unit("rust:a.rs::sum", "a.rs", SUM_BODY),
+            new_unit("rust:c.rs::total", "c.rs", SUM_BODY),
+        ]);
+        db.upsert_similar_pair("rust:a.rs::sum", "rust:c.rs::total", 0.99, None).unwrap();
+        let pair_id = db.get_similar_pairs(None, None, 0.0, false).unwrap()[0].id;
+        db.update_pair_status(pair_id, PairStatus::Ignored).unwrap();
+
+        let new_units = vec![new_unit("rust:c.rs::total", "c.rs", SUM_BODY)];
+        let results = find_similar_units(&db, &mut embedder, &new_units, Some("/p"), &HookConfig::default(), &IgnoreSet::default())
+            .await
+            .unwrap();
+
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_similar_units_skips_irisignore_matches() {
+        let db = Database::open_in_memory().unwrap();
+        let mut embedder = MockEmbedding::default();
+        seed_db(&db, &embedder, &[
+            new_unit("rust:a.rs::sum", "a.rs", SUM_BODY),
+            new_unit("rust:gen/b.rs::sum", "gen/b.rs", SUM_BODY),
+        ]);
+
+        let new_units = vec![
+            new_unit("rust:c.rs::total", "c.rs", SUM_BODY),
+            new_unit("rust:d.rs::legacy_sum", "d.rs", SUM_BODY),
+        ];
+        let ignore = IgnoreSet::parse(None, "gen/*\n*::legacy_*\n");
+        let results = find_similar_units(&db, &mut embedder, &new_units, None, &HookConfig::default(), &ignore)
+            .await
+            .unwrap();
+
+        let pairs: Vec<_> = results.iter().map(|r| (r.current_name.as_str(), r.similar_name.as_str())).collect();
+        assert_eq!(pairs, vec![("rust:c.rs::total", "rust:a.rs::sum")]);
     }
 }
@@ -1,4 +1,4 @@
-//! Claude Code PostToolUse hook - 实时代码相似度检查
+//! Claude Code hook - 按事件分发，PostToolUse 时实时检查代码相似度
 
 mod config;
 mod types;
@@ -11,8 +11,12 @@ pub use parser::*;
 pub use matcher::{find_similar_units, find_similar_units_ann, format_result};
 
 use crate::db::Database;
-use crate::embedding::OllamaEmbedding;
+use crate::embcache::{CachedEmbedder, EmbeddingCache};
+use crate::embedding::{EmbedRole, OllamaEmbedding};
 use crate::store::Store;
+use lsp::{CodeUnit, IgnoreSet};
+use std::future::Future;
+use std::pin::Pin;
 use std::process::Command;
 
 /// 检查并自动索引新项目
@@ -69,32 +73,61 @@ pub async fn handle_post_tool_use(input: &HookInput, config: &HookConfig) -> Res
     }
 
     // 提取代码单元
-    let mut parser = CodeParser::new();
-    let units = parser.extract_functions(content, file_path, config.min_lines);
+    let mut parser = CodeParser::new().with_include_types(config.include_types);
+    let min_lines = config.min_lines_for(get_language(file_path));
+    let mut units = parser.extract_functions(content, file_path, min_lines);
+    if config.strip_comments {
+        for unit in &mut units {
+            unit.body = CodeUnit::strip_comments(&unit.body);
+        }
+    }
+    // 空函数体的 embedding 会与任意空函数相似，不参与比较
+    let empty = CodeUnit::drop_empty_bodies(&mut units);
+    if empty > 0 {
+        tracing::info!("Skipped {} units with empty bodies in {}", empty, file_path);
+    }
     if units.is_empty() {
         return Ok(HookResult::empty());
     }
 
     // 确保数据库目录存在并打开 Store
-    let db_path = get_db_path();
+    let db_path = config.db_path.clone().unwrap_or_else(get_db_path);
     if let Some(parent) = db_path.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
 
     // 打开 Store（包含数据库和向量索引）
-    let store = match Store::open(&db_path) {
+    let mut store = match Store::open(&db_path) {
         Ok(s) => s,
         Err(_) => return Ok(HookResult::empty()),
     };
 
+    // 准备向量索引：没有索引文件时在后台构建，构建完成前回退到暴力搜索
+    if let Err(e) = store.ensure_vector_index_background() {
+        tracing::warn!("Vector index unavailable: {}", e);
+    }
+
     // 检查并自动索引新项目
     ensure_project_indexed(store.db(), input.cwd.as_deref());
 
-    // 初始化 embedder
-    let mut embedder = OllamaEmbedding::new(&config.model);
+    // 初始化 embedder (hook 会阻塞编辑，Ollama 出错时不重试；新代码作为检索文本与已索引的 passage 比较)
+    // 用项目记录的维度查磁盘缓存，未改动的函数无需再请求 Ollama
+    let dimensions = input.cwd.as_deref()
+        .and_then(|cwd| store.db().get_project_by_path(cwd).ok().flatten())
+        .filter(|p| p.embedding_model.as_deref() == Some(config.model.as_str()))
+        .and_then(|p| p.embedding_dimensions);
+    let mut embedder = CachedEmbedder::new(
+        Box::new(OllamaEmbedding::new(&config.model).with_retries(0).with_role(EmbedRole::Query)),
+        EmbeddingCache::default(),
+    ).with_dimensions(dimensions);
+
+    // 从当前目录向上查找 .irisignore
+    let ignore = input.cwd.as_deref()
+        .map(|cwd| IgnoreSet::discover(std::path::Path::new(cwd)))
+        .unwrap_or_default();
 
-    // 根据向量索引状态选择搜索方式
-    let results = if store.vector_index_stats().is_some() {
+    // 根据向量索引状态选择搜索方式 (后台构建在搜索前完成时直接换入)
+    let results = if store.poll_vector_index().unwrap_or(false) {
         // 使用 ANN 搜索（O(log n)）
         find_similar_units_ann(
             &store,
@@ -102,7 +135,8 @@ pub async fn handle_post_tool_use(input: &HookInput, config: &HookConfig) -> Res
             &units,
             input.cwd.as_deref(),
             config,
-        ).await?
+            &ignore,
+        ).await
     } else {
         // 回退到暴力搜索（O(n)）
         find_similar_units(
@@ -111,15 +145,24 @@ pub async fn handle_post_tool_use(input: &HookInput, config: &HookConfig) -> Res
             &units,
             input.cwd.as_deref(),
             config,
-        ).await?
+            &ignore,
+        ).await
     };
 
+    // 不等待后台构建 (hook 会阻塞编辑)：构建线程随 Store 释放而分离，
+    // 在进程退出前完成时索引文件已保存，下次调用直接加载；否则下次调用重新构建
+    drop(store);
+
+    let results = results?;
     if results.is_empty() {
         return Ok(HookResult::empty());
     }
 
-    // 格式化输出
-    let message = format_result(&results);
+    // 格式化输出，汇总行只计入实际检查的单元 (不含 .irisignore 排除的)
+    let checked = units.iter()
+        .filter(|u| !ignore.is_ignored(&u.file_path, &u.qualified_name))
+        .count();
+    let message = format_result(&results, checked);
 
     match config.notify {
         NotifyMode::Block => Ok(HookResult::block(message)),
@@ -127,37 +170,151 @@ pub async fn handle_post_tool_use(input: &HookInput, config: &HookConfig) -> Res
     }
 }
 
-/// Hook 主入口
-pub async fn run_hook() -> Result<()> {
-    use std::io::Read;
+/// 事件处理函数返回的 future
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<HookResult>> + 'a>>;
 
-    // 读取 stdin
-    let mut stdin_data = String::new();
-    std::io::stdin().read_to_string(&mut stdin_data)?;
+/// 事件处理函数
+pub type EventHandler = for<'a> fn(&'a HookInput, &'a HookConfig) -> HandlerFuture<'a>;
+
+/// 事件分发表：`hook_event_name` -> 处理函数，新事件只需在此注册
+const EVENT_HANDLERS: &[(&str, EventHandler)] = &[
+    ("PostToolUse", post_tool_use_handler),
+];
+
+fn post_tool_use_handler<'a>(input: &'a HookInput, config: &'a HookConfig) -> HandlerFuture<'a> {
+    Box::pin(handle_post_tool_use(input, config))
+}
 
-    // 解析输入
-    let input: HookInput = if stdin_data.is_empty() {
-        HookInput {
+/// 未注册事件的默认处理：返回空结果
+fn noop_handler<'a>(_input: &'a HookInput, _config: &'a HookConfig) -> HandlerFuture<'a> {
+    Box::pin(async { Ok(HookResult::empty()) })
+}
+
+/// 查找事件处理函数，未注册或缺少事件名时返回 no-op
+pub fn handler_for(event: Option<&str>) -> EventHandler {
+    event
+        .and_then(|name| EVENT_HANDLERS.iter().find(|(n, _)| *n == name))
+        .map_or(noop_handler, |(_, handler)| *handler)
+}
+
+/// 处理一个 hook 事件 (纯逻辑核心，不涉及 stdin/stdout 与环境变量)
+///
+/// 供嵌入其他工具使用：调用方自行解析输入、构造配置并输出结果。
+/// 按 `EVENT_HANDLERS` 分发，未注册的事件返回空结果
+pub async fn handle_event(input: HookInput, config: &HookConfig) -> Result<HookResult> {
+    let handler = handler_for(input.hook_event_name.as_deref());
+    handler(&input, config).await
+}
+
+/// 解析 stdin 输入，失败时返回 None（不应打断用户工作流）
+fn parse_input(stdin_data: &str) -> Option<HookInput> {
+    if stdin_data.trim().is_empty() {
+        return Some(HookInput {
             hook_event_name: None,
             tool_name: None,
             tool_input: None,
             cwd: None,
+        });
+    }
+
+    match serde_json::from_str(stdin_data) {
+        Ok(input) => Some(input),
+        Err(e) => {
+            tracing::warn!("Failed to parse hook input: {}", e);
+            None
         }
-    } else {
-        serde_json::from_str(&stdin_data)?
+    }
+}
+
+/// 处理原始 stdin 数据，返回要输出的结果
+async fn process_input(stdin_data: &str, config: &HookConfig) -> Result<HookResult> {
+    let input = match parse_input(stdin_data) {
+        Some(i) => i,
+        None => return Ok(HookResult::empty()),
     };
 
+    handle_event(input, config).await
+}
+
+/// Hook 主入口：从 stdin 读取事件、按环境变量加载配置，结果写到 stdout
+pub async fn run_hook() -> Result<()> {
+    use std::io::Read;
+
+    // 读取 stdin
+    let mut stdin_data = String::new();
+    std::io::stdin().read_to_string(&mut stdin_data)?;
+
     // 加载配置
     let config = HookConfig::from_env();
 
     // 处理事件
-    let result = match input.hook_event_name.as_deref() {
-        Some("PostToolUse") => handle_post_tool_use(&input, &config).await?,
-        _ => HookResult::empty(),
-    };
+    let result = process_input(&stdin_data, &config).await?;
 
     // 输出结果
     println!("{}", serde_json::to_string(&result)?);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_garbage_input_yields_empty_result() {
+        let config = HookConfig::default();
+        for garbage in ["not json at all", "{\"hook_event_name\": ", "[1, 2, 3]", "\u{0}\u{1}"] {
+            let result = process_input(garbage, &config).await.unwrap();
+            assert_eq!(serde_json::to_string(&result).unwrap(), "{}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_empty_input_yields_empty_result() {
+        let config = HookConfig::default();
+        let result = process_input("  \n", &config).await.unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_handle_event_without_content_yields_empty_result() {
+        let config = HookConfig::default();
+        let input = HookInput {
+            hook_event_name: Some("PostToolUse".to_string()),
+            tool_name: Some("Write".to_string()),
+            tool_input: None,
+            cwd: None,
+        };
+        let result = handle_event(input, &config).await.unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_event_uses_noop_handler() {
+        let config = HookConfig::default();
+        let input = HookInput {
+            hook_event_name: Some("SessionStart".to_string()),
+            tool_name: None,
+            tool_input: None,
+            cwd: None,
+        };
+        let result = handler_for(Some("SessionStart"))(&input, &config).await.unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), "{}");
+        let result = handle_event(input, &config).await.unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_event_handlers_registered_once() {
+        let names: std::collections::HashSet<_> = EVENT_HANDLERS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names.len(), EVENT_HANDLERS.len());
+        assert!(names.contains("PostToolUse"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_event_yields_empty_result() {
+        let config = HookConfig::default();
+        let result = process_input(r#"{"hook_event_name": "PreToolUse"}"#, &config).await.unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), "{}");
+    }
+}
@@ -1,7 +1,8 @@
 //! 代码解析器 - 使用 tree-sitter 提取代码单元
 
-use lsp::CodeUnit;
+use lsp::{CodeUnit, TYPE_KIND};
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use tree_sitter::Parser;
 
@@ -17,13 +18,59 @@ const CODE_EXTENSIONS: &[(&str, &str)] = &[
     (".go", "go"),
 ];
 
-/// 获取数据库路径
+/// 作为类型声明提取的 Rust 节点 (trait 对应其他语言的 interface)
+const RUST_TYPE_NODES: &[&str] = &["struct_item", "enum_item", "trait_item"];
+
+/// 作为类型声明提取的 Swift 节点 (class_declaration 同时覆盖 struct/enum/actor)
+const SWIFT_TYPE_NODES: &[&str] = &["class_declaration", "struct_declaration", "enum_declaration", "protocol_declaration"];
+
+/// 作为类型声明提取的 Python 节点
+const PYTHON_TYPE_NODES: &[&str] = &["class_definition"];
+
+/// 作为类型声明提取的 TypeScript/JavaScript 节点
+const TS_TYPE_NODES: &[&str] = &["class_declaration", "abstract_class_declaration", "interface_declaration", "enum_declaration"];
+
+/// 可以绑定到变量或类字段的函数表达式 (`function` 为旧版 grammar 的节点名)
+const TS_FUNCTION_VALUES: &[&str] = &["arrow_function", "function_expression", "function", "generator_function"];
+
+/// 语法树遍历过程中不变的参数
+struct VisitContext<'a> {
+    content: &'a str,
+    lines: &'a [&'a str],
+    file_path: &'a str,
+    min_lines: u32,
+}
+
+/// 每行去掉至多 `indent` 个前导空格/制表符后拼接
+fn dedent(lines: &[&str], indent: usize) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let strip = line.len() - line.trim_start_matches([' ', '\t']).len();
+            &line[strip.min(indent)..]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 覆盖数据库路径的环境变量
+pub const DB_PATH_ENV: &str = "AKIN_DB_PATH";
+
+/// 获取数据库路径: `AKIN_DB_PATH` > `~/.vimo/akin/akin.db`
 pub fn get_db_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".vimo")
-        .join("akin")
-        .join("akin.db")
+    resolve_db_path(std::env::var_os(DB_PATH_ENV))
+}
+
+/// 空值视为未设置
+fn resolve_db_path(override_path: Option<OsString>) -> PathBuf {
+    match override_path {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".vimo")
+            .join("akin")
+            .join("akin.db"),
+    }
 }
 
 /// 检查是否是代码文件
@@ -54,6 +101,12 @@ pub fn get_language(file_path: &str) -> Option<&'static str> {
 pub struct CodeParser {
     rust_parser: Option<Parser>,
     swift_parser: Option<Parser>,
+    python_parser: Option<Parser>,
+    typescript_parser: Option<Parser>,
+    /// `.tsx` / `.jsx` / `.js` 使用 tsx grammar (支持 JSX)
+    tsx_parser: Option<Parser>,
+    /// 是否同时提取类型声明 (kind = "type")
+    include_types: bool,
 }
 
 impl CodeParser {
@@ -61,9 +114,18 @@ impl CodeParser {
         Self {
             rust_parser: Self::create_rust_parser(),
             swift_parser: Self::create_swift_parser(),
+            python_parser: Self::create_python_parser(),
+            typescript_parser: Self::create_parser(tree_sitter_typescript::language_typescript()),
+            tsx_parser: Self::create_parser(tree_sitter_typescript::language_tsx()),
+            include_types: false,
         }
     }
 
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
     fn create_rust_parser() -> Option<Parser> {
         let mut parser = Parser::new();
         let language = tree_sitter_rust::language();
@@ -78,6 +140,19 @@ impl CodeParser {
         Some(parser)
     }
 
+    fn create_python_parser() -> Option<Parser> {
+        let mut parser = Parser::new();
+        let language = tree_sitter_python::language();
+        parser.set_language(&language).ok()?;
+        Some(parser)
+    }
+
+    fn create_parser(language: tree_sitter::Language) -> Option<Parser> {
+        let mut parser = Parser::new();
+        parser.set_language(&language).ok()?;
+        Some(parser)
+    }
+
     /// 从代码中提取函数
     pub fn extract_functions(&mut self, content: &str, file_path: &str, min_lines: u32) -> Vec<CodeUnit> {
         let lang = match get_language(file_path) {
@@ -88,6 +163,8 @@ impl CodeParser {
         match lang {
             "rust" => self.extract_rust_functions(content, file_path, min_lines),
             "swift" => self.extract_swift_functions(content, file_path, min_lines),
+            "python" => self.extract_python_functions(content, file_path, min_lines),
+            "typescript" | "javascript" => self.extract_typescript_functions(content, file_path, min_lines),
             _ => vec![],
         }
     }
@@ -110,16 +187,14 @@ impl CodeParser {
         let struct_fields = Self::extract_rust_struct_fields(tree.root_node(), content);
 
         // 第二遍: 提取函数，关联 struct 字段作为上下文
-        Self::visit_rust_node(
-            tree.root_node(),
-            content,
-            &lines,
-            file_path,
-            min_lines,
-            None,
-            &struct_fields,
-            &mut units,
-        );
+        let ctx = VisitContext { content, lines: &lines, file_path, min_lines };
+        Self::visit_rust_node(tree.root_node(), &ctx, None, &struct_fields, &mut units);
+
+        if self.include_types {
+            let mut types = Vec::new();
+            Self::collect_type_units(tree.root_node(), content, &lines, file_path, "rust", RUST_TYPE_NODES, &mut types);
+            units.extend(types.into_iter().filter(|u| u.range_end - u.range_start + 1 >= min_lines));
+        }
 
         units
     }
@@ -166,14 +241,12 @@ impl CodeParser {
 
     fn visit_rust_node(
         node: tree_sitter::Node,
-        content: &str,
-        lines: &[&str],
-        file_path: &str,
-        min_lines: u32,
+        ctx: &VisitContext,
         impl_name: Option<&str>,
         struct_fields: &HashMap<String, Vec<String>>,
         units: &mut Vec<CodeUnit>,
     ) {
+        let VisitContext { content, lines, file_path, min_lines } = *ctx;
         if node.kind() == "function_item" {
             let start_line = node.start_position().row;
             let end_line = node.end_position().row + 1;
@@ -212,6 +285,7 @@ impl CodeParser {
                     body,
                     selection_line: start_line as u32 + 1,
                     selection_column: 0,
+                    signature: None,
                 });
             }
         } else if node.kind() == "impl_item" {
@@ -224,23 +298,14 @@ impl CodeParser {
             for child in node.children(&mut node.walk()) {
                 if child.kind() == "declaration_list" {
                     for member in child.children(&mut child.walk()) {
-                        Self::visit_rust_node(
-                            member,
-                            content,
-                            lines,
-                            file_path,
-                            min_lines,
-                            type_name.as_deref(),
-                            struct_fields,
-                            units,
-                        );
+                        Self::visit_rust_node(member, ctx, type_name.as_deref(), struct_fields, units);
                     }
                 }
             }
         } else {
             // 递归处理其他节点
             for child in node.children(&mut node.walk()) {
-                Self::visit_rust_node(child, content, lines, file_path, min_lines, impl_name, struct_fields, units);
+                Self::visit_rust_node(child, ctx, impl_name, struct_fields, units);
             }
         }
     }
@@ -259,20 +324,61 @@ impl CodeParser {
         let lines: Vec<&str> = content.lines().collect();
         let mut units = Vec::new();
 
-        Self::visit_swift_node(
-            tree.root_node(),
-            content,
-            &lines,
-            file_path,
-            min_lines,
-            None,
-            None, // class_properties
-            &mut units,
-        );
+        let ctx = VisitContext { content, lines: &lines, file_path, min_lines };
+        Self::visit_swift_node(tree.root_node(), &ctx, None, None, &mut units);
+
+        if self.include_types {
+            let mut types = Vec::new();
+            Self::collect_type_units(tree.root_node(), content, &lines, file_path, "swift", SWIFT_TYPE_NODES, &mut types);
+            units.extend(types.into_iter().filter(|u| u.range_end - u.range_start + 1 >= min_lines));
+        }
 
         units
     }
 
+    /// 递归收集类型声明 (嵌套类型同样收集)，extension 不算类型声明
+    fn collect_type_units(
+        node: tree_sitter::Node,
+        content: &str,
+        lines: &[&str],
+        file_path: &str,
+        lang: &str,
+        type_nodes: &[&str],
+        units: &mut Vec<CodeUnit>,
+    ) {
+        let is_extension = node
+            .child_by_field_name("declaration_kind")
+            .is_some_and(|k| &content[k.byte_range()] == "extension");
+
+        if type_nodes.contains(&node.kind()) && !is_extension {
+            // Python 的类名是 identifier，只能按字段取
+            let name = node.children(&mut node.walk())
+                .find(|c| c.kind() == "type_identifier")
+                .or_else(|| node.child_by_field_name("name"))
+                .map(|c| &content[c.byte_range()]);
+
+            if let Some(name) = name {
+                let start_line = node.start_position().row;
+                let end_line = (node.end_position().row + 1).min(lines.len());
+                units.push(CodeUnit {
+                    qualified_name: format!("{}:{}::{}", lang, file_path, name),
+                    file_path: file_path.to_string(),
+                    kind: TYPE_KIND.to_string(),
+                    range_start: start_line as u32 + 1,
+                    range_end: end_line as u32,
+                    body: lines[start_line..end_line].join("\n"),
+                    selection_line: start_line as u32 + 1,
+                    selection_column: 0,
+                    signature: None,
+                });
+            }
+        }
+
+        for child in node.children(&mut node.walk()) {
+            Self::collect_type_units(child, content, lines, file_path, lang, type_nodes, units);
+        }
+    }
+
     /// 从 Swift class_body 提取属性声明
     fn extract_swift_properties(class_body: tree_sitter::Node, content: &str) -> Vec<String> {
         let mut properties = Vec::new();
@@ -287,14 +393,12 @@ impl CodeParser {
 
     fn visit_swift_node(
         node: tree_sitter::Node,
-        content: &str,
-        lines: &[&str],
-        file_path: &str,
-        min_lines: u32,
+        ctx: &VisitContext,
         class_name: Option<&str>,
         class_properties: Option<&[String]>,
         units: &mut Vec<CodeUnit>,
     ) {
+        let VisitContext { content, lines, file_path, min_lines } = *ctx;
         let kind = node.kind();
 
         // Swift 函数定义
@@ -338,6 +442,7 @@ impl CodeParser {
                     body,
                     selection_line: start_line as u32 + 1,
                     selection_column: 0,
+                    signature: None,
                 });
             }
         }
@@ -356,26 +461,271 @@ impl CodeParser {
                     // 提取属性作为上下文
                     let props = Self::extract_swift_properties(child, content);
                     for member in child.children(&mut child.walk()) {
-                        Self::visit_swift_node(
-                            member,
-                            content,
-                            lines,
-                            file_path,
-                            min_lines,
-                            name.as_deref(),
-                            Some(&props),
-                            units,
-                        );
+                        Self::visit_swift_node(member, ctx, name.as_deref(), Some(&props), units);
                     }
                 }
             }
         } else {
             // 递归处理其他节点
             for child in node.children(&mut node.walk()) {
-                Self::visit_swift_node(child, content, lines, file_path, min_lines, class_name, class_properties, units);
+                Self::visit_swift_node(child, ctx, class_name, class_properties, units);
             }
         }
     }
+
+    fn extract_python_functions(&mut self, content: &str, file_path: &str, min_lines: u32) -> Vec<CodeUnit> {
+        let parser = match &mut self.python_parser {
+            Some(p) => p,
+            None => return vec![],
+        };
+
+        let tree = match parser.parse(content.as_bytes(), None) {
+            Some(t) => t,
+            None => return vec![],
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut units = Vec::new();
+
+        let ctx = VisitContext { content, lines: &lines, file_path, min_lines };
+        Self::visit_python_node(tree.root_node(), &ctx, None, None, &mut units);
+
+        if self.include_types {
+            let mut types = Vec::new();
+            Self::collect_type_units(tree.root_node(), content, &lines, file_path, "python", PYTHON_TYPE_NODES, &mut types);
+            units.extend(types.into_iter().filter(|u| u.range_end - u.range_start + 1 >= min_lines));
+        }
+
+        units
+    }
+
+    /// 从 Python class 的 `__init__` 中提取 `self.xxx = ...` 属性赋值
+    fn extract_python_attributes(class_body: tree_sitter::Node, content: &str) -> Vec<String> {
+        fn collect_assignments(node: tree_sitter::Node, content: &str, attributes: &mut Vec<String>) {
+            if node.kind() == "assignment" {
+                let is_self_attribute = node.child_by_field_name("left")
+                    .filter(|left| left.kind() == "attribute")
+                    .and_then(|left| left.child_by_field_name("object"))
+                    .is_some_and(|object| &content[object.byte_range()] == "self");
+                if is_self_attribute {
+                    attributes.push(content[node.byte_range()].to_string());
+                }
+                return;
+            }
+
+            // 嵌套的函数/类有自己的作用域
+            if node.kind() == "function_definition" || node.kind() == "class_definition" {
+                return;
+            }
+            for child in node.children(&mut node.walk()) {
+                collect_assignments(child, content, attributes);
+            }
+        }
+
+        let mut attributes = Vec::new();
+        for member in class_body.children(&mut class_body.walk()) {
+            // 被装饰的 __init__ 包在 decorated_definition 中
+            let function = match member.kind() {
+                "function_definition" => Some(member),
+                "decorated_definition" => member.child_by_field_name("definition"),
+                _ => None,
+            };
+            let Some(function) = function.filter(|f| f.kind() == "function_definition") else { continue };
+
+            let is_init = function.child_by_field_name("name")
+                .is_some_and(|name| &content[name.byte_range()] == "__init__");
+            if !is_init {
+                continue;
+            }
+            if let Some(body) = function.child_by_field_name("body") {
+                for statement in body.children(&mut body.walk()) {
+                    collect_assignments(statement, content, &mut attributes);
+                }
+            }
+        }
+        attributes
+    }
+
+    fn visit_python_node(
+        node: tree_sitter::Node,
+        ctx: &VisitContext,
+        class_name: Option<&str>,
+        class_attributes: Option<&[String]>,
+        units: &mut Vec<CodeUnit>,
+    ) {
+        let VisitContext { content, lines, file_path, min_lines } = *ctx;
+        let kind = node.kind();
+
+        if kind == "function_definition" {
+            let start_line = node.start_position().row;
+            let end_line = node.end_position().row + 1;
+
+            if (end_line - start_line) as u32 >= min_lines {
+                // 方法去掉所在类的缩进，与顶层函数保持一致
+                let mut body = dedent(&lines[start_line..end_line], node.start_position().column);
+
+                // 如果在类中，把 __init__ 里的属性赋值附加到 body 前面作为上下文
+                if let Some(attrs) = class_attributes {
+                    if !attrs.is_empty() {
+                        let attrs_context = format!("# Class attributes:\n{}\n\n", attrs.join("\n"));
+                        body = attrs_context + &body;
+                    }
+                }
+
+                // 获取函数名
+                let func_name = node.child_by_field_name("name")
+                    .map(|c| &content[c.byte_range()])
+                    .unwrap_or("unknown");
+
+                let qualified_name = if let Some(class_n) = class_name {
+                    format!("python:{}::{}::{}", file_path, class_n, func_name)
+                } else {
+                    format!("python:{}::{}", file_path, func_name)
+                };
+
+                units.push(CodeUnit {
+                    qualified_name,
+                    file_path: file_path.to_string(),
+                    kind: if class_name.is_some() { "method" } else { "function" }.to_string(),
+                    range_start: start_line as u32 + 1,
+                    range_end: end_line as u32,
+                    body,
+                    selection_line: start_line as u32 + 1,
+                    selection_column: 0,
+                    signature: None,
+                });
+            }
+        } else if kind == "class_definition" {
+            // 获取类名
+            let name = node.child_by_field_name("name")
+                .map(|c| content[c.byte_range()].to_string());
+
+            // 递归处理类体
+            if let Some(class_body) = node.child_by_field_name("body") {
+                // 提取属性作为上下文
+                let attrs = Self::extract_python_attributes(class_body, content);
+                for member in class_body.children(&mut class_body.walk()) {
+                    Self::visit_python_node(member, ctx, name.as_deref(), Some(&attrs), units);
+                }
+            }
+        } else {
+            // 递归处理其他节点 (包括 decorated_definition)
+            for child in node.children(&mut node.walk()) {
+                Self::visit_python_node(child, ctx, class_name, class_attributes, units);
+            }
+        }
+    }
+
+    fn extract_typescript_functions(&mut self, content: &str, file_path: &str, min_lines: u32) -> Vec<CodeUnit> {
+        let parser = if file_path.to_lowercase().ends_with(".ts") {
+            &mut self.typescript_parser
+        } else {
+            &mut self.tsx_parser
+        };
+        let parser = match parser {
+            Some(p) => p,
+            None => return vec![],
+        };
+
+        let tree = match parser.parse(content.as_bytes(), None) {
+            Some(t) => t,
+            None => return vec![],
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut units = Vec::new();
+
+        Self::visit_typescript_node(tree.root_node(), content, &lines, file_path, None, &mut units);
+        units.retain(|u| u.range_end - u.range_start + 1 >= min_lines);
+
+        if self.include_types {
+            let mut types = Vec::new();
+            Self::collect_type_units(tree.root_node(), content, &lines, file_path, "ts", TS_TYPE_NODES, &mut types);
+            units.extend(types.into_iter().filter(|u| u.range_end - u.range_start + 1 >= min_lines));
+        }
+
+        units
+    }
+
+    /// 命名规则与 `TypeScriptAdapter` 一致：`ts:{file}::{Class}::{method}` / `ts:{file}::{function}`
+    fn visit_typescript_node(
+        node: tree_sitter::Node,
+        content: &str,
+        lines: &[&str],
+        file_path: &str,
+        class_name: Option<&str>,
+        units: &mut Vec<CodeUnit>,
+    ) {
+        let name_of = |n: tree_sitter::Node| n.child_by_field_name("name").map(|c| &content[c.byte_range()]);
+        let is_function_value = |n: tree_sitter::Node| {
+            n.child_by_field_name("value").is_some_and(|v| TS_FUNCTION_VALUES.contains(&v.kind()))
+        };
+
+        match node.kind() {
+            "function_declaration" | "generator_function_declaration" => {
+                Self::push_typescript_unit(node, name_of(node), "function", lines, file_path, class_name, units);
+            }
+            "method_definition" => {
+                let name = name_of(node);
+                let kind = if name == Some("constructor") { "constructor" } else { "method" };
+                Self::push_typescript_unit(node, name, kind, lines, file_path, class_name, units);
+            }
+            // const foo = () => {}
+            "variable_declarator" if is_function_value(node) => {
+                Self::push_typescript_unit(node, name_of(node), "function", lines, file_path, None, units);
+            }
+            // 类字段 handler = () => {}
+            "public_field_definition" if is_function_value(node) => {
+                Self::push_typescript_unit(node, name_of(node), "method", lines, file_path, class_name, units);
+            }
+            "class_declaration" | "abstract_class_declaration" | "class" => {
+                // 匿名类表达式 (const Foo = class {}) 的方法不带类名
+                let name = name_of(node);
+                if let Some(class_body) = node.child_by_field_name("body") {
+                    for member in class_body.children(&mut class_body.walk()) {
+                        Self::visit_typescript_node(member, content, lines, file_path, name, units);
+                    }
+                }
+            }
+            _ => {
+                // 递归处理其他节点 (export、命名空间等)，函数体内的嵌套函数不单独提取
+                for child in node.children(&mut node.walk()) {
+                    Self::visit_typescript_node(child, content, lines, file_path, class_name, units);
+                }
+            }
+        }
+    }
+
+    fn push_typescript_unit(
+        node: tree_sitter::Node,
+        name: Option<&str>,
+        kind: &str,
+        lines: &[&str],
+        file_path: &str,
+        class_name: Option<&str>,
+        units: &mut Vec<CodeUnit>,
+    ) {
+        let start_line = node.start_position().row;
+        let end_line = (node.end_position().row + 1).min(lines.len());
+
+        let func_name = name.unwrap_or("unknown");
+        let qualified_name = match class_name {
+            Some(class_n) => format!("ts:{}::{}::{}", file_path, class_n, func_name),
+            None => format!("ts:{}::{}", file_path, func_name),
+        };
+
+        units.push(CodeUnit {
+            qualified_name,
+            file_path: file_path.to_string(),
+            kind: kind.to_string(),
+            range_start: start_line as u32 + 1,
+            range_end: end_line as u32,
+            body: lines[start_line..end_line].join("\n"),
+            selection_line: start_line as u32 + 1,
+            selection_column: 0,
+            signature: None,
+        });
+    }
 }
 
 impl Default for CodeParser {
@@ -388,6 +738,16 @@ impl Default for CodeParser {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolve_db_path_override() {
+        let custom = resolve_db_path(Some(OsString::from("/tmp/ci/akin.db")));
+        assert_eq!(custom, PathBuf::from("/tmp/ci/akin.db"));
+
+        let default = resolve_db_path(None);
+        assert!(default.ends_with(".vimo/akin/akin.db"));
+        assert_eq!(resolve_db_path(Some(OsString::new())), default);
+    }
+
     #[test]
     fn test_is_code_file() {
         assert!(is_code_file("foo.rs"));
@@ -459,6 +819,220 @@ class Bar {
         assert!(units[1].qualified_name.contains("barMethod"));
     }
 
+    #[test]
+    fn test_extract_python_functions() {
+        let mut parser = CodeParser::new();
+        let content = r#"
+def foo():
+    x = 1
+    y = 2
+    z = 3
+    print(x + y + z)
+
+class Bar:
+    def bar_method(self):
+        a = 1
+        b = 2
+        c = 3
+        d = 4
+        print(a + b + c + d)
+
+    @staticmethod
+    def helper(items):
+        total = 0
+        for item in items:
+            total += item
+        return total
+"#;
+        let units = parser.extract_functions(content, "test.py", 5);
+        let names: Vec<_> = units.iter().map(|u| u.qualified_name.as_str()).collect();
+        assert_eq!(names, vec!["python:test.py::foo", "python:test.py::Bar::bar_method", "python:test.py::Bar::helper"]);
+        assert_eq!(units[0].kind, "function");
+        assert_eq!((units[0].range_start, units[0].range_end), (2, 6));
+        assert_eq!(units[1].kind, "method");
+        assert!(units[1].body.starts_with("def bar_method(self):"));
+    }
+
+    #[test]
+    fn test_extract_python_functions_with_init_attributes() {
+        let mut parser = CodeParser::new().with_include_types(true);
+        let content = r#"
+class Session:
+    def __init__(self, user_id):
+        self.user_id = user_id
+        self.created_at = now()
+        if user_id:
+            self.active = True
+        count = 0
+
+    def is_valid(self):
+        return (
+            self.created_at > 0
+            and self.user_id
+            and self.active
+        )
+"#;
+        let units = parser.extract_functions(content, "test.py", 5);
+        let method = units.iter().find(|u| u.qualified_name == "python:test.py::Session::is_valid").unwrap();
+        assert!(method.body.starts_with("# Class attributes:\n"), "body: {}", method.body);
+        assert!(method.body.contains("self.user_id = user_id"));
+        assert!(method.body.contains("self.created_at = now()"));
+        assert!(method.body.contains("self.active = True"));
+        // 局部变量不算属性
+        let (context, _) = method.body.split_once("\n\n").unwrap();
+        assert!(!context.contains("count"));
+
+        let types: Vec<_> = units.iter().filter(|u| u.is_type()).map(|u| u.qualified_name.as_str()).collect();
+        assert_eq!(types, vec!["python:test.py::Session"]);
+    }
+
+    #[test]
+    fn test_extract_typescript_functions() {
+        let mut parser = CodeParser::new();
+        let content = r#"
+export function foo(a: number, b: number): number {
+    const x = a * 2;
+    const y = b * 2;
+    return x + y;
+}
+
+export class Bar {
+    private total = 0;
+
+    constructor(private readonly name: string) {
+        this.total = 0;
+        this.reset();
+        console.log(name);
+    }
+
+    barMethod(items: number[]): number {
+        let sum = 0;
+        for (const item of items) {
+            sum += item;
+        }
+        return sum;
+    }
+
+    handler = (event: Event) => {
+        event.preventDefault();
+        this.total += 1;
+        console.log(this.total);
+    };
+}
+
+const baz = async (id: string) => {
+    const res = await fetch(id);
+    const body = await res.json();
+    return body;
+};
+
+const tiny = () => 1;
+"#;
+        let units = parser.extract_functions(content, "test.ts", 5);
+        let names: Vec<_> = units.iter().map(|u| (u.qualified_name.as_str(), u.kind.as_str())).collect();
+        assert_eq!(names, vec![
+            ("ts:test.ts::foo", "function"),
+            ("ts:test.ts::Bar::constructor", "constructor"),
+            ("ts:test.ts::Bar::barMethod", "method"),
+            ("ts:test.ts::Bar::handler", "method"),
+            ("ts:test.ts::baz", "function"),
+        ]);
+        assert_eq!((units[0].range_start, units[0].range_end), (2, 6));
+        assert!(units[4].body.starts_with("const baz = async"));
+    }
+
+    #[test]
+    fn test_extract_tsx_and_jsx_components() {
+        let mut parser = CodeParser::new().with_include_types(true);
+        let content = r#"
+interface Props {
+    title: string;
+    items: string[];
+}
+
+export const List = ({ title, items }: Props) => {
+    return (
+        <ul title={title}>
+            {items.map((item) => <li key={item}>{item}</li>)}
+        </ul>
+    );
+};
+"#;
+        let units = parser.extract_functions(content, "List.tsx", 3);
+        let names: Vec<_> = units.iter().map(|u| u.qualified_name.as_str()).collect();
+        assert_eq!(names, vec!["ts:List.tsx::List", "ts:List.tsx::Props"]);
+
+        // .jsx 同样使用 tsx grammar
+        let content = r#"
+export function Greeting({ name }) {
+    const text = `Hello, ${name}`;
+    return <h1 className="greeting">{text}</h1>;
+}
+"#;
+        let units = parser.extract_functions(content, "Greeting.jsx", 3);
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].qualified_name, "ts:Greeting.jsx::Greeting");
+    }
+
+    #[test]
+    fn test_extract_rust_types() {
+        let content = r#"
+struct Session {
+    id: String,
+    user_id: String,
+}
+
+enum State {
+    Active,
+    Closed,
+}
+
+struct Tiny;
+
+fn helper() {
+    let x = 1;
+    let y = 2;
+    println!("{}", x + y);
+}
+"#;
+        // 默认不提取类型
+        let units = CodeParser::new().extract_functions(content, "test.rs", 3);
+        assert!(units.iter().all(|u| !u.is_type()));
+
+        let mut parser = CodeParser::new().with_include_types(true);
+        let units = parser.extract_functions(content, "test.rs", 3);
+        let types: Vec<_> = units.iter().filter(|u| u.is_type()).collect();
+        assert_eq!(types.len(), 2, "found {:?}", types.iter().map(|u| &u.qualified_name).collect::<Vec<_>>());
+        assert_eq!(types[0].qualified_name, "rust:test.rs::Session");
+        assert_eq!((types[0].range_start, types[0].range_end), (2, 5));
+        assert!(types[0].body.starts_with("struct Session {"));
+        assert_eq!(types[1].qualified_name, "rust:test.rs::State");
+        assert_eq!(units.iter().filter(|u| u.kind == "function").count(), 1);
+    }
+
+    #[test]
+    fn test_extract_swift_types() {
+        let mut parser = CodeParser::new().with_include_types(true);
+        let content = r#"
+struct User {
+    let id: Int
+    let name: String
+}
+
+protocol UserStore {
+    func load(id: Int) -> User?
+    func save(_ user: User)
+}
+
+extension User {
+    var display: String { name }
+}
+"#;
+        let units = parser.extract_functions(content, "test.swift", 3);
+        let names: Vec<_> = units.iter().filter(|u| u.is_type()).map(|u| u.qualified_name.as_str()).collect();
+        assert_eq!(names, vec!["swift:test.swift::User", "swift:test.swift::UserStore"]);
+    }
+
     #[test]
     fn test_extract_rust_functions_with_struct_fields() {
         let mut parser = CodeParser::new();
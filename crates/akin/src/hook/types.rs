@@ -69,7 +69,7 @@ pub struct ToolInput {
 }
 
 /// 相似度匹配结果
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SimilarityMatch {
     pub current_name: String,
     pub current_file: String,
@@ -3,18 +3,32 @@
 //! 基于向量嵌入的代码相似度分析工具
 
 mod db;
+mod embcache;
 mod embedding;
+mod histogram;
 pub mod hook;
+#[cfg(any(test, feature = "testing"))]
+mod mock;
+#[cfg(feature = "onnx")]
+mod onnx;
 mod scanner;
 mod store;
 mod vector_index;
+mod watch;
 
 pub use db::{
     Database, PairStatus, ProjectRecord, CodeUnitRecord,
-    SimilarPairRecord, SimilarityGroupRecord, ProjectStats
+    SimilarPairRecord, SimilarityGroupRecord, ProjectStats, ModelMismatch, BodyMatch, SCHEMA_VERSION
 };
-pub use embedding::{OllamaEmbedding, bytes_to_embedding, embedding_to_bytes, cosine_similarity};
-pub use hook::{HookConfig, HookResult, HookInput, CodeParser, run_hook};
+pub use embcache::{CachedEmbedder, EmbeddingCache};
+pub use embedding::{Embedder, EmbedRole, EmbeddingError, OllamaEmbedding, DEFAULT_RETRIES, default_model, default_prefixes, bytes_to_embedding, embedding_to_bytes, cosine_similarity, apply_length_penalty, line_count};
+#[cfg(feature = "onnx")]
+pub use onnx::{OnnxEmbedding, Pooling};
+#[cfg(any(test, feature = "testing"))]
+pub use mock::MockEmbedding;
+pub use histogram::{SimilarityHistogram, HISTOGRAM_MIN};
+pub use hook::{HookConfig, HookResult, HookInput, CodeParser, MinLines, handle_event, run_hook};
 pub use scanner::{Scanner, SimilarPair};
-pub use store::{Store, SimilarUnit, StoreError};
-pub use vector_index::{VectorIndex, VectorIndexConfig, SearchResult, VectorIndexError};
+pub use store::{Store, SimilarUnit, StoreError, VerifyReport, IndexMetadata, DEFAULT_CANDIDATES, DEFAULT_CLUSTER_CANDIDATES, MAX_RADIUS_RESULTS};
+pub use vector_index::{VectorIndex, VectorIndexConfig, Metric, Quantization, SearchResult, VectorIndexError};
+pub use watch::{EventCoalescer, is_ignored_path, DEFAULT_DEBOUNCE_MS};
@@ -0,0 +1,105 @@
+//! 确定性嵌入 - 测试用，无需 Ollama
+//!
+//! 将标识符 token 哈希到固定维度后计数并 L2 归一化：
+//! 相同文本得到相同向量，共享大部分 token 的文本向量相近
+
+use async_trait::async_trait;
+use ndarray::Array1;
+
+use crate::embedding::{Embedder, Result};
+
+/// 默认维度
+const DEFAULT_DIMENSIONS: usize = 256;
+
+/// 基于 token 哈希的确定性嵌入生成器
+#[derive(Debug, Clone)]
+pub struct MockEmbedding {
+    dimensions: usize,
+}
+
+impl MockEmbedding {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions: dimensions.max(1) }
+    }
+
+    /// 同步生成嵌入
+    pub fn embed_sync(&self, text: &str) -> Array1<f32> {
+        let mut vector = Array1::<f32>::zeros(self.dimensions);
+        let tokens = text
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|t| !t.is_empty());
+        for token in tokens {
+            vector[(fnv1a(token) % self.dimensions as u64) as usize] += 1.0;
+        }
+
+        let norm = vector.dot(&vector).sqrt();
+        if norm > 0.0 { vector / norm } else { vector }
+    }
+}
+
+impl Default for MockEmbedding {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIMENSIONS)
+    }
+}
+
+#[async_trait]
+impl Embedder for MockEmbedding {
+    async fn embed(&mut self, text: &str) -> Result<Array1<f32>> {
+        Ok(self.embed_sync(text))
+    }
+
+    fn model_name(&self) -> &str {
+        "mock"
+    }
+}
+
+/// FNV-1a 哈希 (跨平台、跨版本稳定)
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::cosine_similarity;
+
+    #[test]
+    fn test_identical_text_identical_vector() {
+        let emb = MockEmbedding::default();
+        let a = emb.embed_sync("fn add(a: i32, b: i32) -> i32 { a + b }");
+        let b = emb.embed_sync("fn add(a: i32, b: i32) -> i32 { a + b }");
+        assert_eq!(a, b);
+        assert_eq!(a.len(), DEFAULT_DIMENSIONS);
+        assert!((a.dot(&a) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_similar_text_closer_than_unrelated() {
+        let emb = MockEmbedding::default();
+        let base = emb.embed_sync("let mut total = 0; for item in items { total += item; } total");
+        let renamed = emb.embed_sync("let mut sum = 0; for item in items { sum += item; } sum");
+        let unrelated = emb.embed_sync("std::fs::read_to_string(path).unwrap().lines().count()");
+
+        let close = cosine_similarity(&base, &renamed);
+        let far = cosine_similarity(&base, &unrelated);
+        assert!(close > 0.4, "close = {}", close);
+        assert!(far < 0.2, "far = {}", far);
+    }
+
+    #[test]
+    fn test_empty_text_zero_vector() {
+        let emb = MockEmbedding::new(8);
+        assert_eq!(emb.embed_sync("  {} ;").sum(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_embedder_trait() {
+        let mut emb = MockEmbedding::new(16);
+        let v = emb.embed("fn main() {}").await.unwrap();
+        assert_eq!(v, emb.embed_sync("fn main() {}"));
+        assert_eq!(emb.model_name(), "mock");
+    }
+}
@@ -0,0 +1,157 @@
+//! 本地 ONNX 嵌入后端 - 无需 Ollama 服务
+//!
+//! 模型目录需包含 `model.onnx` 与 `tokenizer.json` (sentence-transformers 导出格式)
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ndarray::{Array1, Array2, Axis};
+use ort::session::{Session, SessionInputValue};
+use ort::value::Tensor;
+use tokenizers::{Tokenizer, TruncationParams};
+
+use crate::embedding::{Embedder, EmbeddingError, Result};
+
+/// 最大输入 token 数
+const MAX_TOKENS: usize = 512;
+
+/// 池化方式 (模型未直接输出 `sentence_embedding` 时使用)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pooling {
+    /// 按 attention mask 对所有 token 取平均
+    #[default]
+    Mean,
+    /// 取第一个 token ([CLS])，适用于 bge 系列
+    Cls,
+}
+
+/// ONNX 嵌入生成器
+#[derive(Clone)]
+pub struct OnnxEmbedding {
+    session: Arc<Session>,
+    tokenizer: Arc<Tokenizer>,
+    model_dir: PathBuf,
+    model_name: String,
+    pooling: Pooling,
+    needs_token_type_ids: bool,
+}
+
+impl OnnxEmbedding {
+    /// 从模型目录加载
+    pub fn load(model_dir: &Path) -> Result<Self> {
+        let model_path = model_dir.join("model.onnx");
+        let tokenizer_path = model_dir.join("tokenizer.json");
+
+        let session = Session::builder()
+            .and_then(|b| b.commit_from_file(&model_path))
+            .map_err(|e| EmbeddingError::Model(format!("{}: {}", model_path.display(), e)))?;
+
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| EmbeddingError::Model(format!("{}: {}", tokenizer_path.display(), e)))?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| EmbeddingError::Model(e.to_string()))?;
+
+        let needs_token_type_ids = session.inputs.iter().any(|i| i.name == "token_type_ids");
+        let model_name = model_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| model_dir.display().to_string());
+
+        Ok(Self {
+            session: Arc::new(session),
+            tokenizer: Arc::new(tokenizer),
+            model_dir: model_dir.to_path_buf(),
+            model_name,
+            pooling: Pooling::default(),
+            needs_token_type_ids,
+        })
+    }
+
+    pub fn with_pooling(mut self, pooling: Pooling) -> Self {
+        self.pooling = pooling;
+        self
+    }
+
+    /// 模型目录
+    pub fn model_dir(&self) -> &Path {
+        &self.model_dir
+    }
+
+    /// 同步推理
+    fn embed_sync(&self, text: &str) -> Result<Array1<f32>> {
+        let encoding = self
+            .tokenizer
+            .encode(text, true)
+            .map_err(|e| EmbeddingError::Model(e.to_string()))?;
+
+        let len = encoding.get_ids().len();
+        let to_i64 = |v: &[u32]| -> Vec<i64> { v.iter().map(|&x| x as i64).collect() };
+        let shape = (1, len);
+
+        let ids = Array2::from_shape_vec(shape, to_i64(encoding.get_ids()))
+            .map_err(|e| EmbeddingError::Model(e.to_string()))?;
+        let mask = Array2::from_shape_vec(shape, to_i64(encoding.get_attention_mask()))
+            .map_err(|e| EmbeddingError::Model(e.to_string()))?;
+
+        let onnx_err = |e: ort::Error| EmbeddingError::Model(e.to_string());
+
+        let mut inputs: Vec<(&str, SessionInputValue)> = vec![
+            ("input_ids", Tensor::from_array(ids).map_err(onnx_err)?.into()),
+            ("attention_mask", Tensor::from_array(mask.clone()).map_err(onnx_err)?.into()),
+        ];
+        if self.needs_token_type_ids {
+            let type_ids = Array2::from_shape_vec(shape, to_i64(encoding.get_type_ids()))
+                .map_err(|e| EmbeddingError::Model(e.to_string()))?;
+            inputs.push(("token_type_ids", Tensor::from_array(type_ids).map_err(onnx_err)?.into()));
+        }
+
+        let outputs = self.session.run(inputs).map_err(onnx_err)?;
+
+        // 优先使用模型自带的句向量输出
+        let embedding = if let Some(pooled) = outputs.get("sentence_embedding") {
+            let view = pooled.try_extract_tensor::<f32>().map_err(onnx_err)?;
+            view.iter().copied().collect::<Array1<f32>>()
+        } else {
+            // last_hidden_state: [1, seq, dim]
+            let hidden = outputs[0].try_extract_tensor::<f32>().map_err(onnx_err)?;
+            let hidden = hidden
+                .index_axis(Axis(0), 0)
+                .into_dimensionality::<ndarray::Ix2>()
+                .map_err(|e| EmbeddingError::Model(e.to_string()))?;
+
+            match self.pooling {
+                Pooling::Cls => hidden.row(0).to_owned(),
+                Pooling::Mean => {
+                    let weights = mask.row(0).mapv(|m| m as f32);
+                    let count = weights.sum().max(1.0);
+                    weights.dot(&hidden) / count
+                }
+            }
+        };
+
+        // L2 归一化，与余弦相似度保持一致
+        let norm = embedding.dot(&embedding).sqrt();
+        Ok(if norm > 0.0 { embedding / norm } else { embedding })
+    }
+}
+
+#[async_trait]
+impl Embedder for OnnxEmbedding {
+    async fn embed(&mut self, text: &str) -> Result<Array1<f32>> {
+        // 推理是 CPU 密集型，放到阻塞线程池避免卡住 runtime
+        let this = self.clone();
+        let text = text.to_string();
+        tokio::task::spawn_blocking(move || this.embed_sync(&text))
+            .await
+            .map_err(|e| EmbeddingError::Model(e.to_string()))?
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+}
@@ -1,6 +1,6 @@
 use crate::db::Database;
-use crate::embedding::{cosine_similarity, OllamaEmbedding};
-use lsp::{CodeUnit, LanguageAdapter};
+use crate::embedding::{apply_length_penalty, cosine_similarity, line_count, Embedder, OllamaEmbedding};
+use lsp::{CodeUnit, IgnoreSet, LanguageAdapter};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -25,20 +25,32 @@ pub struct SimilarPair {
 
 /// 代码扫描器
 pub struct Scanner {
-    embedding: OllamaEmbedding,
+    embedding: Box<dyn Embedder>,
     threshold: f32,
     min_lines: u32,
+    /// 长度差异惩罚权重 (0 = 关闭)
+    length_penalty: f32,
+    /// `.irisignore` 模式，匹配的单元不参与索引和比较
+    ignore: IgnoreSet,
 }
 
 impl Scanner {
     pub fn new(model: &str) -> Self {
         Self {
-            embedding: OllamaEmbedding::new(model),
+            embedding: Box::new(OllamaEmbedding::new(model)),
             threshold: 0.85,
             min_lines: 3,
+            length_penalty: 0.0,
+            ignore: IgnoreSet::default(),
         }
     }
 
+    /// 替换嵌入后端 (默认 Ollama)
+    pub fn with_embedder<E: Embedder + 'static>(mut self, embedder: E) -> Self {
+        self.embedding = Box::new(embedder);
+        self
+    }
+
     pub fn with_threshold(mut self, threshold: f32) -> Self {
         self.threshold = threshold;
         self
@@ -49,6 +61,20 @@ impl Scanner {
         self
     }
 
+    pub fn with_length_penalty(mut self, weight: f32) -> Self {
+        self.length_penalty = weight;
+        self
+    }
+
+    pub fn with_ignore(mut self, ignore: IgnoreSet) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    fn is_ignored(&self, unit: &CodeUnit) -> bool {
+        self.ignore.is_ignored(&unit.file_path, &unit.qualified_name)
+    }
+
     /// 索引项目
     pub async fn index_project<A: LanguageAdapter>(
         &self,
@@ -60,10 +86,10 @@ impl Scanner {
             .await
             .map_err(|e| ScanError::Lsp(e.to_string()))?;
 
-        // 过滤小函数
+        // 过滤小函数、空函数体和忽略的函数
         let filtered: Vec<CodeUnit> = units
             .into_iter()
-            .filter(|u| (u.range_end - u.range_start) >= self.min_lines)
+            .filter(|u| (u.range_end - u.range_start) >= self.min_lines && u.has_body() && !self.is_ignored(u))
             .collect();
 
         // TODO: 生成嵌入并存储到数据库
@@ -77,10 +103,11 @@ impl Scanner {
         units: &[CodeUnit],
     ) -> Result<Vec<SimilarPair>> {
         let mut pairs = Vec::new();
+        let units: Vec<&CodeUnit> = units.iter().filter(|u| !self.is_ignored(u)).collect();
 
         // 生成所有嵌入
         let mut embeddings = Vec::with_capacity(units.len());
-        for unit in units {
+        for unit in &units {
             let emb = self.embedding.embed(&unit.body).await?;
             embeddings.push(emb);
         }
@@ -88,7 +115,12 @@ impl Scanner {
         // 两两比较
         for i in 0..units.len() {
             for j in (i + 1)..units.len() {
-                let similarity = cosine_similarity(&embeddings[i], &embeddings[j]);
+                let similarity = apply_length_penalty(
+                    cosine_similarity(&embeddings[i], &embeddings[j]),
+                    line_count(units[i].range_start, units[i].range_end),
+                    line_count(units[j].range_start, units[j].range_end),
+                    self.length_penalty,
+                );
                 if similarity >= self.threshold {
                     pairs.push(SimilarPair {
                         unit_a: units[i].qualified_name.clone(),
@@ -109,3 +141,53 @@ impl Scanner {
         Ok(pairs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockEmbedding;
+
+    fn unit(name: &str, body: &str) -> CodeUnit {
+        CodeUnit {
+            qualified_name: name.to_string(),
+            file_path: "lib.rs".to_string(),
+            kind: "function".to_string(),
+            range_start: 0,
+            range_end: 4,
+            body: body.to_string(),
+            selection_line: 0,
+            selection_column: 0,
+            signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_similarities_with_mock_embedder() {
+        let units = vec![
+            unit("sum_a", "fn sum_a(items: &[i32]) -> i32 { let mut total = 0; for item in items { total += item; } total }"),
+            unit("sum_b", "fn sum_b(items: &[i32]) -> i32 { let mut total = 0; for item in items { total += item; } total }"),
+            unit("load", "fn load(path: &str) -> String { std::fs::read_to_string(path).unwrap() }"),
+        ];
+
+        let mut scanner = Scanner::new("unused").with_embedder(MockEmbedding::default());
+        let pairs = scanner.scan_similarities(&units).await.unwrap();
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].unit_a.as_str(), pairs[0].unit_b.as_str()), ("sum_a", "sum_b"));
+        assert!(pairs[0].similarity > 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_scan_similarities_skips_ignored_units() {
+        let body = "fn sum(items: &[i32]) -> i32 { let mut total = 0; for item in items { total += item; } total }";
+        let units = vec![unit("sum_a", body), unit("sum_b", body), unit("legacy::sum", body)];
+
+        let mut scanner = Scanner::new("unused")
+            .with_embedder(MockEmbedding::default())
+            .with_ignore(IgnoreSet::parse(None, "legacy::*"));
+        let pairs = scanner.scan_similarities(&units).await.unwrap();
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].unit_a.as_str(), pairs[0].unit_b.as_str()), ("sum_a", "sum_b"));
+    }
+}
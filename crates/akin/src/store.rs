@@ -1,12 +1,15 @@
 //! 存储层 - 协调 SQLite 数据库和向量索引
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::db::{Database, CodeUnitRecord};
+use crate::db::{Database, CodeUnitRecord, SCHEMA_VERSION};
 use crate::embedding::bytes_to_embedding;
-use crate::vector_index::VectorIndex;
+use crate::vector_index::{Metric, Quantization, SearchResult, VectorIndex, VectorIndexConfig};
 
 /// 存储层错误
 #[derive(Error, Debug)]
@@ -19,71 +22,213 @@ pub enum StoreError {
     Io(#[from] std::io::Error),
     #[error("Vector index not initialized")]
     VectorIndexNotInitialized,
+    #[error("Background vector index build panicked")]
+    BackgroundBuildPanicked,
+    #[error("Blocking search task failed: {0}")]
+    SearchTask(#[from] tokio::task::JoinError),
+    #[error("Embedding dimension mismatch: vector index expects {expected}, {model} produced {got}")]
+    DimensionMismatch { expected: usize, got: usize, model: String },
+    #[error("Vector index metric mismatch: index was built with {expected}, opened with {got}; rebuild it to switch")]
+    MetricMismatch { expected: Metric, got: Metric },
+    #[error("Vector index quantization mismatch: index stores {expected}, opened with {got}; rebuild it to switch")]
+    QuantizationMismatch { expected: Quantization, got: Quantization },
+    #[error("{} code units already exist in the target database (e.g. {})", .0.len(), .0[0])]
+    DuplicateUnits(Vec<String>),
+    #[error("{} uses schema version {version}; open it with this version of akin once to migrate it before importing", .path.display())]
+    OutdatedSource { path: PathBuf, version: u32 },
 }
 
 pub type Result<T> = std::result::Result<T, StoreError>;
 
+/// ANN 每次查询的默认候选数 (k)
+///
+/// 阈值过滤发生在取出 k 个最近邻之后：若某函数有超过 k 个更相似的近邻，
+/// 阈值以上的配对也会被漏掉。k 越大召回越高，查询越慢
+pub const DEFAULT_CANDIDATES: usize = 100;
+
+/// 聚类时每个单元取的近邻数 (k)，[`Store::cluster`] 的默认值
+///
+/// 簇比 k 大时仍能经由成员间的边连通，因此不需要像扫描那样大
+pub const DEFAULT_CLUSTER_CANDIDATES: usize = 20;
+
+/// 半径搜索 (不指定 k) 返回的最大近邻数
+pub const MAX_RADIUS_RESULTS: usize = 10_000;
+
 /// ANN 搜索结果
 #[derive(Debug, Clone)]
 pub struct SimilarUnit {
     pub qualified_name: String,
     pub file_path: String,
     pub range_start: u32,
+    pub range_end: u32,
     pub project_id: i64,
     pub similarity: f32,
+    /// 向量索引返回的原始距离 (`SearchResult::distance`)，含义取决于索引度量
+    pub distance: f32,
+}
+
+/// 数据库与向量索引的一致性检查结果 (`Store::verify`)
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// 是否找到并加载了向量索引文件
+    pub index_loaded: bool,
+    /// 向量索引条目数
+    pub index_size: usize,
+    /// 向量索引维度
+    pub index_dimensions: usize,
+    /// 带 embedding 的单元数
+    pub units_with_embeddings: usize,
+    /// 有 embedding 但不在向量索引中的单元
+    pub missing_vectors: Vec<String>,
+    /// 在向量索引中但没有对应单元 (或单元已无 embedding) 的向量 ID
+    pub orphan_vectors: Vec<u64>,
+    /// embedding 维度与索引不一致的单元 (名称, 维度)，无法解码的记为 0
+    pub dimension_mismatches: Vec<(String, usize)>,
+}
+
+impl VerifyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing_vectors.is_empty()
+            && self.orphan_vectors.is_empty()
+            && self.dimension_mismatches.is_empty()
+    }
+}
+
+/// 向量索引的维度、度量与精度记录，保存在 `.usearch` 旁的 `.usearch.json`
+///
+/// usearch 文件本身按固定维度读取，维度不符时无法给出可读的错误，因此单独记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub dimensions: usize,
+    /// 距离度量 (旧记录没有该字段，当时固定为余弦)
+    #[serde(default)]
+    pub metric: Metric,
+    /// 向量存储精度 (旧记录没有该字段，当时固定为 f32)
+    #[serde(default)]
+    pub quantization: Quantization,
+    /// 第一次写入向量时使用的模型 (未知时为 None)
+    pub model: Option<String>,
+}
+
+impl IndexMetadata {
+    /// 读取数据库旁的 `.usearch.json`，文件不存在或无法解析时返回 None
+    pub fn load(db_path: &Path) -> Option<Self> {
+        read_index_metadata(&db_path.with_extension("usearch.json"))
+    }
 }
 
 /// 存储层 - 管理 Database + VectorIndex
 pub struct Store {
     db: Database,
-    vector_index: Option<VectorIndex>,
+    db_path: PathBuf,
+    /// 共享给 `spawn_blocking` 中的搜索任务
+    vector_index: Option<Arc<VectorIndex>>,
     vector_index_path: PathBuf,
-    /// qualified_name -> rowid 的映射（用于向量索引）
+    /// 后台构建中的向量索引 (构建线程使用独立的数据库连接，完成后由所有者线程换入)
+    pending_index: Option<JoinHandle<Result<(VectorIndex, usize)>>>,
+    /// qualified_name -> vector_id 的映射（用于向量索引，ID 持久化在数据库中）
     name_to_id: std::collections::HashMap<String, u64>,
-    /// rowid -> qualified_name 的反向映射
+    /// vector_id -> qualified_name 的反向映射
     id_to_name: std::collections::HashMap<u64, String>,
-    next_id: u64,
+    /// 已记录的索引维度 (首次写入向量或构建索引时确定)
+    index_meta: Option<IndexMetadata>,
+    index_meta_path: PathBuf,
+    /// 调用方声明的嵌入模型，用于维度记录与错误信息
+    embedding_model: Option<String>,
+    /// 新建索引使用的度量，与已有索引不一致时拒绝加载
+    metric: Metric,
+    /// 新建索引使用的存储精度，与已有索引不一致时拒绝加载
+    quantization: Quantization,
 }
 
 impl Store {
     /// 打开或创建 Store
+    ///
+    /// 向量索引延迟加载：只有调用 `ensure_vector_index` / `load_vector_index`
+    /// (或写入带 embedding 的单元) 时才会加载或构建，纯数据库操作不付出构建开销
     pub fn open(db_path: &Path) -> Result<Self> {
         let db = Database::open(db_path)?;
 
         // 向量索引放在同目录，扩展名改为 .usearch
         let vector_index_path = db_path.with_extension("usearch");
+        let index_meta_path = db_path.with_extension("usearch.json");
+        let index_meta = read_index_metadata(&index_meta_path);
 
-        let mut store = Self {
+        Ok(Self {
             db,
+            db_path: db_path.to_path_buf(),
             vector_index: None,
             vector_index_path,
+            pending_index: None,
             name_to_id: std::collections::HashMap::new(),
             id_to_name: std::collections::HashMap::new(),
-            next_id: 1,
-        };
+            index_meta,
+            index_meta_path,
+            embedding_model: None,
+            metric: Metric::default(),
+            quantization: Quantization::default(),
+        })
+    }
 
-        // 尝试加载已有的向量索引
-        if !store.try_load_vector_index()? {
-            // 没有向量索引，尝试从数据库自动构建
-            let count = store.db.get_code_units_by_projects(None)?.len();
-            if count > 0 {
-                tracing::info!("Building vector index from {} code units...", count);
-                let indexed = store.rebuild_vector_index()?;
-                tracing::info!("Vector index built with {} embeddings", indexed);
-            }
-        }
+    /// 向量索引的距离度量 (默认余弦)
+    ///
+    /// 只影响新建的索引；已有索引使用其他度量时，加载返回 `StoreError::MetricMismatch`，
+    /// 需要 `rebuild_vector_index` 才能切换
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// 向量索引的存储精度 (默认 f32)，f16/i8 分别把 `.usearch` 缩小到约 1/2、1/4
+    ///
+    /// 与 `with_metric` 相同，只影响新建的索引，切换需要 `rebuild_vector_index`
+    pub fn with_quantization(mut self, quantization: Quantization) -> Self {
+        self.quantization = quantization;
+        self
+    }
 
+    /// 打开 Store 并立即准备向量索引（加载已有索引，缺失时从数据库构建）
+    pub fn open_with_index(db_path: &Path) -> Result<Self> {
+        let mut store = Self::open(db_path)?;
+        store.ensure_vector_index()?;
         Ok(store)
     }
 
+    /// 加载已有的向量索引文件（不会构建），返回是否已加载
+    pub fn load_vector_index(&mut self) -> Result<bool> {
+        self.wait_vector_index()?;
+        if self.vector_index.is_some() {
+            return Ok(true);
+        }
+        self.try_load_vector_index()
+    }
+
     /// 尝试加载向量索引（如果存在），返回是否成功加载
+    ///
+    /// 条目数与数据库中带 embedding 的单元数不一致时 (写入中断、索引文件过期或被替换)，
+    /// 搜索结果会指向错误的单元，此时从数据库重建
     fn try_load_vector_index(&mut self) -> Result<bool> {
+        if !self.try_load_vector_index_unchecked()? {
+            return Ok(false);
+        }
+        let size = self.vector_index.as_ref().map_or(0, |index| index.size());
+        let expected = self.db.count_embeddings()?;
+        if size != expected {
+            tracing::warn!("Vector index holds {} vectors but the database has {} embeddings, rebuilding", size, expected);
+            self.rebuild_vector_index()?;
+        }
+        Ok(true)
+    }
+
+    /// 加载向量索引文件，不与数据库比对
+    fn try_load_vector_index_unchecked(&mut self) -> Result<bool> {
         if self.vector_index_path.exists() {
-            match VectorIndex::load(&self.vector_index_path) {
+            match VectorIndex::load_with_config(&self.vector_index_path, self.index_config()?) {
                 Ok(index) => {
                     // 同时重建 mapping
                     self.rebuild_mappings()?;
-                    self.vector_index = Some(index);
+                    self.record_index_metadata(index.dimensions())?;
+                    self.vector_index = Some(Arc::new(index));
                     return Ok(true);
                 }
                 Err(e) => {
@@ -94,91 +239,286 @@ impl Store {
         Ok(false)
     }
 
-    /// 确保向量索引已初始化
+    /// 确保向量索引已初始化：优先加载已有文件，否则从数据库构建
     pub fn ensure_vector_index(&mut self) -> Result<&VectorIndex> {
-        if self.vector_index.is_none() {
-            let index = VectorIndex::with_defaults()?;
-            // 预分配容量
-            let count = self.db.get_code_units_by_projects(None)?.len();
+        self.wait_vector_index()?;
+        if self.vector_index.is_none() && !self.try_load_vector_index()? {
+            let count = self.db.count_code_units(None)?;
             if count > 0 {
-                index.reserve(count + 1000)?; // 预留一些空间
+                tracing::info!("Building vector index from {} code units...", count);
+                let indexed = self.rebuild_vector_index()?;
+                tracing::info!("Vector index built with {} embeddings", indexed);
+            } else {
+                self.vector_index = Some(Arc::new(VectorIndex::new(self.index_config()?)?));
             }
-            self.vector_index = Some(index);
         }
-        Ok(self.vector_index.as_ref().unwrap())
+        Ok(self.vector_index.as_deref().unwrap())
     }
 
-    /// 重建 name <-> id 映射
-    fn rebuild_mappings(&mut self) -> Result<()> {
-        let units = self.db.get_code_units_by_projects(None)?;
+    /// 准备向量索引但不阻塞：已有索引文件时直接加载，否则在后台线程从数据库构建
+    ///
+    /// 构建期间索引不可用 (`vector_index_stats()` 为 None)，调用方应暂时回退到暴力搜索；
+    /// 构建线程会自行保存 `.usearch`，完成后由 `poll_vector_index` / `wait_vector_index` 换入。
+    /// 不等待时构建线程随 Store 释放而分离，进程提前退出也不会留下不完整的索引文件
+    pub fn ensure_vector_index_background(&mut self) -> Result<()> {
+        if self.vector_index.is_some() || self.pending_index.is_some() || self.try_load_vector_index()? {
+            return Ok(());
+        }
+        if self.db.count_code_units(None)? == 0 {
+            self.vector_index = Some(Arc::new(VectorIndex::new(self.index_config()?)?));
+            return Ok(());
+        }
+
+        // ID 在本线程分配，构建线程只读数据库
+        self.db.assign_missing_vector_ids()?;
+
+        let db_path = self.db_path.clone();
+        let index_path = self.vector_index_path.clone();
+        let dimensions = self.index_meta.as_ref().map(|d| d.dimensions);
+        let config = self.index_config()?;
+        self.pending_index = Some(std::thread::spawn(move || {
+            let db = Database::open_readonly(&db_path)?;
+            let (index, count) = build_vector_index(&db, dimensions, config)?;
+            // 先写临时文件再改名，进程中途退出时不会留下截断的索引
+            let temp_path = index_path.with_extension("usearch.tmp");
+            index.save(&temp_path)?;
+            std::fs::rename(&temp_path, &index_path)?;
+            Ok((index, count))
+        }));
+        tracing::info!("Building vector index in background");
+        Ok(())
+    }
 
-        for (idx, unit) in units.iter().enumerate() {
-            let id = (idx + 1) as u64;
-            self.name_to_id.insert(unit.qualified_name.clone(), id);
-            self.id_to_name.insert(id, unit.qualified_name.clone());
+    /// 后台构建已完成时换入索引 (不阻塞)，返回索引是否可用
+    pub fn poll_vector_index(&mut self) -> Result<bool> {
+        if self.pending_index.as_ref().is_some_and(|handle| handle.is_finished()) {
+            self.wait_vector_index()?;
         }
+        Ok(self.vector_index.is_some())
+    }
+
+    /// 等待后台构建完成并换入索引，返回索引是否可用
+    pub fn wait_vector_index(&mut self) -> Result<bool> {
+        if let Some(handle) = self.pending_index.take() {
+            let (index, count) = handle.join().map_err(|_| StoreError::BackgroundBuildPanicked)??;
+            // 启动构建前分配了新 ID，重新加载映射
+            self.rebuild_mappings()?;
+            self.record_index_metadata(index.dimensions())?;
+            self.vector_index = Some(Arc::new(index));
+            tracing::info!("Vector index built with {} embeddings", count);
+        }
+        Ok(self.vector_index.is_some())
+    }
+
+    /// 从数据库加载 name <-> id 映射
+    fn rebuild_mappings(&mut self) -> Result<()> {
+        self.name_to_id.clear();
+        self.id_to_name.clear();
 
-        self.next_id = (units.len() + 1) as u64;
+        for (name, id) in self.db.get_vector_ids()? {
+            self.name_to_id.insert(name.clone(), id);
+            self.id_to_name.insert(id, name);
+        }
         Ok(())
     }
 
-    /// 获取或分配 ID
-    fn get_or_allocate_id(&mut self, name: &str) -> u64 {
+    /// 获取 ID (由数据库分配)
+    fn get_or_allocate_id(&mut self, name: &str) -> Result<u64> {
         if let Some(&id) = self.name_to_id.get(name) {
-            id
-        } else {
-            let id = self.next_id;
-            self.next_id += 1;
-            self.name_to_id.insert(name.to_string(), id);
-            self.id_to_name.insert(id, name.to_string());
-            id
+            return Ok(id);
+        }
+
+        let id = self.db.ensure_vector_id(name)?;
+        self.name_to_id.insert(name.to_string(), id);
+        self.id_to_name.insert(id, name.to_string());
+        Ok(id)
+    }
+
+    /// 已记录的索引维度
+    pub fn index_metadata(&self) -> Option<&IndexMetadata> {
+        self.index_meta.as_ref()
+    }
+
+    /// 声明后续写入使用的嵌入模型，并检查其维度与向量索引是否一致
+    ///
+    /// 尚未记录维度时以此为准；不一致时返回 `StoreError::DimensionMismatch`，
+    /// 换模型后需要重新创建索引
+    pub fn set_embedding_model(&mut self, model: &str, dimensions: usize) -> Result<()> {
+        self.embedding_model = Some(model.to_string());
+        self.check_dimensions(dimensions)
+    }
+
+    /// 检查向量维度，尚未记录时记录下来
+    fn check_dimensions(&mut self, got: usize) -> Result<()> {
+        match self.recorded_dimensions() {
+            Some(expected) if expected != got => Err(StoreError::DimensionMismatch {
+                expected,
+                got,
+                model: self.embedding_model.clone().unwrap_or_else(|| "unknown model".to_string()),
+            }),
+            Some(_) => Ok(()),
+            None => self.record_index_metadata(got),
+        }
+    }
+
+    /// 已记录的维度；旧版本创建的索引文件没有记录，当时固定使用默认维度
+    fn recorded_dimensions(&self) -> Option<usize> {
+        self.index_meta.as_ref().map(|d| d.dimensions).or_else(|| {
+            self.vector_index_path.exists().then(|| VectorIndexConfig::default().dimensions)
+        })
+    }
+
+    /// 写入维度记录 (已有记录时不覆盖)
+    fn record_index_metadata(&mut self, dimensions: usize) -> Result<()> {
+        if self.index_meta.is_some() {
+            return Ok(());
+        }
+        self.write_index_metadata(IndexMetadata {
+            dimensions,
+            metric: self.metric,
+            quantization: self.quantization,
+            model: self.embedding_model.clone(),
+        })
+    }
+
+    fn write_index_metadata(&mut self, meta: IndexMetadata) -> Result<()> {
+        let json = serde_json::to_string(&meta).map_err(std::io::Error::other)?;
+        std::fs::write(&self.index_meta_path, json)?;
+        self.index_meta = Some(meta);
+        Ok(())
+    }
+
+    /// 按已记录的维度创建/加载索引，已记录的度量或精度与设置不一致时报错
+    fn index_config(&self) -> Result<VectorIndexConfig> {
+        // 旧版本创建的索引文件没有记录，当时固定使用余弦与 f32
+        let legacy = self.vector_index_path.exists();
+        let recorded_metric = self.index_meta.as_ref().map(|m| m.metric)
+            .or_else(|| legacy.then_some(Metric::Cosine));
+        if let Some(expected) = recorded_metric.filter(|&m| m != self.metric) {
+            return Err(StoreError::MetricMismatch { expected, got: self.metric });
+        }
+        let recorded_quantization = self.index_meta.as_ref().map(|m| m.quantization)
+            .or_else(|| legacy.then_some(Quantization::F32));
+        if let Some(expected) = recorded_quantization.filter(|&q| q != self.quantization) {
+            return Err(StoreError::QuantizationMismatch { expected, got: self.quantization });
+        }
+
+        Ok(self.new_index_config(self.recorded_dimensions().unwrap_or(VectorIndexConfig::default().dimensions)))
+    }
+
+    /// 按当前设置 (不检查已有记录) 的索引配置
+    fn new_index_config(&self, dimensions: usize) -> VectorIndexConfig {
+        VectorIndexConfig {
+            dimensions,
+            metric: self.metric,
+            quantization: self.quantization,
+            ..Default::default()
         }
     }
 
     /// 插入或更新 CodeUnit，同时更新向量索引
+    ///
+    /// embedding 维度与索引不一致时返回 `StoreError::DimensionMismatch`，不写入任何内容
     pub fn upsert_code_unit(&mut self, record: &CodeUnitRecord) -> Result<()> {
+        let embedding = record.embedding.as_deref().and_then(bytes_to_embedding);
+        if let Some(ref embedding) = embedding {
+            self.check_dimensions(embedding.len())?;
+        }
+
         // 1. 写入数据库
         self.db.upsert_code_unit(record)?;
 
         // 2. 如果有 embedding，更新向量索引
-        if let Some(ref emb_bytes) = record.embedding {
-            if let Some(embedding) = bytes_to_embedding(emb_bytes) {
-                self.ensure_vector_index()?;
+        if let Some(embedding) = embedding {
+            self.ensure_vector_index()?;
 
-                let id = self.get_or_allocate_id(&record.qualified_name);
+            let id = self.get_or_allocate_id(&record.qualified_name)?;
 
-                // 确保容量足够
-                let index = self.vector_index.as_ref().unwrap();
-                if index.size() >= index.capacity() {
-                    index.reserve(index.capacity() + 1000)?;
-                }
-
-                // 如果已存在，先删除
-                if index.contains(id) {
-                    index.remove(id)?;
-                }
+            // 确保容量足够
+            let index = self.vector_index.as_ref().unwrap();
+            if index.size() >= index.capacity() {
+                index.reserve(index.capacity() + 1000)?;
+            }
 
-                // 添加新向量
-                let vec: Vec<f32> = embedding.to_vec();
-                index.add(id, &vec)?;
+            // 如果已存在，先删除
+            if index.contains(id) {
+                index.remove(id)?;
             }
+
+            // 添加新向量
+            let vec: Vec<f32> = embedding.to_vec();
+            index.add(id, &vec)?;
         }
 
         Ok(())
     }
 
+    /// 删除单个 CodeUnit 及其向量与相似配对，返回是否存在
+    pub fn remove_unit(&mut self, qualified_name: &str) -> Result<bool> {
+        self.load_vector_index()?;
+        self.remove_vector(qualified_name)?;
+        self.db.delete_pairs_involving(qualified_name)?;
+        Ok(self.db.delete_code_unit(qualified_name)?)
+    }
+
+    /// 删除文件的所有 CodeUnit 及其向量与相似配对 (文件已删除时使用)，返回删除的单元数
+    pub fn remove_units_by_file(&mut self, file_path: &str) -> Result<usize> {
+        self.load_vector_index()?;
+        let units = self.db.get_code_units_by_file(file_path)?;
+        for unit in &units {
+            self.remove_vector(&unit.qualified_name)?;
+            self.db.delete_pairs_involving(&unit.qualified_name)?;
+        }
+        self.db.delete_code_units_by_file(file_path)?;
+        Ok(units.len())
+    }
+
+    /// 删除项目及其全部代码单元、向量、相似配对与分组，并保存向量索引，返回删除的单元数
+    ///
+    /// 项目已不存在时返回 0
+    pub fn remove_project(&mut self, project_id: i64) -> Result<usize> {
+        self.load_vector_index()?;
+        for unit in self.db.get_code_units_by_project(project_id)? {
+            self.remove_vector(&unit.qualified_name)?;
+        }
+        let removed = self.db.delete_project(project_id)?;
+        self.save_vector_index()?;
+        Ok(removed)
+    }
+
+    /// 从向量索引与映射中移除单元 (ID 按数据库查找，映射未加载时也能删除)
+    fn remove_vector(&mut self, qualified_name: &str) -> Result<()> {
+        let Some(id) = self.db.get_vector_id(qualified_name)? else {
+            return Ok(());
+        };
+        self.name_to_id.remove(qualified_name);
+        self.id_to_name.remove(&id);
+        if let Some(ref index) = self.vector_index {
+            if index.contains(id) {
+                index.remove(id)?;
+            }
+        }
+        Ok(())
+    }
+
     /// ANN 搜索相似代码单元
+    ///
+    /// `k` 为 None 时按阈值做半径搜索，返回阈值以上的全部近邻 (最多 `MAX_RADIUS_RESULTS` 个)，
+    /// 不会因为密集簇中更相似的近邻超过 k 个而漏掉
     pub fn search_similar(
         &self,
         query_embedding: &[f32],
-        k: usize,
+        k: Option<usize>,
         threshold: f32,
     ) -> Result<Vec<SimilarUnit>> {
         let index = self.vector_index.as_ref()
             .ok_or(StoreError::VectorIndexNotInitialized)?;
 
         // ANN 搜索
-        let results = index.search(query_embedding, k)?;
+        let results = match k {
+            Some(k) => index.search(query_embedding, k)?,
+            None => index.search_radius(query_embedding, threshold, MAX_RADIUS_RESULTS)?,
+        };
 
         // 转换为 SimilarUnit
         let mut similar_units = Vec::new();
@@ -197,8 +537,10 @@ impl Store {
                         qualified_name: unit.qualified_name,
                         file_path: unit.file_path,
                         range_start: unit.range_start,
+                        range_end: unit.range_end,
                         project_id: unit.project_id,
                         similarity,
+                        distance: result.distance,
                     });
                 }
             }
@@ -233,41 +575,178 @@ impl Store {
             .collect())
     }
 
+    /// 批量并行 ANN 搜索，按查询分组返回
+    /// 外层下标与 `queries` 一一对应，没有结果的查询对应空 Vec
+    ///
+    /// rayon 计算会阻塞调用线程，异步上下文中请使用 [`Store::search_similar_grouped_async`]
+    pub fn search_similar_grouped(
+        &self,
+        queries: &[&[f32]],
+        k: usize,
+        threshold: f32,
+    ) -> Result<Vec<Vec<(String, f32)>>> {
+        let index = self.vector_index.as_ref()
+            .ok_or(StoreError::VectorIndexNotInitialized)?;
+
+        let groups = search_ids_parallel(index, queries, k, threshold);
+        Ok(similarity_groups(self.resolve_groups(groups)))
+    }
+
+    /// `search_similar_grouped` 的异步版本：搜索在 `spawn_blocking` 线程中进行，不阻塞 tokio runtime
+    ///
+    /// 查询向量需要移交给阻塞线程，因此按值传入
+    pub async fn search_similar_grouped_async(
+        &self,
+        queries: Vec<Vec<f32>>,
+        k: usize,
+        threshold: f32,
+    ) -> Result<Vec<Vec<(String, f32)>>> {
+        Ok(similarity_groups(self.search_results_grouped_async(queries, k, threshold).await?))
+    }
+
+    /// 同 [`Store::search_similar_grouped_async`]，但保留完整的 [`SearchResult`] (含原始距离)
+    pub async fn search_results_grouped_async(
+        &self,
+        queries: Vec<Vec<f32>>,
+        k: usize,
+        threshold: f32,
+    ) -> Result<Vec<Vec<(String, SearchResult)>>> {
+        let index = Arc::clone(
+            self.vector_index.as_ref().ok_or(StoreError::VectorIndexNotInitialized)?,
+        );
+
+        let groups = tokio::task::spawn_blocking(move || {
+            let queries: Vec<&[f32]> = queries.iter().map(Vec::as_slice).collect();
+            search_ids_parallel(&index, &queries, k, threshold)
+        })
+        .await?;
+
+        Ok(self.resolve_groups(groups))
+    }
+
     /// 批量并行 ANN 搜索（接受切片引用，避免克隆）
     /// 返回 Vec<(query_index, qualified_name, similarity)>
-    pub fn search_batch_parallel<'a>(
+    ///
+    /// 需要按查询处理结果时使用 [`Store::search_similar_grouped`]
+    pub fn search_batch_parallel(
         &self,
-        queries: &[(usize, &'a [f32])], // (index, embedding slice)
+        queries: &[(usize, &[f32])], // (index, embedding slice)
         k: usize,
         threshold: f32,
     ) -> Result<Vec<(usize, String, f32)>> {
-        let index = self.vector_index.as_ref()
-            .ok_or(StoreError::VectorIndexNotInitialized)?;
+        let embeddings: Vec<&[f32]> = queries.iter().map(|(_, emb)| *emb).collect();
+        let groups = self.search_similar_grouped(&embeddings, k, threshold)?;
+        Ok(flatten_groups(queries.iter().map(|(query_idx, _)| *query_idx), groups))
+    }
+
+    /// `search_batch_parallel` 的异步版本，见 [`Store::search_similar_grouped_async`]
+    pub async fn search_batch_parallel_async(
+        &self,
+        queries: Vec<(usize, Vec<f32>)>,
+        k: usize,
+        threshold: f32,
+    ) -> Result<Vec<(usize, String, f32)>> {
+        let (indices, embeddings): (Vec<usize>, Vec<Vec<f32>>) = queries.into_iter().unzip();
+        let groups = self.search_similar_grouped_async(embeddings, k, threshold).await?;
+        Ok(flatten_groups(indices, groups))
+    }
+
+    /// 向量 ID 转为 qualified_name，丢弃已没有映射的 ID
+    fn resolve_groups(&self, groups: Vec<Vec<SearchResult>>) -> Vec<Vec<(String, SearchResult)>> {
+        groups.into_iter()
+            .map(|hits| {
+                hits.into_iter()
+                    .filter_map(|result| {
+                        self.id_to_name.get(&result.id).map(|name| (name.clone(), result))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// 构建 kNN 相似度图：对每个有 embedding 的单元做 ANN 搜索
+    /// 返回去重后的边 (name_a, name_b, similarity)，name_a < name_b
+    pub fn search_knn_graph(&self, k: usize, threshold: f32) -> Result<Vec<(String, String, f32)>> {
+        let units = self.db.get_code_units_by_projects(None)?;
 
-        // 直接引用映射表（&HashMap 是 Sync 的）
-        let id_to_name = &self.id_to_name;
-
-        let results: Vec<_> = queries
-            .par_iter()
-            .flat_map(|(query_idx, emb)| {
-                match index.search(*emb, k) {
-                    Ok(hits) => hits
-                        .into_iter()
-                        .filter_map(|r| {
-                            let similarity = r.similarity();
-                            if similarity >= threshold {
-                                id_to_name.get(&r.id).map(|name| (*query_idx, name.clone(), similarity))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>(),
-                    Err(_) => vec![],
+        let embedded: Vec<(String, Vec<f32>)> = units
+            .into_iter()
+            .filter_map(|u| {
+                let emb = bytes_to_embedding(u.embedding.as_ref()?)?;
+                Some((u.qualified_name, emb.to_vec()))
+            })
+            .collect();
+
+        let queries: Vec<&[f32]> = embedded.iter().map(|(_, emb)| emb.as_slice()).collect();
+
+        // k + 1：结果中包含自身
+        let groups = self.search_similar_grouped(&queries, k + 1, threshold)?;
+
+        let mut seen = std::collections::HashSet::new();
+        let mut edges = Vec::new();
+        for ((source, _), hits) in embedded.iter().zip(groups) {
+            for (name, similarity) in hits {
+                if *source == name {
+                    continue;
                 }
+                let (a, b) = if *source < name { (source.clone(), name) } else { (name, source.clone()) };
+                if seen.insert((a.clone(), b.clone())) {
+                    edges.push((a, b, similarity));
+                }
+            }
+        }
+
+        Ok(edges)
+    }
+
+    /// 查找互相相似的代码簇：每个单元取 `k` 个近邻构成 kNN 图，取阈值以上的连通分量
+    /// 返回成员数 >= min_size 的簇，按大小降序
+    pub fn cluster(&self, k: usize, threshold: f32, min_size: usize) -> Result<Vec<Vec<String>>> {
+        let edges = self.search_knn_graph(k, threshold)?;
+
+        // union-find
+        let mut index: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+        for (a, b, _) in &edges {
+            for name in [a.as_str(), b.as_str()] {
+                let next = index.len();
+                index.entry(name).or_insert(next);
+            }
+        }
+
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+
+        let mut parent: Vec<usize> = (0..index.len()).collect();
+        for (a, b, _) in &edges {
+            let ra = find(&mut parent, index[a.as_str()]);
+            let rb = find(&mut parent, index[b.as_str()]);
+            if ra != rb {
+                parent[ra] = rb;
+            }
+        }
+
+        let mut components: std::collections::HashMap<usize, Vec<String>> = std::collections::HashMap::new();
+        for (name, &i) in &index {
+            let root = find(&mut parent, i);
+            components.entry(root).or_default().push(name.to_string());
+        }
+
+        let mut clusters: Vec<Vec<String>> = components
+            .into_values()
+            .filter(|c| c.len() >= min_size)
+            .map(|mut c| {
+                c.sort();
+                c
             })
             .collect();
+        clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
 
-        Ok(results)
+        Ok(clusters)
     }
 
     /// 带过滤的 ANN 搜索
@@ -311,8 +790,10 @@ impl Store {
                         qualified_name: unit.qualified_name,
                         file_path: unit.file_path,
                         range_start: unit.range_start,
+                        range_end: unit.range_end,
                         project_id: unit.project_id,
                         similarity,
+                        distance: result.distance,
                     });
                 }
             }
@@ -321,6 +802,122 @@ impl Store {
         Ok(similar_units)
     }
 
+    /// 导入另一个数据库的项目与代码单元，把其向量合并进本索引并保存，返回导入的单元数
+    ///
+    /// 源数据库只读打开：不迁移，也不写入其 `.usearch.json`，版本落后时返回
+    /// `StoreError::OutdatedSource` (先用 `Store::open` 打开一次完成迁移)。
+    /// 导入的向量 ID 整体偏移到本库最大 ID 之后；任一 qualified_name 已存在时返回
+    /// `StoreError::DuplicateUnits`，不写入任何内容。向量先写入索引，数据库写入失败时撤回。
+    /// 相似配对与分组不导入，需要重新计算
+    pub fn import_from(&mut self, other_db_path: &Path) -> Result<usize> {
+        let other_db = Database::open_readonly(other_db_path)?;
+        let version = other_db.schema_version()?;
+        if version < SCHEMA_VERSION {
+            return Err(StoreError::OutdatedSource { path: other_db_path.to_path_buf(), version });
+        }
+        let units = other_db.get_code_units_by_projects(None)?;
+        if units.is_empty() {
+            return Ok(0);
+        }
+
+        let mut duplicates = Vec::new();
+        for unit in &units {
+            if self.db.get_code_unit(&unit.qualified_name)?.is_some() {
+                duplicates.push(unit.qualified_name.clone());
+            }
+        }
+        if !duplicates.is_empty() {
+            return Err(StoreError::DuplicateUnits(duplicates));
+        }
+
+        // 旧版本的索引文件没有记录，当时固定使用默认维度、余弦与 f32
+        let other_index_path = other_db_path.with_extension("usearch");
+        let other_meta = read_index_metadata(&other_db_path.with_extension("usearch.json")).or_else(|| {
+            other_index_path.exists().then(|| IndexMetadata {
+                dimensions: VectorIndexConfig::default().dimensions,
+                metric: Metric::Cosine,
+                quantization: Quantization::F32,
+                model: None,
+            })
+        });
+        let dimensions = other_meta.as_ref().map(|m| m.dimensions).or_else(|| {
+            units.iter().find_map(|u| u.embedding.as_deref().and_then(bytes_to_embedding)).map(|e| e.len())
+        });
+        if let Some(dimensions) = dimensions {
+            if self.embedding_model.is_none() {
+                self.embedding_model = other_meta.as_ref().and_then(|m| m.model.clone());
+            }
+            self.check_dimensions(dimensions)?;
+        }
+        self.ensure_vector_index()?;
+
+        // 对方尚未分配 ID 的单元 (没有构建过索引) 接在其最大 ID 之后，不修改对方数据库
+        let offset = self.db.max_vector_id()?;
+        let mut other_ids: std::collections::HashMap<String, u64> = other_db.get_vector_ids()?.into_iter().collect();
+        let mut next_id = other_db.max_vector_id()?;
+        let mut records = Vec::with_capacity(units.len());
+        for unit in units {
+            let other_id = *other_ids.entry(unit.qualified_name.clone()).or_insert_with(|| {
+                next_id += 1;
+                next_id
+            });
+            records.push((CodeUnitRecord { group_id: None, ..unit }, other_id + offset));
+        }
+
+        // 先写向量：失败时删除已写入的部分，数据库保持不变
+        let index = Arc::clone(self.vector_index.as_ref().unwrap());
+        if let Some(&(_, id)) = records.iter().find(|(_, id)| index.contains(*id)) {
+            return Err(crate::vector_index::VectorIndexError::DuplicateId(id).into());
+        }
+        let other_index = other_meta.and_then(|meta| {
+            let config = VectorIndexConfig {
+                dimensions: meta.dimensions,
+                metric: meta.metric,
+                quantization: meta.quantization,
+                ..Default::default()
+            };
+            VectorIndex::load_with_config(&other_index_path, config)
+                .map_err(|e| tracing::warn!("Ignoring vector index of {}: {}", other_db_path.display(), e))
+                .ok()
+        });
+        let rollback = |records: &[(CodeUnitRecord, u64)]| {
+            for (_, id) in records {
+                if index.contains(*id) {
+                    let _ = index.remove(*id);
+                }
+            }
+        };
+        if let Err(e) = add_imported_vectors(&index, other_index.as_ref(), &records, offset) {
+            rollback(&records);
+            return Err(e);
+        }
+
+        // 项目按 root_path 合并
+        let imported = (|| {
+            let mut project_ids = std::collections::HashMap::new();
+            for project in other_db.get_all_projects()? {
+                let id = self.db.get_or_create_project(&project.name, &project.root_path, &project.language)?;
+                if let (Some(model), Some(dims)) = (&project.embedding_model, project.embedding_dimensions) {
+                    let prefix = project.embedding_prefix.as_deref().unwrap_or_default();
+                    self.db.set_project_model(id, model, dims, prefix)?;
+                }
+                project_ids.insert(project.id, id);
+            }
+            for (unit, _) in records.iter_mut() {
+                unit.project_id = project_ids.get(&unit.project_id).copied().unwrap_or(unit.project_id);
+            }
+            self.db.import_code_units(&records)
+        })();
+        if let Err(e) = imported {
+            rollback(&records);
+            return Err(e.into());
+        }
+
+        self.rebuild_mappings()?;
+        self.save_vector_index()?;
+        Ok(records.len())
+    }
+
     /// 保存向量索引
     pub fn save_vector_index(&self) -> Result<()> {
         if let Some(ref index) = self.vector_index {
@@ -331,35 +928,77 @@ impl Store {
 
     /// 从现有数据库重建向量索引
     pub fn rebuild_vector_index(&mut self) -> Result<usize> {
-        let units = self.db.get_code_units_by_projects(None)?;
+        // 先等待进行中的后台构建，避免之后被旧结果覆盖
+        self.wait_vector_index()?;
+
+        // 重建时可以切换度量与精度，不检查已有记录
+        self.db.assign_missing_vector_ids()?;
+        let dimensions = self.index_meta.as_ref().map(|d| d.dimensions);
+        let config = self.new_index_config(dimensions.unwrap_or(VectorIndexConfig::default().dimensions));
+        let (index, count) = build_vector_index(&self.db, dimensions, config)?;
+
+        // 重新加载 mapping (ID 来自数据库，重建前后保持一致)
+        self.rebuild_mappings()?;
+        let model = self.index_meta.as_ref().and_then(|m| m.model.clone()).or_else(|| self.embedding_model.clone());
+        self.write_index_metadata(IndexMetadata {
+            dimensions: index.dimensions(),
+            metric: self.metric,
+            quantization: self.quantization,
+            model,
+        })?;
+        self.vector_index = Some(Arc::new(index));
+        self.save_vector_index()?;
 
-        // 重建 mapping
-        self.name_to_id.clear();
-        self.id_to_name.clear();
-        self.next_id = 1;
+        Ok(count)
+    }
 
-        // 创建新索引
-        let index = VectorIndex::with_defaults()?;
-        index.reserve(units.len() + 1000)?;
+    /// 检查数据库与向量索引是否一致 (只加载已有索引，不会构建)
+    pub fn verify(&mut self) -> Result<VerifyReport> {
+        // 不经过条目数检查，否则过期的索引会被直接重建，漂移无从报告
+        self.wait_vector_index()?;
+        let mut report = VerifyReport {
+            index_loaded: self.vector_index.is_some() || self.try_load_vector_index_unchecked()?,
+            ..Default::default()
+        };
+        if !report.index_loaded {
+            // 没有索引文件时映射未加载，按数据库中的 ID 判断
+            self.rebuild_mappings()?;
+        }
 
-        let mut count = 0;
-        for unit in &units {
-            // 分配 ID 并更新 mapping
-            let id = self.get_or_allocate_id(&unit.qualified_name);
-
-            if let Some(ref emb_bytes) = unit.embedding {
-                if let Some(embedding) = bytes_to_embedding(emb_bytes) {
-                    let vec: Vec<f32> = embedding.to_vec();
-                    index.add(id, &vec)?;
-                    count += 1;
+        let index = self.vector_index.as_ref();
+        report.index_size = index.map_or(0, |idx| idx.size());
+        report.index_dimensions = index.map_or(self.index_config()?.dimensions, |idx| idx.dimensions());
+        let in_index = |id: u64| index.is_some_and(|idx| idx.contains(id));
+
+        let mut live_ids = std::collections::HashSet::new();
+        for unit in self.db.get_code_units_by_projects(None)? {
+            let Some(ref emb_bytes) = unit.embedding else { continue };
+            report.units_with_embeddings += 1;
+
+            let dimensions = bytes_to_embedding(emb_bytes).map_or(0, |e| e.len());
+            if dimensions != report.index_dimensions {
+                report.dimension_mismatches.push((unit.qualified_name, dimensions));
+                continue;
+            }
+
+            match self.name_to_id.get(&unit.qualified_name) {
+                Some(&id) if in_index(id) => {
+                    live_ids.insert(id);
                 }
+                _ => report.missing_vectors.push(unit.qualified_name),
             }
         }
 
-        self.vector_index = Some(index);
-        self.save_vector_index()?;
+        // ID 由数据库连续分配，逐个检查是否残留在索引中
+        for id in 1..=self.db.max_vector_id()? {
+            if in_index(id) && !live_ids.contains(&id) {
+                report.orphan_vectors.push(id);
+            }
+        }
 
-        Ok(count)
+        report.missing_vectors.sort();
+        report.dimension_mismatches.sort();
+        Ok(report)
     }
 
     /// 获取向量索引统计
@@ -367,6 +1006,11 @@ impl Store {
         self.vector_index.as_ref().map(|idx| (idx.size(), idx.memory_usage()))
     }
 
+    /// 数据库文件路径 (`.usearch` 与 `.usearch.json` 在同目录)
+    pub fn db_path(&self) -> &Path {
+        &self.db_path
+    }
+
     /// 获取底层数据库引用
     pub fn db(&self) -> &Database {
         &self.db
@@ -378,10 +1022,119 @@ impl Store {
     }
 }
 
+/// 读取维度记录，文件不存在或无法解析时返回 None
+fn read_index_metadata(path: &Path) -> Option<IndexMetadata> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| tracing::warn!("Ignoring invalid {}: {}", path.display(), e))
+        .ok()
+}
+
+/// 把导入单元的向量写入 `index`：`other` 可用且度量一致时直接复制，其余从 embedding 字段补齐
+fn add_imported_vectors(
+    index: &VectorIndex,
+    other: Option<&VectorIndex>,
+    records: &[(CodeUnitRecord, u64)],
+    offset: u64,
+) -> Result<()> {
+    let with_embeddings: Vec<(&CodeUnitRecord, u64)> = records.iter()
+        .filter(|(unit, _)| unit.embedding.is_some())
+        .map(|(unit, id)| (unit, id - offset))
+        .collect();
+    if let Some(other) = other.filter(|other| other.metric() == index.metric()) {
+        let keys: Vec<u64> = with_embeddings.iter()
+            .map(|&(_, id)| id)
+            .filter(|&id| other.contains(id))
+            .collect();
+        index.merge(other, &keys, offset)?;
+    }
+    for (unit, other_id) in with_embeddings {
+        let id = other_id + offset;
+        if index.contains(id) {
+            continue;
+        }
+        let Some(embedding) = unit.embedding.as_deref().and_then(bytes_to_embedding) else { continue };
+        if index.size() >= index.capacity() {
+            index.reserve(index.capacity() + 1000)?;
+        }
+        index.add(id, &embedding.to_vec())?;
+    }
+    Ok(())
+}
+
+/// 并行执行多个 ANN 查询，按查询顺序返回相似度在阈值以上的结果
+fn search_ids_parallel(
+    index: &VectorIndex,
+    queries: &[&[f32]],
+    k: usize,
+    threshold: f32,
+) -> Vec<Vec<SearchResult>> {
+    queries
+        .par_iter()
+        .map(|emb| match index.search(emb, k) {
+            Ok(hits) => hits
+                .into_iter()
+                .filter(|r| r.similarity() >= threshold)
+                .collect(),
+            Err(_) => vec![],
+        })
+        .collect()
+}
+
+/// 分组结果只保留 (qualified_name, similarity)
+fn similarity_groups(groups: Vec<Vec<(String, SearchResult)>>) -> Vec<Vec<(String, f32)>> {
+    groups.into_iter()
+        .map(|hits| hits.into_iter().map(|(name, r)| (name, r.similarity())).collect())
+        .collect()
+}
+
+/// 分组结果展开为 (query_index, qualified_name, similarity)，`indices` 与分组一一对应
+fn flatten_groups(
+    indices: impl IntoIterator<Item = usize>,
+    groups: Vec<Vec<(String, f32)>>,
+) -> Vec<(usize, String, f32)> {
+    indices.into_iter()
+        .zip(groups)
+        .flat_map(|(query_idx, hits)| {
+            hits.into_iter().map(move |(name, similarity)| (query_idx, name, similarity))
+        })
+        .collect()
+}
+
+/// 从数据库构建向量索引 (只读，向量 ID 需已分配)，返回 (索引, 向量数)
+///
+/// `dimensions` 为 None (尚无维度记录) 时取第一个 embedding 的维度，否则使用 `config` 的设置
+fn build_vector_index(db: &Database, dimensions: Option<usize>, config: VectorIndexConfig) -> Result<(VectorIndex, usize)> {
+    let units = db.get_code_units_by_projects(None)?;
+    let ids: std::collections::HashMap<String, u64> = db.get_vector_ids()?.into_iter().collect();
+
+    let dimensions = dimensions
+        .or_else(|| units.iter().find_map(|u| u.embedding.as_deref().and_then(bytes_to_embedding)).map(|e| e.len()))
+        .unwrap_or(config.dimensions);
+    let index = VectorIndex::new(VectorIndexConfig { dimensions, ..config })?;
+    index.reserve(units.len() + 1000)?;
+
+    let mut count = 0;
+    for unit in &units {
+        let Some(&id) = ids.get(&unit.qualified_name) else { continue };
+
+        if let Some(ref emb_bytes) = unit.embedding {
+            if let Some(embedding) = bytes_to_embedding(emb_bytes) {
+                let vec: Vec<f32> = embedding.to_vec();
+                index.add(id, &vec)?;
+                count += 1;
+            }
+        }
+    }
+
+    Ok((index, count))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::embedding::embedding_to_bytes;
+    use ndarray::Array1;
     use tempfile::tempdir;
 
     fn create_test_embedding(seed: f32) -> Vec<f32> {
@@ -389,6 +1142,14 @@ mod tests {
         (0..1024).map(|i| (i as f32 * seed).sin()).collect()
     }
 
+    /// 带 embedding 的测试单元 `rust::test::<name>`，位于 `/test/src/<name>.rs`
+    fn embedded_record(name: &str, project_id: i64, embedding: Vec<f32>) -> CodeUnitRecord {
+        CodeUnitRecord {
+            embedding: Some(embedding_to_bytes(&embedding.into())),
+            ..CodeUnitRecord::fixture(&format!("rust::test::{}", name), project_id, &format!("/test/src/{}.rs", name))
+        }
+    }
+
     #[test]
     fn test_store_basic() {
         let dir = tempdir().unwrap();
@@ -412,12 +1173,13 @@ mod tests {
             structure_hash: "def456".to_string(),
             embedding: Some(embedding_to_bytes(&emb.clone().into())),
             group_id: None,
+            signature: None,
         };
 
         store.upsert_code_unit(&record).unwrap();
 
         // 搜索
-        let results = store.search_similar(&emb, 10, 0.5).unwrap();
+        let results = store.search_similar(&emb, Some(10), 0.5).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].qualified_name, "rust::test::foo");
         assert!(results[0].similarity > 0.99); // 自己和自己的相似度应该接近 1
@@ -445,6 +1207,7 @@ mod tests {
                 structure_hash: format!("struct_{}", i),
                 embedding: Some(embedding_to_bytes(&emb.into())),
                 group_id: None,
+                signature: None,
             };
             store.upsert_code_unit(&record).unwrap();
         }
@@ -462,6 +1225,236 @@ mod tests {
         assert!(!results.iter().any(|r| r.qualified_name.contains("func_0")));
     }
 
+    #[test]
+    fn test_store_cluster() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+
+        // a/b/c 使用相同 embedding，d/e 各不相同
+        let seeds = [("a", 1.0), ("b", 1.0), ("c", 1.0), ("d", 7.3), ("e", 13.9)];
+        for (i, (name, seed)) in seeds.iter().enumerate() {
+            let emb = create_test_embedding(*seed);
+            let record = CodeUnitRecord {
+                qualified_name: format!("rust::test::{}", name),
+                project_id,
+                file_path: "/test/src/lib.rs".to_string(),
+                kind: "function".to_string(),
+                range_start: i as u32 * 10,
+                range_end: i as u32 * 10 + 10,
+                content_hash: format!("hash_{}", name),
+                structure_hash: format!("struct_{}", name),
+                embedding: Some(embedding_to_bytes(&emb.into())),
+                group_id: None,
+                signature: None,
+            };
+            store.upsert_code_unit(&record).unwrap();
+        }
+
+        let clusters = store.cluster(DEFAULT_CLUSTER_CANDIDATES, 0.95, 2).unwrap();
+        assert_eq!(clusters, vec![vec![
+            "rust::test::a".to_string(),
+            "rust::test::b".to_string(),
+            "rust::test::c".to_string(),
+        ]]);
+
+        // min_size 过大时没有结果
+        assert!(store.cluster(DEFAULT_CLUSTER_CANDIDATES, 0.95, 4).unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_batch_parallel_async_matches_sync() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+
+        let seeds = [1.0, 1.0, 7.3, 13.9];
+        for (i, seed) in seeds.iter().enumerate() {
+            let record = CodeUnitRecord {
+                qualified_name: format!("rust::test::f{}", i),
+                project_id,
+                file_path: "/test/src/lib.rs".to_string(),
+                kind: "function".to_string(),
+                range_start: i as u32 * 10,
+                range_end: i as u32 * 10 + 10,
+                content_hash: format!("hash_{}", i),
+                structure_hash: format!("struct_{}", i),
+                embedding: Some(embedding_to_bytes(&create_test_embedding(*seed).into())),
+                group_id: None,
+                signature: None,
+            };
+            store.upsert_code_unit(&record).unwrap();
+        }
+
+        let embeddings: Vec<Vec<f32>> = seeds.iter().map(|&seed| create_test_embedding(seed)).collect();
+        let queries: Vec<(usize, &[f32])> = embeddings.iter()
+            .enumerate()
+            .map(|(i, emb)| (i, emb.as_slice()))
+            .collect();
+
+        let mut sync = store.search_batch_parallel(&queries, 10, 0.5).unwrap();
+
+        // 保留原始距离的分组结果与相似度一致
+        let results = store.search_results_grouped_async(embeddings.clone(), 10, 0.5).await.unwrap();
+        assert_eq!(results.len(), seeds.len());
+        for (_, result) in results.iter().flatten() {
+            assert!((result.metric.distance(result.similarity()) - result.distance).abs() < 1e-6);
+        }
+
+        let mut async_results = store
+            .search_batch_parallel_async(embeddings.into_iter().enumerate().collect(), 10, 0.5)
+            .await
+            .unwrap();
+
+        let key = |r: &(usize, String, f32)| (r.0, r.1.clone());
+        sync.sort_by_key(key);
+        async_results.sort_by_key(key);
+        assert_eq!(sync, async_results);
+        assert!(sync.iter().any(|(i, name, _)| *i == 0 && name == "rust::test::f1"));
+
+        // 索引未初始化时报错
+        let empty = Store::open(&dir.path().join("empty.db")).unwrap();
+        assert!(matches!(
+            empty.search_batch_parallel_async(vec![(0, vec![0.0; 4])], 10, 0.5).await,
+            Err(StoreError::VectorIndexNotInitialized)
+        ));
+    }
+
+    #[test]
+    fn test_search_similar_grouped() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+
+        for (i, seed) in [1.0, 7.3].iter().enumerate() {
+            let record = CodeUnitRecord {
+                qualified_name: format!("rust::test::f{}", i),
+                project_id,
+                file_path: "/test/src/lib.rs".to_string(),
+                kind: "function".to_string(),
+                range_start: i as u32 * 10,
+                range_end: i as u32 * 10 + 10,
+                content_hash: format!("hash_{}", i),
+                structure_hash: format!("struct_{}", i),
+                embedding: Some(embedding_to_bytes(&create_test_embedding(*seed).into())),
+                group_id: None,
+                signature: None,
+            };
+            store.upsert_code_unit(&record).unwrap();
+        }
+
+        let f0 = create_test_embedding(1.0);
+        let f1 = create_test_embedding(7.3);
+        let unrelated = create_test_embedding(29.7);
+        let queries: Vec<&[f32]> = vec![&f1, &unrelated, &f0];
+
+        let groups = store.search_similar_grouped(&queries, 10, 0.99).unwrap();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].len(), 1);
+        assert_eq!(groups[0][0].0, "rust::test::f1");
+        // 没有结果的查询对应空 Vec，不影响后续下标
+        assert!(groups[1].is_empty());
+        assert_eq!(groups[2].len(), 1);
+        assert_eq!(groups[2][0].0, "rust::test::f0");
+
+        // 扁平版本保留调用方给出的查询编号
+        let flat = store.search_batch_parallel(&[(7, &f0), (9, &unrelated)], 10, 0.99).unwrap();
+        assert_eq!(flat.len(), 1);
+        assert_eq!((flat[0].0, flat[0].1.as_str()), (7, "rust::test::f0"));
+    }
+
+    #[test]
+    fn test_store_open_is_lazy() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        {
+            let mut store = Store::open(&db_path).unwrap();
+            let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+            let emb = create_test_embedding(1.0);
+            let record = CodeUnitRecord {
+                qualified_name: "rust::test::foo".to_string(),
+                project_id,
+                file_path: "/test/src/lib.rs".to_string(),
+                kind: "function".to_string(),
+                range_start: 10,
+                range_end: 20,
+                content_hash: "abc123".to_string(),
+                structure_hash: "def456".to_string(),
+                embedding: Some(embedding_to_bytes(&emb.into())),
+                group_id: None,
+                signature: None,
+            };
+            // 只写数据库，不生成 .usearch 文件
+            store.db_mut().upsert_code_unit(&record).unwrap();
+        }
+
+        // open 不加载也不构建索引
+        let mut store = Store::open(&db_path).unwrap();
+        assert!(store.vector_index_stats().is_none());
+        assert!(!store.load_vector_index().unwrap());
+        assert!(store.vector_index_stats().is_none());
+
+        // 显式调用时从数据库构建
+        store.ensure_vector_index().unwrap();
+        assert_eq!(store.vector_index_stats().unwrap().0, 1);
+
+        // open_with_index 直接加载已保存的索引
+        let store = Store::open_with_index(&db_path).unwrap();
+        let results = store.search_similar(&create_test_embedding(1.0), Some(10), 0.5).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_store_background_index_build() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        for i in 0..4 {
+            let emb = create_test_embedding(i as f32 + 1.0);
+            let record = CodeUnitRecord {
+                qualified_name: format!("rust::test::func_{}", i),
+                project_id,
+                file_path: "/test/src/lib.rs".to_string(),
+                kind: "function".to_string(),
+                range_start: i * 10,
+                range_end: i * 10 + 10,
+                content_hash: format!("hash_{}", i),
+                structure_hash: format!("struct_{}", i),
+                embedding: Some(embedding_to_bytes(&emb.into())),
+                group_id: None,
+                signature: None,
+            };
+            // 只写数据库，不生成 .usearch 文件
+            store.db_mut().upsert_code_unit(&record).unwrap();
+        }
+
+        let mut store = Store::open(&db_path).unwrap();
+        store.ensure_vector_index_background().unwrap();
+        // 换入前索引不可用
+        assert!(store.vector_index_stats().is_none());
+
+        assert!(store.wait_vector_index().unwrap());
+        assert_eq!(store.vector_index_stats().unwrap().0, 4);
+        assert!(store.poll_vector_index().unwrap());
+
+        let results = store.search_similar(&create_test_embedding(1.0), Some(10), 0.99).unwrap();
+        assert_eq!(results[0].qualified_name, "rust::test::func_0");
+
+        // 构建线程已保存索引文件
+        let mut store = Store::open(&db_path).unwrap();
+        assert!(store.load_vector_index().unwrap());
+        assert_eq!(store.vector_index_stats().unwrap().0, 4);
+    }
+
     #[test]
     fn test_store_rebuild_index() {
         let dir = tempdir().unwrap();
@@ -484,6 +1477,7 @@ mod tests {
                 structure_hash: format!("struct_{}", i),
                 embedding: Some(embedding_to_bytes(&emb.into())),
                 group_id: None,
+                signature: None,
             };
             store.db_mut().upsert_code_unit(&record).unwrap();
         }
@@ -502,12 +1496,452 @@ mod tests {
         eprintln!("Raw usearch results: {:?}", raw_results);
 
         // 验证可以搜索
-        let results = store.search_similar(&query, 10, 0.0).unwrap();
+        let results = store.search_similar(&query, Some(10), 0.0).unwrap();
         eprintln!("Search results: {:?}", results);
 
         // 如果 usearch 返回少于 3 个，就检查实际数量
-        assert!(results.len() >= 1, "Should have at least 1 result");
+        assert!(!results.is_empty(), "Should have at least 1 result");
         // 至少应该找到最相似的那个（完全匹配）
         assert_eq!(results[0].qualified_name, "rust::test::func_0");
     }
+
+    #[test]
+    fn test_larger_candidates_recover_missed_pair() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+
+        let base = create_test_embedding(1.0);
+        let mix = |other: Vec<f32>, weight: f32| -> Vec<f32> {
+            base.iter().zip(other).map(|(b, o)| b + weight * o).collect()
+        };
+
+        // 比 target 更相似的近邻数量超过默认 k
+        let mut embeddings: Vec<(String, Vec<f32>)> = (0..DEFAULT_CANDIDATES + 20)
+            .map(|i| (format!("rust::test::near_{}", i), mix(create_test_embedding(10.0 + i as f32), 0.01)))
+            .collect();
+        embeddings.push(("rust::test::target".to_string(), mix(create_test_embedding(3.7), 0.3)));
+
+        for (i, (name, emb)) in embeddings.into_iter().enumerate() {
+            let record = CodeUnitRecord {
+                qualified_name: name.clone(),
+                project_id,
+                file_path: "/test/src/lib.rs".to_string(),
+                kind: "function".to_string(),
+                range_start: i as u32 * 10,
+                range_end: i as u32 * 10 + 10,
+                content_hash: format!("hash_{}", name),
+                structure_hash: format!("struct_{}", name),
+                embedding: Some(embedding_to_bytes(&emb.into())),
+                group_id: None,
+                signature: None,
+            };
+            store.upsert_code_unit(&record).unwrap();
+        }
+
+        let found = |k: Option<usize>| {
+            store.search_similar(&base, k, 0.9).unwrap()
+                .iter()
+                .any(|u| u.qualified_name == "rust::test::target")
+        };
+        assert!(!found(Some(DEFAULT_CANDIDATES)));
+        assert!(found(Some(DEFAULT_CANDIDATES * 2)));
+        // 不指定 k 时按阈值半径搜索
+        assert!(found(None));
+    }
+
+    #[test]
+    fn test_rebuild_preserves_vector_ids() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+
+        for (i, name) in ["a", "b", "c"].iter().enumerate() {
+            store.upsert_code_unit(&embedded_record(name, project_id, create_test_embedding(i as f32 + 1.0))).unwrap();
+        }
+        let before = store.name_to_id.clone();
+
+        // 删除中间的单元后重建，其余单元 ID 不变
+        store.db().delete_code_units_by_file("/test/src/b.rs").unwrap();
+        assert_eq!(store.rebuild_vector_index().unwrap(), 2);
+        assert_eq!(store.name_to_id.get("rust::test::a"), before.get("rust::test::a"));
+        assert_eq!(store.name_to_id.get("rust::test::c"), before.get("rust::test::c"));
+        assert!(!store.name_to_id.contains_key("rust::test::b"));
+
+        // 重新打开后加载的映射一致
+        drop(store);
+        let mut store = Store::open(&db_path).unwrap();
+        assert!(store.load_vector_index().unwrap());
+        assert_eq!(store.name_to_id.get("rust::test::c"), before.get("rust::test::c"));
+
+        let results = store.search_similar(&create_test_embedding(3.0), Some(1), 0.9).unwrap();
+        assert_eq!(results[0].qualified_name, "rust::test::c");
+    }
+
+    #[test]
+    fn test_reload_maps_ids_to_names_after_out_of_order_upserts() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        let make_record = |name: &str, seed: f32| CodeUnitRecord {
+            content_hash: format!("hash_{}_{}", name, seed),
+            ..embedded_record(name, project_id, create_test_embedding(seed))
+        };
+
+        // 写入顺序与名称/行顺序无关，并更新已有单元、删除中间的单元
+        for (name, seed) in [("d", 4.0), ("b", 2.0), ("e", 5.0), ("a", 1.0), ("c", 3.0), ("b", 6.0)] {
+            store.upsert_code_unit(&make_record(name, seed)).unwrap();
+        }
+        store.db().delete_code_units_by_file("/test/src/e.rs").unwrap();
+        store.save_vector_index().unwrap();
+        drop(store);
+
+        // 重新加载索引文件后，每个向量仍映射到写入它的单元
+        let mut store = Store::open(&db_path).unwrap();
+        assert!(store.load_vector_index().unwrap());
+        for (name, seed) in [("a", 1.0), ("b", 6.0), ("c", 3.0), ("d", 4.0)] {
+            let results = store.search_similar(&create_test_embedding(seed), Some(1), 0.99).unwrap();
+            assert_eq!(results[0].qualified_name, format!("rust::test::{}", name), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_stale_or_truncated_index_is_rebuilt() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let index_path = db_path.with_extension("usearch");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        let make_record = |name: &str, seed: f32| embedded_record(name, project_id, create_test_embedding(seed));
+        store.upsert_code_unit(&make_record("a", 1.0)).unwrap();
+        store.upsert_code_unit(&make_record("b", 2.0)).unwrap();
+        store.save_vector_index().unwrap();
+        // 索引保存之后写入的单元 (例如进程在下一次保存前退出)
+        store.upsert_code_unit(&make_record("c", 3.0)).unwrap();
+        drop(store);
+
+        let mut store = Store::open(&db_path).unwrap();
+        assert!(store.load_vector_index().unwrap());
+        assert_eq!(store.vector_index_stats().unwrap().0, 3);
+        let results = store.search_similar(&create_test_embedding(3.0), Some(1), 0.99).unwrap();
+        assert_eq!(results[0].qualified_name, "rust::test::c");
+        drop(store);
+
+        // 截断的索引文件无法加载，同样从数据库重建
+        let bytes = std::fs::read(&index_path).unwrap();
+        std::fs::write(&index_path, &bytes[..bytes.len() / 2]).unwrap();
+        let mut store = Store::open(&db_path).unwrap();
+        assert_eq!(store.ensure_vector_index().unwrap().size(), 3);
+        let results = store.search_similar(&create_test_embedding(2.0), Some(1), 0.99).unwrap();
+        assert_eq!(results[0].qualified_name, "rust::test::b");
+    }
+
+    #[test]
+    fn test_verify_detects_drift() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+
+        let make_record = |name: &str, emb: Vec<f32>| embedded_record(name, project_id, emb);
+
+        for (i, name) in ["a", "b", "c"].iter().enumerate() {
+            store.upsert_code_unit(&make_record(name, create_test_embedding(i as f32 + 1.0))).unwrap();
+        }
+        store.save_vector_index().unwrap();
+        assert!(store.verify().unwrap().is_consistent());
+
+        // 注入漂移：只写数据库的单元、删除单元残留的向量、维度错误的 embedding
+        store.db().upsert_code_unit(&make_record("d", create_test_embedding(4.0))).unwrap();
+        let orphan_id = store.db().get_vector_id("rust::test::a").unwrap().unwrap();
+        store.db().delete_code_units_by_file("/test/src/a.rs").unwrap();
+        store.db().upsert_code_unit(&make_record("e", vec![0.1, 0.2, 0.3])).unwrap();
+
+        let report = store.verify().unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.missing_vectors, vec!["rust::test::d".to_string()]);
+        assert_eq!(report.orphan_vectors, vec![orphan_id]);
+        assert_eq!(report.dimension_mismatches, vec![("rust::test::e".to_string(), 3)]);
+        assert_eq!(report.units_with_embeddings, 4);
+
+        // 修复：去掉维度错误的单元后重建
+        store.db().delete_code_units_by_file("/test/src/e.rs").unwrap();
+        store.rebuild_vector_index().unwrap();
+        assert!(store.verify().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_dimension_mismatch_rejected() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        let make_record = |name: &str, emb: Vec<f32>| embedded_record(name, project_id, emb);
+
+        // 按模型声明的维度建索引，不限于默认维度
+        store.set_embedding_model("small", 8).unwrap();
+        let small: Vec<f32> = (0..8).map(|i| i as f32 + 1.0).collect();
+        store.upsert_code_unit(&make_record("a", small.clone())).unwrap();
+        assert_eq!(store.search_similar(&small, Some(10), 0.99).unwrap().len(), 1);
+        store.save_vector_index().unwrap();
+
+        // 维度不符的向量被拒绝，数据库也不写入
+        let err = store.upsert_code_unit(&make_record("b", vec![0.5; 4])).unwrap_err();
+        assert!(matches!(err, StoreError::DimensionMismatch { expected: 8, got: 4, ref model } if model == "small"));
+        assert!(store.db().get_code_unit("rust::test::b").unwrap().is_none());
+
+        // 维度记录持久化，重新打开后换模型可提前发现
+        let mut store = Store::open(&db_path).unwrap();
+        assert_eq!(store.index_metadata(), Some(&IndexMetadata {
+            dimensions: 8,
+            metric: Metric::Cosine,
+            quantization: Quantization::F32,
+            model: Some("small".to_string()),
+        }));
+        let err = store.set_embedding_model("large", 16).unwrap_err();
+        assert!(matches!(err, StoreError::DimensionMismatch { expected: 8, got: 16, .. }));
+        assert!(store.load_vector_index().unwrap());
+        assert_eq!(store.search_similar(&small, Some(10), 0.99).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_metric_persisted_and_checked_on_load() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap().with_metric(Metric::InnerProduct);
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        // 内积只对归一化向量等价于余弦
+        let emb = Array1::from_vec(create_test_embedding(1.0));
+        let emb = (&emb / emb.dot(&emb).sqrt()).to_vec();
+        store.upsert_code_unit(&CodeUnitRecord {
+            qualified_name: "rust::test::foo".to_string(),
+            project_id,
+            file_path: "/test/src/lib.rs".to_string(),
+            kind: "function".to_string(),
+            range_start: 1,
+            range_end: 10,
+            content_hash: "abc".to_string(),
+            structure_hash: "def".to_string(),
+            embedding: Some(embedding_to_bytes(&emb.clone().into())),
+            group_id: None,
+            signature: None,
+        }).unwrap();
+        store.save_vector_index().unwrap();
+        drop(store);
+
+        // 用默认的余弦打开已有的内积索引被拒绝
+        let mut store = Store::open(&db_path).unwrap();
+        let err = store.load_vector_index().unwrap_err();
+        assert!(matches!(err, StoreError::MetricMismatch { expected: Metric::InnerProduct, got: Metric::Cosine }));
+
+        let mut store = Store::open(&db_path).unwrap().with_metric(Metric::InnerProduct);
+        assert!(store.load_vector_index().unwrap());
+        let results = store.search_similar(&emb, Some(1), 0.99).unwrap();
+        assert!((results[0].similarity - 1.0).abs() < 1e-3);
+
+        // 重建可以切换度量
+        let mut store = Store::open(&db_path).unwrap();
+        assert_eq!(store.rebuild_vector_index().unwrap(), 1);
+        assert_eq!(store.index_metadata().unwrap().metric, Metric::Cosine);
+        assert!(Store::open(&db_path).unwrap().load_vector_index().unwrap());
+    }
+
+    #[test]
+    fn test_quantization_persisted_and_checked_on_load() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap().with_quantization(Quantization::F16);
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        let emb = create_test_embedding(1.0);
+        store.upsert_code_unit(&CodeUnitRecord {
+            qualified_name: "rust::test::foo".to_string(),
+            project_id,
+            file_path: "/test/src/lib.rs".to_string(),
+            kind: "function".to_string(),
+            range_start: 1,
+            range_end: 10,
+            content_hash: "abc".to_string(),
+            structure_hash: "def".to_string(),
+            embedding: Some(embedding_to_bytes(&emb.clone().into())),
+            group_id: None,
+            signature: None,
+        }).unwrap();
+        store.save_vector_index().unwrap();
+        drop(store);
+
+        let err = Store::open(&db_path).unwrap().load_vector_index().unwrap_err();
+        assert!(matches!(err, StoreError::QuantizationMismatch { expected: Quantization::F16, got: Quantization::F32 }));
+
+        let mut store = Store::open(&db_path).unwrap().with_quantization(Quantization::F16);
+        assert!(store.load_vector_index().unwrap());
+        let results = store.search_similar(&emb, Some(1), 0.99).unwrap();
+        assert_eq!(results[0].qualified_name, "rust::test::foo");
+    }
+
+    #[test]
+    fn test_import_from_merges_units_and_vectors() {
+        let dir = tempdir().unwrap();
+        let make_record = |project_id: i64, name: &str, seed: f32| embedded_record(name, project_id, create_test_embedding(seed));
+
+        let mut target = Store::open(&dir.path().join("target.db")).unwrap();
+        let project_a = target.db().get_or_create_project("a", "/a", "rust").unwrap();
+        target.upsert_code_unit(&make_record(project_a, "a::one", 1.0)).unwrap();
+        target.upsert_code_unit(&make_record(project_a, "a::two", 2.0)).unwrap();
+
+        // 另一个库的 ID 同样从 1 开始，带索引文件
+        let other_path = dir.path().join("other.db");
+        let mut other = Store::open(&other_path).unwrap();
+        let project_b = other.db().get_or_create_project("b", "/b", "rust").unwrap();
+        other.db().set_project_model(project_b, "bge-m3", 1024, "passage: ").unwrap();
+        other.upsert_code_unit(&make_record(project_b, "b::one", 3.0)).unwrap();
+        other.upsert_code_unit(&make_record(project_b, "b::two", 4.0)).unwrap();
+        other.save_vector_index().unwrap();
+        drop(other);
+        let other_meta_path = other_path.with_extension("usearch.json");
+        let other_meta = std::fs::read(&other_meta_path).unwrap();
+
+        assert_eq!(target.import_from(&other_path).unwrap(), 2);
+        assert_eq!(target.vector_index_stats().unwrap().0, 4);
+        assert_eq!(std::fs::read(&other_meta_path).unwrap(), other_meta);
+        let results = target.search_similar(&create_test_embedding(4.0), Some(1), 0.99).unwrap();
+        assert_eq!(results[0].qualified_name, "rust::test::b::two");
+        assert_eq!(results[0].file_path, "/test/src/b::two.rs");
+
+        // 项目随单元一起导入，模型记录保留
+        let imported = target.db().get_project_by_path("/b").unwrap().unwrap();
+        assert_eq!(imported.embedding_model.as_deref(), Some("bge-m3"));
+        assert_eq!(imported.embedding_prefix.as_deref(), Some("passage: "));
+        assert_eq!(target.db().get_code_unit("rust::test::b::one").unwrap().unwrap().project_id, imported.id);
+        let mut ids: Vec<u64> = target.db().get_vector_ids().unwrap().into_iter().map(|(_, id)| id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        // 重复导入被拒绝且不写入
+        let err = target.import_from(&other_path).unwrap_err();
+        assert!(matches!(err, StoreError::DuplicateUnits(ref names) if names.len() == 2));
+        assert_eq!(target.db().count_code_units(None).unwrap(), 4);
+
+        // 没有索引文件的库从 embedding 字段补齐向量
+        let bare_path = dir.path().join("bare.db");
+        let bare = Database::open(&bare_path).unwrap();
+        let project_c = bare.get_or_create_project("c", "/c", "rust").unwrap();
+        bare.upsert_code_unit(&make_record(project_c, "c::one", 5.0)).unwrap();
+        drop(bare);
+        assert_eq!(target.import_from(&bare_path).unwrap(), 1);
+        assert_eq!(target.search_similar(&create_test_embedding(5.0), Some(1), 0.99).unwrap()[0].qualified_name, "rust::test::c::one");
+        assert!(!bare_path.with_extension("usearch.json").exists());
+
+        // 合并后的索引已保存
+        drop(target);
+        let mut reopened = Store::open(&dir.path().join("target.db")).unwrap();
+        assert!(reopened.load_vector_index().unwrap());
+        assert_eq!(reopened.vector_index_stats().unwrap().0, 5);
+    }
+
+    #[test]
+    fn test_import_from_rejects_outdated_source() {
+        let dir = tempdir().unwrap();
+        let old_path = dir.path().join("old.db");
+        rusqlite::Connection::open(&old_path).unwrap()
+            .execute_batch("CREATE TABLE schema_version (version INTEGER NOT NULL); INSERT INTO schema_version VALUES (1);")
+            .unwrap();
+
+        let mut target = Store::open(&dir.path().join("target.db")).unwrap();
+        let err = target.import_from(&old_path).unwrap_err();
+        assert!(matches!(err, StoreError::OutdatedSource { version: 1, .. }));
+        // 源数据库未被迁移
+        assert_eq!(Database::open_readonly(&old_path).unwrap().schema_version().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_import_from_index_failure_writes_nothing() {
+        let dir = tempdir().unwrap();
+        let make_record = |project_id: i64, name: &str, seed: f32| embedded_record(name, project_id, create_test_embedding(seed));
+
+        let mut target = Store::open(&dir.path().join("target.db")).unwrap();
+        let project_a = target.db().get_or_create_project("a", "/a", "rust").unwrap();
+        target.upsert_code_unit(&make_record(project_a, "a::one", 1.0)).unwrap();
+
+        let other_path = dir.path().join("other.db");
+        let mut other = Store::open(&other_path).unwrap();
+        let project_b = other.db().get_or_create_project("b", "/b", "rust").unwrap();
+        other.upsert_code_unit(&make_record(project_b, "b::one", 2.0)).unwrap();
+        other.save_vector_index().unwrap();
+        drop(other);
+
+        // 索引里残留了导入单元将使用的 ID
+        target.vector_index.as_ref().unwrap().add(2, &create_test_embedding(9.0)).unwrap();
+        assert!(target.import_from(&other_path).is_err());
+        assert_eq!(target.db().count_code_units(None).unwrap(), 1);
+        assert!(target.db().get_project_by_path("/b").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_remove_units_by_file_drops_vectors_and_pairs() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let project_id = store.db_mut().get_or_create_project("test", "/test", "rust").unwrap();
+        for (name, file, seed) in [("a", "a", 1.0), ("b", "a", 2.0), ("c", "c", 3.0)] {
+            let record = CodeUnitRecord {
+                file_path: format!("/test/src/{}.rs", file),
+                ..embedded_record(name, project_id, create_test_embedding(seed))
+            };
+            store.upsert_code_unit(&record).unwrap();
+        }
+        store.db().upsert_similar_pair("rust::test::a", "rust::test::c", 0.9, None).unwrap();
+        store.db().upsert_similar_pair("rust::test::b", "rust::test::c", 0.9, None).unwrap();
+        store.save_vector_index().unwrap();
+
+        // 重新打开，索引尚未加载时也能删除向量
+        let mut store = Store::open(&db_path).unwrap();
+        assert_eq!(store.remove_units_by_file("/test/src/a.rs").unwrap(), 2);
+        assert_eq!(store.vector_index_stats().unwrap().0, 1);
+        assert!(store.db().get_similar_pairs(None, None, 0.0, false).unwrap().is_empty());
+        assert!(store.search_similar(&create_test_embedding(1.0), Some(10), 0.99).unwrap().is_empty());
+        assert_eq!(store.remove_units_by_file("/test/src/a.rs").unwrap(), 0);
+
+        assert!(store.remove_unit("rust::test::c").unwrap());
+        assert!(!store.remove_unit("rust::test::c").unwrap());
+        assert_eq!(store.vector_index_stats().unwrap().0, 0);
+        store.save_vector_index().unwrap();
+        assert!(store.verify().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_remove_project_keeps_index_consistent() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut store = Store::open(&db_path).unwrap();
+        let gone = store.db().get_or_create_project("gone", "/gone", "rust").unwrap();
+        let kept = store.db().get_or_create_project("kept", "/kept", "rust").unwrap();
+        for (name, project_id, seed) in [("gone::a", gone, 1.0), ("gone::b", gone, 2.0), ("kept::c", kept, 3.0)] {
+            store.upsert_code_unit(&embedded_record(name, project_id, create_test_embedding(seed))).unwrap();
+        }
+        store.db().upsert_similar_pair("rust::test::gone::a", "rust::test::kept::c", 0.9, None).unwrap();
+        store.save_vector_index().unwrap();
+
+        assert_eq!(store.remove_project(gone).unwrap(), 2);
+        assert_eq!(store.vector_index_stats().unwrap().0, 1);
+        assert!(store.search_similar(&create_test_embedding(1.0), Some(10), 0.99).unwrap().is_empty());
+
+        // 索引已保存，重新打开后仍一致；重复删除不报错
+        let mut store = Store::open(&db_path).unwrap();
+        assert!(store.verify().unwrap().is_consistent());
+        assert_eq!(store.remove_project(gone).unwrap(), 0);
+        assert_eq!(store.search_similar(&create_test_embedding(3.0), Some(1), 0.99).unwrap()[0].qualified_name, "rust::test::kept::c");
+    }
 }
@@ -1,6 +1,7 @@
 //! 向量索引模块 - 基于 usearch HNSW 算法的 ANN 搜索
 
 use std::path::Path;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use usearch::{Index, IndexOptions, MetricKind, ScalarKind};
 
@@ -13,6 +14,10 @@ pub enum VectorIndexError {
     Io(#[from] std::io::Error),
     #[error("dimension mismatch: expected {expected}, got {got}")]
     DimensionMismatch { expected: usize, got: usize },
+    #[error("metric mismatch: expected {expected}, got {got}")]
+    MetricMismatch { expected: Metric, got: Metric },
+    #[error("vector id {0} already exists")]
+    DuplicateId(u64),
 }
 
 impl From<cxx::Exception> for VectorIndexError {
@@ -23,19 +28,134 @@ impl From<cxx::Exception> for VectorIndexError {
 
 pub type Result<T> = std::result::Result<T, VectorIndexError>;
 
+/// 距离度量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// 余弦距离
+    #[default]
+    Cosine,
+    /// 内积距离 (`1 - a·b`)，向量已归一化时与余弦等价且更快
+    InnerProduct,
+    /// 欧氏距离的平方
+    L2,
+}
+
+impl Metric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::InnerProduct => "inner_product",
+            Self::L2 => "l2",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "cosine" | "cos" => Some(Self::Cosine),
+            "inner_product" | "ip" | "dot" => Some(Self::InnerProduct),
+            "l2" => Some(Self::L2),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> MetricKind {
+        match self {
+            Self::Cosine => MetricKind::Cos,
+            Self::InnerProduct => MetricKind::IP,
+            Self::L2 => MetricKind::L2sq,
+        }
+    }
+
+    /// 距离转相似度 (归一化向量时为余弦相似度)
+    ///
+    /// 单位向量满足 `|a - b|² = 2 - 2·cos`，因此 L2 的相似度为 `1 - distance / 2`
+    pub fn similarity(self, distance: f32) -> f32 {
+        match self {
+            Self::Cosine | Self::InnerProduct => 1.0 - distance,
+            Self::L2 => 1.0 - distance / 2.0,
+        }
+    }
+
+    /// 相似度转回该度量下的原始距离，[`Metric::similarity`] 的逆运算
+    pub fn distance(self, similarity: f32) -> f32 {
+        match self {
+            Self::Cosine | Self::InnerProduct => 1.0 - similarity,
+            Self::L2 => 2.0 * (1.0 - similarity),
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 向量存储精度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// 32 位浮点 (每维 4 字节)
+    #[default]
+    F32,
+    /// 16 位浮点 (每维 2 字节)，召回几乎不变
+    F16,
+    /// 8 位整数 (每维 1 字节)，要求向量已归一化
+    I8,
+}
+
+impl Quantization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::F16 => "f16",
+            Self::I8 => "i8",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "f32" => Some(Self::F32),
+            "f16" => Some(Self::F16),
+            "i8" => Some(Self::I8),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> ScalarKind {
+        match self {
+            Self::F32 => ScalarKind::F32,
+            Self::F16 => ScalarKind::F16,
+            Self::I8 => ScalarKind::I8,
+        }
+    }
+}
+
+impl std::fmt::Display for Quantization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `search_radius` 的初始 k
+const RADIUS_INITIAL_K: usize = 32;
+
 /// 搜索结果
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     /// 向量 ID (对应 code_unit 的 rowid)
     pub id: u64,
-    /// 距离 (越小越相似，cosine distance = 1 - similarity)
+    /// 距离 (越小越相似，含义取决于 `metric`)
     pub distance: f32,
+    /// 产生该距离的度量
+    pub metric: Metric,
 }
 
 impl SearchResult {
     /// 转换为相似度 (0-1，越大越相似)
     pub fn similarity(&self) -> f32 {
-        1.0 - self.distance
+        self.metric.similarity(self.distance)
     }
 }
 
@@ -50,6 +170,10 @@ pub struct VectorIndexConfig {
     pub expansion_add: usize,
     /// 搜索扩展因子，影响搜索精度
     pub expansion_search: usize,
+    /// 距离度量
+    pub metric: Metric,
+    /// 向量存储精度
+    pub quantization: Quantization,
 }
 
 impl Default for VectorIndexConfig {
@@ -59,6 +183,8 @@ impl Default for VectorIndexConfig {
             connectivity: 16, // HNSW M 参数，0 表示自动选择
             expansion_add: 128, // 构建时的扩展因子
             expansion_search: 64, // 搜索时的扩展因子
+            metric: Metric::Cosine,
+            quantization: Quantization::F32,
         }
     }
 }
@@ -71,6 +197,8 @@ impl VectorIndexConfig {
             connectivity: 8,
             expansion_add: 64,
             expansion_search: 32,
+            metric: Metric::Cosine,
+            quantization: Quantization::F32,
         }
     }
 }
@@ -86,8 +214,8 @@ impl VectorIndex {
     pub fn new(config: VectorIndexConfig) -> Result<Self> {
         let options = IndexOptions {
             dimensions: config.dimensions,
-            metric: MetricKind::Cos, // 余弦距离
-            quantization: ScalarKind::F32,
+            metric: config.metric.kind(),
+            quantization: config.quantization.kind(),
             connectivity: config.connectivity,
             expansion_add: config.expansion_add,
             expansion_search: config.expansion_search,
@@ -112,8 +240,8 @@ impl VectorIndex {
     pub fn load_with_config(path: &Path, config: VectorIndexConfig) -> Result<Self> {
         let options = IndexOptions {
             dimensions: config.dimensions,
-            metric: MetricKind::Cos,
-            quantization: ScalarKind::F32,
+            metric: config.metric.kind(),
+            quantization: config.quantization.kind(),
             connectivity: config.connectivity,
             expansion_add: config.expansion_add,
             expansion_search: config.expansion_search,
@@ -158,6 +286,48 @@ impl VectorIndex {
         Ok(())
     }
 
+    /// 读取向量 (量化索引返回还原后的近似值)，不存在时返回 None
+    pub fn get(&self, id: u64) -> Result<Option<Vec<f32>>> {
+        let mut vector = vec![0.0; self.config.dimensions];
+        let found = self.index.get(id, &mut vector)?;
+        Ok((found > 0).then_some(vector))
+    }
+
+    /// 把 `other` 中的向量复制进来，ID 加上 `id_offset` 以免冲突，返回复制的数量
+    ///
+    /// usearch 无法枚举索引中的 ID，由调用方通过 `ids` 提供 (`other` 中不存在的 ID 跳过)；
+    /// 维度与度量必须一致，偏移后的 ID 已存在时返回 `DuplicateId` (之前的向量已写入)
+    pub fn merge(&self, other: &VectorIndex, ids: &[u64], id_offset: u64) -> Result<usize> {
+        if other.dimensions() != self.dimensions() {
+            return Err(VectorIndexError::DimensionMismatch {
+                expected: self.dimensions(),
+                got: other.dimensions(),
+            });
+        }
+        if other.metric() != self.metric() {
+            return Err(VectorIndexError::MetricMismatch {
+                expected: self.metric(),
+                got: other.metric(),
+            });
+        }
+
+        if self.size() + ids.len() > self.capacity() {
+            self.reserve(self.size() + ids.len())?;
+        }
+
+        let mut merged = 0;
+        for &id in ids {
+            let Some(vector) = other.get(id)? else { continue };
+            let new_id = id + id_offset;
+            if self.contains(new_id) {
+                return Err(VectorIndexError::DuplicateId(new_id));
+            }
+            self.add(new_id, &vector)?;
+            merged += 1;
+        }
+        Ok(merged)
+    }
+
     /// 删除向量
     pub fn remove(&self, id: u64) -> Result<bool> {
         let count = self.index.remove(id)?;
@@ -183,12 +353,34 @@ impl VectorIndex {
             .keys
             .iter()
             .zip(matches.distances.iter())
-            .map(|(&id, &distance)| SearchResult { id, distance })
+            .map(|(&id, &distance)| SearchResult { id, distance, metric: self.config.metric })
             .collect();
 
         Ok(results)
     }
 
+    /// 半径搜索：返回相似度不低于 `min_similarity` 的全部近邻 (按相似度降序，最多 `max_results` 个)
+    ///
+    /// 从较小的 k 开始，最远的结果仍在阈值以上时 k 翻倍重搜，
+    /// 直到结果跌出阈值、取尽索引或达到 `max_results`
+    pub fn search_radius(&self, query: &[f32], min_similarity: f32, max_results: usize) -> Result<Vec<SearchResult>> {
+        let limit = max_results.min(self.size());
+        let mut k = RADIUS_INITIAL_K.min(limit);
+        let mut results = Vec::new();
+        while k > 0 {
+            results = self.search(query, k)?;
+            let exhausted = results.len() < k || k >= limit;
+            if exhausted || results.last().is_some_and(|r| r.similarity() < min_similarity) {
+                break;
+            }
+            k = (k * 2).min(limit);
+        }
+
+        results.retain(|r| r.similarity() >= min_similarity);
+        results.truncate(max_results);
+        Ok(results)
+    }
+
     /// 带过滤的搜索
     pub fn search_filtered<F>(&self, query: &[f32], k: usize, filter: F) -> Result<Vec<SearchResult>>
     where
@@ -206,7 +398,7 @@ impl VectorIndex {
             .keys
             .iter()
             .zip(matches.distances.iter())
-            .map(|(&id, &distance)| SearchResult { id, distance })
+            .map(|(&id, &distance)| SearchResult { id, distance, metric: self.config.metric })
             .collect();
 
         Ok(results)
@@ -231,6 +423,16 @@ impl VectorIndex {
     pub fn dimensions(&self) -> usize {
         self.config.dimensions
     }
+
+    /// 获取距离度量
+    pub fn metric(&self) -> Metric {
+        self.config.metric
+    }
+
+    /// 获取存储精度
+    pub fn quantization(&self) -> Quantization {
+        self.config.quantization
+    }
 }
 
 #[cfg(test)]
@@ -296,7 +498,7 @@ mod tests {
     #[test]
     fn test_save_and_load() {
         let config = VectorIndexConfig::for_test(4);
-        let index = VectorIndex::new(config.clone()).unwrap();
+        let index = VectorIndex::new(config).unwrap();
         index.reserve(10).unwrap();
         index.add(1, &[1.0, 0.0, 0.0, 0.0]).unwrap();
         index.add(2, &[0.0, 1.0, 0.0, 0.0]).unwrap();
@@ -320,7 +522,169 @@ mod tests {
         let result = SearchResult {
             id: 1,
             distance: 0.1, // cosine distance
+            metric: Metric::Cosine,
         };
         assert!((result.similarity() - 0.9).abs() < 0.001);
+
+        // L2 平方距离 0.2 对应余弦相似度 0.9
+        assert!((Metric::L2.similarity(0.2) - 0.9).abs() < 0.001);
+        for metric in [Metric::Cosine, Metric::InnerProduct, Metric::L2] {
+            assert!((metric.distance(metric.similarity(0.2)) - 0.2).abs() < 1e-6);
+        }
+        assert_eq!(Metric::parse("IP"), Some(Metric::InnerProduct));
+        assert_eq!(Metric::parse("manhattan"), None);
+    }
+
+    #[test]
+    fn test_metrics_agree_on_normalized_vectors() {
+        let a = [0.6, 0.8, 0.0, 0.0];
+        let b = [0.8, 0.6, 0.0, 0.0]; // cos(a, b) = 0.96
+
+        for metric in [Metric::Cosine, Metric::InnerProduct, Metric::L2] {
+            let index = VectorIndex::new(VectorIndexConfig { metric, ..VectorIndexConfig::for_test(4) }).unwrap();
+            index.reserve(10).unwrap();
+            index.add(1, &a).unwrap();
+            index.add(2, &b).unwrap();
+            assert_eq!(index.metric(), metric);
+
+            let results = index.search(&a, 2).unwrap();
+            assert_eq!(results[0].id, 1, "{}", metric);
+            assert!((results[0].similarity() - 1.0).abs() < 0.001, "{}", metric);
+            assert!((results[1].similarity() - 0.96).abs() < 0.001, "{}", metric);
+        }
+    }
+
+    #[test]
+    fn test_merge_offsets_ids() {
+        let a = VectorIndex::new(VectorIndexConfig::for_test(4)).unwrap();
+        a.reserve(10).unwrap();
+        a.add(1, &[1.0, 0.0, 0.0, 0.0]).unwrap();
+
+        let b = VectorIndex::new(VectorIndexConfig::for_test(4)).unwrap();
+        b.reserve(10).unwrap();
+        b.add(1, &[0.0, 1.0, 0.0, 0.0]).unwrap();
+        b.add(2, &[0.0, 0.0, 1.0, 0.0]).unwrap();
+
+        // 不存在的 ID 跳过
+        assert_eq!(a.merge(&b, &[1, 2, 3], 10).unwrap(), 2);
+        assert_eq!(a.size(), 3);
+        assert_eq!(a.search(&[0.0, 0.0, 1.0, 0.0], 1).unwrap()[0].id, 12);
+        assert_eq!(a.get(11).unwrap(), Some(vec![0.0, 1.0, 0.0, 0.0]));
+        assert_eq!(a.get(2).unwrap(), None);
+
+        // 偏移后冲突
+        assert!(matches!(a.merge(&b, &[1], 10), Err(VectorIndexError::DuplicateId(11))));
+
+        // 维度或度量不同
+        let wide = VectorIndex::new(VectorIndexConfig::for_test(8)).unwrap();
+        assert!(matches!(a.merge(&wide, &[], 0), Err(VectorIndexError::DimensionMismatch { expected: 4, got: 8 })));
+        let ip = VectorIndex::new(VectorIndexConfig { metric: Metric::InnerProduct, ..VectorIndexConfig::for_test(4) }).unwrap();
+        assert!(matches!(a.merge(&ip, &[], 0), Err(VectorIndexError::MetricMismatch { .. })));
+    }
+
+    #[test]
+    fn test_search_radius_returns_whole_cluster() {
+        let dimensions = 16;
+        let index = VectorIndex::new(VectorIndexConfig::for_test(dimensions)).unwrap();
+        index.reserve(400).unwrap();
+
+        // 300 个几乎相同的向量 (e0 加微小扰动) + 100 个随机向量
+        let mut center = vec![0.0; dimensions];
+        center[0] = 1.0;
+        for (id, noise) in random_unit_vectors(300, dimensions, 0x9e37_79b9_7f4a_7c15).iter().enumerate() {
+            let v: Vec<f32> = center.iter().zip(noise).map(|(c, n)| c + 0.01 * n).collect();
+            index.add(id as u64, &v).unwrap();
+        }
+        for (id, v) in random_unit_vectors(100, dimensions, 0x2545_f491_4f6c_dd1d).iter().enumerate() {
+            index.add(1000 + id as u64, v).unwrap();
+        }
+
+        // 固定 k 漏掉第 100 个之后的重复
+        assert_eq!(index.search(&center, 100).unwrap().len(), 100);
+
+        let results = index.search_radius(&center, 0.99, 10_000).unwrap();
+        assert!(results.len() >= 295, "found {}", results.len());
+        assert!(results.iter().all(|r| r.id < 300 && r.similarity() >= 0.99));
+        assert!(results.windows(2).all(|w| w[0].distance <= w[1].distance));
+
+        // max_results 截断；阈值以上没有结果时为空
+        assert_eq!(index.search_radius(&center, 0.99, 50).unwrap().len(), 50);
+        let mut opposite = vec![0.0; dimensions];
+        opposite[0] = -1.0;
+        assert!(index.search_radius(&opposite, 0.99, 10_000).unwrap().is_empty());
+        let empty = VectorIndex::new(VectorIndexConfig::for_test(dimensions)).unwrap();
+        assert!(empty.search_radius(&center, 0.5, 100).unwrap().is_empty());
+    }
+
+    /// 确定性伪随机单位向量 (xorshift)
+    fn random_unit_vectors(count: usize, dimensions: usize, mut seed: u64) -> Vec<Vec<f32>> {
+        (0..count)
+            .map(|_| {
+                let v: Vec<f32> = (0..dimensions)
+                    .map(|_| {
+                        seed ^= seed << 13;
+                        seed ^= seed >> 7;
+                        seed ^= seed << 17;
+                        (seed % 2001) as f32 / 1000.0 - 1.0
+                    })
+                    .collect();
+                let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+                v.into_iter().map(|x| x / norm).collect()
+            })
+            .collect()
+    }
+
+    /// top-k 召回率：ANN 结果中属于精确 top-k 的比例
+    fn recall_at(quantization: Quantization, data: &[Vec<f32>], queries: &[Vec<f32>], k: usize) -> f32 {
+        let config = VectorIndexConfig { dimensions: data[0].len(), quantization, ..Default::default() };
+        let index = VectorIndex::new(config).unwrap();
+        index.reserve(data.len()).unwrap();
+        for (id, v) in data.iter().enumerate() {
+            index.add(id as u64, v).unwrap();
+        }
+
+        let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        let mut hits = 0;
+        for query in queries {
+            let mut exact: Vec<(usize, f32)> = data.iter().enumerate().map(|(id, v)| (id, dot(query, v))).collect();
+            exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let truth: std::collections::HashSet<u64> = exact[..k].iter().map(|&(id, _)| id as u64).collect();
+            hits += index.search(query, k).unwrap().iter().filter(|r| truth.contains(&r.id)).count();
+        }
+        hits as f32 / (queries.len() * k) as f32
+    }
+
+    #[test]
+    fn test_quantization_recall_tradeoff() {
+        let data = random_unit_vectors(2000, 32, 0x9e37_79b9_7f4a_7c15);
+        let queries = random_unit_vectors(50, 32, 0x2545_f491_4f6c_dd1d);
+
+        let f32_recall = recall_at(Quantization::F32, &data, &queries, 10);
+        let f16_recall = recall_at(Quantization::F16, &data, &queries, 10);
+        let i8_recall = recall_at(Quantization::I8, &data, &queries, 10);
+        println!("recall@10: f32 {:.3}, f16 {:.3}, i8 {:.3}", f32_recall, f16_recall, i8_recall);
+
+        // f16 只损失尾数精度，召回与 f32 基本一致；i8 损失更多但仍可用
+        assert!(f32_recall > 0.8, "f32 recall {}", f32_recall);
+        assert!(f16_recall >= f32_recall - 0.02, "f16 recall {} vs f32 {}", f16_recall, f32_recall);
+        assert!(i8_recall > 0.6, "i8 recall {}", i8_recall);
+    }
+
+    #[test]
+    fn test_quantized_index_save_and_load() {
+        let config = VectorIndexConfig { quantization: Quantization::F16, ..VectorIndexConfig::for_test(4) };
+        let index = VectorIndex::new(config).unwrap();
+        index.reserve(10).unwrap();
+        index.add(1, &[0.6, 0.8, 0.0, 0.0]).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f16.usearch");
+        index.save(&path).unwrap();
+
+        let loaded = VectorIndex::load_with_config(&path, config).unwrap();
+        assert_eq!(loaded.quantization(), Quantization::F16);
+        let results = loaded.search(&[0.6, 0.8, 0.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].id, 1);
+        assert!((results[0].similarity() - 1.0).abs() < 0.01);
     }
 }
@@ -0,0 +1,135 @@
+//! 文件事件合并 - 监听模式下的去抖
+//!
+//! 编辑器保存一次文件常会产生多个事件（临时文件、多次写入），
+//! 按规范化路径合并，同一文件在去抖窗口内最多处理一次
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 默认去抖窗口 (毫秒)
+pub const DEFAULT_DEBOUNCE_MS: u64 = 500;
+
+/// 是否是编辑器的交换/备份/临时文件
+pub fn is_ignored_path(path: &Path) -> bool {
+    let name = match path.file_name() {
+        Some(n) => n.to_string_lossy(),
+        None => return true,
+    };
+
+    name.ends_with('~')
+        || name.ends_with(".swp")
+        || name.ends_with(".swo")
+        || name.ends_with(".tmp")
+}
+
+/// 文件事件合并器
+pub struct EventCoalescer {
+    debounce: Duration,
+    /// 规范化路径 -> 最近一次事件时间
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl EventCoalescer {
+    pub fn new(debounce: Duration) -> Self {
+        Self {
+            debounce,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// 记录一个文件事件，返回是否被接受（忽略的文件返回 false）
+    pub fn push(&mut self, path: &Path, now: Instant) -> bool {
+        if is_ignored_path(path) {
+            return false;
+        }
+
+        // 已删除的文件无法规范化，直接使用原路径
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.pending.insert(key, now);
+        true
+    }
+
+    /// 取出窗口内已静默的文件（按路径排序）
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let debounce = self.debounce;
+        let mut ready: Vec<PathBuf> = self.pending
+            .iter()
+            .filter(|(_, &last)| now.saturating_duration_since(last) >= debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in &ready {
+            self.pending.remove(path);
+        }
+
+        ready.sort();
+        ready
+    }
+
+    /// 下一个文件就绪的时间点（用于设置等待超时）
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().map(|&last| last + self.debounce)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl Default for EventCoalescer {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(DEFAULT_DEBOUNCE_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ignored_path() {
+        assert!(is_ignored_path(Path::new("/p/.main.rs.swp")));
+        assert!(is_ignored_path(Path::new("/p/main.rs~")));
+        assert!(is_ignored_path(Path::new("/p/main.rs.tmp")));
+        assert!(!is_ignored_path(Path::new("/p/main.rs")));
+        assert!(!is_ignored_path(Path::new("/p/tmp.rs")));
+    }
+
+    #[test]
+    fn test_coalesces_rapid_events() {
+        let mut coalescer = EventCoalescer::new(Duration::from_millis(500));
+        let t0 = Instant::now();
+
+        let path = Path::new("/nonexistent/src/lib.rs");
+        assert!(coalescer.push(path, t0));
+        assert!(coalescer.push(path, t0 + Duration::from_millis(100)));
+        assert!(coalescer.push(path, t0 + Duration::from_millis(200)));
+        assert!(!coalescer.push(Path::new("/nonexistent/src/.lib.rs.swp"), t0));
+
+        // 最后一次事件后窗口未满，不处理
+        assert!(coalescer.drain_ready(t0 + Duration::from_millis(600)).is_empty());
+        assert_eq!(coalescer.next_deadline(), Some(t0 + Duration::from_millis(700)));
+
+        // 窗口结束后只处理一次
+        let ready = coalescer.drain_ready(t0 + Duration::from_millis(700));
+        assert_eq!(ready, vec![PathBuf::from("/nonexistent/src/lib.rs")]);
+        assert!(coalescer.is_empty());
+        assert!(coalescer.drain_ready(t0 + Duration::from_millis(2000)).is_empty());
+    }
+
+    #[test]
+    fn test_coalesces_by_canonical_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        std::fs::write(&file, "fn a() {}").unwrap();
+
+        let mut coalescer = EventCoalescer::new(Duration::from_millis(10));
+        let t0 = Instant::now();
+        coalescer.push(&file, t0);
+        coalescer.push(&dir.path().join(".").join("a.rs"), t0);
+
+        let ready = coalescer.drain_ready(t0 + Duration::from_millis(10));
+        assert_eq!(ready.len(), 1);
+    }
+}
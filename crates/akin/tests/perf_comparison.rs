@@ -87,6 +87,7 @@ fn test_perf_comparison() {
         connectivity: 16,
         expansion_add: 128,
         expansion_search: 64,
+        ..Default::default()
     };
     let index = VectorIndex::new(config).unwrap();
     index.reserve(n_vectors + 100).unwrap();
@@ -153,6 +154,7 @@ fn test_ann_recall() {
         connectivity: 16,
         expansion_add: 128,
         expansion_search: 64,
+        ..Default::default()
     };
     let index = VectorIndex::new(config).unwrap();
     index.reserve(n_vectors + 100).unwrap();
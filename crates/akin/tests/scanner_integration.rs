@@ -17,6 +17,7 @@ fn make_code_unit(name: &str, body: &str) -> CodeUnit {
         body: body.to_string(),
         selection_line: 0,
         selection_column: 0,
+        signature: None,
     }
 }
 
@@ -1,28 +1,71 @@
-use lsp::{FunctionNode, FunctionRef, LanguageAdapter};
-use std::collections::HashMap;
+use lsp::{CallHierarchy, CodeUnit, FunctionNode, FunctionRef, IgnoreSet, LanguageAdapter, QualifiedName};
+use std::collections::{HashMap, HashSet, VecDeque};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ArchError {
     #[error("LSP error: {0}")]
     Lsp(String),
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
 }
 
 pub type Result<T> = std::result::Result<T, ArchError>;
 
+/// 默认入口点模式，匹配规则见 [`ArchitectureAnalyzer::matches_entry_pattern`]
+pub const DEFAULT_ENTRY_PATTERNS: &[&str] = &[
+    "main",
+    "test_",
+    "_test",
+    "new",
+    "default",
+    "init",
+    "setup",
+    "run",
+];
+
 /// 架构分析器
 pub struct ArchitectureAnalyzer {
     /// 函数映射: (file_path, line) -> FunctionNode
     functions: HashMap<FunctionRef, FunctionNode>,
+    /// 死码检测时视方法为存活 (调用者可能被动态派发隐藏)
+    methods_as_live: bool,
+    /// `.irisignore` 模式，构建调用图时按文件路径和限定名匹配
+    ignore: IgnoreSet,
+    /// 匹配忽略模式的函数 (仍在调用图中，但不报告为死码)
+    ignored: HashSet<FunctionRef>,
+    /// 入口点模式 (不报告为死码，也不算根函数)
+    entry_patterns: Vec<String>,
 }
 
 impl ArchitectureAnalyzer {
     pub fn new() -> Self {
         Self {
             functions: HashMap::new(),
+            methods_as_live: false,
+            ignore: IgnoreSet::default(),
+            ignored: HashSet::new(),
+            entry_patterns: DEFAULT_ENTRY_PATTERNS.iter().map(|p| p.to_string()).collect(),
         }
     }
 
+    pub fn with_methods_as_live(mut self, methods_as_live: bool) -> Self {
+        self.methods_as_live = methods_as_live;
+        self
+    }
+
+    /// 在默认入口点模式之外追加模式
+    pub fn with_entry_patterns(mut self, patterns: impl IntoIterator<Item = String>) -> Self {
+        self.entry_patterns.extend(patterns);
+        self
+    }
+
+    /// 设置忽略模式，需在构建调用图前调用
+    pub fn with_ignore(mut self, ignore: IgnoreSet) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
     /// 构建调用图
     pub async fn build_call_graph<A: LanguageAdapter>(
         &mut self,
@@ -32,82 +75,227 @@ impl ArchitectureAnalyzer {
             .get_functions()
             .await
             .map_err(|e| ArchError::Lsp(e.to_string()))?;
+        self.add_call_hierarchies(adapter, &units).await
+    }
+
+    /// 只为指定文件中的函数构建调用图
+    ///
+    /// 指向其他文件的边仅在 LSP 能解析到时出现，调用方/被调用方节点本身不会加入图中
+    pub async fn build_file_call_graph<A: LanguageAdapter>(
+        &mut self,
+        adapter: &mut A,
+        files: &[String],
+    ) -> Result<()> {
+        let units = adapter
+            .get_functions_in(files)
+            .await
+            .map_err(|e| ArchError::Lsp(e.to_string()))?;
+        self.add_call_hierarchies(adapter, &units).await
+    }
 
+    async fn add_call_hierarchies<A: LanguageAdapter>(
+        &mut self,
+        adapter: &mut A,
+        units: &[CodeUnit],
+    ) -> Result<()> {
         // 每处理 BATCH_SIZE 个函数后暂停，避免 LSP 服务器过载
         const BATCH_SIZE: usize = 50;
         const BATCH_DELAY_MS: u64 = 2000;
 
-        for (idx, unit) in units.iter().enumerate() {
+        // 类型声明没有调用关系，不进入调用图
+        for (idx, unit) in units.iter().filter(|u| !u.is_type()).enumerate() {
             if idx > 0 && idx % BATCH_SIZE == 0 {
                 tokio::time::sleep(std::time::Duration::from_millis(BATCH_DELAY_MS)).await;
             }
 
-            let key = FunctionRef::new(unit.file_path.clone(), unit.selection_line);
-
             let hierarchy = adapter
                 .get_call_hierarchy(unit)
                 .await
                 .map_err(|e| ArchError::Lsp(e.to_string()))?;
 
-            // 直接使用 FunctionRef，无需格式转换
-            let callers: Vec<FunctionRef> = hierarchy
-                .incoming
-                .iter()
-                .map(|c| c.as_ref())
-                .collect();
-
-            let callees: Vec<FunctionRef> = hierarchy
-                .outgoing
-                .iter()
-                .map(|c| c.as_ref())
-                .collect();
-
-            // 提取短名字用于显示
-            let short_name = unit.qualified_name
-                .split("::")
-                .last()
-                .unwrap_or(&unit.qualified_name)
-                .to_string();
-
-            let node = FunctionNode {
-                file_path: unit.file_path.clone(),
-                line: unit.selection_line,
-                name: short_name,
-                callers,
-                callees,
-            };
-
-            self.functions.insert(key, node);
+            self.add_unit(unit, &hierarchy);
         }
 
         Ok(())
     }
 
-    /// 检测死代码 (无调用者的函数)
+    /// 将函数及其调用关系加入调用图
+    fn add_unit(&mut self, unit: &CodeUnit, hierarchy: &CallHierarchy) {
+        let key = FunctionRef::new(unit.file_path.clone(), unit.selection_line);
+        if self.ignore.is_ignored(&unit.file_path, &unit.qualified_name) {
+            self.ignored.insert(key.clone());
+        }
+
+        // 直接使用 FunctionRef，无需格式转换
+        let callers: Vec<FunctionRef> = hierarchy
+            .incoming
+            .iter()
+            .map(|c| c.as_ref())
+            .collect();
+
+        let callees: Vec<FunctionRef> = hierarchy
+            .outgoing
+            .iter()
+            .map(|c| c.as_ref())
+            .collect();
+
+        // 提取短名字用于显示
+        let short_name = QualifiedName::parse(&unit.qualified_name).short().to_string();
+
+        let node = FunctionNode {
+            file_path: unit.file_path.clone(),
+            line: unit.selection_line,
+            name: short_name,
+            end_line: unit.range_end,
+            kind: unit.kind.clone(),
+            signature: unit.signature.clone(),
+            callers,
+            callees,
+        };
+
+        self.functions.insert(key, node);
+    }
+
+    /// 检测死代码 (无调用者的函数，忽略的函数除外)，按文件和行号排序
     pub fn find_dead_code(&self) -> Vec<&FunctionNode> {
-        self.functions
+        self.find_dead_code_sorted(DeadCodeSort::Location)
+    }
+
+    /// 以指定的入口点模式 (替代分析器的模式) 检测死代码，按文件和行号排序
+    pub fn find_dead_code_with(&self, patterns: &[String]) -> Vec<&FunctionNode> {
+        self.dead_code(DeadCodeSort::Location, patterns)
+    }
+
+    /// 检测死代码并按指定方式排序
+    pub fn find_dead_code_sorted(&self, sort: DeadCodeSort) -> Vec<&FunctionNode> {
+        self.dead_code(sort, &self.entry_patterns)
+    }
+
+    fn dead_code(&self, sort: DeadCodeSort, patterns: &[String]) -> Vec<&FunctionNode> {
+        let mut dead: Vec<&FunctionNode> = self.functions
             .values()
             .filter(|node| {
-                node.callers.is_empty() && !Self::is_entry_point(node)
+                node.callers.is_empty()
+                    && !Self::is_entry_point_with(node, patterns)
+                    && !self.ignored.contains(&node.as_ref())
+                    && (!self.methods_as_live || !node.is_method())
             })
-            .collect()
+            .collect();
+
+        sort_nodes(&mut dead, sort);
+        dead
+    }
+
+    /// 检测死代码并按文件分组
+    ///
+    /// 文件按其第一个函数在 `sort` 结果中出现的顺序排列，组内保持 `sort` 的顺序
+    pub fn find_dead_code_by_file(&self, sort: DeadCodeSort) -> Vec<(&str, Vec<&FunctionNode>)> {
+        Self::group_by_file(self.find_dead_code_sorted(sort))
+    }
+
+    /// 从根函数不可达的函数 (忽略的函数除外)，按文件和行号排序
+    ///
+    /// 与 `find_dead_code` 不同，只被其他死函数调用的函数 (死子图、互相递归的死函数) 也会报告。
+    /// `roots` 按名字匹配 (同 `find_function_ref`，但同名函数全部作为根)，
+    /// 为空时以所有入口点为根；`methods_as_live` 时方法也作为根
+    pub fn find_unreachable(&self, roots: &[&str]) -> Vec<&FunctionNode> {
+        self.find_unreachable_sorted(roots, DeadCodeSort::Location)
+    }
+
+    /// 检测不可达函数并按指定方式排序
+    pub fn find_unreachable_sorted(&self, roots: &[&str], sort: DeadCodeSort) -> Vec<&FunctionNode> {
+        let entries: Vec<FunctionRef> = self.functions
+            .iter()
+            .filter(|(_, node)| {
+                let is_root = if roots.is_empty() {
+                    Self::is_entry_point_with(node, &self.entry_patterns)
+                } else {
+                    roots.iter().any(|root| Self::matches_name(node, root))
+                };
+                is_root || (self.methods_as_live && node.is_method())
+            })
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        let mut unreachable = self.unreachable_functions(&entries);
+        sort_nodes(&mut unreachable, sort);
+        unreachable
+    }
+
+    /// 按文件分组，文件按其第一个函数出现的顺序排列，组内保持原顺序
+    pub fn group_by_file(nodes: Vec<&FunctionNode>) -> Vec<(&str, Vec<&FunctionNode>)> {
+        let mut groups: Vec<(&str, Vec<&FunctionNode>)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+
+        for node in nodes {
+            let file = node.file_path.as_str();
+            let i = *index.entry(file).or_insert_with(|| {
+                groups.push((file, Vec::new()));
+                groups.len() - 1
+            });
+            groups[i].1.push(node);
+        }
+
+        groups
     }
 
-    /// 判断是否是入口点
+    /// 按默认模式判断是否是入口点
     #[doc(hidden)]
     pub fn is_entry_point(node: &FunctionNode) -> bool {
-        let name_lower = node.name.to_lowercase();
-        let entry_patterns = [
-            "main",
-            "test_",
-            "_test",
-            "new",
-            "default",
-            "init",
-            "setup",
-            "run",
-        ];
-        entry_patterns.iter().any(|p| name_lower.contains(p))
+        DEFAULT_ENTRY_PATTERNS.iter().any(|p| Self::matches_entry_pattern(&node.name, p))
+    }
+
+    /// 按给定模式判断是否是入口点
+    pub fn is_entry_point_with(node: &FunctionNode, patterns: &[String]) -> bool {
+        patterns.iter().any(|p| Self::matches_entry_pattern(&node.name, p))
+    }
+
+    /// 按标识符边界匹配入口点模式 (不区分大小写)
+    ///
+    /// 名称中的非字母数字字符视为 `_` 分隔符 (`init(frame:)` -> `init_frame_`)，
+    /// 驼峰边界同样拆分 (`setupViews` -> `setup_views`，`HTTPServer` -> `http_server`)：
+    /// - `test_` 形式匹配前缀，`_test` 形式匹配后缀
+    /// - 其他模式须与完整的 `_` 分段匹配：`run` 匹配 `run_server` / `runLoop`，不匹配 `prerun_cleanup` / `rerun`
+    pub fn matches_entry_pattern(name: &str, pattern: &str) -> bool {
+        let name = Self::split_identifier(name);
+        let pattern = Self::split_identifier(pattern);
+
+        if pattern.is_empty() {
+            return false;
+        }
+        if pattern.ends_with('_') {
+            return name.starts_with(&pattern);
+        }
+        if pattern.starts_with('_') {
+            return name.ends_with(&pattern);
+        }
+
+        name == pattern
+            || name.starts_with(&format!("{}_", pattern))
+            || name.ends_with(&format!("_{}", pattern))
+            || name.contains(&format!("_{}_", pattern))
+    }
+
+    /// 转为小写的 `_` 分段形式：非字母数字字符变为 `_`，驼峰边界前插入 `_`
+    fn split_identifier(name: &str) -> String {
+        let chars: Vec<char> = name.chars().collect();
+        let mut result = String::with_capacity(name.len() + 4);
+        for (i, &c) in chars.iter().enumerate() {
+            if !c.is_alphanumeric() {
+                result.push('_');
+                continue;
+            }
+            if c.is_uppercase() && i > 0 {
+                let prev = chars[i - 1];
+                // `setupViews` 的 V；`HTTPServer` 的 S (连续大写后接小写)
+                let acronym_end = prev.is_uppercase() && chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+                if prev.is_lowercase() || prev.is_numeric() || acronym_end {
+                    result.push('_');
+                }
+            }
+            result.extend(c.to_lowercase());
+        }
+        result
     }
 
     /// 获取调用树
@@ -123,8 +311,14 @@ impl ArchitectureAnalyzer {
         result
     }
 
+    /// 函数名是否匹配 (短名字相同或以 `::name` 结尾)
+    fn matches_name(node: &FunctionNode, name: &str) -> bool {
+        node.name == name || node.name.ends_with(&format!("::{}", name))
+    }
+
     /// 通过名字查找函数引用
-    fn find_function_ref(&self, name: &str) -> Option<FunctionRef> {
+    /// 按名字查找函数 (精确匹配短名字，否则后缀匹配)
+    pub fn find_function_ref(&self, name: &str) -> Option<FunctionRef> {
         // 精确匹配短名字
         self.functions.iter()
             .find(|(_, node)| node.name == name)
@@ -170,6 +364,276 @@ impl ArchitectureAnalyzer {
         }
     }
 
+    /// 从多个入口沿调用边 BFS，返回所有可达函数 (包含入口本身)
+    pub fn reachable_from(&self, entries: &[FunctionRef]) -> HashSet<FunctionRef> {
+        let mut visited: HashSet<FunctionRef> = HashSet::new();
+        let mut queue: VecDeque<FunctionRef> = VecDeque::new();
+
+        for entry in entries {
+            if self.functions.contains_key(entry) && visited.insert(entry.clone()) {
+                queue.push_back(entry.clone());
+            }
+        }
+
+        while let Some(current) = queue.pop_front() {
+            if let Some(node) = self.functions.get(&current) {
+                for callee in &node.callees {
+                    if self.functions.contains_key(callee) && visited.insert(callee.clone()) {
+                        queue.push_back(callee.clone());
+                    }
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// 从入口不可达的函数 (忽略的函数除外)，按文件和行号排序
+    pub fn unreachable_functions(&self, entries: &[FunctionRef]) -> Vec<&FunctionNode> {
+        let reachable = self.reachable_from(entries);
+        let mut result: Vec<&FunctionNode> = self.functions
+            .iter()
+            .filter(|(k, _)| !reachable.contains(*k) && !self.ignored.contains(*k))
+            .map(|(_, node)| node)
+            .collect();
+        result.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+        result
+    }
+
+    /// 查找调用环 (Tarjan 强连通分量，沿 `callees` 边)
+    ///
+    /// 返回节点数大于 1 的强连通分量以及自递归函数。分量从位置最靠前的函数开始，
+    /// 按分量内的深度优先顺序排列 (简单环即调用顺序)；分量之间按首个函数的位置排序
+    pub fn find_cycles(&self) -> Vec<Vec<FunctionRef>> {
+        let mut keys: Vec<&FunctionRef> = self.functions.keys().collect();
+        keys.sort_by(|a, b| ref_order(a, b));
+
+        let mut index: HashMap<&FunctionRef, usize> = HashMap::new();
+        let mut lowlink: HashMap<&FunctionRef, usize> = HashMap::new();
+        let mut on_stack: HashSet<&FunctionRef> = HashSet::new();
+        let mut stack: Vec<&FunctionRef> = Vec::new();
+        let mut cycles = Vec::new();
+
+        for start in keys {
+            if index.contains_key(start) {
+                continue;
+            }
+
+            // 显式栈代替递归: (节点, 下一个待检查的被调用者下标)
+            let mut work: Vec<(&FunctionRef, usize)> = vec![(start, 0)];
+            while let Some((v, next)) = work.pop() {
+                if next == 0 {
+                    index.insert(v, index.len());
+                    lowlink.insert(v, index[v]);
+                    stack.push(v);
+                    on_stack.insert(v);
+                }
+
+                let callees = &self.functions[v].callees;
+                let mut child = None;
+                let mut i = next;
+                while i < callees.len() {
+                    let Some((w, _)) = self.functions.get_key_value(&callees[i]) else {
+                        i += 1;
+                        continue;
+                    };
+                    i += 1;
+                    if !index.contains_key(w) {
+                        child = Some(w);
+                        break;
+                    }
+                    if on_stack.contains(w) {
+                        lowlink.insert(v, lowlink[v].min(index[w]));
+                    }
+                }
+
+                if let Some(w) = child {
+                    work.push((v, i));
+                    work.push((w, 0));
+                    continue;
+                }
+
+                if lowlink[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack.remove(w);
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 || callees.contains(v) {
+                        cycles.push(self.cycle_order(&component));
+                    }
+                }
+                if let Some(&(parent, _)) = work.last() {
+                    lowlink.insert(parent, lowlink[parent].min(lowlink[v]));
+                }
+            }
+        }
+
+        cycles.sort_by(|a, b| ref_order(&a[0], &b[0]));
+        cycles
+    }
+
+    /// 强连通分量内从位置最靠前的函数开始深度优先排列
+    fn cycle_order(&self, component: &[&FunctionRef]) -> Vec<FunctionRef> {
+        let members: HashSet<&FunctionRef> = component.iter().copied().collect();
+        let start = component.iter().copied().min_by(|a, b| ref_order(a, b)).unwrap();
+
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![start];
+        while let Some(current) = pending.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            order.push(current.clone());
+            // 逆序入栈，保持被调用者的原有顺序
+            for callee in self.functions[current].callees.iter().rev() {
+                if members.contains(callee) && !visited.contains(callee) {
+                    pending.push(callee);
+                }
+            }
+        }
+        order
+    }
+
+    /// 叶子函数：被调用但不调用其他函数 (纯 sink)，按文件和行号排序
+    pub fn leaf_functions(&self) -> Vec<&FunctionNode> {
+        self.filter_by_location(|node| node.callees.is_empty() && !node.callers.is_empty())
+    }
+
+    /// 根函数：调用其他函数但没有调用者 (source)，不含入口点，按文件和行号排序
+    pub fn root_functions(&self) -> Vec<&FunctionNode> {
+        self.filter_by_location(|node| {
+            node.callers.is_empty() && !node.callees.is_empty() && !Self::is_entry_point_with(node, &self.entry_patterns)
+        })
+    }
+
+    fn filter_by_location<F: Fn(&FunctionNode) -> bool>(&self, predicate: F) -> Vec<&FunctionNode> {
+        let mut result: Vec<&FunctionNode> = self.functions
+            .values()
+            .filter(|node| predicate(node))
+            .collect();
+        result.sort_by(|a, b| {
+            a.file_path.cmp(&b.file_path)
+                .then(a.line.cmp(&b.line))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        result
+    }
+
+    /// 按中心度排名最繁忙的函数，返回前 `top` 个 (节点, 分数)
+    pub fn hot_paths(&self, metric: CentralityMetric, top: usize) -> Vec<(&FunctionNode, f64)> {
+        let scores = match metric {
+            CentralityMetric::FanProduct => self.fan_product_scores(),
+            CentralityMetric::Betweenness => self.betweenness_scores(),
+        };
+
+        let mut ranked: Vec<(&FunctionNode, f64)> = scores
+            .into_iter()
+            .filter_map(|(func_ref, score)| self.functions.get(func_ref).map(|n| (n, score)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.name.cmp(&b.0.name))
+        });
+        ranked.truncate(top);
+        ranked
+    }
+
+    /// 每个函数的 fan-in / fan-out 及中心度 (fan_in * fan_out)
+    ///
+    /// 按中心度降序，相同时按总连接数降序、再按位置和名字排序；包含所有函数
+    pub fn metrics(&self) -> Vec<FunctionMetrics<'_>> {
+        let mut metrics: Vec<(&FunctionRef, FunctionMetrics)> = self.fan_product_scores()
+            .into_iter()
+            .map(|(func_ref, score)| {
+                let node = &self.functions[func_ref];
+                let metrics = FunctionMetrics {
+                    node,
+                    fan_in: node.callers.len(),
+                    fan_out: node.callees.len(),
+                    centrality: score as usize,
+                };
+                (func_ref, metrics)
+            })
+            .collect();
+        metrics.sort_by(|(ref_a, a), (ref_b, b)| {
+            b.centrality.cmp(&a.centrality)
+                .then((b.fan_in + b.fan_out).cmp(&(a.fan_in + a.fan_out)))
+                .then_with(|| ref_order(ref_a, ref_b))
+                .then_with(|| a.node.name.cmp(&b.node.name))
+        });
+        metrics.into_iter().map(|(_, metrics)| metrics).collect()
+    }
+
+    /// fan_in * fan_out
+    fn fan_product_scores(&self) -> HashMap<&FunctionRef, f64> {
+        self.functions
+            .iter()
+            .map(|(k, n)| (k, (n.callers.len() * n.callees.len()) as f64))
+            .collect()
+    }
+
+    /// Brandes 算法计算有向图的介数中心度 (只考虑图内已知节点)
+    fn betweenness_scores(&self) -> HashMap<&FunctionRef, f64> {
+        let refs: Vec<&FunctionRef> = self.functions.keys().collect();
+        let index: HashMap<&FunctionRef, usize> = refs.iter().enumerate().map(|(i, r)| (*r, i)).collect();
+        let adjacency: Vec<Vec<usize>> = refs
+            .iter()
+            .map(|r| {
+                self.functions[*r]
+                    .callees
+                    .iter()
+                    .filter_map(|c| index.get(c).copied())
+                    .collect()
+            })
+            .collect();
+
+        let n = refs.len();
+        let mut centrality = vec![0.0_f64; n];
+
+        for s in 0..n {
+            let mut stack = Vec::new();
+            let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut sigma = vec![0.0_f64; n];
+            let mut dist = vec![-1_i64; n];
+            sigma[s] = 1.0;
+            dist[s] = 0;
+
+            let mut queue = VecDeque::from([s]);
+            while let Some(v) = queue.pop_front() {
+                stack.push(v);
+                for &w in &adjacency[v] {
+                    if dist[w] < 0 {
+                        dist[w] = dist[v] + 1;
+                        queue.push_back(w);
+                    }
+                    if dist[w] == dist[v] + 1 {
+                        sigma[w] += sigma[v];
+                        preds[w].push(v);
+                    }
+                }
+            }
+
+            let mut delta = vec![0.0_f64; n];
+            while let Some(w) = stack.pop() {
+                for &v in &preds[w] {
+                    delta[v] += sigma[v] / sigma[w] * (1.0 + delta[w]);
+                }
+                if w != s {
+                    centrality[w] += delta[w];
+                }
+            }
+        }
+
+        refs.into_iter().zip(centrality).collect()
+    }
+
     /// 获取所有函数
     pub fn functions(&self) -> &HashMap<FunctionRef, FunctionNode> {
         &self.functions
@@ -183,18 +647,97 @@ impl ArchitectureAnalyzer {
     }
 }
 
+/// 按文件和行号比较函数引用
+fn ref_order(a: &FunctionRef, b: &FunctionRef) -> std::cmp::Ordering {
+    a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line))
+}
+
+/// 按指定方式排序，相同时按文件、行号、名字排序
+fn sort_nodes(nodes: &mut [&FunctionNode], sort: DeadCodeSort) {
+    let by_location = |a: &&FunctionNode, b: &&FunctionNode| {
+        a.file_path.cmp(&b.file_path)
+            .then(a.line.cmp(&b.line))
+            .then_with(|| a.name.cmp(&b.name))
+    };
+
+    match sort {
+        DeadCodeSort::Location => nodes.sort_by(by_location),
+        DeadCodeSort::Size => nodes.sort_by(|a, b| {
+            b.line_count().cmp(&a.line_count()).then_with(|| by_location(a, b))
+        }),
+        DeadCodeSort::Name => nodes.sort_by(|a, b| {
+            a.name.cmp(&b.name).then_with(|| by_location(a, b))
+        }),
+    }
+}
+
 impl Default for ArchitectureAnalyzer {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// 死代码输出排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeadCodeSort {
+    /// 按文件路径、行号
+    #[default]
+    Location,
+    /// 按函数行数降序
+    Size,
+    /// 按函数名
+    Name,
+}
+
+impl DeadCodeSort {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "file" | "location" => Some(Self::Location),
+            "size" => Some(Self::Size),
+            "name" => Some(Self::Name),
+            _ => None,
+        }
+    }
+}
+
+/// 热点路径的中心度指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CentralityMetric {
+    /// fan_in * fan_out: 开销极低，偏向同时被大量调用且调用大量函数的枢纽
+    #[default]
+    FanProduct,
+    /// 介数中心度: 经过该节点的最短调用路径数，更准确但为 O(V·E)
+    Betweenness,
+}
+
+impl CentralityMetric {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "fan" | "fan-product" => Some(Self::FanProduct),
+            "betweenness" => Some(Self::Betweenness),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum CallDirection {
     Incoming,
     Outgoing,
 }
 
+/// 单个函数的连接度指标
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionMetrics<'a> {
+    pub node: &'a FunctionNode,
+    /// 调用者数量
+    pub fan_in: usize,
+    /// 被调用者数量
+    pub fan_out: usize,
+    /// fan_in * fan_out，越大越像枢纽
+    pub centrality: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct CallTreeNode {
     pub name: String,
@@ -212,6 +755,9 @@ mod tests {
             file_path: "/test/file.rs".to_string(),
             line: 1,
             name: name.to_string(),
+            end_line: 1,
+            kind: "function".to_string(),
+            signature: None,
             callers: callers.into_iter().map(|(f, l)| FunctionRef::new(f.to_string(), l)).collect(),
             callees: callees.into_iter().map(|(f, l)| FunctionRef::new(f.to_string(), l)).collect(),
         }
@@ -241,6 +787,74 @@ mod tests {
         assert!(!ArchitectureAnalyzer::is_entry_point(&node));
     }
 
+    #[test]
+    fn test_is_entry_point_respects_boundaries() {
+        for name in ["renew_token", "prerun_cleanup", "maintain", "newsletter", "contest_entry"] {
+            let node = make_node(name, vec![], vec![]);
+            assert!(!ArchitectureAnalyzer::is_entry_point(&node), "{} should not be an entry point", name);
+        }
+        for name in ["setup_database", "run_server", "new_with_capacity", "parse_test", "init(frame:)", "Default"] {
+            let node = make_node(name, vec![], vec![]);
+            assert!(ArchitectureAnalyzer::is_entry_point(&node), "{} should be an entry point", name);
+        }
+    }
+
+    #[test]
+    fn test_matches_entry_pattern() {
+        assert!(ArchitectureAnalyzer::matches_entry_pattern("handle_request", "handle"));
+        assert!(ArchitectureAnalyzer::matches_entry_pattern("on_http_request", "http_request"));
+        assert!(ArchitectureAnalyzer::matches_entry_pattern("bench_sort", "bench_"));
+        assert!(!ArchitectureAnalyzer::matches_entry_pattern("workbench_sort", "bench_"));
+        assert!(ArchitectureAnalyzer::matches_entry_pattern("sort_bench", "_bench"));
+        assert!(!ArchitectureAnalyzer::matches_entry_pattern("handler", "handle"));
+        assert!(!ArchitectureAnalyzer::matches_entry_pattern("anything", ""));
+    }
+
+    #[test]
+    fn test_matches_entry_pattern_camel_case() {
+        for name in ["setupViews", "runLoop", "newInstance", "initWithFrame", "testParsesInput", "parseTest", "HTTPServerMain"] {
+            let node = make_node(name, vec![], vec![]);
+            assert!(ArchitectureAnalyzer::is_entry_point(&node), "{} should be an entry point", name);
+        }
+        for name in ["renewToken", "rerun", "initialize", "setupper", "defaultsKey"] {
+            let node = make_node(name, vec![], vec![]);
+            assert!(!ArchitectureAnalyzer::is_entry_point(&node), "{} should not be an entry point", name);
+        }
+        // 模式本身的驼峰同样拆分
+        assert!(ArchitectureAnalyzer::matches_entry_pattern("setUpWithError", "setUp"));
+        assert!(ArchitectureAnalyzer::matches_entry_pattern("set_up", "setUp"));
+        assert!(!ArchitectureAnalyzer::matches_entry_pattern("setupper", "setUp"));
+    }
+
+    #[test]
+    fn test_find_dead_code_false_positive_entry_points() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/test/file.rs", 1, make_node("renew_token", vec![], vec![]));
+        analyzer.add_function("/test/file.rs", 2, make_node("prerun_cleanup", vec![], vec![]));
+        analyzer.add_function("/test/file.rs", 3, make_node("run", vec![], vec![]));
+
+        let dead: Vec<_> = analyzer.find_dead_code().iter().map(|n| n.name.clone()).collect();
+        assert_eq!(dead, vec!["prerun_cleanup", "renew_token"]);
+    }
+
+    #[test]
+    fn test_find_dead_code_custom_entry_patterns() {
+        let build = || {
+            let mut analyzer = ArchitectureAnalyzer::new();
+            analyzer.add_function("/test/file.rs", 1, make_node("handle_click", vec![], vec![]));
+            analyzer.add_function("/test/file.rs", 2, make_node("main", vec![], vec![]));
+            analyzer
+        };
+
+        // find_dead_code_with 只使用给定模式
+        let names = |nodes: Vec<&FunctionNode>| nodes.iter().map(|n| n.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(build().find_dead_code_with(&["handle".to_string()])), vec!["main"]);
+
+        // with_entry_patterns 在默认模式之外追加
+        let analyzer = build().with_entry_patterns(["handle".to_string()]);
+        assert!(analyzer.find_dead_code().is_empty());
+    }
+
     #[test]
     fn test_find_dead_code_no_callers() {
         let mut analyzer = ArchitectureAnalyzer::new();
@@ -278,6 +892,150 @@ mod tests {
         assert!(dead.is_empty()); // main is entry point, foo has caller
     }
 
+    #[test]
+    fn test_find_dead_code_skips_ignored() {
+        let ignore = IgnoreSet::parse(None, "*::legacy::*\n/test/gen.rs\n");
+        let mut analyzer = ArchitectureAnalyzer::new().with_ignore(ignore);
+        let no_calls = CallHierarchy { incoming: vec![], outgoing: vec![] };
+
+        for (name, file, line) in [
+            ("rust:file.rs::legacy::parse", "/test/file.rs", 1),
+            ("rust:file.rs::helper", "/test/file.rs", 5),
+            ("rust:gen.rs::generated", "/test/gen.rs", 1),
+        ] {
+            let unit = CodeUnit {
+                qualified_name: name.to_string(),
+                file_path: file.to_string(),
+                kind: "function".to_string(),
+                range_start: line,
+                range_end: line + 2,
+                body: String::new(),
+                selection_line: line,
+                selection_column: 0,
+                signature: None,
+            };
+            analyzer.add_unit(&unit, &no_calls);
+        }
+
+        let dead: Vec<_> = analyzer.find_dead_code().iter().map(|n| n.name.clone()).collect();
+        assert_eq!(dead, vec!["helper"]);
+        assert_eq!(analyzer.functions().len(), 3);
+    }
+
+    #[test]
+    fn test_add_unit_keeps_signature() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        let no_calls = CallHierarchy { incoming: vec![], outgoing: vec![] };
+        let body = "pub fn compute_totals(items: &[Item]) -> u64 {\n    0\n}";
+        let unit = CodeUnit {
+            qualified_name: "rust:file.rs::compute_totals".to_string(),
+            file_path: "/test/file.rs".to_string(),
+            kind: "function".to_string(),
+            range_start: 3,
+            range_end: 5,
+            body: body.to_string(),
+            selection_line: 3,
+            selection_column: 7,
+            signature: CodeUnit::parse_signature(body),
+        };
+        analyzer.add_unit(&unit, &no_calls);
+
+        let dead = analyzer.find_dead_code();
+        assert_eq!(dead[0].signature.as_deref(), Some("pub fn compute_totals(items: &[Item]) -> u64"));
+    }
+
+    #[test]
+    fn test_leaf_and_root_functions() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+
+        // main -> handler -> helper, isolated 没有任何调用关系
+        analyzer.add_function("/test/file.rs", 1, make_node("main", vec![], vec![("/test/file.rs", 2)]));
+        analyzer.add_function("/test/file.rs", 2, make_node("handler", vec![("/test/file.rs", 1)], vec![("/test/file.rs", 3)]));
+        analyzer.add_function("/test/file.rs", 3, make_node("helper", vec![("/test/file.rs", 2)], vec![]));
+        analyzer.add_function("/test/file.rs", 4, make_node("orphan_caller", vec![], vec![("/test/file.rs", 3)]));
+        analyzer.add_function("/test/file.rs", 5, make_node("isolated", vec![], vec![]));
+
+        let leaves: Vec<_> = analyzer.leaf_functions().iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(leaves, vec!["helper"]);
+
+        // main 是入口点，不算根
+        let roots: Vec<_> = analyzer.root_functions().iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(roots, vec!["orphan_caller"]);
+    }
+
+    fn make_dead_graph() -> ArchitectureAnalyzer {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        for (file, line, end_line, name) in [
+            ("/b.rs", 10, 12, "small"),
+            ("/a.rs", 30, 80, "large"),
+            ("/a.rs", 5, 20, "medium"),
+        ] {
+            let mut node = make_node(name, vec![], vec![]);
+            node.file_path = file.to_string();
+            node.line = line;
+            node.end_line = end_line;
+            analyzer.add_function(file, line, node);
+        }
+        analyzer
+    }
+
+    #[test]
+    fn test_find_dead_code_methods_as_live() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/test/file.rs", 1, make_node("unused_fn", vec![], vec![]));
+        let mut method = make_node("fmt_impl", vec![], vec![]);
+        method.kind = "method".to_string();
+        analyzer.add_function("/test/file.rs", 2, method);
+
+        assert_eq!(analyzer.find_dead_code().len(), 2);
+
+        let analyzer = analyzer.with_methods_as_live(true);
+        let dead = analyzer.find_dead_code();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].name, "unused_fn");
+    }
+
+    #[test]
+    fn test_find_dead_code_sorted() {
+        let analyzer = make_dead_graph();
+        let names = |sort| -> Vec<String> {
+            analyzer.find_dead_code_sorted(sort).iter().map(|n| n.name.clone()).collect()
+        };
+
+        assert_eq!(names(DeadCodeSort::Location), vec!["medium", "large", "small"]);
+        assert_eq!(names(DeadCodeSort::Size), vec!["large", "medium", "small"]);
+        assert_eq!(names(DeadCodeSort::Name), vec!["large", "medium", "small"]);
+        assert_eq!(DeadCodeSort::parse("file"), Some(DeadCodeSort::Location));
+        assert_eq!(DeadCodeSort::parse("lines"), None);
+    }
+
+    #[test]
+    fn test_find_dead_code_by_file() {
+        let mut analyzer = make_dead_graph();
+        let mut huge = make_node("huge", vec![], vec![]);
+        huge.file_path = "/b.rs".to_string();
+        huge.line = 40;
+        huge.end_line = 200;
+        analyzer.add_function("/b.rs", 40, huge);
+
+        let groups = |sort| -> Vec<(String, Vec<String>)> {
+            analyzer.find_dead_code_by_file(sort)
+                .into_iter()
+                .map(|(file, nodes)| (file.to_string(), nodes.iter().map(|n| n.name.clone()).collect()))
+                .collect()
+        };
+
+        assert_eq!(groups(DeadCodeSort::Location), vec![
+            ("/a.rs".to_string(), vec!["medium".to_string(), "large".to_string()]),
+            ("/b.rs".to_string(), vec!["small".to_string(), "huge".to_string()]),
+        ]);
+        // 按大小排序时，包含最大函数的文件排在前面
+        assert_eq!(groups(DeadCodeSort::Size), vec![
+            ("/b.rs".to_string(), vec!["huge".to_string(), "small".to_string()]),
+            ("/a.rs".to_string(), vec!["large".to_string(), "medium".to_string()]),
+        ]);
+    }
+
     #[test]
     fn test_get_call_tree_outgoing() {
         let mut analyzer = ArchitectureAnalyzer::new();
@@ -362,6 +1120,133 @@ mod tests {
         assert_eq!(tree.len(), 2);
     }
 
+    /// a -> hub -> c, b -> hub -> d
+    fn make_hub_graph() -> ArchitectureAnalyzer {
+        let f = |l: u32| FunctionRef::new("/test/file.rs".to_string(), l);
+        let mut analyzer = ArchitectureAnalyzer::new();
+
+        let mut a = make_node("a", vec![], vec![]);
+        a.callees = vec![f(3)];
+        analyzer.add_function("/test/file.rs", 1, a);
+
+        let mut b = make_node("b", vec![], vec![]);
+        b.callees = vec![f(3)];
+        analyzer.add_function("/test/file.rs", 2, b);
+
+        let mut hub = make_node("hub", vec![], vec![]);
+        hub.callers = vec![f(1), f(2)];
+        hub.callees = vec![f(4), f(5)];
+        analyzer.add_function("/test/file.rs", 3, hub);
+
+        let mut c = make_node("c", vec![], vec![]);
+        c.callers = vec![f(3)];
+        analyzer.add_function("/test/file.rs", 4, c);
+
+        let mut d = make_node("d", vec![], vec![]);
+        d.callers = vec![f(3)];
+        analyzer.add_function("/test/file.rs", 5, d);
+
+        analyzer
+    }
+
+    #[test]
+    fn test_reachable_from_multiple_entries() {
+        let analyzer = make_hub_graph();
+        let f = |l: u32| FunctionRef::new("/test/file.rs".to_string(), l);
+
+        // a -> hub -> c, d
+        let reachable = analyzer.reachable_from(&[f(1)]);
+        assert_eq!(reachable, [f(1), f(3), f(4), f(5)].into_iter().collect());
+
+        // b 不可达
+        let unreachable: Vec<_> = analyzer.unreachable_functions(&[f(1)])
+            .iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(unreachable, vec!["b"]);
+
+        // 多入口覆盖全部
+        assert!(analyzer.unreachable_functions(&[f(1), f(2)]).is_empty());
+
+        // 未知入口被忽略
+        assert!(analyzer.reachable_from(&[f(99)]).is_empty());
+    }
+
+    #[test]
+    fn test_find_unreachable_dead_subgraph() {
+        let f = |l: u32| FunctionRef::new("/test/file.rs".to_string(), l);
+        let mut analyzer = ArchitectureAnalyzer::new();
+
+        // main -> d 存活；a -> b -> c 只有 a 没有调用者
+        let mut main_node = make_node("main", vec![], vec![]);
+        main_node.callees = vec![f(2)];
+        analyzer.add_function("/test/file.rs", 1, main_node);
+        analyzer.add_function("/test/file.rs", 2, make_node("d", vec![("/test/file.rs", 1)], vec![]));
+        analyzer.add_function("/test/file.rs", 3, make_node("a", vec![], vec![("/test/file.rs", 4)]));
+        analyzer.add_function("/test/file.rs", 4, make_node("b", vec![("/test/file.rs", 3)], vec![("/test/file.rs", 5)]));
+        analyzer.add_function("/test/file.rs", 5, make_node("c", vec![("/test/file.rs", 4)], vec![]));
+
+        let names = |nodes: Vec<&FunctionNode>| nodes.iter().map(|n| n.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(analyzer.find_dead_code()), vec!["a"]);
+        assert_eq!(names(analyzer.find_unreachable(&[])), vec!["a", "b", "c"]);
+
+        // 显式指定根时不再使用入口点
+        assert_eq!(names(analyzer.find_unreachable(&["a"])), vec!["d", "main"]);
+    }
+
+    #[test]
+    fn test_find_unreachable_cycle() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+
+        // x <-> y 互相递归，但没有入口点能到达
+        analyzer.add_function("/test/file.rs", 1, make_node("main", vec![], vec![]));
+        analyzer.add_function("/test/file.rs", 2, make_node("x", vec![("/test/file.rs", 3)], vec![("/test/file.rs", 3)]));
+        analyzer.add_function("/test/file.rs", 3, make_node("y", vec![("/test/file.rs", 2)], vec![("/test/file.rs", 2)]));
+
+        assert!(analyzer.find_dead_code().is_empty());
+        let unreachable: Vec<_> = analyzer.find_unreachable(&["main"]).iter().map(|n| n.name.clone()).collect();
+        assert_eq!(unreachable, vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let analyzer = make_hub_graph();
+        let metrics = analyzer.metrics();
+        assert_eq!(metrics.len(), 5);
+
+        let hub = &metrics[0];
+        assert_eq!(hub.node.name, "hub");
+        assert_eq!((hub.fan_in, hub.fan_out, hub.centrality), (2, 2, 4));
+
+        // 其余中心度都为 0，按连接数和位置排序
+        let rest: Vec<_> = metrics[1..].iter().map(|m| (m.node.name.as_str(), m.fan_in, m.fan_out)).collect();
+        assert_eq!(rest, vec![("a", 0, 1), ("b", 0, 1), ("c", 1, 0), ("d", 1, 0)]);
+        assert!(metrics[1..].iter().all(|m| m.centrality == 0));
+    }
+
+    #[test]
+    fn test_hot_paths_fan_product() {
+        let analyzer = make_hub_graph();
+        let hot = analyzer.hot_paths(CentralityMetric::FanProduct, 10);
+        assert_eq!(hot.len(), 1); // 只有 hub 同时有调用者和被调用者
+        assert_eq!(hot[0].0.name, "hub");
+        assert_eq!(hot[0].1, 4.0);
+    }
+
+    #[test]
+    fn test_hot_paths_betweenness() {
+        let analyzer = make_hub_graph();
+        let hot = analyzer.hot_paths(CentralityMetric::Betweenness, 1);
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].0.name, "hub");
+        assert_eq!(hot[0].1, 4.0); // a->c, a->d, b->c, b->d 都经过 hub
+    }
+
+    #[test]
+    fn test_centrality_metric_parse() {
+        assert_eq!(CentralityMetric::parse("fan"), Some(CentralityMetric::FanProduct));
+        assert_eq!(CentralityMetric::parse("betweenness"), Some(CentralityMetric::Betweenness));
+        assert_eq!(CentralityMetric::parse("pagerank"), None);
+    }
+
     #[test]
     fn test_get_call_tree_handles_cycles() {
         let mut analyzer = ArchitectureAnalyzer::new();
@@ -380,5 +1265,30 @@ mod tests {
 
         // Should not infinite loop
         assert_eq!(tree.len(), 2);
+
+        let f = |l: u32| FunctionRef::new("/test/file.rs".to_string(), l);
+        assert_eq!(analyzer.find_cycles(), vec![vec![f(1), f(2)]]);
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let f = |l: u32| FunctionRef::new("/test/file.rs".to_string(), l);
+        let mut analyzer = ArchitectureAnalyzer::new();
+
+        // main -> a -> b -> c -> a, c -> d, rec -> rec, 还有指向图外的调用
+        analyzer.add_function("/test/file.rs", 1, make_node("main", vec![], vec![("/test/file.rs", 2)]));
+        analyzer.add_function("/test/file.rs", 2, make_node("a", vec![], vec![("/test/file.rs", 3)]));
+        analyzer.add_function("/test/file.rs", 3, make_node("b", vec![], vec![("/test/file.rs", 4), ("/external.rs", 1)]));
+        analyzer.add_function("/test/file.rs", 4, make_node("c", vec![], vec![("/test/file.rs", 5), ("/test/file.rs", 2)]));
+        analyzer.add_function("/test/file.rs", 5, make_node("d", vec![], vec![]));
+        analyzer.add_function("/test/file.rs", 6, make_node("rec", vec![], vec![("/test/file.rs", 6)]));
+
+        assert_eq!(analyzer.find_cycles(), vec![vec![f(2), f(3), f(4)], vec![f(6)]]);
+    }
+
+    #[test]
+    fn test_find_cycles_acyclic() {
+        assert!(make_hub_graph().find_cycles().is_empty());
+        assert!(ArchitectureAnalyzer::new().find_cycles().is_empty());
     }
 }
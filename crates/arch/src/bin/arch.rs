@@ -2,7 +2,7 @@
 
 use arch::{ArchitectureAnalyzer, MermaidGenerator, CallDirection};
 use clap::{Parser, Subcommand};
-use lsp::{LanguageAdapter, RustAdapter, SwiftAdapter};
+use lsp::{LanguageAdapter, QualifiedName, RustAdapter, SwiftAdapter};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -259,5 +259,5 @@ async fn cmd_call_tree(path: &str, entry: &str, lang: &str, depth: usize, incomi
 // ==================== Helpers ====================
 
 fn short_name(name: &str) -> String {
-    name.split("::").last().unwrap_or(name).to_string()
+    QualifiedName::parse(name).short().to_string()
 }
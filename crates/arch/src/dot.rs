@@ -0,0 +1,231 @@
+use crate::analyzer::ArchitectureAnalyzer;
+use crate::mermaid::{module_graph, select_nodes, Direction, MermaidGenerator, ModuleGraph, NodeSelection, OTHER_MODULES_ID};
+use lsp::FunctionRef;
+use std::collections::HashSet;
+
+/// Graphviz DOT 生成器 - 大图在浏览器中渲染 Mermaid 过慢时使用
+///
+/// 节点形状与 Mermaid 语义一致：无调用者 (入口点) 为 `doubleoctagon`，
+/// 无被调用者 (叶子) 为 `ellipse`，其余为 `box`
+pub struct DotGenerator {
+    max_nodes: usize,
+    /// 模块图只保留跨模块连接最多的前 N 个模块
+    top_modules: Option<usize>,
+    /// 以红色虚线边框标记的节点 (通常来自 `find_dead_code`)
+    dead_nodes: HashSet<FunctionRef>,
+    direction: Direction,
+}
+
+impl DotGenerator {
+    pub fn new() -> Self {
+        Self {
+            max_nodes: 100,
+            top_modules: None,
+            dead_nodes: HashSet::new(),
+            direction: Direction::default(),
+        }
+    }
+
+    /// 布局方向，输出为 `rankdir` (默认 `TB`)
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_max_nodes(mut self, max: usize) -> Self {
+        self.max_nodes = max;
+        self
+    }
+
+    /// 模块图只保留前 N 个模块 (按跨模块调用数)，其余合并为一个节点
+    pub fn with_top_modules(mut self, top: Option<usize>) -> Self {
+        self.top_modules = top;
+        self
+    }
+
+    /// 调用图中用红色虚线边框标记这些节点
+    pub fn with_dead_nodes(mut self, nodes: HashSet<FunctionRef>) -> Self {
+        self.dead_nodes = nodes;
+        self
+    }
+
+    /// 选取调用图节点，规则同 `MermaidGenerator::select_nodes`
+    pub fn select_nodes<'a>(&self, analyzer: &'a ArchitectureAnalyzer) -> NodeSelection<'a> {
+        select_nodes(analyzer, self.max_nodes)
+    }
+
+    /// 生成调用图 DOT 代码
+    pub fn generate_call_graph(&self, analyzer: &ArchitectureAnalyzer) -> String {
+        let mut lines = vec![
+            "digraph calls {".to_string(),
+            format!("    rankdir={};", self.direction.rankdir()),
+            "    node [shape=box];".to_string(),
+        ];
+
+        let sorted = self.select_nodes(analyzer).included;
+        let included: HashSet<&FunctionRef> = sorted.iter().map(|(r, _)| *r).collect();
+
+        // 生成节点
+        for (func_ref, node) in &sorted {
+            let mut attrs = vec![format!("label={}", quote(&MermaidGenerator::short_name(&node.name)))];
+            if node.callers.is_empty() {
+                attrs.push("shape=doubleoctagon".to_string());
+            } else if node.callees.is_empty() {
+                attrs.push("shape=ellipse".to_string());
+            }
+            if self.dead_nodes.contains(*func_ref) {
+                attrs.push(r##"style=dashed, color="#d33", fontcolor="#d33", penwidth=2"##.to_string());
+            }
+            lines.push(format!(
+                "    {} [{}];",
+                quote(&MermaidGenerator::ref_to_id(func_ref)),
+                attrs.join(", ")
+            ));
+        }
+
+        // 生成边
+        for (func_ref, node) in &sorted {
+            for callee in &node.callees {
+                if included.contains(callee) {
+                    lines.push(format!(
+                        "    {} -> {};",
+                        quote(&MermaidGenerator::ref_to_id(func_ref)),
+                        quote(&MermaidGenerator::ref_to_id(callee))
+                    ));
+                }
+            }
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    /// 生成模块依赖图 DOT 代码，边标签为跨模块调用次数
+    pub fn generate_module_diagram(&self, analyzer: &ArchitectureAnalyzer, workspace: &str) -> String {
+        let ModuleGraph { modules, collapsed, edges } = module_graph(analyzer, workspace, self.top_modules);
+        let mut lines = vec![
+            "digraph modules {".to_string(),
+            format!("    rankdir={};", self.direction.rankdir()),
+            "    node [shape=box];".to_string(),
+        ];
+
+        for module in &modules {
+            lines.push(format!("    {} [label={}];", quote(&MermaidGenerator::node_id(module)), quote(module)));
+        }
+        if collapsed > 0 {
+            lines.push(format!(
+                "    {} [label={}, style=dashed];",
+                quote(OTHER_MODULES_ID),
+                quote(&format!("{} other modules", collapsed))
+            ));
+        }
+
+        for ((from, to), count) in edges {
+            lines.push(format!(
+                "    {} -> {} [label=\"{}\"];",
+                quote(&MermaidGenerator::node_id(&from)),
+                quote(&MermaidGenerator::node_id(&to)),
+                count
+            ));
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+impl Default for DotGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// DOT 双引号字符串
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp::FunctionNode;
+
+    fn make_node(file: &str, line: u32, name: &str, callers: &[u32], callees: &[u32]) -> FunctionNode {
+        let refs = |lines: &[u32]| lines.iter().map(|&l| FunctionRef::new(file.to_string(), l)).collect();
+        FunctionNode {
+            file_path: file.to_string(),
+            line,
+            name: name.to_string(),
+            end_line: line,
+            kind: "function".to_string(),
+            signature: None,
+            callers: refs(callers),
+            callees: refs(callees),
+        }
+    }
+
+    #[test]
+    fn test_call_graph_shapes_and_edges() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "main", &[], &[2]));
+        analyzer.add_function("/ws/a.rs", 2, make_node("/ws/a.rs", 2, "run", &[1], &[3]));
+        analyzer.add_function("/ws/a.rs", 3, make_node("/ws/a.rs", 3, "leaf", &[2], &[]));
+
+        let dot = DotGenerator::new().generate_call_graph(&analyzer);
+        let lines: Vec<&str> = dot.lines().collect();
+        assert_eq!(lines, vec![
+            "digraph calls {",
+            "    rankdir=TB;",
+            "    node [shape=box];",
+            r#"    "_ws_a_rs_2" [label="run"];"#,
+            r#"    "_ws_a_rs_1" [label="main", shape=doubleoctagon];"#,
+            r#"    "_ws_a_rs_3" [label="leaf", shape=ellipse];"#,
+            r#"    "_ws_a_rs_2" -> "_ws_a_rs_3";"#,
+            r#"    "_ws_a_rs_1" -> "_ws_a_rs_2";"#,
+            "}",
+        ]);
+    }
+
+    #[test]
+    fn test_call_graph_highlights_dead_nodes() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "unused", &[], &[]));
+
+        let dead = [FunctionRef::new("/ws/a.rs".to_string(), 1)].into_iter().collect();
+        let dot = DotGenerator::new().with_dead_nodes(dead).generate_call_graph(&analyzer);
+        assert!(dot.contains(r##""_ws_a_rs_1" [label="unused", shape=doubleoctagon, style=dashed, color="#d33""##));
+    }
+
+    #[test]
+    fn test_module_diagram() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        let mut fa = make_node("/ws/a.rs", 1, "fa", &[], &[]);
+        fa.callees = vec![FunctionRef::new("/ws/b.rs".to_string(), 1)];
+        analyzer.add_function("/ws/a.rs", 1, fa);
+        analyzer.add_function("/ws/b.rs", 1, make_node("/ws/b.rs", 1, "fb", &[], &[]));
+
+        let dot = DotGenerator::new().generate_module_diagram(&analyzer, "/ws");
+        let lines: Vec<&str> = dot.lines().collect();
+        assert_eq!(lines, vec![
+            "digraph modules {",
+            "    rankdir=TB;",
+            "    node [shape=box];",
+            r#"    "a" [label="a"];"#,
+            r#"    "b" [label="b"];"#,
+            r#"    "a" -> "b" [label="1"];"#,
+            "}",
+        ]);
+    }
+
+    #[test]
+    fn test_direction_sets_rankdir() {
+        let analyzer = ArchitectureAnalyzer::new();
+        let dot = DotGenerator::new().with_direction(Direction::LeftRight).generate_call_graph(&analyzer);
+        assert_eq!(dot, "digraph calls {\n    rankdir=LR;\n    node [shape=box];\n}");
+    }
+
+    #[test]
+    fn test_quote_escapes() {
+        assert_eq!(quote(r#"say "hi"\n"#), r#""say \"hi\"\\n""#);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::analyzer::ArchitectureAnalyzer;
+use lsp::{FunctionNode, FunctionRef};
+use serde::{Deserialize, Serialize};
+
+/// 完整调用图的 JSON 导出格式，供外部分析脚本读取
+///
+/// 函数按文件、行号排序；调用关系解析为显示名，只保留两端都在图中的边
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallGraphExport {
+    pub functions: Vec<ExportedFunction>,
+}
+
+/// 导出的函数节点
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedFunction {
+    pub name: String,
+    pub file: String,
+    pub line: u32,
+    pub end_line: u32,
+    pub kind: String,
+    pub callers: Vec<ExportedRef>,
+    pub callees: Vec<ExportedRef>,
+}
+
+/// 调用关系另一端的函数 (同名函数靠 file/line 区分)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedRef {
+    pub name: String,
+    pub file: String,
+    pub line: u32,
+}
+
+impl CallGraphExport {
+    pub fn from_analyzer(analyzer: &ArchitectureAnalyzer) -> Self {
+        let functions = analyzer.functions();
+        let mut nodes: Vec<(&FunctionRef, &FunctionNode)> = functions.iter().collect();
+        nodes.sort_by(|(a, _), (b, _)| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+
+        let resolve = |refs: &[FunctionRef]| -> Vec<ExportedRef> {
+            let mut resolved: Vec<ExportedRef> = refs
+                .iter()
+                .filter_map(|r| functions.get(r).map(|node| ExportedRef {
+                    name: node.name.clone(),
+                    file: r.file_path.clone(),
+                    line: r.line,
+                }))
+                .collect();
+            resolved.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+            resolved.dedup();
+            resolved
+        };
+
+        let functions = nodes
+            .into_iter()
+            .map(|(func_ref, node)| ExportedFunction {
+                name: node.name.clone(),
+                file: func_ref.file_path.clone(),
+                line: func_ref.line,
+                end_line: node.end_line,
+                kind: node.kind.clone(),
+                callers: resolve(&node.callers),
+                callees: resolve(&node.callees),
+            })
+            .collect();
+
+        Self { functions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_node(file: &str, line: u32, name: &str, callers: &[u32], callees: &[u32]) -> FunctionNode {
+        let refs = |lines: &[u32]| lines.iter().map(|&l| FunctionRef::new(file.to_string(), l)).collect();
+        FunctionNode {
+            file_path: file.to_string(),
+            line,
+            name: name.to_string(),
+            end_line: line + 2,
+            kind: "function".to_string(),
+            signature: None,
+            callers: refs(callers),
+            callees: refs(callees),
+        }
+    }
+
+    #[test]
+    fn test_export_resolves_names() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 10, make_node("/ws/a.rs", 10, "helper", &[1], &[]));
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "main", &[], &[10, 99]));
+
+        let export = CallGraphExport::from_analyzer(&analyzer);
+        let names: Vec<&str> = export.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "helper"]);
+
+        // 指向图外 (line 99) 的调用被丢弃
+        let main = &export.functions[0];
+        assert_eq!(main.callees, vec![ExportedRef { name: "helper".into(), file: "/ws/a.rs".into(), line: 10 }]);
+        assert_eq!(export.functions[1].callers[0].name, "main");
+        assert_eq!(export.functions[1].end_line, 12);
+    }
+
+    #[test]
+    fn test_export_json_roundtrip() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "main", &[], &[]));
+
+        let export = CallGraphExport::from_analyzer(&analyzer);
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["functions"][0]["name"], "main");
+        assert_eq!(json["functions"][0]["callees"], serde_json::json!([]));
+
+        let parsed: CallGraphExport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, export);
+    }
+}
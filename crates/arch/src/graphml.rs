@@ -0,0 +1,123 @@
+use crate::analyzer::ArchitectureAnalyzer;
+use crate::mermaid::MermaidGenerator;
+use lsp::{FunctionNode, FunctionRef};
+
+/// GraphML 生成器 - 供 Gephi、Cytoscape 等图分析工具导入
+///
+/// 节点带 `name`/`file`/`line` 属性，边为调用方向 (caller -> callee)
+pub struct GraphMlGenerator;
+
+impl GraphMlGenerator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 生成完整调用图的 GraphML 文档 (节点按文件、行号排序，输出稳定)
+    pub fn generate(&self, analyzer: &ArchitectureAnalyzer) -> String {
+        let functions = analyzer.functions();
+        let mut nodes: Vec<(&FunctionRef, &FunctionNode)> = functions.iter().collect();
+        nodes.sort_by(|(a, _), (b, _)| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+
+        let mut lines = vec![
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#.to_string(),
+            r#"  <key id="name" for="node" attr.name="name" attr.type="string"/>"#.to_string(),
+            r#"  <key id="file" for="node" attr.name="file" attr.type="string"/>"#.to_string(),
+            r#"  <key id="line" for="node" attr.name="line" attr.type="int"/>"#.to_string(),
+            r#"  <graph id="calls" edgedefault="directed">"#.to_string(),
+        ];
+
+        for (func_ref, node) in &nodes {
+            lines.push(format!(r#"    <node id="{}">"#, escape(&MermaidGenerator::ref_to_id(func_ref))));
+            lines.push(format!(r#"      <data key="name">{}</data>"#, escape(&node.name)));
+            lines.push(format!(r#"      <data key="file">{}</data>"#, escape(&node.file_path)));
+            lines.push(format!(r#"      <data key="line">{}</data>"#, node.line));
+            lines.push("    </node>".to_string());
+        }
+
+        // 只保留两端都在图中的边
+        let mut edge_count = 0;
+        for (func_ref, node) in &nodes {
+            for callee in node.callees.iter().filter(|c| functions.contains_key(*c)) {
+                lines.push(format!(
+                    r#"    <edge id="e{}" source="{}" target="{}"/>"#,
+                    edge_count,
+                    escape(&MermaidGenerator::ref_to_id(func_ref)),
+                    escape(&MermaidGenerator::ref_to_id(callee)),
+                ));
+                edge_count += 1;
+            }
+        }
+
+        lines.push("  </graph>".to_string());
+        lines.push("</graphml>".to_string());
+        lines.join("\n")
+    }
+}
+
+impl Default for GraphMlGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 转义 XML 特殊字符
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_node(file: &str, line: u32, name: &str, callees: &[u32]) -> FunctionNode {
+        FunctionNode {
+            file_path: file.to_string(),
+            line,
+            name: name.to_string(),
+            end_line: line,
+            kind: "function".to_string(),
+            signature: None,
+            callers: vec![],
+            callees: callees.iter().map(|&l| FunctionRef::new(file.to_string(), l)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Vec<T> & \"x\""), "Vec&lt;T&gt; &amp; &quot;x&quot;");
+    }
+
+    #[test]
+    fn test_generate_parses_as_xml() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "main", &[2, 3]));
+        analyzer.add_function("/ws/a.rs", 2, make_node("/ws/a.rs", 2, "parse<T>", &[3, 99]));
+        analyzer.add_function("/ws/a.rs", 3, make_node("/ws/a.rs", 3, "run", &[]));
+
+        let graphml = GraphMlGenerator::new().generate(&analyzer);
+        let doc = roxmltree::Document::parse(&graphml).unwrap();
+
+        let count = |tag: &str| doc.descendants().filter(|n| n.has_tag_name(tag)).count();
+        assert_eq!(count("node"), 3);
+        // 指向图外节点 (line 99) 的边被丢弃
+        assert_eq!(count("edge"), 3);
+
+        let graph = doc.descendants().find(|n| n.has_tag_name("graph")).unwrap();
+        assert_eq!(graph.attribute("edgedefault"), Some("directed"));
+
+        let names: Vec<&str> = doc.descendants()
+            .filter(|n| n.has_tag_name("data") && n.attribute("key") == Some("name"))
+            .filter_map(|n| n.text())
+            .collect();
+        assert_eq!(names, vec!["main", "parse<T>", "run"]);
+
+        let edge = doc.descendants().find(|n| n.has_tag_name("edge")).unwrap();
+        assert_eq!(edge.attribute("source"), Some("_ws_a_rs_1"));
+        assert_eq!(edge.attribute("target"), Some("_ws_a_rs_2"));
+    }
+}
@@ -1,9 +1,17 @@
 //! arch - 架构分析
 //!
-//! 调用图分析、死码检测、文档生成
+//! 调用图分析、死码检测、文档生成 (Mermaid / Graphviz DOT / GraphML)
 
 mod analyzer;
+mod dot;
+mod export;
+mod graphml;
 mod mermaid;
+mod project;
 
-pub use analyzer::{ArchitectureAnalyzer, CallDirection, CallTreeNode};
-pub use mermaid::MermaidGenerator;
+pub use analyzer::{ArchError, ArchitectureAnalyzer, CallDirection, CallTreeNode, CentralityMetric, DeadCodeSort, FunctionMetrics, DEFAULT_ENTRY_PATTERNS};
+pub use dot::DotGenerator;
+pub use export::{CallGraphExport, ExportedFunction, ExportedRef};
+pub use graphml::GraphMlGenerator;
+pub use mermaid::{Direction, MermaidGenerator, NodeSelection};
+pub use project::{analyze_file, analyze_project, analyze_project_with, AnalyzeOptions};
@@ -1,16 +1,111 @@
 use crate::analyzer::ArchitectureAnalyzer;
-use lsp::{FunctionNode, FunctionRef};
-use std::collections::{HashMap, HashSet};
+use lsp::{FunctionNode, FunctionRef, QualifiedName};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
+/// 调用图节点选取结果：按连接数保留前 `max_nodes` 个，其余被截断
+pub struct NodeSelection<'a> {
+    pub included: Vec<(&'a FunctionRef, &'a FunctionNode)>,
+    pub excluded: Vec<(&'a FunctionRef, &'a FunctionNode)>,
+}
+
+impl NodeSelection<'_> {
+    pub fn total(&self) -> usize {
+        self.included.len() + self.excluded.len()
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        !self.excluded.is_empty()
+    }
+}
+
+/// 模块图中被合并模块的节点 ID
+pub(crate) const OTHER_MODULES_ID: &str = "_other";
+
+/// 图的布局方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// 从上到下
+    #[default]
+    TopDown,
+    /// 从左到右，适合较宽的图
+    LeftRight,
+    /// 从下到上
+    BottomTop,
+    /// 从右到左
+    RightLeft,
+}
+
+impl Direction {
+    /// Mermaid flowchart 方向
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TopDown => "TD",
+            Self::LeftRight => "LR",
+            Self::BottomTop => "BT",
+            Self::RightLeft => "RL",
+        }
+    }
+
+    /// Graphviz `rankdir` 值
+    pub fn rankdir(&self) -> &'static str {
+        match self {
+            Self::TopDown => "TB",
+            other => other.as_str(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "TD" | "TB" => Some(Self::TopDown),
+            "LR" => Some(Self::LeftRight),
+            "BT" => Some(Self::BottomTop),
+            "RL" => Some(Self::RightLeft),
+            _ => None,
+        }
+    }
+}
+
+/// 调用图 subgraph ID 前缀，避免与函数节点 ID 冲突
+const CLUSTER_ID_PREFIX: &str = "cluster_";
+
+/// 模块依赖图，供各格式的生成器渲染
+pub(crate) struct ModuleGraph {
+    /// 模块名 (已排序，不含被合并的模块)
+    pub modules: Vec<String>,
+    /// 被合并为 `OTHER_MODULES_ID` 的模块数
+    pub collapsed: usize,
+    /// 跨模块调用边及调用次数 (已排序)
+    pub edges: Vec<((String, String), usize)>,
+}
+
 /// Mermaid 图生成器
 pub struct MermaidGenerator {
     max_nodes: usize,
+    /// 模块图只保留跨模块连接最多的前 N 个模块
+    top_modules: Option<usize>,
+    /// 以 `dead` 样式标记的节点 (通常来自 `find_dead_code`)
+    dead_nodes: HashSet<FunctionRef>,
+    /// 调用图按模块分组为 subgraph
+    clustering: bool,
+    direction: Direction,
 }
 
 impl MermaidGenerator {
     pub fn new() -> Self {
-        Self { max_nodes: 100 }
+        Self {
+            max_nodes: 100,
+            top_modules: None,
+            dead_nodes: HashSet::new(),
+            clustering: false,
+            direction: Direction::default(),
+        }
+    }
+
+    /// flowchart 布局方向 (默认 `TD`)
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
     }
 
     pub fn with_max_nodes(mut self, max: usize) -> Self {
@@ -18,30 +113,69 @@ impl MermaidGenerator {
         self
     }
 
-    /// 生成调用图 Mermaid 代码
+    /// 模块图只保留前 N 个模块 (按跨模块调用数)，其余合并为一个节点
+    pub fn with_top_modules(mut self, top: Option<usize>) -> Self {
+        self.top_modules = top;
+        self
+    }
+
+    /// 调用图中用红色虚线边框标记这些节点
+    pub fn with_dead_nodes(mut self, nodes: HashSet<FunctionRef>) -> Self {
+        self.dead_nodes = nodes;
+        self
+    }
+
+    /// 调用图中按模块 (`extract_module`) 将函数包进 `subgraph ... end`，跨模块的边照常绘制
+    pub fn with_clustering(mut self, enabled: bool) -> Self {
+        self.clustering = enabled;
+        self
+    }
+
+    /// 选取调用图节点：按连接数降序 (相同时按位置)，超出 `max_nodes` 的归入 excluded
+    pub fn select_nodes<'a>(&self, analyzer: &'a ArchitectureAnalyzer) -> NodeSelection<'a> {
+        select_nodes(analyzer, self.max_nodes)
+    }
+
+    /// 生成调用图 Mermaid 代码 (分组时模块名为完整路径)
     pub fn generate_call_graph(&self, analyzer: &ArchitectureAnalyzer) -> String {
-        let functions = analyzer.functions();
-        let mut lines = vec!["flowchart TD".to_string()];
+        self.generate_call_graph_in(analyzer, "")
+    }
+
+    /// 生成调用图 Mermaid 代码，分组时模块名相对于 `workspace`
+    pub fn generate_call_graph_in(&self, analyzer: &ArchitectureAnalyzer, workspace: &str) -> String {
+        let mut lines = vec![format!("flowchart {}", self.direction.as_str())];
 
         // 按连接数排序，取前 N 个
-        let mut sorted: Vec<(&FunctionRef, &FunctionNode)> = functions.iter().collect();
-        sorted.sort_by_key(|(_, n)| std::cmp::Reverse(n.callers.len() + n.callees.len()));
-        sorted.truncate(self.max_nodes);
+        let sorted = self.select_nodes(analyzer).included;
 
         // 使用 FunctionRef 作为 included 集合
         let included: HashSet<&FunctionRef> = sorted.iter().map(|(r, _)| *r).collect();
 
         // 生成节点
-        for (func_ref, node) in &sorted {
+        let node_line = |func_ref: &FunctionRef, node: &FunctionNode| {
             let node_id = Self::ref_to_id(func_ref);
-            let style = if node.callers.is_empty() {
-                format!("    {}[[{}]]", node_id, node.name)
+            if node.callers.is_empty() {
+                format!("{}[[{}]]", node_id, node.name)
             } else if node.callees.is_empty() {
-                format!("    {}([{}])", node_id, node.name)
+                format!("{}([{}])", node_id, node.name)
             } else {
-                format!("    {}[{}]", node_id, node.name)
-            };
-            lines.push(style);
+                format!("{}[{}]", node_id, node.name)
+            }
+        };
+        if self.clustering {
+            let mut clusters: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for (func_ref, node) in &sorted {
+                clusters.entry(Self::extract_module(&node.file_path, workspace))
+                    .or_default()
+                    .push(node_line(func_ref, node));
+            }
+            for (module, nodes) in clusters {
+                lines.push(format!("    subgraph {}{}[\"{}\"]", CLUSTER_ID_PREFIX, Self::node_id(&module), module));
+                lines.extend(nodes.into_iter().map(|n| format!("        {}", n)));
+                lines.push("    end".to_string());
+            }
+        } else {
+            lines.extend(sorted.iter().map(|(func_ref, node)| format!("    {}", node_line(func_ref, node))));
         }
 
         // 生成边
@@ -57,41 +191,32 @@ impl MermaidGenerator {
             }
         }
 
+        // 标记死代码节点
+        let dead_ids: Vec<String> = sorted.iter()
+            .filter(|(func_ref, _)| self.dead_nodes.contains(*func_ref))
+            .map(|(func_ref, _)| Self::ref_to_id(func_ref))
+            .collect();
+        if !dead_ids.is_empty() {
+            lines.push("    classDef dead stroke:#d33,stroke-width:2px,stroke-dasharray:5 5,color:#d33".to_string());
+            lines.push(format!("    class {} dead", dead_ids.join(",")));
+        }
+
         lines.join("\n")
     }
 
     /// 生成模块依赖图
     pub fn generate_module_diagram(&self, analyzer: &ArchitectureAnalyzer, workspace: &str) -> String {
-        let functions = analyzer.functions();
-        let mut lines = vec!["flowchart TD".to_string()];
-
-        // 按文件分组
-        let mut modules: HashMap<String, Vec<&FunctionNode>> = HashMap::new();
-        for node in functions.values() {
-            let module = Self::extract_module(&node.file_path, workspace);
-            modules.entry(module).or_default().push(node);
-        }
-
-        // 计算跨模块调用
-        let mut edges: HashMap<(String, String), usize> = HashMap::new();
-        for node in functions.values() {
-            let from_module = Self::extract_module(&node.file_path, workspace);
-            for callee in &node.callees {
-                // 直接通过 FunctionRef 查找
-                if let Some(callee_node) = functions.get(callee) {
-                    let to_module = Self::extract_module(&callee_node.file_path, workspace);
-                    if from_module != to_module {
-                        *edges.entry((from_module.clone(), to_module)).or_insert(0) += 1;
-                    }
-                }
-            }
-        }
+        let ModuleGraph { modules: names, collapsed, edges } = module_graph(analyzer, workspace, self.top_modules);
+        let mut lines = vec![format!("flowchart {}", self.direction.as_str())];
 
         // 生成模块节点
-        for module in modules.keys() {
+        for module in &names {
             let id = Self::node_id(module);
             lines.push(format!("    {}[{}]", id, module));
         }
+        if collapsed > 0 {
+            lines.push(format!("    {}[{} other modules]", OTHER_MODULES_ID, collapsed));
+        }
 
         // 生成边 (带权重)
         for ((from, to), count) in edges {
@@ -107,7 +232,7 @@ impl MermaidGenerator {
     }
 
     /// 将 FunctionRef 转换为 Mermaid 节点 ID
-    fn ref_to_id(func_ref: &FunctionRef) -> String {
+    pub(crate) fn ref_to_id(func_ref: &FunctionRef) -> String {
         format!("{}_{}", Self::node_id(&func_ref.file_path), func_ref.line)
     }
 
@@ -121,7 +246,7 @@ impl MermaidGenerator {
 
     #[doc(hidden)]
     pub fn short_name(name: &str) -> String {
-        name.split("::").last().unwrap_or(name).to_string()
+        QualifiedName::parse(name).short().to_string()
     }
 
     #[doc(hidden)]
@@ -140,6 +265,81 @@ impl MermaidGenerator {
     }
 }
 
+/// 选取调用图节点：按连接数降序 (相同时按位置)，超出 `max_nodes` 的归入 excluded
+pub(crate) fn select_nodes(analyzer: &ArchitectureAnalyzer, max_nodes: usize) -> NodeSelection<'_> {
+    let mut sorted: Vec<(&FunctionRef, &FunctionNode)> = analyzer.functions().iter().collect();
+    sorted.sort_by(|(ra, a), (rb, b)| {
+        (b.callers.len() + b.callees.len())
+            .cmp(&(a.callers.len() + a.callees.len()))
+            .then_with(|| ra.file_path.cmp(&rb.file_path))
+            .then(ra.line.cmp(&rb.line))
+    });
+
+    let excluded = sorted.split_off(max_nodes.min(sorted.len()));
+    NodeSelection { included: sorted, excluded }
+}
+
+/// 计算模块依赖图：按文件分组，统计跨模块调用，`top_modules` 时合并其余模块
+pub(crate) fn module_graph(analyzer: &ArchitectureAnalyzer, workspace: &str, top_modules: Option<usize>) -> ModuleGraph {
+    let functions = analyzer.functions();
+
+    // 每个函数所属模块只计算一次
+    let module_of: HashMap<&FunctionRef, String> = functions
+        .iter()
+        .map(|(func_ref, node)| (func_ref, MermaidGenerator::extract_module(&node.file_path, workspace)))
+        .collect();
+
+    // 计算跨模块调用，被调用者按 FunctionRef 直接查找 (同名函数不会混淆)
+    let mut edges: HashMap<(String, String), usize> = HashMap::new();
+    for (func_ref, node) in functions {
+        let from_module = &module_of[func_ref];
+        for callee in &node.callees {
+            if let Some(to_module) = module_of.get(callee) {
+                if from_module != to_module {
+                    *edges.entry((from_module.clone(), to_module.clone())).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    let mut names: Vec<&String> = module_of.values().collect::<HashSet<_>>().into_iter().collect();
+    names.sort();
+
+    // 只保留跨模块调用最多的前 N 个模块，其余合并后重新聚合边
+    let mut collapsed = 0;
+    if let Some(top) = top_modules.filter(|&top| top < names.len()) {
+        let mut weight: HashMap<&str, usize> = HashMap::new();
+        for ((from, to), count) in &edges {
+            *weight.entry(from.as_str()).or_insert(0) += count;
+            *weight.entry(to.as_str()).or_insert(0) += count;
+        }
+        let weight_of = |m: &str| weight.get(m).copied().unwrap_or(0);
+        names.sort_by(|a, b| weight_of(b).cmp(&weight_of(a)).then_with(|| a.cmp(b)));
+        collapsed = names.len() - top;
+        names.truncate(top);
+        names.sort();
+
+        let kept: HashSet<&str> = names.iter().map(|m| m.as_str()).collect();
+        let mut merged: HashMap<(String, String), usize> = HashMap::new();
+        for ((from, to), count) in &edges {
+            let from = if kept.contains(from.as_str()) { from.as_str() } else { OTHER_MODULES_ID };
+            let to = if kept.contains(to.as_str()) { to.as_str() } else { OTHER_MODULES_ID };
+            if from != to {
+                *merged.entry((from.to_string(), to.to_string())).or_insert(0) += count;
+            }
+        }
+        edges = merged;
+    }
+
+    let mut edges: Vec<_> = edges.into_iter().collect();
+    edges.sort();
+    ModuleGraph {
+        modules: names.into_iter().cloned().collect(),
+        collapsed,
+        edges,
+    }
+}
+
 impl Default for MermaidGenerator {
     fn default() -> Self {
         Self::new()
@@ -202,6 +402,165 @@ mod tests {
         assert_eq!(mod2, "b::lib");
     }
 
+    fn make_node(file: &str, line: u32, name: &str, callees: &[u32]) -> FunctionNode {
+        FunctionNode {
+            file_path: file.to_string(),
+            line,
+            name: name.to_string(),
+            end_line: line,
+            kind: "function".to_string(),
+            signature: None,
+            callers: vec![],
+            callees: callees.iter().map(|&l| FunctionRef::new(file.to_string(), l)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_select_nodes_truncates_by_connections() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "hub", &[2, 3, 4]));
+        analyzer.add_function("/ws/a.rs", 2, make_node("/ws/a.rs", 2, "mid", &[3]));
+        analyzer.add_function("/ws/a.rs", 3, make_node("/ws/a.rs", 3, "leaf_a", &[]));
+        analyzer.add_function("/ws/a.rs", 4, make_node("/ws/a.rs", 4, "leaf_b", &[]));
+
+        let selection = MermaidGenerator::new().with_max_nodes(2).select_nodes(&analyzer);
+        let names = |nodes: &[(&FunctionRef, &FunctionNode)]| -> Vec<String> {
+            nodes.iter().map(|(_, n)| n.name.clone()).collect()
+        };
+
+        assert_eq!(names(&selection.included), vec!["hub", "mid"]);
+        assert_eq!(names(&selection.excluded), vec!["leaf_a", "leaf_b"]);
+        assert_eq!(selection.total(), 4);
+        assert!(selection.is_truncated());
+
+        let selection = MermaidGenerator::new().select_nodes(&analyzer);
+        assert!(!selection.is_truncated());
+    }
+
+    #[test]
+    fn test_call_graph_highlights_dead_nodes() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        let main = make_node("/ws/a.rs", 1, "main", &[2]);
+        let mut helper = make_node("/ws/a.rs", 2, "helper", &[]);
+        helper.callers.push(FunctionRef::new("/ws/a.rs".to_string(), 1));
+        analyzer.add_function("/ws/a.rs", 1, main);
+        analyzer.add_function("/ws/a.rs", 2, helper);
+        analyzer.add_function("/ws/a.rs", 3, make_node("/ws/a.rs", 3, "unused", &[]));
+
+        let dead: HashSet<FunctionRef> = analyzer.find_dead_code().into_iter().map(|n| n.as_ref()).collect();
+        let mermaid = MermaidGenerator::new().with_dead_nodes(dead).generate_call_graph(&analyzer);
+
+        // main 是入口点，不标记
+        assert!(mermaid.contains("classDef dead"));
+        assert!(mermaid.contains("    class _ws_a_rs_3 dead"));
+        assert!(!mermaid.contains("_ws_a_rs_1 dead"));
+
+        let plain = MermaidGenerator::new().generate_call_graph(&analyzer);
+        assert!(!plain.contains("classDef dead"));
+    }
+
+    #[test]
+    fn test_call_graph_clusters_by_module() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        let mut main = make_node("/ws/src/main.rs", 1, "main", &[]);
+        main.callees.push(FunctionRef::new("/ws/src/util.rs".to_string(), 1));
+        let mut helper = make_node("/ws/src/util.rs", 1, "helper", &[]);
+        helper.callers.push(FunctionRef::new("/ws/src/main.rs".to_string(), 1));
+        analyzer.add_function("/ws/src/main.rs", 1, main);
+        analyzer.add_function("/ws/src/util.rs", 1, helper);
+
+        let mermaid = MermaidGenerator::new()
+            .with_clustering(true)
+            .generate_call_graph_in(&analyzer, "/ws");
+        let lines: Vec<&str> = mermaid.lines().collect();
+        assert_eq!(lines, vec![
+            "flowchart TD",
+            r#"    subgraph cluster_src_main["src::main"]"#,
+            "        _ws_src_main_rs_1[[main]]",
+            "    end",
+            r#"    subgraph cluster_src_util["src::util"]"#,
+            "        _ws_src_util_rs_1([helper])",
+            "    end",
+            "    _ws_src_main_rs_1 --> _ws_src_util_rs_1",
+        ]);
+
+        // 不分组时输出不变
+        let flat = MermaidGenerator::new().generate_call_graph_in(&analyzer, "/ws");
+        assert!(!flat.contains("subgraph"));
+    }
+
+    #[test]
+    fn test_module_diagram_collapses_to_top_modules() {
+        let call = |file: &str, line: u32, name: &str, callees: &[(&str, u32)]| FunctionNode {
+            callees: callees.iter().map(|&(f, l)| FunctionRef::new(f.to_string(), l)).collect(),
+            ..make_node(file, line, name, &[])
+        };
+
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, call("/ws/a.rs", 1, "fa", &[("/ws/b.rs", 1), ("/ws/c.rs", 1)]));
+        analyzer.add_function("/ws/b.rs", 1, call("/ws/b.rs", 1, "fb", &[("/ws/c.rs", 1)]));
+        analyzer.add_function("/ws/c.rs", 1, call("/ws/c.rs", 1, "fc", &[]));
+        analyzer.add_function("/ws/d.rs", 1, call("/ws/d.rs", 1, "fd", &[("/ws/c.rs", 1)]));
+
+        // 权重: c=3, a=2, b=2, d=1；a 与 b 相同时按名称
+        let mermaid = MermaidGenerator::new()
+            .with_top_modules(Some(2))
+            .generate_module_diagram(&analyzer, "/ws");
+        let lines: Vec<&str> = mermaid.lines().collect();
+        assert_eq!(lines, vec![
+            "flowchart TD",
+            "    a[a]",
+            "    c[c]",
+            "    _other[2 other modules]",
+            "    _other -->|2| c",
+            "    a -->|1| _other",
+            "    a -->|1| c",
+        ]);
+
+        // 未超过上限时不合并
+        let full = MermaidGenerator::new()
+            .with_top_modules(Some(4))
+            .generate_module_diagram(&analyzer, "/ws");
+        assert!(!full.contains("_other"));
+        assert!(full.contains("    d -->|1| c"));
+    }
+
+    #[test]
+    fn test_direction() {
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, make_node("/ws/a.rs", 1, "main", &[]));
+
+        let gen = MermaidGenerator::new().with_direction(Direction::LeftRight);
+        assert!(gen.generate_call_graph(&analyzer).starts_with("flowchart LR\n"));
+        assert!(gen.generate_module_diagram(&analyzer, "/ws").starts_with("flowchart LR\n"));
+        assert!(MermaidGenerator::new().generate_call_graph(&analyzer).starts_with("flowchart TD\n"));
+
+        assert_eq!(Direction::parse("lr"), Some(Direction::LeftRight));
+        assert_eq!(Direction::parse("TB"), Some(Direction::TopDown));
+        assert_eq!(Direction::parse("up"), None);
+        assert_eq!(Direction::TopDown.rankdir(), "TB");
+        assert_eq!(Direction::RightLeft.rankdir(), "RL");
+    }
+
+    #[test]
+    fn test_module_diagram_same_name_functions() {
+        let call = |file: &str, name: &str, callees: &[(&str, u32)]| FunctionNode {
+            callees: callees.iter().map(|&(f, l)| FunctionRef::new(f.to_string(), l)).collect(),
+            ..make_node(file, 1, name, &[])
+        };
+
+        // 两个 helper 分别在 b、c 中，a 只调用 b 的 helper，c 的 helper 调用 d
+        let mut analyzer = ArchitectureAnalyzer::new();
+        analyzer.add_function("/ws/a.rs", 1, call("/ws/a.rs", "run", &[("/ws/b.rs", 1)]));
+        analyzer.add_function("/ws/b.rs", 1, call("/ws/b.rs", "helper", &[]));
+        analyzer.add_function("/ws/c.rs", 1, call("/ws/c.rs", "helper", &[("/ws/d.rs", 1)]));
+        analyzer.add_function("/ws/d.rs", 1, call("/ws/d.rs", "sink", &[]));
+
+        let mermaid = MermaidGenerator::new().generate_module_diagram(&analyzer, "/ws");
+        let edges: Vec<&str> = mermaid.lines().filter(|l| l.contains("-->")).collect();
+        assert_eq!(edges, vec!["    a -->|1| b", "    c -->|1| d"]);
+    }
+
     #[test]
     fn test_generator_builder() {
         let gen = MermaidGenerator::new().with_max_nodes(50);
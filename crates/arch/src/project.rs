@@ -0,0 +1,96 @@
+//! 项目级入口 - 选择语言适配器并构建调用图
+
+use std::path::Path;
+
+use lsp::{GoAdapter, IgnoreSet, JavaAdapter, KotlinAdapter, LanguageAdapter, RustAdapter, SwiftAdapter, TypeScriptAdapter, VueAdapter};
+
+use crate::analyzer::{ArchError, ArchitectureAnalyzer, Result};
+
+/// 分析项目：按语言选择适配器，启动 LSP，构建调用图后停止
+///
+/// 支持的语言: rust, swift, typescript/ts, vue, java, kotlin/kt, go。
+/// 从 `path` 向上查找的 `.irisignore` 中匹配的函数不报告为死码
+pub async fn analyze_project(path: &str, lang: &str) -> Result<ArchitectureAnalyzer> {
+    analyze_project_with(path, lang, AnalyzeOptions::default()).await
+}
+
+/// 项目分析选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnalyzeOptions {
+    /// 收集源文件时跟随符号链接 (链接成环时每个目录只遍历一次)
+    pub follow_symlinks: bool,
+    /// TypeScript: 同时分析 .vue/.svelte 的 <script> 块 (SFC 函数没有调用边)
+    pub sfc: bool,
+}
+
+/// 按选项分析项目，见 [`analyze_project`]
+pub async fn analyze_project_with(path: &str, lang: &str, opts: AnalyzeOptions) -> Result<ArchitectureAnalyzer> {
+    analyze(path, lang, None, opts).await
+}
+
+/// 只分析单个文件：LSP 工作区为文件所在目录，只提取该文件的函数
+///
+/// 跨文件的调用边可能不完整 (LSP 未索引整个项目)
+pub async fn analyze_file(file: &str, lang: &str) -> Result<ArchitectureAnalyzer> {
+    let workspace = Path::new(file)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| ".".to_string());
+    let files = [file.to_string()];
+    analyze(&workspace, lang, Some(&files), AnalyzeOptions::default()).await
+}
+
+async fn analyze(path: &str, lang: &str, files: Option<&[String]>, opts: AnalyzeOptions) -> Result<ArchitectureAnalyzer> {
+    let mut analyzer = ArchitectureAnalyzer::new().with_ignore(IgnoreSet::discover(Path::new(path)));
+    let follow = opts.follow_symlinks;
+
+    match lang {
+        "rust" => build_with(&mut RustAdapter::new(path).with_follow_symlinks(follow), &mut analyzer, files).await?,
+        "swift" => build_with(&mut SwiftAdapter::new(path).with_follow_symlinks(follow), &mut analyzer, files).await?,
+        "typescript" | "ts" => {
+            let mut adapter = TypeScriptAdapter::new(path).with_sfc(opts.sfc).with_follow_symlinks(follow);
+            build_with(&mut adapter, &mut analyzer, files).await?
+        }
+        "vue" => build_with(&mut VueAdapter::new(path).with_follow_symlinks(follow), &mut analyzer, files).await?,
+        "java" => build_with(&mut JavaAdapter::new(path).with_follow_symlinks(follow), &mut analyzer, files).await?,
+        "kotlin" | "kt" => build_with(&mut KotlinAdapter::new(path).with_follow_symlinks(follow), &mut analyzer, files).await?,
+        "go" => build_with(&mut GoAdapter::new(path).with_follow_symlinks(follow), &mut analyzer, files).await?,
+        _ => return Err(ArchError::UnsupportedLanguage(lang.to_string())),
+    }
+
+    Ok(analyzer)
+}
+
+async fn build_with<A: LanguageAdapter>(
+    adapter: &mut A,
+    analyzer: &mut ArchitectureAnalyzer,
+    files: Option<&[String]>,
+) -> Result<()> {
+    adapter.start().await.map_err(|e| ArchError::Lsp(e.to_string()))?;
+
+    // 无论构建是否成功都要停止 LSP 进程
+    let built = match files {
+        Some(files) => analyzer.build_file_call_graph(adapter, files).await,
+        None => analyzer.build_call_graph(adapter).await,
+    };
+    adapter.stop().map_err(|e| ArchError::Lsp(e.to_string()))?;
+
+    built
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_analyze_project_unsupported_language() {
+        let result = analyze_project("/tmp", "cobol").await;
+        assert!(matches!(result, Err(ArchError::UnsupportedLanguage(l)) if l == "cobol"));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_file_unsupported_language() {
+        let result = analyze_file("/tmp/main.cob", "cobol").await;
+        assert!(matches!(result, Err(ArchError::UnsupportedLanguage(l)) if l == "cobol"));
+    }
+}
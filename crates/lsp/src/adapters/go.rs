@@ -0,0 +1,271 @@
+use crate::protocol::{LspClient, Result, LspError};
+use crate::types::{CodeUnit, CallHierarchy, CallHierarchyItem};
+use super::{LanguageAdapter, collect_source_files, is_type_symbol, type_unit, wait_for_server, pick_probe_file, fetch_document_symbols, DEFAULT_STARTUP_TIMEOUT};
+use async_trait::async_trait;
+use lsp_types::{DocumentSymbol, SymbolKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Go 语言适配器 (gopls)
+pub struct GoAdapter {
+    workspace: String,
+    client: LspClient,
+    initialized: bool,
+    /// 是否同时提取类型声明 (struct/interface)
+    include_types: bool,
+    /// 收集源文件时是否跟随符号链接
+    follow_symlinks: bool,
+    /// 就绪探测时已打开的文件，避免重复 didOpen
+    probe_file: Option<String>,
+    /// 等待服务器就绪的最长时间
+    startup_timeout: Duration,
+}
+
+impl GoAdapter {
+    pub fn new(workspace: &str) -> Self {
+        Self {
+            workspace: workspace.to_string(),
+            client: LspClient::new(workspace),
+            initialized: false,
+            include_types: false,
+            follow_symlinks: false,
+            probe_file: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
+        }
+    }
+
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// 启动时等待服务器就绪 (索引完成) 的最长时间，超时后照常继续
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// 查找 gopls 路径
+    fn find_gopls() -> Option<String> {
+        // PATH 中查找
+        if let Ok(output) = std::process::Command::new("which")
+            .arg("gopls")
+            .output()
+        {
+            if output.status.success() {
+                return Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
+            }
+        }
+
+        // go install 的安装位置: $GOPATH/bin, 默认 ~/go/bin
+        let mut candidates: Vec<PathBuf> = std::env::var_os("GOPATH")
+            .map(|gopath| std::env::split_paths(&gopath).map(|p| p.join("bin").join("gopls")).collect())
+            .unwrap_or_default();
+        if let Some(home) = std::env::var_os("HOME") {
+            candidates.push(Path::new(&home).join("go").join("bin").join("gopls"));
+        }
+        candidates.push(PathBuf::from("/usr/local/bin/gopls"));
+        candidates.push(PathBuf::from("/opt/homebrew/bin/gopls"));
+
+        candidates
+            .into_iter()
+            .find(|path| path.exists())
+            .map(|path| path.to_string_lossy().to_string())
+    }
+
+    /// 提取函数符号
+    ///
+    /// gopls 把方法作为顶层符号返回，名称形如 `(*Server).Start`，
+    /// struct/interface 的子符号只有字段和方法签名，因此不需要递归
+    fn extract_functions(
+        &self,
+        symbols: &[DocumentSymbol],
+        file_path: &str,
+        content: &str,
+        units: &mut Vec<CodeUnit>,
+    ) {
+        for symbol in symbols {
+            // Function = 12, Method = 6
+            if matches!(symbol.kind, SymbolKind::FUNCTION | SymbolKind::METHOD) {
+                let (receiver, name) = split_receiver(&symbol.name);
+                let qualified_name = match receiver {
+                    Some(r) => format!("go:{}::{}::{}", file_path, r, name),
+                    None => format!("go:{}::{}", file_path, name),
+                };
+
+                let range_start = symbol.range.start.line;
+                let range_end = symbol.range.end.line;
+
+                let lines: Vec<&str> = content.lines().collect();
+                let body = lines
+                    .get(range_start as usize..=range_end as usize)
+                    .map(|l| l.join("\n"))
+                    .unwrap_or_default();
+
+                let signature = CodeUnit::parse_signature(&body);
+                units.push(CodeUnit {
+                    qualified_name,
+                    file_path: file_path.to_string(),
+                    kind: if symbol.kind == SymbolKind::METHOD { "method" } else { "function" }.to_string(),
+                    range_start,
+                    range_end,
+                    body,
+                    selection_line: symbol.selection_range.start.line,
+                    selection_column: symbol.selection_range.start.character,
+                    signature,
+                });
+            } else if self.include_types && is_type_symbol(symbol.kind) {
+                let qualified_name = format!("go:{}::{}", file_path, symbol.name);
+                units.push(type_unit(symbol, qualified_name, file_path, content));
+            }
+        }
+    }
+}
+
+/// 拆分 gopls 方法符号名: `(*Server).Start` -> (Some("Server"), "Start")
+///
+/// 接收者的指针标记和泛型参数 (`(*List[T]).Push`) 会被去掉，普通函数名原样返回
+fn split_receiver(name: &str) -> (Option<&str>, &str) {
+    let Some(rest) = name.strip_prefix('(') else {
+        return (None, name);
+    };
+    let Some((receiver, func)) = rest.split_once(").") else {
+        return (None, name);
+    };
+    let receiver = receiver.trim_start_matches('*');
+    let receiver = receiver.split('[').next().unwrap_or(receiver);
+    (Some(receiver), func)
+}
+
+#[async_trait]
+impl LanguageAdapter for GoAdapter {
+    async fn start(&mut self) -> Result<()> {
+        let gopls_path = Self::find_gopls()
+            .ok_or_else(|| LspError::Protocol("gopls not found. Install with: go install golang.org/x/tools/gopls@latest".into()))?;
+
+        self.client.start(&gopls_path, &[])?;
+        self.client.initialize().await?;
+
+        // 用探测代替固定等待：小项目立即开始，大项目等到索引结束
+        let probe = pick_probe_file(self.get_source_files()?).map(|f| (f, "go"));
+        self.probe_file = wait_for_server(&mut self.client, probe, self.startup_timeout).await?;
+
+        self.initialized = true;
+        Ok(())
+    }
+
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>> {
+        if !self.initialized {
+            return Err(LspError::NotStarted);
+        }
+
+        let mut units = Vec::new();
+
+        let fetched = fetch_document_symbols(&mut self.client, files, self.probe_file.as_deref(), |_| "go").await?;
+        for (file_path, content, symbols) in fetched {
+            let symbols = symbols?;
+            self.extract_functions(&symbols, &file_path, &content, &mut units);
+        }
+
+        Ok(units)
+    }
+
+    fn get_source_files(&self) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        collect_go_files(Path::new(&self.workspace), self.follow_symlinks, &mut files)?;
+        Ok(files)
+    }
+
+    async fn get_call_hierarchy(&mut self, unit: &CodeUnit) -> Result<CallHierarchy> {
+        let items = self.client.prepare_call_hierarchy(
+            &unit.file_path,
+            unit.selection_line,
+            unit.selection_column,
+        ).await?;
+
+        let mut incoming = Vec::new();
+        let mut outgoing = Vec::new();
+
+        if let Some(item) = items.first() {
+            let callers = self.client.incoming_calls(item).await?;
+            for call in callers {
+                incoming.push(CallHierarchyItem {
+                    name: call.from.name.clone(),
+                    file_path: call.from.uri.path().to_string(),
+                    line: call.from.selection_range.start.line,
+                });
+            }
+
+            let callees = self.client.outgoing_calls(item).await?;
+            for call in callees {
+                outgoing.push(CallHierarchyItem {
+                    name: call.to.name.clone(),
+                    file_path: call.to.uri.path().to_string(),
+                    line: call.to.selection_range.start.line,
+                });
+            }
+        }
+
+        Ok(CallHierarchy { incoming, outgoing })
+    }
+
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>> {
+        self.client.hover(&unit.file_path, unit.selection_line, unit.selection_column).await
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.client.shutdown()
+    }
+}
+
+/// 递归收集 Go 文件
+fn collect_go_files(dir: &Path, follow_symlinks: bool, files: &mut Vec<String>) -> Result<()> {
+    // vendor/ 是第三方依赖，testdata/ 按 go 工具约定不参与构建
+    let skip_dirs = ["vendor", "testdata", ".git", "node_modules"];
+    collect_source_files(dir, &skip_dirs, follow_symlinks, &|path| {
+        path.extension().is_some_and(|ext| ext == "go")
+    }, files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_split_receiver() {
+        assert_eq!(split_receiver("(*Server).Start"), (Some("Server"), "Start"));
+        assert_eq!(split_receiver("(Point).String"), (Some("Point"), "String"));
+        assert_eq!(split_receiver("(*List[T]).Push"), (Some("List"), "Push"));
+        assert_eq!(split_receiver("main"), (None, "main"));
+    }
+
+    #[test]
+    fn test_collect_go_files_skips_vendor_and_testdata() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for sub in ["pkg", "vendor/github.com/x", "testdata"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(root.join("main.go"), "package main").unwrap();
+        fs::write(root.join("pkg/util.go"), "package pkg").unwrap();
+        fs::write(root.join("pkg/README.md"), "").unwrap();
+        fs::write(root.join("vendor/github.com/x/x.go"), "package x").unwrap();
+        fs::write(root.join("testdata/fixture.go"), "package fixture").unwrap();
+
+        let mut files = Vec::new();
+        collect_go_files(root, false, &mut files).unwrap();
+        files.sort();
+
+        let expected: Vec<String> = ["main.go", "pkg/util.go"]
+            .iter()
+            .map(|f| root.join(f).to_string_lossy().to_string())
+            .collect();
+        assert_eq!(files, expected);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::protocol::{LspClient, Result, LspError};
+use crate::types::{CodeUnit, CallHierarchy, CallHierarchyItem};
+use super::{LanguageAdapter, collect_source_files, is_type_symbol, type_unit, wait_for_server, pick_probe_file, fetch_document_symbols};
+use async_trait::async_trait;
+use lsp_types::{DocumentSymbol, SymbolKind};
+use std::path::Path;
+use std::time::Duration;
+
+/// 默认等待索引完成的最长时间 (kotlin-language-server 首次启动需解析 Gradle 依赖)
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Kotlin 语言适配器 (kotlin-language-server)
+pub struct KotlinAdapter {
+    workspace: String,
+    client: LspClient,
+    initialized: bool,
+    /// 就绪探测时已打开的文件，避免重复 didOpen
+    probe_file: Option<String>,
+    /// 是否同时提取类型声明 (struct/enum/class/interface)
+    include_types: bool,
+    /// 收集源文件时是否跟随符号链接
+    follow_symlinks: bool,
+    /// 等待服务器就绪的最长时间
+    startup_timeout: Duration,
+}
+
+impl KotlinAdapter {
+    pub fn new(workspace: &str) -> Self {
+        Self {
+            workspace: workspace.to_string(),
+            client: LspClient::new(workspace),
+            initialized: false,
+            probe_file: None,
+            include_types: false,
+            follow_symlinks: false,
+            startup_timeout: READY_TIMEOUT,
+        }
+    }
+
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// 启动时等待服务器就绪 (索引完成) 的最长时间，超时后照常继续
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// 查找 kotlin-language-server 路径
+    fn find_server() -> Option<String> {
+        // PATH 中查找
+        if let Ok(output) = std::process::Command::new("which")
+            .arg("kotlin-language-server")
+            .output()
+        {
+            if output.status.success() {
+                return Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
+            }
+        }
+
+        // 常见路径 (Homebrew / 手动安装)
+        let common_paths = [
+            "/usr/local/bin/kotlin-language-server",
+            "/opt/homebrew/bin/kotlin-language-server",
+        ];
+        for path in common_paths {
+            if Path::new(path).exists() {
+                return Some(path.to_string());
+            }
+        }
+
+        None
+    }
+
+    /// 递归提取函数符号
+    fn extract_functions(
+        &self,
+        symbols: &[DocumentSymbol],
+        file_path: &str,
+        content: &str,
+        parent_name: Option<&str>,
+        units: &mut Vec<CodeUnit>,
+    ) {
+        for symbol in symbols {
+            let qualified_name = match parent_name {
+                Some(p) => format!("kotlin:{}::{}::{}", file_path, p, symbol.name),
+                None => format!("kotlin:{}::{}", file_path, symbol.name),
+            };
+
+            // Function = 12, Method = 6, Constructor = 9
+            if matches!(symbol.kind, SymbolKind::FUNCTION | SymbolKind::METHOD | SymbolKind::CONSTRUCTOR) {
+                let range_start = symbol.range.start.line;
+                let range_end = symbol.range.end.line;
+
+                let lines: Vec<&str> = content.lines().collect();
+                let body = lines
+                    .get(range_start as usize..=range_end as usize)
+                    .map(|l| l.join("\n"))
+                    .unwrap_or_default();
+
+                let signature = CodeUnit::parse_signature(&body);
+                units.push(CodeUnit {
+                    qualified_name,
+                    file_path: file_path.to_string(),
+                    kind: match symbol.kind {
+                        SymbolKind::CONSTRUCTOR => "constructor",
+                        SymbolKind::METHOD => "method",
+                        _ => "function",
+                    }.to_string(),
+                    range_start,
+                    range_end,
+                    body,
+                    selection_line: symbol.selection_range.start.line,
+                    selection_column: symbol.selection_range.start.character,
+                    signature,
+                });
+            } else if self.include_types && is_type_symbol(symbol.kind) {
+                units.push(type_unit(symbol, qualified_name, file_path, content));
+            }
+
+            // 递归处理子符号 (class/object/interface 内的方法)
+            if let Some(children) = &symbol.children {
+                let new_parent = if matches!(symbol.kind, SymbolKind::CLASS | SymbolKind::INTERFACE | SymbolKind::OBJECT | SymbolKind::ENUM) {
+                    Some(symbol.name.as_str())
+                } else {
+                    parent_name
+                };
+                self.extract_functions(children, file_path, content, new_parent, units);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LanguageAdapter for KotlinAdapter {
+    async fn start(&mut self) -> Result<()> {
+        let server_path = Self::find_server()
+            .ok_or_else(|| LspError::Protocol("kotlin-language-server not found. Install with: brew install kotlin-language-server".into()))?;
+
+        self.client.start(&server_path, &[])?;
+        self.client.initialize().await?;
+
+        // 启动很慢且耗时差异大，用探测代替固定等待
+        let probe = pick_probe_file(self.get_source_files()?).map(|f| (f, "kotlin"));
+        self.probe_file = wait_for_server(&mut self.client, probe, self.startup_timeout).await?;
+
+        self.initialized = true;
+        Ok(())
+    }
+
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>> {
+        if !self.initialized {
+            return Err(LspError::NotStarted);
+        }
+
+        let mut units = Vec::new();
+
+        let fetched = fetch_document_symbols(&mut self.client, files, self.probe_file.as_deref(), |_| "kotlin").await?;
+        for (file_path, content, symbols) in fetched {
+            let symbols = symbols?;
+            self.extract_functions(&symbols, &file_path, &content, None, &mut units);
+        }
+
+        Ok(units)
+    }
+
+    fn get_source_files(&self) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        collect_kotlin_files(Path::new(&self.workspace), self.follow_symlinks, &mut files)?;
+        Ok(files)
+    }
+
+    async fn get_call_hierarchy(&mut self, unit: &CodeUnit) -> Result<CallHierarchy> {
+        let items = self.client.prepare_call_hierarchy(
+            &unit.file_path,
+            unit.selection_line,
+            unit.selection_column,
+        ).await?;
+
+        let mut incoming = Vec::new();
+        let mut outgoing = Vec::new();
+
+        if let Some(item) = items.first() {
+            let callers = self.client.incoming_calls(item).await?;
+            for call in callers {
+                incoming.push(CallHierarchyItem {
+                    name: call.from.name.clone(),
+                    file_path: call.from.uri.path().to_string(),
+                    line: call.from.selection_range.start.line,
+                });
+            }
+
+            let callees = self.client.outgoing_calls(item).await?;
+            for call in callees {
+                outgoing.push(CallHierarchyItem {
+                    name: call.to.name.clone(),
+                    file_path: call.to.uri.path().to_string(),
+                    line: call.to.selection_range.start.line,
+                });
+            }
+        }
+
+        Ok(CallHierarchy { incoming, outgoing })
+    }
+
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>> {
+        self.client.hover(&unit.file_path, unit.selection_line, unit.selection_column).await
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.client.shutdown()
+    }
+}
+
+/// 递归收集 Kotlin 源文件 (.kt / .kts)
+fn collect_kotlin_files(dir: &Path, follow_symlinks: bool, files: &mut Vec<String>) -> Result<()> {
+    // 跳过构建产物和非源码目录
+    let skip_dirs = [
+        "build",
+        ".gradle",
+        ".git",
+        ".idea",
+        "out",
+        "node_modules",
+    ];
+    collect_source_files(dir, &skip_dirs, follow_symlinks, &|path| {
+        matches!(path.extension().and_then(|e| e.to_str()), Some("kt" | "kts"))
+    }, files)
+}
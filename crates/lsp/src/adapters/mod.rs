@@ -1,18 +1,30 @@
+mod go;
 mod java;
+mod kotlin;
 mod rust;
 mod swift;
 mod typescript;
 mod vue;
 
+pub use go::GoAdapter;
 pub use java::JavaAdapter;
+pub use kotlin::KotlinAdapter;
 pub use rust::RustAdapter;
 pub use swift::SwiftAdapter;
 pub use typescript::TypeScriptAdapter;
 pub use vue::VueAdapter;
 
-use crate::types::{CodeUnit, CallHierarchy};
-use crate::protocol::Result;
+use crate::types::{CodeUnit, CallHierarchy, TYPE_KIND, extract_signature};
+use crate::protocol::{LspClient, LspError, Result};
 use async_trait::async_trait;
+use lsp_types::{DocumentSymbol, SymbolKind};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 等待语言服务器就绪的默认最长时间 (`with_startup_timeout` 可覆盖)
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// 语言适配器 trait
 #[async_trait]
@@ -21,7 +33,13 @@ pub trait LanguageAdapter: Send + Sync {
     async fn start(&mut self) -> Result<()>;
 
     /// 获取所有函数
-    async fn get_functions(&mut self) -> Result<Vec<CodeUnit>>;
+    async fn get_functions(&mut self) -> Result<Vec<CodeUnit>> {
+        let files = self.get_source_files()?;
+        self.get_functions_in(&files).await
+    }
+
+    /// 只获取指定文件中的函数 (不遍历整个工作区)
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>>;
 
     /// 获取源文件列表
     fn get_source_files(&self) -> Result<Vec<String>>;
@@ -29,6 +47,276 @@ pub trait LanguageAdapter: Send + Sync {
     /// 获取调用层次
     async fn get_call_hierarchy(&mut self, unit: &CodeUnit) -> Result<CallHierarchy>;
 
+    /// 获取函数名位置的悬停信息
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>>;
+
     /// 停止
     fn stop(&mut self) -> Result<()>;
 }
+
+/// 打开探测文件 (路径, languageId) 并等待服务器就绪，代替启动后的固定等待
+///
+/// 小项目几乎立即返回，大项目等到索引结束 (`$/progress` end)；超时只记录警告并继续。
+/// 返回已打开的探测文件，提取函数时不再重复 didOpen
+pub(crate) async fn wait_for_server(
+    client: &mut LspClient,
+    probe: Option<(String, &str)>,
+    timeout: Duration,
+) -> Result<Option<String>> {
+    let Some((path, language_id)) = probe else {
+        return Ok(None);
+    };
+
+    let content = fs::read_to_string(&path).map_err(LspError::Io)?;
+    client.open_file(&path, &content, language_id)?;
+    if !client.wait_until_ready(&path, timeout).await? {
+        tracing::warn!("Language server not ready after {:?}, continuing", timeout);
+    }
+    Ok(Some(path))
+}
+
+/// 每批同时打开并请求符号的文件数，避免一次向服务器压入整个项目
+const SYMBOL_BATCH_SIZE: usize = 32;
+
+/// 打开文件并批量请求文档符号，返回 (路径, 内容, 符号)，顺序与 `files` 相同
+///
+/// 探测文件启动时已打开，不再重复 didOpen；单个文件的请求失败只体现在其结果中
+pub(crate) async fn fetch_document_symbols(
+    client: &mut LspClient,
+    files: &[String],
+    probe_file: Option<&str>,
+    language_id: impl Fn(&str) -> &'static str,
+) -> Result<Vec<(String, String, Result<Vec<DocumentSymbol>>)>> {
+    let mut fetched = Vec::with_capacity(files.len());
+
+    for batch in files.chunks(SYMBOL_BATCH_SIZE) {
+        let mut contents = Vec::with_capacity(batch.len());
+        let mut opened = false;
+        for file_path in batch {
+            let content = fs::read_to_string(file_path).map_err(LspError::Io)?;
+            if probe_file != Some(file_path.as_str()) {
+                client.open_file(file_path, &content, language_id(file_path))?;
+                opened = true;
+            }
+            contents.push(content);
+        }
+        if opened {
+            // 等待文件处理
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let symbols = client.document_symbols_batch(batch).await;
+        fetched.extend(symbols.into_iter().zip(contents).map(|((path, symbols), content)| (path, content, symbols)));
+    }
+
+    Ok(fetched)
+}
+
+/// 选择探测文件：取最大的源文件，空文件或只有导入的小文件可能永远没有符号
+///
+/// 所有文件都为空时返回 None (不等待)
+pub(crate) fn pick_probe_file(files: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut best: Option<(u64, String)> = None;
+    for file in files {
+        let size = fs::metadata(&file).map_or(0, |m| m.len());
+        if size > best.as_ref().map_or(0, |(best_size, _)| *best_size) {
+            best = Some((size, file));
+        }
+    }
+    best.map(|(_, file)| file)
+}
+
+/// 是否是类型声明符号 (struct/enum/class/interface)
+pub(crate) fn is_type_symbol(kind: SymbolKind) -> bool {
+    matches!(kind, SymbolKind::STRUCT | SymbolKind::ENUM | SymbolKind::CLASS | SymbolKind::INTERFACE)
+}
+
+/// 将类型声明符号转为 CodeUnit (kind = "type")，body 为完整声明
+pub(crate) fn type_unit(symbol: &DocumentSymbol, qualified_name: String, file_path: &str, content: &str) -> CodeUnit {
+    let range_start = symbol.range.start.line;
+    let range_end = symbol.range.end.line;
+    let body = content
+        .lines()
+        .skip(range_start as usize)
+        .take((range_end - range_start) as usize + 1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let signature = CodeUnit::parse_signature(&body);
+
+    CodeUnit {
+        qualified_name,
+        file_path: file_path.to_string(),
+        kind: TYPE_KIND.to_string(),
+        range_start,
+        range_end,
+        body,
+        selection_line: symbol.selection_range.start.line,
+        selection_column: symbol.selection_range.start.character,
+        signature,
+    }
+}
+
+/// 通过 hover 为函数附加签名，`prepend` 时同时加到函数体前
+///
+/// 单个函数失败只记录日志，返回成功附加的数量
+pub async fn attach_signatures<A: LanguageAdapter + ?Sized>(
+    adapter: &mut A,
+    units: &mut [CodeUnit],
+    prepend: bool,
+) -> usize {
+    let mut attached = 0;
+    for unit in units.iter_mut() {
+        match adapter.hover(unit).await {
+            Ok(Some(text)) => {
+                if let Some(signature) = extract_signature(&text) {
+                    unit.attach_signature(signature, prepend);
+                    attached += 1;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!("hover failed for {}: {}", unit.qualified_name, e),
+        }
+    }
+    attached
+}
+
+/// 递归收集源文件：跳过名称在 `skip_dirs` 中的目录，`accept` 决定是否收集文件
+///
+/// 默认不跟随符号链接 (链接本身被跳过)；跟随时按规范化路径记录已访问目录，
+/// 链接成环或多个链接指向同一目录时只遍历一次
+pub(crate) fn collect_source_files(
+    dir: &Path,
+    skip_dirs: &[&str],
+    follow_symlinks: bool,
+    accept: &dyn Fn(&Path) -> bool,
+    files: &mut Vec<String>,
+) -> Result<()> {
+    let mut visited = HashSet::new();
+    walk_dir(dir, skip_dirs, follow_symlinks, accept, &mut visited, files)
+}
+
+fn walk_dir(
+    dir: &Path,
+    skip_dirs: &[&str],
+    follow_symlinks: bool,
+    accept: &dyn Fn(&Path) -> bool,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<String>,
+) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    if dir.file_name().is_some_and(|n| skip_dirs.iter().any(|&s| n == s)) {
+        return Ok(());
+    }
+    if !visited.insert(dir.canonicalize().map_err(LspError::Io)?) {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir).map_err(LspError::Io)? {
+        let entry = entry.map_err(LspError::Io)?;
+        if !follow_symlinks && entry.file_type().map_err(LspError::Io)?.is_symlink() {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.is_dir() {
+            walk_dir(&path, skip_dirs, follow_symlinks, accept, visited, files)?;
+        } else if accept(&path) {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{Position, Range};
+
+    #[allow(deprecated)]
+    fn symbol(name: &str, kind: SymbolKind, start: u32, end: u32) -> DocumentSymbol {
+        let range = Range::new(Position::new(start, 0), Position::new(end, 1));
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: Range::new(Position::new(start, 11), Position::new(start, 15)),
+            children: None,
+        }
+    }
+
+    #[test]
+    fn test_is_type_symbol() {
+        assert!(is_type_symbol(SymbolKind::STRUCT));
+        assert!(is_type_symbol(SymbolKind::ENUM));
+        assert!(is_type_symbol(SymbolKind::CLASS));
+        assert!(is_type_symbol(SymbolKind::INTERFACE));
+        assert!(!is_type_symbol(SymbolKind::FUNCTION));
+        assert!(!is_type_symbol(SymbolKind::MODULE));
+    }
+
+    #[test]
+    fn test_type_unit() {
+        let content = "use std::fmt;\npub struct User {\n    id: u64,\n    name: String,\n}\n";
+        let sym = symbol("User", SymbolKind::STRUCT, 1, 4);
+        let unit = type_unit(&sym, "rust:lib.rs::User".to_string(), "lib.rs", content);
+
+        assert!(unit.is_type());
+        assert_eq!(unit.kind, "type");
+        assert_eq!((unit.range_start, unit.range_end), (1, 4));
+        assert_eq!(unit.body, "pub struct User {\n    id: u64,\n    name: String,\n}");
+        assert_eq!((unit.selection_line, unit.selection_column), (1, 11));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_source_files_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        std::fs::write(root.join("src/nested/a.rs"), "").unwrap();
+        // 指回祖先目录的环
+        symlink(root.join("src"), root.join("src/nested/loop")).unwrap();
+        let external = tempfile::tempdir().unwrap();
+        std::fs::write(external.path().join("ext.rs"), "").unwrap();
+        symlink(external.path(), root.join("src/external")).unwrap();
+
+        let is_rs = |p: &Path| p.extension().is_some_and(|e| e == "rs");
+        let names = |follow: bool| {
+            let mut files = Vec::new();
+            collect_source_files(root, &[], follow, &is_rs, &mut files).unwrap();
+            let mut names: Vec<String> = files.iter()
+                .map(|f| Path::new(f).file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // 默认不跟随：外部目录和环都被跳过
+        assert_eq!(names(false), vec!["a.rs", "lib.rs"]);
+        // 跟随时环只遍历一次，且能终止
+        assert_eq!(names(true), vec!["a.rs", "ext.rs", "lib.rs"]);
+    }
+
+    #[test]
+    fn test_pick_probe_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        std::fs::write(path("mod.rs"), "mod a;").unwrap();
+        std::fs::write(path("empty.rs"), "").unwrap();
+        std::fs::write(path("lib.rs"), "pub fn run() {\n    start();\n}\n").unwrap();
+
+        let files = vec![path("empty.rs"), path("mod.rs"), path("lib.rs"), path("missing.rs")];
+        assert_eq!(pick_probe_file(files), Some(path("lib.rs")));
+        assert_eq!(pick_probe_file(vec![path("empty.rs")]), None);
+        assert_eq!(pick_probe_file(Vec::new()), None);
+    }
+}
@@ -1,16 +1,24 @@
 use crate::protocol::{LspClient, Result, LspError};
 use crate::types::{CodeUnit, CallHierarchy, CallHierarchyItem};
-use super::LanguageAdapter;
+use super::{LanguageAdapter, collect_source_files, is_type_symbol, type_unit, wait_for_server, pick_probe_file, fetch_document_symbols, DEFAULT_STARTUP_TIMEOUT};
 use async_trait::async_trait;
 use lsp_types::{DocumentSymbol, SymbolKind};
-use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 /// Rust 语言适配器 (rust-analyzer)
 pub struct RustAdapter {
     workspace: String,
     client: LspClient,
     initialized: bool,
+    /// 是否同时提取类型声明 (struct/enum/class/interface)
+    include_types: bool,
+    /// 收集源文件时是否跟随符号链接
+    follow_symlinks: bool,
+    /// 就绪探测时已打开的文件，避免重复 didOpen
+    probe_file: Option<String>,
+    /// 等待服务器就绪的最长时间
+    startup_timeout: Duration,
 }
 
 impl RustAdapter {
@@ -19,9 +27,29 @@ impl RustAdapter {
             workspace: workspace.to_string(),
             client: LspClient::new(workspace),
             initialized: false,
+            include_types: false,
+            follow_symlinks: false,
+            probe_file: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
         }
     }
 
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// 启动时等待服务器就绪 (索引完成) 的最长时间，超时后照常继续
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
     /// 递归提取函数符号
     fn extract_functions(
         &self,
@@ -49,6 +77,7 @@ impl RustAdapter {
                     .map(|l| l.join("\n"))
                     .unwrap_or_default();
 
+                let signature = CodeUnit::parse_signature(&body);
                 units.push(CodeUnit {
                     qualified_name,
                     file_path: file_path.to_string(),
@@ -58,7 +87,10 @@ impl RustAdapter {
                     body,
                     selection_line: symbol.selection_range.start.line,
                     selection_column: symbol.selection_range.start.character,
+                    signature,
                 });
+            } else if self.include_types && is_type_symbol(symbol.kind) {
+                units.push(type_unit(symbol, qualified_name, file_path, content));
             }
 
             // 递归处理子符号 (impl 块内的方法)
@@ -78,36 +110,26 @@ impl RustAdapter {
 impl LanguageAdapter for RustAdapter {
     async fn start(&mut self) -> Result<()> {
         self.client.start("rust-analyzer", &[])?;
-
-        // 等待初始化
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
         self.client.initialize().await?;
 
-        // 等待索引
-        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        // 用探测代替固定等待：小项目立即开始，大项目等到索引结束
+        let probe = pick_probe_file(self.get_source_files()?).map(|f| (f, "rust"));
+        self.probe_file = wait_for_server(&mut self.client, probe, self.startup_timeout).await?;
 
         self.initialized = true;
         Ok(())
     }
 
-    async fn get_functions(&mut self) -> Result<Vec<CodeUnit>> {
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>> {
         if !self.initialized {
             return Err(LspError::NotStarted);
         }
 
         let mut units = Vec::new();
-        let files = self.get_source_files()?;
-
-        for file_path in files {
-            let content = fs::read_to_string(&file_path)
-                .map_err(|e| LspError::Io(e))?;
-
-            self.client.open_file(&file_path, &content, "rust")?;
-
-            // 等待文件处理
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
-            let symbols = self.client.document_symbols(&file_path).await?;
+        let fetched = fetch_document_symbols(&mut self.client, files, self.probe_file.as_deref(), |_| "rust").await?;
+        for (file_path, content, symbols) in fetched {
+            let symbols = symbols?;
             self.extract_functions(&symbols, &file_path, &content, None, &mut units);
         }
 
@@ -116,7 +138,7 @@ impl LanguageAdapter for RustAdapter {
 
     fn get_source_files(&self) -> Result<Vec<String>> {
         let mut files = Vec::new();
-        collect_rust_files(Path::new(&self.workspace), &mut files)?;
+        collect_rust_files(Path::new(&self.workspace), self.follow_symlinks, &mut files)?;
         Ok(files)
     }
 
@@ -155,32 +177,18 @@ impl LanguageAdapter for RustAdapter {
         Ok(CallHierarchy { incoming, outgoing })
     }
 
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>> {
+        self.client.hover(&unit.file_path, unit.selection_line, unit.selection_column).await
+    }
+
     fn stop(&mut self) -> Result<()> {
         self.client.shutdown()
     }
 }
 
-/// 递归收集 .rs 文件
-fn collect_rust_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
-    if !dir.is_dir() {
-        return Ok(());
-    }
-
-    // 跳过 target 目录
-    if dir.file_name().map(|n| n == "target").unwrap_or(false) {
-        return Ok(());
-    }
-
-    for entry in fs::read_dir(dir).map_err(LspError::Io)? {
-        let entry = entry.map_err(LspError::Io)?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            collect_rust_files(&path, files)?;
-        } else if path.extension().map(|e| e == "rs").unwrap_or(false) {
-            files.push(path.to_string_lossy().to_string());
-        }
-    }
-
-    Ok(())
+/// 递归收集 .rs 文件 (跳过 target 目录)
+fn collect_rust_files(dir: &Path, follow_symlinks: bool, files: &mut Vec<String>) -> Result<()> {
+    collect_source_files(dir, &["target"], follow_symlinks, &|path| {
+        path.extension().map(|e| e == "rs").unwrap_or(false)
+    }, files)
 }
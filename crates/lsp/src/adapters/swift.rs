@@ -1,10 +1,11 @@
 use crate::protocol::{LspClient, Result, LspError};
 use crate::types::{CodeUnit, CallHierarchy, CallHierarchyItem};
-use super::LanguageAdapter;
+use super::{LanguageAdapter, collect_source_files, is_type_symbol, type_unit, wait_for_server, pick_probe_file, fetch_document_symbols, DEFAULT_STARTUP_TIMEOUT};
 use async_trait::async_trait;
 use lsp_types::{DocumentSymbol, SymbolKind};
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 /// Swift 语言适配器 (sourcekit-lsp)
 pub struct SwiftAdapter {
@@ -13,6 +14,25 @@ pub struct SwiftAdapter {
     initialized: bool,
     /// Xcode 项目的 call hierarchy 不可用，跳过调用
     is_xcode_project: bool,
+    /// 是否收集 Package.swift 中的测试 target
+    include_tests: bool,
+    /// 是否同时提取类型声明 (struct/enum/class/interface)
+    include_types: bool,
+    /// 收集源文件时是否跟随符号链接
+    follow_symlinks: bool,
+    /// 就绪探测时已打开的文件，避免重复 didOpen
+    probe_file: Option<String>,
+    /// 等待服务器就绪的最长时间
+    startup_timeout: Duration,
+}
+
+/// Package.swift 中声明的 target
+#[derive(Debug, Clone, PartialEq)]
+struct PackageTarget {
+    name: String,
+    /// 相对包根目录的源码路径
+    path: String,
+    is_test: bool,
 }
 
 impl SwiftAdapter {
@@ -26,9 +46,58 @@ impl SwiftAdapter {
             client: LspClient::new(workspace),
             initialized: false,
             is_xcode_project,
+            include_tests: false,
+            include_types: false,
+            follow_symlinks: false,
+            probe_file: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
         }
     }
 
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// 启动时等待服务器就绪 (索引完成) 的最长时间，超时后照常继续
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// 收集 Package.swift 中的测试 target (默认跳过)
+    pub fn with_include_tests(mut self, enabled: bool) -> Self {
+        self.include_tests = enabled;
+        self
+    }
+
+    /// 按 Package.swift 声明的 target 收集源文件，没有清单或未解析出 target 时返回 None
+    fn collect_package_files(&self) -> Result<Option<Vec<String>>> {
+        let workspace = Path::new(&self.workspace);
+        let manifest = match fs::read_to_string(workspace.join("Package.swift")) {
+            Ok(m) => m,
+            Err(_) => return Ok(None),
+        };
+
+        let targets = parse_package_targets(&manifest);
+        if targets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut files = Vec::new();
+        for target in targets.iter().filter(|t| self.include_tests || !t.is_test) {
+            collect_swift_files(&workspace.join(&target.path), self.follow_symlinks, &mut files)?;
+        }
+        files.sort();
+        files.dedup();
+        Ok(Some(files))
+    }
+
     /// 检测是否是 Xcode 项目 (非 SwiftPM)
     fn detect_xcode_project(workspace_path: &Path) -> bool {
         // 有 Package.swift 就是 SwiftPM
@@ -150,7 +219,7 @@ impl SwiftAdapter {
         }
 
         // 用户目录下的 DerivedData
-        if let Some(home) = std::env::var("HOME").ok() {
+        if let Ok(home) = std::env::var("HOME") {
             let user_derived = Path::new(&home)
                 .join("Library/Developer/Xcode/DerivedData");
             if user_derived.exists() {
@@ -190,6 +259,7 @@ impl SwiftAdapter {
                 // 清理函数名 (移除参数签名)
                 let clean_name = symbol.name.split('(').next().unwrap_or(&symbol.name);
 
+                let signature = CodeUnit::parse_signature(&body);
                 units.push(CodeUnit {
                     qualified_name: qualified_name.replace(&symbol.name, clean_name),
                     file_path: file_path.to_string(),
@@ -203,7 +273,10 @@ impl SwiftAdapter {
                     body,
                     selection_line: symbol.selection_range.start.line,
                     selection_column: symbol.selection_range.start.character,
+                    signature,
                 });
+            } else if self.include_types && is_type_symbol(symbol.kind) {
+                units.push(type_unit(symbol, qualified_name, file_path, content));
             }
 
             // 递归处理子符号
@@ -228,44 +301,39 @@ impl LanguageAdapter for SwiftAdapter {
         let args = self.detect_workspace_args();
         let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
         self.client.start(&sourcekit_path, &args_ref)?;
-
-        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
         self.client.initialize().await?;
-        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+        // 用探测代替固定等待：小项目立即开始，大项目等到索引结束
+        let probe = pick_probe_file(self.get_source_files()?).map(|f| (f, "swift"));
+        self.probe_file = wait_for_server(&mut self.client, probe, self.startup_timeout).await?;
 
         self.initialized = true;
         Ok(())
     }
 
-    async fn get_functions(&mut self) -> Result<Vec<CodeUnit>> {
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>> {
         if !self.initialized {
             return Err(LspError::NotStarted);
         }
 
         let mut units = Vec::new();
-        let files = self.get_source_files()?;
-
-        for file_path in &files {
-            let content = fs::read_to_string(file_path)
-                .map_err(|e| LspError::Io(e))?;
 
-            self.client.open_file(file_path, &content, "swift")?;
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-
-            match self.client.document_symbols(file_path).await {
-                Ok(symbols) => {
-                    self.extract_functions(&symbols, file_path, &content, None, &mut units);
-                }
-                Err(_) => continue,
-            }
+        let fetched = fetch_document_symbols(&mut self.client, files, self.probe_file.as_deref(), |_| "swift").await?;
+        for (file_path, content, symbols) in fetched {
+            let Ok(symbols) = symbols else { continue };
+            self.extract_functions(&symbols, &file_path, &content, None, &mut units);
         }
 
         Ok(units)
     }
 
     fn get_source_files(&self) -> Result<Vec<String>> {
+        if let Some(files) = self.collect_package_files()? {
+            return Ok(files);
+        }
+
         let mut files = Vec::new();
-        collect_swift_files(Path::new(&self.workspace), &mut files)?;
+        collect_swift_files(Path::new(&self.workspace), self.follow_symlinks, &mut files)?;
         Ok(files)
     }
 
@@ -308,36 +376,149 @@ impl LanguageAdapter for SwiftAdapter {
         Ok(CallHierarchy { incoming, outgoing })
     }
 
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>> {
+        self.client.hover(&unit.file_path, unit.selection_line, unit.selection_column).await
+    }
+
     fn stop(&mut self) -> Result<()> {
         self.client.shutdown()
     }
 }
 
 /// 递归收集 .swift 文件
-fn collect_swift_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
-    if !dir.is_dir() {
-        return Ok(());
-    }
-
+fn collect_swift_files(dir: &Path, follow_symlinks: bool, files: &mut Vec<String>) -> Result<()> {
     // 跳过构建目录和第三方依赖
     let skip_dirs = [".build", "build", "Build", "DerivedData", "Pods", "SourcePackages", "Checkouts"];
-    if dir.file_name()
-        .map(|n| skip_dirs.iter().any(|&s| n == s))
-        .unwrap_or(false)
-    {
-        return Ok(());
-    }
+    collect_source_files(dir, &skip_dirs, follow_symlinks, &|path| {
+        path.extension().map(|e| e == "swift").unwrap_or(false)
+    }, files)
+}
 
-    for entry in fs::read_dir(dir).map_err(LspError::Io)? {
-        let entry = entry.map_err(LspError::Io)?;
-        let path = entry.path();
+/// 从 Package.swift 解析 target 声明 (`.target` / `.executableTarget` / `.testTarget` / `.macro`)
+///
+/// 只读取顶层的 `name:` 与 `path:` 参数；未指定 `path` 时使用 SwiftPM 默认布局
+/// (`Sources/<name>`，测试为 `Tests/<name>`)
+fn parse_package_targets(manifest: &str) -> Vec<PackageTarget> {
+    // 只去掉整行注释：行内 `//` 可能出现在 URL 字符串中
+    let manifest = manifest.lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let kinds = [
+        (".target(", false),
+        (".executableTarget(", false),
+        (".macro(", false),
+        (".testTarget(", true),
+    ];
+
+    let mut targets: Vec<PackageTarget> = Vec::new();
+    for (marker, is_test) in kinds {
+        let mut rest = manifest.as_str();
+        while let Some(pos) = rest.find(marker) {
+            rest = &rest[pos + marker.len()..];
+            let args = split_top_level_args(rest);
+
+            // `.target(name: "X")` 也用于依赖引用，由此得到的默认路径若不存在会在收集时被跳过；按路径去重
+            let Some(name) = string_arg(&args, "name") else { continue };
+            let path = string_arg(&args, "path").unwrap_or_else(|| {
+                format!("{}/{}", if is_test { "Tests" } else { "Sources" }, name)
+            });
+
+            if !targets.iter().any(|t| t.path == path) {
+                targets.push(PackageTarget { name, path, is_test });
+            }
+        }
+    }
+    targets
+}
 
-        if path.is_dir() {
-            collect_swift_files(&path, files)?;
-        } else if path.extension().map(|e| e == "swift").unwrap_or(false) {
-            files.push(path.to_string_lossy().to_string());
+/// 切分调用参数列表 (从左括号之后开始)，直到匹配的右括号为止，只在顶层逗号处分割
+fn split_top_level_args(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => {
+                args.push(s[start..i].trim());
+                break;
+            }
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
         }
     }
+    args
+}
 
-    Ok(())
+/// 读取 `key: "value"` 形式的字符串参数
+fn string_arg(args: &[&str], key: &str) -> Option<String> {
+    args.iter().find_map(|arg| {
+        let value = arg.strip_prefix(key)?.trim_start().strip_prefix(':')?.trim();
+        let value = value.strip_prefix('"')?.strip_suffix('"')?;
+        Some(value.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+// swift-tools-version:5.9
+import PackageDescription
+
+let package = Package(
+    name: "Demo",
+    products: [.library(name: "Demo", targets: ["Core"])],
+    targets: [
+        .target(
+            name: "Core",
+            dependencies: [.product(name: "Logging", package: "swift-log")]
+        ),
+        .executableTarget(name: "demo-cli", dependencies: [.target(name: "Core")], path: "Apps/CLI"),
+        // .target(name: "Legacy"),
+        .testTarget(name: "CoreTests", dependencies: ["Core"]),
+    ]
+)
+"#;
+
+    #[test]
+    fn test_parse_package_targets() {
+        let targets = parse_package_targets(MANIFEST);
+        let summary: Vec<_> = targets.iter()
+            .map(|t| (t.name.as_str(), t.path.as_str(), t.is_test))
+            .collect();
+
+        assert_eq!(summary, vec![
+            ("Core", "Sources/Core", false),
+            ("demo-cli", "Apps/CLI", false),
+            ("CoreTests", "Tests/CoreTests", true),
+        ]);
+    }
+
+    #[test]
+    fn test_split_top_level_args() {
+        let args = split_top_level_args(r#"name: "A", deps: [.x(name: "B")], path: "a,b")"#);
+        assert_eq!(args, vec![r#"name: "A""#, r#"deps: [.x(name: "B")]"#, r#"path: "a,b""#]);
+        assert_eq!(string_arg(&args, "path"), Some("a,b".to_string()));
+        assert_eq!(string_arg(&args, "deps"), None);
+    }
 }
@@ -1,16 +1,38 @@
 use crate::protocol::{LspClient, Result, LspError};
 use crate::types::{CodeUnit, CallHierarchy, CallHierarchyItem};
-use super::LanguageAdapter;
+use super::{LanguageAdapter, collect_source_files, is_type_symbol, type_unit, wait_for_server, pick_probe_file, fetch_document_symbols, DEFAULT_STARTUP_TIMEOUT};
 use async_trait::async_trait;
 use lsp_types::{DocumentSymbol, SymbolKind};
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 /// TypeScript/JavaScript 语言适配器 (typescript-language-server)
 pub struct TypeScriptAdapter {
     workspace: String,
     client: LspClient,
     initialized: bool,
+    /// 是否提取 .vue/.svelte 单文件组件中的 <script> 块
+    include_sfc: bool,
+    /// 是否同时提取类型声明 (struct/enum/class/interface)
+    include_types: bool,
+    /// 收集源文件时是否跟随符号链接
+    follow_symlinks: bool,
+    /// 就绪探测时已打开的文件，避免重复 didOpen
+    probe_file: Option<String>,
+    /// 等待服务器就绪的最长时间
+    startup_timeout: Duration,
+}
+
+/// 单文件组件 (.vue/.svelte) 中的 <script> 块
+#[derive(Debug, Clone, PartialEq)]
+struct SfcScript {
+    /// 标签内的源码
+    content: String,
+    /// 源码第 0 行在原文件中的行号
+    line_offset: u32,
+    /// 语言标识符
+    language_id: &'static str,
 }
 
 impl TypeScriptAdapter {
@@ -19,9 +41,39 @@ impl TypeScriptAdapter {
             workspace: workspace.to_string(),
             client: LspClient::new(workspace),
             initialized: false,
+            include_sfc: false,
+            include_types: false,
+            follow_symlinks: false,
+            probe_file: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
         }
     }
 
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// 启动时等待服务器就绪 (索引完成) 的最长时间，超时后照常继续
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// 启用 .vue/.svelte 的 <script> 块提取
+    ///
+    /// 脚本内容以虚拟文档形式交给 typescript-language-server，
+    /// 提取出的函数行号会映射回原文件。SFC 函数不支持调用层次查询。
+    pub fn with_sfc(mut self, enabled: bool) -> Self {
+        self.include_sfc = enabled;
+        self
+    }
+
     /// 查找 typescript-language-server 路径
     fn find_tsserver() -> Option<String> {
         // PATH 中查找
@@ -74,6 +126,7 @@ impl TypeScriptAdapter {
                     .map(|l| l.join("\n"))
                     .unwrap_or_default();
 
+                let signature = CodeUnit::parse_signature(&body);
                 units.push(CodeUnit {
                     qualified_name,
                     file_path: file_path.to_string(),
@@ -87,7 +140,10 @@ impl TypeScriptAdapter {
                     body,
                     selection_line: symbol.selection_range.start.line,
                     selection_column: symbol.selection_range.start.character,
+                    signature,
                 });
+            } else if self.include_types && is_type_symbol(symbol.kind) {
+                units.push(type_unit(symbol, qualified_name, file_path, content));
             }
 
             // 递归处理子符号 (class 内的方法)
@@ -109,9 +165,87 @@ impl TypeScriptAdapter {
             Some("tsx") => "typescriptreact",
             Some("jsx") => "javascriptreact",
             Some("js") | Some("mjs") | Some("cjs") => "javascript",
+            Some("ts") | Some("mts") | Some("cts") => "typescript",
             _ => "typescript",
         }
     }
+
+    /// 是否是单文件组件
+    fn is_sfc(file_path: &str) -> bool {
+        matches!(
+            Path::new(file_path).extension().and_then(|e| e.to_str()),
+            Some("vue") | Some("svelte")
+        )
+    }
+
+    /// 提取 SFC 中所有 <script> 块 (Vue 可能同时有 <script> 和 <script setup>)
+    fn extract_sfc_scripts(content: &str) -> Vec<SfcScript> {
+        let mut scripts = Vec::new();
+        let mut cursor = 0;
+
+        while let Some(start) = content[cursor..].find("<script") {
+            let tag_start = cursor + start;
+            let tag_end = match content[tag_start..].find('>') {
+                Some(i) => tag_start + i,
+                None => break,
+            };
+            let inner_start = tag_end + 1;
+            let inner_end = match content[inner_start..].find("</script>") {
+                Some(i) => inner_start + i,
+                None => break,
+            };
+
+            let attrs = &content[tag_start..tag_end];
+            let language_id = if attrs.contains("lang=\"tsx\"") || attrs.contains("lang='tsx'") {
+                "typescriptreact"
+            } else if attrs.contains("lang=\"ts\"") || attrs.contains("lang='ts'") {
+                "typescript"
+            } else {
+                "javascript"
+            };
+
+            scripts.push(SfcScript {
+                content: content[inner_start..inner_end].to_string(),
+                line_offset: content[..inner_start].matches('\n').count() as u32,
+                language_id,
+            });
+
+            cursor = inner_end + "</script>".len();
+        }
+
+        scripts
+    }
+
+    /// 通过虚拟文档提取 SFC <script> 块中的函数，服务器不支持时跳过
+    async fn extract_sfc_functions(&mut self, file_path: &str, content: &str, units: &mut Vec<CodeUnit>) {
+        for (i, script) in Self::extract_sfc_scripts(content).into_iter().enumerate() {
+            let ext = if script.language_id == "javascript" { "js" } else { "ts" };
+            let virtual_path = format!("{}.{}.{}", file_path, i, ext);
+
+            if let Err(e) = self.client.open_file(&virtual_path, &script.content, script.language_id) {
+                tracing::debug!("Skipping SFC script {}: {}", virtual_path, e);
+                continue;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+            let symbols = match self.client.document_symbols(&virtual_path).await {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    tracing::debug!("Skipping SFC script {}: {}", virtual_path, e);
+                    continue;
+                }
+            };
+
+            let mut script_units = Vec::new();
+            self.extract_functions(&symbols, file_path, &script.content, None, &mut script_units);
+            for mut unit in script_units {
+                unit.range_start += script.line_offset;
+                unit.range_end += script.line_offset;
+                unit.selection_line += script.line_offset;
+                units.push(unit);
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -121,32 +255,38 @@ impl LanguageAdapter for TypeScriptAdapter {
             .ok_or_else(|| LspError::Protocol("typescript-language-server not found. Install with: npm install -g typescript-language-server typescript".into()))?;
 
         self.client.start(&tsserver_path, &["--stdio"])?;
-
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
         self.client.initialize().await?;
-        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+        // 用探测代替固定等待：小项目立即开始，大项目等到索引结束 (SFC 走虚拟文档，不作探测)
+        let files = self.get_source_files()?.into_iter().filter(|f| !Self::is_sfc(f));
+        let probe = pick_probe_file(files).map(|f| {
+            let lang_id = Self::get_language_id(&f);
+            (f, lang_id)
+        });
+        self.probe_file = wait_for_server(&mut self.client, probe, self.startup_timeout).await?;
 
         self.initialized = true;
         Ok(())
     }
 
-    async fn get_functions(&mut self) -> Result<Vec<CodeUnit>> {
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>> {
         if !self.initialized {
             return Err(LspError::NotStarted);
         }
 
         let mut units = Vec::new();
-        let files = self.get_source_files()?;
-
-        for file_path in files {
-            let content = fs::read_to_string(&file_path)
-                .map_err(|e| LspError::Io(e))?;
 
-            let lang_id = Self::get_language_id(&file_path);
-            self.client.open_file(&file_path, &content, lang_id)?;
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        // SFC 文件由虚拟 TS 文件单独处理
+        let (sfc_files, files): (Vec<String>, Vec<String>) = files.iter().cloned().partition(|f| Self::is_sfc(f));
+        for file_path in &sfc_files {
+            let content = fs::read_to_string(file_path)
+                .map_err(LspError::Io)?;
+            self.extract_sfc_functions(file_path, &content, &mut units).await;
+        }
 
-            let symbols = self.client.document_symbols(&file_path).await?;
+        let fetched = fetch_document_symbols(&mut self.client, &files, self.probe_file.as_deref(), Self::get_language_id).await?;
+        for (file_path, content, symbols) in fetched {
+            let symbols = symbols?;
             self.extract_functions(&symbols, &file_path, &content, None, &mut units);
         }
 
@@ -155,7 +295,7 @@ impl LanguageAdapter for TypeScriptAdapter {
 
     fn get_source_files(&self) -> Result<Vec<String>> {
         let mut files = Vec::new();
-        collect_ts_files(Path::new(&self.workspace), &mut files)?;
+        collect_ts_files(Path::new(&self.workspace), self.include_sfc, self.follow_symlinks, &mut files)?;
         Ok(files)
     }
 
@@ -192,17 +332,17 @@ impl LanguageAdapter for TypeScriptAdapter {
         Ok(CallHierarchy { incoming, outgoing })
     }
 
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>> {
+        self.client.hover(&unit.file_path, unit.selection_line, unit.selection_column).await
+    }
+
     fn stop(&mut self) -> Result<()> {
         self.client.shutdown()
     }
 }
 
 /// 递归收集 TypeScript/JavaScript 文件
-fn collect_ts_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
-    if !dir.is_dir() {
-        return Ok(());
-    }
-
+fn collect_ts_files(dir: &Path, include_sfc: bool, follow_symlinks: bool, files: &mut Vec<String>) -> Result<()> {
     // 跳过常见的非源码目录
     let skip_dirs = [
         "node_modules",
@@ -215,36 +355,87 @@ fn collect_ts_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
         ".turbo",
         ".cache",
     ];
-    if dir.file_name()
-        .map(|n| skip_dirs.iter().any(|&s| n == s))
-        .unwrap_or(false)
-    {
-        return Ok(());
-    }
-
-    for entry in fs::read_dir(dir).map_err(LspError::Io)? {
-        let entry = entry.map_err(LspError::Io)?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            collect_ts_files(&path, files)?;
-        } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            // 收集 .ts, .tsx, .js, .jsx, .mjs, .cjs
-            if matches!(ext, "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs") {
-                // 跳过声明文件和配置文件
-                let file_name = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("");
-                if !file_name.ends_with(".d.ts")
-                    && !file_name.ends_with(".config.ts")
-                    && !file_name.ends_with(".config.js")
-                    && !file_name.ends_with(".config.mjs")
-                {
-                    files.push(path.to_string_lossy().to_string());
-                }
-            }
+    collect_source_files(dir, &skip_dirs, follow_symlinks, &|path| {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| is_ts_source(name, include_sfc))
+    }, files)
+}
+
+/// 判断文件名是否是需要收集的源码文件
+fn is_ts_source(file_name: &str, include_sfc: bool) -> bool {
+    let ext = match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext,
+        None => return false,
+    };
+
+    // 收集 .ts, .tsx, .mts, .cts, .js, .jsx, .mjs, .cjs (可选 .vue, .svelte)
+    let is_source = matches!(ext, "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs")
+        || (include_sfc && matches!(ext, "vue" | "svelte"));
+    if !is_source {
+        return false;
+    }
+
+    // 跳过声明文件和配置文件
+    !file_name.ends_with(".d.ts")
+        && !file_name.ends_with(".d.mts")
+        && !file_name.ends_with(".d.cts")
+        && !file_name.ends_with(".config.ts")
+        && !file_name.ends_with(".config.js")
+        && !file_name.ends_with(".config.mjs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ts_source_extensions() {
+        for name in ["a.ts", "a.tsx", "a.mts", "a.cts", "a.js", "a.jsx", "a.mjs", "a.cjs"] {
+            assert!(is_ts_source(name, false), "{} should be collected", name);
         }
+        assert!(!is_ts_source("a.rs", false));
+        assert!(!is_ts_source("Makefile", false));
+    }
+
+    #[test]
+    fn test_is_ts_source_skips_declarations_and_configs() {
+        assert!(!is_ts_source("types.d.ts", false));
+        assert!(!is_ts_source("types.d.mts", false));
+        assert!(!is_ts_source("types.d.cts", false));
+        assert!(!is_ts_source("vite.config.ts", false));
     }
 
-    Ok(())
+    #[test]
+    fn test_is_ts_source_sfc_opt_in() {
+        assert!(!is_ts_source("App.vue", false));
+        assert!(!is_ts_source("App.svelte", false));
+        assert!(is_ts_source("App.vue", true));
+        assert!(is_ts_source("App.svelte", true));
+    }
+
+    #[test]
+    fn test_language_id_module_extensions() {
+        assert_eq!(TypeScriptAdapter::get_language_id("a.mts"), "typescript");
+        assert_eq!(TypeScriptAdapter::get_language_id("a.cts"), "typescript");
+        assert_eq!(TypeScriptAdapter::get_language_id("a.mjs"), "javascript");
+        assert_eq!(TypeScriptAdapter::get_language_id("a.tsx"), "typescriptreact");
+    }
+
+    #[test]
+    fn test_extract_sfc_scripts() {
+        let content = "<template>\n  <div/>\n</template>\n<script lang=\"ts\">\nexport function foo() {}\n</script>\n<script setup>\nconst x = 1\n</script>\n";
+        let scripts = TypeScriptAdapter::extract_sfc_scripts(content);
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(scripts[0].language_id, "typescript");
+        assert_eq!(scripts[0].line_offset, 3);
+        assert!(scripts[0].content.contains("export function foo"));
+        assert_eq!(scripts[1].language_id, "javascript");
+        assert_eq!(scripts[1].line_offset, 6);
+    }
+
+    #[test]
+    fn test_extract_sfc_scripts_none() {
+        assert!(TypeScriptAdapter::extract_sfc_scripts("<template><div/></template>").is_empty());
+    }
 }
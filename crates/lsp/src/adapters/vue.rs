@@ -1,17 +1,25 @@
 use crate::protocol::{LspClient, Result, LspError};
 use crate::types::{CodeUnit, CallHierarchy, CallHierarchyItem};
-use super::LanguageAdapter;
+use super::{LanguageAdapter, collect_source_files, is_type_symbol, type_unit, wait_for_server, pick_probe_file, fetch_document_symbols, DEFAULT_STARTUP_TIMEOUT};
 use async_trait::async_trait;
 use lsp_types::{DocumentSymbol, SymbolKind};
 use serde_json::json;
-use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 /// Vue 语言适配器 (vue-language-server / Volar)
 pub struct VueAdapter {
     workspace: String,
     client: LspClient,
     initialized: bool,
+    /// 是否同时提取类型声明 (struct/enum/class/interface)
+    include_types: bool,
+    /// 收集源文件时是否跟随符号链接
+    follow_symlinks: bool,
+    /// 就绪探测时已打开的文件，避免重复 didOpen
+    probe_file: Option<String>,
+    /// 等待服务器就绪的最长时间
+    startup_timeout: Duration,
 }
 
 impl VueAdapter {
@@ -20,9 +28,29 @@ impl VueAdapter {
             workspace: workspace.to_string(),
             client: LspClient::new(workspace),
             initialized: false,
+            include_types: false,
+            follow_symlinks: false,
+            probe_file: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
         }
     }
 
+    pub fn with_include_types(mut self, enabled: bool) -> Self {
+        self.include_types = enabled;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// 启动时等待服务器就绪 (索引完成) 的最长时间，超时后照常继续
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
     /// 查找 vue-language-server 路径
     fn find_vue_language_server() -> Option<String> {
         // PATH 中查找
@@ -84,6 +112,7 @@ impl VueAdapter {
                     .map(|l| l.join("\n"))
                     .unwrap_or_default();
 
+                let signature = CodeUnit::parse_signature(&body);
                 units.push(CodeUnit {
                     qualified_name,
                     file_path: file_path.to_string(),
@@ -97,7 +126,10 @@ impl VueAdapter {
                     body,
                     selection_line: symbol.selection_range.start.line,
                     selection_column: symbol.selection_range.start.character,
+                    signature,
                 });
+            } else if self.include_types && is_type_symbol(symbol.kind) {
+                units.push(type_unit(symbol, qualified_name, file_path, content));
             }
 
             // 递归处理子符号 (class/组件内的方法)
@@ -133,8 +165,6 @@ impl LanguageAdapter for VueAdapter {
 
         self.client.start(&server_path, &["--stdio"])?;
 
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-
         // vue-language-server 需要 TypeScript SDK 路径才能正常工作
         let tsdk = Self::find_tsdk(&self.workspace);
         let init_options = match tsdk {
@@ -142,29 +172,28 @@ impl LanguageAdapter for VueAdapter {
             None => json!({}),
         };
         self.client.initialize_with_options(init_options).await?;
-        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+        // 用探测代替固定等待：小项目立即开始，大项目等到索引结束
+        let probe = pick_probe_file(self.get_source_files()?).map(|f| {
+            let lang_id = Self::get_language_id(&f);
+            (f, lang_id)
+        });
+        self.probe_file = wait_for_server(&mut self.client, probe, self.startup_timeout).await?;
 
         self.initialized = true;
         Ok(())
     }
 
-    async fn get_functions(&mut self) -> Result<Vec<CodeUnit>> {
+    async fn get_functions_in(&mut self, files: &[String]) -> Result<Vec<CodeUnit>> {
         if !self.initialized {
             return Err(LspError::NotStarted);
         }
 
         let mut units = Vec::new();
-        let files = self.get_source_files()?;
-
-        for file_path in files {
-            let content = fs::read_to_string(&file_path)
-                .map_err(|e| LspError::Io(e))?;
-
-            let lang_id = Self::get_language_id(&file_path);
-            self.client.open_file(&file_path, &content, lang_id)?;
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
-            let symbols = self.client.document_symbols(&file_path).await?;
+        let fetched = fetch_document_symbols(&mut self.client, files, self.probe_file.as_deref(), Self::get_language_id).await?;
+        for (file_path, content, symbols) in fetched {
+            let symbols = symbols?;
             self.extract_functions(&symbols, &file_path, &content, None, &mut units);
         }
 
@@ -173,7 +202,7 @@ impl LanguageAdapter for VueAdapter {
 
     fn get_source_files(&self) -> Result<Vec<String>> {
         let mut files = Vec::new();
-        collect_vue_files(Path::new(&self.workspace), &mut files)?;
+        collect_vue_files(Path::new(&self.workspace), self.follow_symlinks, &mut files)?;
         Ok(files)
     }
 
@@ -210,17 +239,17 @@ impl LanguageAdapter for VueAdapter {
         Ok(CallHierarchy { incoming, outgoing })
     }
 
+    async fn hover(&mut self, unit: &CodeUnit) -> Result<Option<String>> {
+        self.client.hover(&unit.file_path, unit.selection_line, unit.selection_column).await
+    }
+
     fn stop(&mut self) -> Result<()> {
         self.client.shutdown()
     }
 }
 
 /// 递归收集 Vue 项目文件 (.vue, .ts, .tsx, .js, .jsx)
-fn collect_vue_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
-    if !dir.is_dir() {
-        return Ok(());
-    }
-
+fn collect_vue_files(dir: &Path, follow_symlinks: bool, files: &mut Vec<String>) -> Result<()> {
     // 跳过常见的非源码目录
     let skip_dirs = [
         "node_modules",
@@ -234,36 +263,18 @@ fn collect_vue_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
         ".cache",
         ".output",
     ];
-    if dir.file_name()
-        .map(|n| skip_dirs.iter().any(|&s| n == s))
-        .unwrap_or(false)
-    {
-        return Ok(());
-    }
-
-    for entry in fs::read_dir(dir).map_err(LspError::Io)? {
-        let entry = entry.map_err(LspError::Io)?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            collect_vue_files(&path, files)?;
-        } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            // 收集 .vue, .ts, .tsx, .js, .jsx
-            if matches!(ext, "vue" | "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs") {
-                // 跳过声明文件和配置文件
-                let file_name = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("");
-                if !file_name.ends_with(".d.ts")
-                    && !file_name.ends_with(".config.ts")
-                    && !file_name.ends_with(".config.js")
-                    && !file_name.ends_with(".config.mjs")
-                {
-                    files.push(path.to_string_lossy().to_string());
-                }
-            }
+    collect_source_files(dir, &skip_dirs, follow_symlinks, &|path| {
+        // 收集 .vue, .ts, .tsx, .js, .jsx
+        if !matches!(path.extension().and_then(|e| e.to_str()), Some("vue" | "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs")) {
+            return false;
         }
-    }
-
-    Ok(())
+        // 跳过声明文件和配置文件
+        let file_name = path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("");
+        !file_name.ends_with(".d.ts")
+            && !file_name.ends_with(".config.ts")
+            && !file_name.ends_with(".config.js")
+            && !file_name.ends_with(".config.mjs")
+    }, files)
 }
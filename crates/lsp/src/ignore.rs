@@ -0,0 +1,162 @@
+//! `.irisignore` - 从所有分析中排除指定函数/模块
+//!
+//! 每行一个 glob 模式 (`#` 开头为注释)，同时匹配文件路径和限定名：
+//! - `*` 匹配任意字符序列 (包括 `/` 与 `::`)，`?` 匹配单个字符
+//! - 文件路径既按原样匹配，也按相对于 `.irisignore` 所在目录的路径匹配
+
+use std::path::{Path, PathBuf};
+
+/// 忽略文件名
+pub const IGNORE_FILE: &str = ".irisignore";
+
+/// 一组忽略模式
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreSet {
+    /// `.irisignore` 所在目录，用于计算相对路径
+    root: Option<PathBuf>,
+    patterns: Vec<String>,
+}
+
+impl IgnoreSet {
+    /// 从文本解析，`root` 为相对路径的基准目录
+    pub fn parse(root: Option<&Path>, content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+
+        Self {
+            root: root.map(Path::to_path_buf),
+            patterns,
+        }
+    }
+
+    /// 从 `start` 向上查找 `.irisignore` 并加载，找不到或读取失败时返回空集合
+    pub fn discover(start: &Path) -> Self {
+        let start = start.canonicalize().unwrap_or_else(|_| start.to_path_buf());
+
+        for dir in start.ancestors() {
+            let file = dir.join(IGNORE_FILE);
+            if !file.is_file() {
+                continue;
+            }
+            return match std::fs::read_to_string(&file) {
+                Ok(content) => Self::parse(Some(dir), &content),
+                Err(e) => {
+                    tracing::warn!("Failed to read {}: {}", file.display(), e);
+                    Self::default()
+                }
+            };
+        }
+
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// 文件路径或限定名匹配任一模式时返回 true
+    pub fn is_ignored(&self, file_path: &str, qualified_name: &str) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        let relative = self.root.as_ref().and_then(|root| {
+            Path::new(file_path)
+                .strip_prefix(root)
+                .ok()
+                .map(|p| p.to_string_lossy().to_string())
+        });
+
+        self.patterns.iter().any(|pattern| {
+            glob_match(pattern, qualified_name)
+                || glob_match(pattern, file_path)
+                || relative.as_deref().is_some_and(|r| glob_match(pattern, r))
+        })
+    }
+}
+
+/// 简单 glob 匹配：`*` 任意序列，`?` 单个字符 (与 SQLite GLOB 一致，`*` 可跨越分隔符)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置及其当前匹配到的文本位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("src/*.rs", "src/lib.rs"));
+        assert!(glob_match("src/*", "src/generated/api.rs"));
+        assert!(glob_match("*::legacy::*", "rust:src/lib.rs::legacy::parse"));
+        assert!(glob_match("fo?", "foo"));
+        assert!(!glob_match("fo?", "fooo"));
+        assert!(!glob_match("src/*.rs", "tests/lib.rs"));
+        assert!(!glob_match("*.swift", "main.rs"));
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_blank_lines() {
+        let set = IgnoreSet::parse(None, "# generated\n\n  src/gen/*  \n*::old_*\n");
+        assert_eq!(set.len(), 2);
+        assert!(set.is_ignored("src/gen/api.rs", "rust:src/gen/api.rs::call"));
+        assert!(set.is_ignored("src/lib.rs", "rust:src/lib.rs::old_parse"));
+        assert!(!set.is_ignored("src/lib.rs", "rust:src/lib.rs::parse"));
+        assert!(!IgnoreSet::default().is_ignored("a.rs", "a"));
+    }
+
+    #[test]
+    fn test_discover_from_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(root.join(IGNORE_FILE), "vendor/*\n").unwrap();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+
+        let set = IgnoreSet::discover(&root.join("src/nested"));
+        assert_eq!(set.len(), 1);
+
+        // 绝对路径按相对于 .irisignore 所在目录匹配
+        let vendored = root.join("vendor/dep.rs");
+        assert!(set.is_ignored(&vendored.to_string_lossy(), "rust:vendor/dep.rs::f"));
+        let own = root.join("src/lib.rs");
+        assert!(!set.is_ignored(&own.to_string_lossy(), "rust:src/lib.rs::f"));
+    }
+
+    #[test]
+    fn test_discover_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(IgnoreSet::discover(dir.path()).is_empty());
+    }
+}
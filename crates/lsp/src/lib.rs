@@ -1,7 +1,9 @@
 mod protocol;
 mod types;
 mod adapters;
+mod ignore;
 
-pub use protocol::LspClient;
-pub use types::{CodeUnit, FunctionNode, FunctionRef, CallHierarchy, CallHierarchyItem};
-pub use adapters::{LanguageAdapter, JavaAdapter, RustAdapter, SwiftAdapter, TypeScriptAdapter, VueAdapter};
+pub use protocol::{LspClient, ProgressState};
+pub use types::{CodeUnit, TYPE_KIND, FunctionNode, FunctionRef, CallHierarchy, CallHierarchyItem, QualifiedName, extract_signature};
+pub use ignore::{IgnoreSet, IGNORE_FILE};
+pub use adapters::{LanguageAdapter, attach_signatures, DEFAULT_STARTUP_TIMEOUT, GoAdapter, JavaAdapter, KotlinAdapter, RustAdapter, SwiftAdapter, TypeScriptAdapter, VueAdapter};
@@ -25,12 +25,24 @@ pub enum LspError {
 
 pub type Result<T> = std::result::Result<T, LspError>;
 
+/// 服务端 `$/progress` 报告的进行中任务 (如 rust-analyzer 的 "Indexing")
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressState {
+    pub title: String,
+    pub message: Option<String>,
+    pub percentage: Option<u32>,
+}
+
+/// 进行中的任务，按 progress token 索引
+type ProgressMap = Arc<Mutex<HashMap<String, ProgressState>>>;
+
 /// LSP 客户端 - 管理与语言服务器的通信
 pub struct LspClient {
     process: Option<Child>,
     stdin: Option<Arc<Mutex<ChildStdin>>>,
     request_id: Arc<Mutex<i64>>,
     pending: Arc<Mutex<HashMap<i64, oneshot::Sender<Value>>>>,
+    progress: ProgressMap,
     workspace: String,
 }
 
@@ -41,6 +53,7 @@ impl LspClient {
             stdin: None,
             request_id: Arc::new(Mutex::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            progress: Arc::new(Mutex::new(HashMap::new())),
             workspace: workspace.to_string(),
         }
     }
@@ -80,8 +93,9 @@ impl LspClient {
 
         // 启动响应读取线程
         let pending = Arc::clone(&self.pending);
+        let progress = Arc::clone(&self.progress);
         std::thread::spawn(move || {
-            Self::read_responses(stdout, pending, stdin);
+            Self::read_responses(stdout, pending, progress, stdin);
         });
 
         self.process = Some(child);
@@ -97,10 +111,11 @@ impl LspClient {
         stdin.flush()
     }
 
-    /// 读取 LSP 响应，并自动回复服务端→客户端请求
+    /// 读取 LSP 响应，自动回复服务端→客户端请求并处理通知
     fn read_responses(
         stdout: ChildStdout,
         pending: Arc<Mutex<HashMap<i64, oneshot::Sender<Value>>>>,
+        progress: ProgressMap,
         stdin: Arc<Mutex<ChildStdin>>,
     ) {
         let mut reader = BufReader::new(stdout);
@@ -145,19 +160,17 @@ impl LspClient {
                 let has_method = msg.get("method").is_some();
 
                 if has_id && has_method {
-                    // 服务端→客户端请求 (同时有 id 和 method)
-                    // 自动回复 null result，防止服务端阻塞
-                    if let Some(id) = msg.get("id") {
-                        let method = msg.get("method").and_then(|m| m.as_str()).unwrap_or("unknown");
-                        tracing::debug!("[LSP] 自动回复服务端请求: {} (id={})", method, id);
-                        let response = json!({
-                            "jsonrpc": "2.0",
-                            "id": id.clone(),
-                            "result": null
-                        });
-                        if let Ok(resp_str) = serde_json::to_string(&response) {
-                            let _ = Self::write_message(&stdin, &resp_str);
-                        }
+                    // 服务端→客户端请求 (同时有 id 和 method)：必须回复，否则服务端可能一直等待
+                    let method = msg.get("method").and_then(|m| m.as_str()).unwrap_or("unknown");
+                    let id = msg.get("id").cloned().unwrap_or(Value::Null);
+                    tracing::debug!("[LSP] 自动回复服务端请求: {} (id={})", method, id);
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": server_request_result(method, msg.get("params"))
+                    });
+                    if let Ok(resp_str) = serde_json::to_string(&response) {
+                        let _ = Self::write_message(&stdin, &resp_str);
                     }
                 } else if has_id {
                     // 服务端响应 (只有 id，没有 method)
@@ -167,34 +180,64 @@ impl LspClient {
                         }
                     }
                 } else if has_method {
-                    // 纯通知 — 处理需要回复的特殊通知
-                    let method = msg.get("method").and_then(|m| m.as_str()).unwrap_or("");
-                    if method == "tsserver/request" {
-                        // vue-language-server (Volar) 专用: 通过通知转发 tsserver 请求
-                        // 需要回复 tsserver/response 通知，否则 Volar 会阻塞
-                        // Volar 发送格式: params = [requestId, command, args]
-                        // Volar 接收格式: params = [requestId, result]
-                        if let Some(params) = msg.get("params").and_then(|p| p.as_array()) {
-                            if let Some(req_id) = params.first() {
-                                tracing::debug!("[LSP] 回复 tsserver/request (id={})", req_id);
-                                let response = json!({
-                                    "jsonrpc": "2.0",
-                                    "method": "tsserver/response",
-                                    "params": [req_id.clone(), null]
-                                });
-                                if let Ok(resp_str) = serde_json::to_string(&response) {
-                                    let _ = Self::write_message(&stdin, &resp_str);
-                                }
-                            }
-                        }
+                    Self::handle_notification(&msg, &progress, &stdin);
+                }
+            }
+        }
+    }
+
+    /// 处理服务端通知
+    fn handle_notification(msg: &Value, progress: &ProgressMap, stdin: &Arc<Mutex<ChildStdin>>) {
+        let method = msg.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let params = msg.get("params");
+
+        match method {
+            "$/progress" => {
+                if let Some(params) = params {
+                    apply_progress(&mut progress.lock().unwrap(), params);
+                }
+            }
+            "window/logMessage" | "window/showMessage" => {
+                if let Some(text) = params.and_then(|p| p.get("message")).and_then(|m| m.as_str()) {
+                    tracing::debug!("[LSP] {}", text);
+                }
+            }
+            "tsserver/request" => {
+                // vue-language-server (Volar) 专用: 通过通知转发 tsserver 请求
+                // 需要回复 tsserver/response 通知，否则 Volar 会阻塞
+                // Volar 发送格式: params = [requestId, command, args]
+                // Volar 接收格式: params = [requestId, result]
+                if let Some(req_id) = params.and_then(|p| p.as_array()).and_then(|p| p.first()) {
+                    tracing::debug!("[LSP] 回复 tsserver/request (id={})", req_id);
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "method": "tsserver/response",
+                        "params": [req_id.clone(), null]
+                    });
+                    if let Ok(resp_str) = serde_json::to_string(&response) {
+                        let _ = Self::write_message(stdin, &resp_str);
                     }
                 }
             }
+            _ => {}
         }
     }
 
+    /// 服务端通过 `$/progress` 报告的进行中任务 (按标题排序)，可用于显示索引进度
+    pub fn active_progress(&self) -> Vec<ProgressState> {
+        let mut states: Vec<ProgressState> = self.progress.lock().unwrap().values().cloned().collect();
+        states.sort_by(|a, b| a.title.cmp(&b.title));
+        states
+    }
+
     /// 发送请求
     pub async fn request<R: for<'de> Deserialize<'de>>(&mut self, method: &str, params: Value) -> Result<R> {
+        let rx = self.send_request(method, params)?;
+        Self::await_response(rx).await
+    }
+
+    /// 写出请求并注册响应 channel，不等待响应 (批量请求先全部发出再统一等待)
+    fn send_request(&self, method: &str, params: Value) -> Result<oneshot::Receiver<Value>> {
         let id = {
             let mut id = self.request_id.lock().unwrap();
             *id += 1;
@@ -217,6 +260,11 @@ impl LspClient {
         let stdin = self.stdin.as_ref().ok_or(LspError::NotStarted)?;
         Self::write_message(stdin, &msg).map_err(LspError::Io)?;
 
+        Ok(rx)
+    }
+
+    /// 等待响应并解析 result
+    async fn await_response<R: for<'de> Deserialize<'de>>(rx: oneshot::Receiver<Value>) -> Result<R> {
         let response = tokio::time::timeout(
             std::time::Duration::from_secs(30),
             rx
@@ -262,6 +310,9 @@ impl LspClient {
         let mut params = json!({
             "rootUri": root_uri,
             "capabilities": {
+                "window": {
+                    "workDoneProgress": true
+                },
                 "textDocument": {
                     "documentSymbol": {
                         "hierarchicalDocumentSymbolSupport": true
@@ -271,6 +322,9 @@ impl LspClient {
                     },
                     "references": {
                         "dynamicRegistration": false
+                    },
+                    "hover": {
+                        "contentFormat": ["markdown", "plaintext"]
                     }
                 }
             }
@@ -307,18 +361,36 @@ impl LspClient {
 
     /// 获取文档符号
     pub async fn document_symbols(&mut self, path: &str) -> Result<Vec<DocumentSymbol>> {
+        let rx = self.send_document_symbols(path)?;
+        Self::await_response(rx).await.map(nested_symbols)
+    }
+
+    /// 批量获取文档符号：先发出全部请求再并发等待，结果与 `paths` 一一对应 (顺序相同)
+    ///
+    /// 单个文件失败不影响其他文件
+    pub async fn document_symbols_batch(&mut self, paths: &[String]) -> Vec<(String, Result<Vec<DocumentSymbol>>)> {
+        let this = &*self;
+        futures::future::join_all(paths.iter().map(|path| {
+            let sent = this.send_document_symbols(path);
+            async move {
+                let result = match sent {
+                    Ok(rx) => Self::await_response(rx).await.map(nested_symbols),
+                    Err(e) => Err(e),
+                };
+                (path.clone(), result)
+            }
+        }))
+        .await
+    }
+
+    fn send_document_symbols(&self, path: &str) -> Result<oneshot::Receiver<Value>> {
         let uri = Url::from_file_path(path)
             .map_err(|_| LspError::Protocol("Invalid path".into()))?
             .to_string();
 
-        let result: DocumentSymbolResponse = self.request("textDocument/documentSymbol", json!({
+        self.send_request("textDocument/documentSymbol", json!({
             "textDocument": { "uri": uri }
-        })).await?;
-
-        match result {
-            DocumentSymbolResponse::Nested(symbols) => Ok(symbols),
-            DocumentSymbolResponse::Flat(_) => Ok(vec![]),
-        }
+        }))
     }
 
     /// 准备调用层次
@@ -360,6 +432,50 @@ impl LspClient {
         })).await
     }
 
+    /// 获取悬停信息 (markdown 或纯文本)，无信息时返回 None
+    pub async fn hover(&mut self, path: &str, line: u32, column: u32) -> Result<Option<String>> {
+        let uri = Url::from_file_path(path)
+            .map_err(|_| LspError::Protocol("Invalid path".into()))?
+            .to_string();
+
+        let result: Option<Hover> = self.request("textDocument/hover", json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": column }
+        })).await?;
+
+        Ok(result.and_then(|h| hover_text(&h.contents)))
+    }
+
+    /// 等待服务器就绪：轮询 probe 文件的 documentSymbol，直到没有进行中的 `$/progress` 任务
+    /// (如 rust-analyzer 索引) 且返回非空结果，或超时
+    ///
+    /// probe 文件本身没有符号时，服务器报告过的 `$/progress` 任务全部结束后的空结果也视为就绪，
+    /// 不必等满超时。调用前需先 `open_file(probe_path, ..)`。返回是否在超时前就绪
+    pub async fn wait_until_ready(&mut self, probe_path: &str, timeout: std::time::Duration) -> Result<bool> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut saw_progress = false;
+
+        loop {
+            let result = self.document_symbols(probe_path).await;
+            let idle = {
+                let progress = self.progress.lock().unwrap();
+                saw_progress |= !progress.is_empty();
+                progress.is_empty()
+            };
+            match result {
+                Ok(symbols) if idle && (!symbols.is_empty() || saw_progress) => return Ok(true),
+                Ok(_) => {}
+                Err(LspError::NotStarted) => return Err(LspError::NotStarted),
+                Err(e) => tracing::debug!("LSP not ready yet: {}", e),
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(false);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
+
     /// 关闭
     pub fn shutdown(&mut self) -> Result<()> {
         if let Some(mut process) = self.process.take() {
@@ -374,3 +490,179 @@ impl Drop for LspClient {
         let _ = self.shutdown();
     }
 }
+
+/// 服务端→客户端请求的默认回复 (按 method 分派)
+///
+/// 我们不支持这些功能，但必须给出格式正确的结果：
+/// `workspace/configuration` 需要与 items 等长的数组 (null 表示使用默认配置)，
+/// `workspace/applyEdit` 需要明确拒绝；其余 (如 `window/workDoneProgress/create`、
+/// `client/registerCapability`) 回复 null 即可
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|p| p.get("items"))
+                .and_then(|i| i.as_array())
+                .map_or(0, |items| items.len());
+            Value::Array(vec![Value::Null; items])
+        }
+        "workspace/applyEdit" => json!({ "applied": false }),
+        "workspace/workspaceFolders" => json!([]),
+        _ => Value::Null,
+    }
+}
+
+/// 根据 `$/progress` 通知更新进行中的任务：begin 新增，report 更新，end 移除
+fn apply_progress(progress: &mut HashMap<String, ProgressState>, params: &Value) {
+    let token = match params.get("token") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => return,
+    };
+    let Some(value) = params.get("value") else { return };
+    let message = value.get("message").and_then(|m| m.as_str()).map(str::to_string);
+    let percentage = value.get("percentage").and_then(|p| p.as_u64()).map(|p| p as u32);
+
+    match value.get("kind").and_then(|k| k.as_str()) {
+        Some("begin") => {
+            let title = value.get("title").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            progress.insert(token, ProgressState { title, message, percentage });
+        }
+        Some("report") => {
+            if let Some(state) = progress.get_mut(&token) {
+                if message.is_some() {
+                    state.message = message;
+                }
+                if percentage.is_some() {
+                    state.percentage = percentage;
+                }
+            }
+        }
+        Some("end") => {
+            progress.remove(&token);
+        }
+        _ => {}
+    }
+}
+
+/// 只使用层级结构的符号 (扁平的 SymbolInformation 没有 range 层次)
+fn nested_symbols(response: DocumentSymbolResponse) -> Vec<DocumentSymbol> {
+    match response {
+        DocumentSymbolResponse::Nested(symbols) => symbols,
+        DocumentSymbolResponse::Flat(_) => vec![],
+    }
+}
+
+/// 将 hover 内容拼成文本，带语言标记的片段转为 markdown 代码块
+fn hover_text(contents: &HoverContents) -> Option<String> {
+    let marked = |m: &MarkedString| match m {
+        MarkedString::String(s) => s.clone(),
+        MarkedString::LanguageString(ls) => format!("```{}\n{}\n```", ls.language, ls.value),
+    };
+
+    let text = match contents {
+        HoverContents::Scalar(m) => marked(m),
+        HoverContents::Array(items) => items.iter().map(marked).collect::<Vec<_>>().join("\n\n"),
+        HoverContents::Markup(markup) => markup.value.clone(),
+    };
+
+    if text.trim().is_empty() { None } else { Some(text) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hover_text() {
+        let markup = HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: "```rust\nfn a()\n```".to_string(),
+        });
+        assert_eq!(hover_text(&markup).as_deref(), Some("```rust\nfn a()\n```"));
+
+        let array = HoverContents::Array(vec![
+            MarkedString::LanguageString(LanguageString {
+                language: "swift".to_string(),
+                value: "func a() -> Int".to_string(),
+            }),
+            MarkedString::String("Docs".to_string()),
+        ]);
+        assert_eq!(hover_text(&array).as_deref(), Some("```swift\nfunc a() -> Int\n```\n\nDocs"));
+
+        let empty = HoverContents::Scalar(MarkedString::String("  ".to_string()));
+        assert_eq!(hover_text(&empty), None);
+    }
+
+    #[test]
+    fn test_server_request_result() {
+        let params = json!({ "items": [{ "section": "rust-analyzer" }, { "section": "files" }] });
+        assert_eq!(server_request_result("workspace/configuration", Some(&params)), json!([null, null]));
+        assert_eq!(server_request_result("workspace/configuration", None), json!([]));
+        assert_eq!(server_request_result("workspace/applyEdit", None), json!({ "applied": false }));
+        assert_eq!(server_request_result("window/workDoneProgress/create", Some(&json!({ "token": 1 }))), Value::Null);
+        assert_eq!(server_request_result("client/registerCapability", None), Value::Null);
+    }
+
+    #[test]
+    fn test_apply_progress() {
+        let mut progress = HashMap::new();
+        let notify = |progress: &mut HashMap<String, ProgressState>, token: Value, value: Value| {
+            apply_progress(progress, &json!({ "token": token, "value": value }));
+        };
+
+        notify(&mut progress, json!("rustAnalyzer/Indexing"), json!({ "kind": "begin", "title": "Indexing", "percentage": 0 }));
+        notify(&mut progress, json!(7), json!({ "kind": "begin", "title": "Loading" }));
+        notify(&mut progress, json!("rustAnalyzer/Indexing"), json!({ "kind": "report", "message": "12/40 (core)", "percentage": 30 }));
+        assert_eq!(progress["rustAnalyzer/Indexing"], ProgressState {
+            title: "Indexing".to_string(),
+            message: Some("12/40 (core)".to_string()),
+            percentage: Some(30),
+        });
+        assert_eq!(progress["7"].title, "Loading");
+
+        notify(&mut progress, json!("rustAnalyzer/Indexing"), json!({ "kind": "end" }));
+        // 未 begin 的 token 的 report 被忽略
+        notify(&mut progress, json!("unknown"), json!({ "kind": "report", "percentage": 50 }));
+        assert_eq!(progress.keys().collect::<Vec<_>>(), vec!["7"]);
+    }
+
+    /// `cat` 回显的 documentSymbol 响应无法解析，服务器永远不会"就绪"：应在超时后返回而不是挂起
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_until_ready_times_out() {
+        let mut client = LspClient::new("/");
+        client.start("cat", &[]).unwrap();
+
+        let start = std::time::Instant::now();
+        let ready = client.wait_until_ready("/p/a.rs", std::time::Duration::from_millis(200)).await.unwrap();
+        assert!(!ready);
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        client.shutdown().unwrap();
+    }
+
+    /// `cat` 把请求原样回显：客户端把它当作服务端请求自动回复 `result: null`，
+    /// 回复再被回显后按 id 送达，因此每个请求都会收到 (无法解析为符号的) 响应
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_document_symbols_batch_keeps_order() {
+        let mut client = LspClient::new("/");
+        client.start("cat", &[]).unwrap();
+
+        let paths: Vec<String> = ["/p/a.rs", "relative.rs", "/p/b.rs", "/p/c.rs"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let results = client.document_symbols_batch(&paths).await;
+
+        let returned: Vec<&str> = results.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(returned, vec!["/p/a.rs", "relative.rs", "/p/b.rs", "/p/c.rs"]);
+        assert!(matches!(&results[1].1, Err(LspError::Protocol(msg)) if msg == "Invalid path"));
+        for i in [0, 2, 3] {
+            assert!(matches!(results[i].1, Err(LspError::Json(_))), "{:?}", results[i].1);
+        }
+
+        client.shutdown().unwrap();
+    }
+}
@@ -1,13 +1,22 @@
 use serde::{Deserialize, Serialize};
 
-/// 代码单元 - 函数/方法
+/// 类型声明单元的 kind (struct/enum/class/interface)
+pub const TYPE_KIND: &str = "type";
+
+/// 从函数体解析签名时最多读取的行数
+const MAX_SIGNATURE_LINES: usize = 4;
+
+/// 函数体至少需要的非空白字符数，更短的视为空函数体 (不参与索引与比较)
+const MIN_BODY_CHARS: usize = 8;
+
+/// 代码单元 - 函数/方法 (可选包含类型声明)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeUnit {
     /// 完整限定名: "rust:src/lib.rs::module::Function"
     pub qualified_name: String,
     /// 文件路径
     pub file_path: String,
-    /// 类型: "function", "method"
+    /// 类型: "function", "method", "type" 等
     pub kind: String,
     /// 起始行
     pub range_start: u32,
@@ -19,9 +28,17 @@ pub struct CodeUnit {
     pub selection_line: u32,
     /// 函数名精确位置 - 列
     pub selection_column: u32,
+    /// 签名: 提取时从函数体开头解析，启用 hover 签名提取时以 LSP 结果为准
+    #[serde(default)]
+    pub signature: Option<String>,
 }
 
 impl CodeUnit {
+    /// 是否是类型声明 (不参与调用图分析)
+    pub fn is_type(&self) -> bool {
+        self.kind == TYPE_KIND
+    }
+
     /// 内容哈希 (SHA256 前16位)
     pub fn content_hash(&self) -> String {
         use sha2::{Sha256, Digest};
@@ -41,22 +58,75 @@ impl CodeUnit {
         format!("{:016x}", u64::from_be_bytes(result[..8].try_into().unwrap()))
     }
 
-    /// 规范化代码 - 移除注释、归一化空格、替换字面量
-    #[doc(hidden)]
-    pub fn normalize_code(code: &str) -> String {
-        let mut result = code.to_string();
+    /// 函数体是否有实际内容 (非空白字符不少于 `MIN_BODY_CHARS`)
+    pub fn has_body(&self) -> bool {
+        self.body.chars().filter(|c| !c.is_whitespace()).take(MIN_BODY_CHARS).count() >= MIN_BODY_CHARS
+    }
+
+    /// 移除函数体为空或过短的单元，返回移除数量
+    ///
+    /// 空函数体的 embedding 彼此几乎相同，入库后会与任意空函数互相匹配
+    pub fn drop_empty_bodies(units: &mut Vec<CodeUnit>) -> usize {
+        let before = units.len();
+        units.retain(Self::has_body);
+        before - units.len()
+    }
+
+    /// 附加签名，`prepend` 时同时加到函数体前 (函数体已以签名开头时不重复)
+    pub fn attach_signature(&mut self, signature: String, prepend: bool) {
+        if prepend && !self.body.trim_start().starts_with(signature.as_str()) {
+            self.body = format!("{}\n{}", signature, self.body);
+        }
+        self.signature = Some(signature);
+    }
 
+    /// 从函数体开头解析轻量签名
+    ///
+    /// 跳过注释、属性与注解行，取到第一个 `{` 之前 (最多 `MAX_SIGNATURE_LINES` 行)，
+    /// 多行参数列表合并为一行
+    pub fn parse_signature(body: &str) -> Option<String> {
+        let mut parts: Vec<&str> = Vec::new();
+
+        for line in body.lines().map(str::trim) {
+            if parts.is_empty() && (line.is_empty() || is_preamble_line(line)) {
+                continue;
+            }
+            if let Some(pos) = line.find('{') {
+                parts.push(&line[..pos]);
+                break;
+            }
+            parts.push(line);
+            if line.ends_with(';') || parts.len() >= MAX_SIGNATURE_LINES {
+                break;
+            }
+        }
+
+        let signature = parts
+            .join(" ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace("( ", "(")
+            .replace(", )", ")")
+            .replace(" )", ")");
+        let signature = signature.trim_end_matches(';').trim_end();
+
+        if signature.is_empty() { None } else { Some(signature.to_string()) }
+    }
+
+    /// 移除注释 (含文档注释)，保留代码行结构与字面量，去掉因此变空的行
+    pub fn strip_comments(code: &str) -> String {
         // 移除单行注释
-        result = result.lines()
+        let mut result = code.lines()
             .map(|line| {
                 if let Some(pos) = line.find("//") {
-                    &line[..pos]
+                    line[..pos].trim_end()
                 } else {
                     line
                 }
             })
             .collect::<Vec<_>>()
-            .join(" ");
+            .join("\n");
 
         // 移除多行注释 (简化处理)
         while let Some(start) = result.find("/*") {
@@ -67,6 +137,17 @@ impl CodeUnit {
             }
         }
 
+        result.lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 规范化代码 - 移除注释、归一化空格、替换字面量
+    #[doc(hidden)]
+    pub fn normalize_code(code: &str) -> String {
+        let mut result = Self::strip_comments(code);
+
         // 归一化空格
         result = result.split_whitespace().collect::<Vec<_>>().join(" ");
 
@@ -78,6 +159,56 @@ impl CodeUnit {
 
         result
     }
+
+    /// 跨语言归一化：去掉语言特有的声明/可见性关键字 (fn/func/let/var...)，
+    /// 统一 this -> self、nil/null/None -> nil，字符串字面量保持不变
+    pub fn cross_normalize(code: &str) -> String {
+        code.lines()
+            .map(|line| {
+                let mut out = String::with_capacity(line.len());
+                let mut word = String::new();
+                let mut in_string = false;
+                let mut chars = line.chars();
+
+                while let Some(c) = chars.next() {
+                    if in_string {
+                        out.push(c);
+                        if c == '\\' {
+                            if let Some(nc) = chars.next() {
+                                out.push(nc);
+                            }
+                        } else if c == '"' {
+                            in_string = false;
+                        }
+                    } else if c.is_alphanumeric() || c == '_' {
+                        word.push(c);
+                    } else {
+                        out.push_str(cross_keyword(&word));
+                        word.clear();
+                        in_string = c == '"';
+                        out.push(c);
+                    }
+                }
+                out.push_str(cross_keyword(&word));
+
+                out.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// 跨语言关键字映射，返回空串表示丢弃
+fn cross_keyword(word: &str) -> &str {
+    match word {
+        "fn" | "func" | "fun" | "function" | "def"
+        | "let" | "var" | "val" | "const" | "mut"
+        | "pub" | "public" | "private" | "fileprivate" | "internal" => "",
+        "this" => "self",
+        "null" | "None" | "undefined" => "nil",
+        _ => word,
+    }
 }
 
 fn regex_replace_strings(s: &str) -> String {
@@ -121,6 +252,90 @@ fn regex_replace_numbers(s: &str) -> String {
     result
 }
 
+/// 完整限定名解析: "{lang}:{file_path}::{parent}::{name}"
+///
+/// 语言前缀可省略；兼容 "rust::file::name" 这种双冒号前缀写法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QualifiedName<'a> {
+    raw: &'a str,
+    language: Option<&'a str>,
+    file: &'a str,
+    symbol: &'a str,
+}
+
+impl<'a> QualifiedName<'a> {
+    pub fn parse(raw: &'a str) -> Self {
+        // 语言前缀: 开头的小写字母 + ':'；"xx::" 仅当后面紧跟文件路径时才视为前缀
+        let prefix_len = raw.bytes().take_while(|b| b.is_ascii_lowercase()).count();
+        let after = &raw[prefix_len..];
+        let (language, rest) = match after.strip_prefix("::") {
+            Some(rest) if prefix_len > 0 => {
+                let first = rest.split("::").next().unwrap_or(rest);
+                if first.contains('/') || first.contains('.') {
+                    (Some(&raw[..prefix_len]), rest)
+                } else {
+                    (None, raw)
+                }
+            }
+            _ => match after.strip_prefix(':') {
+                Some(rest) if prefix_len > 0 => (Some(&raw[..prefix_len]), rest),
+                _ => (None, raw),
+            },
+        };
+
+        let (file, symbol) = match rest.split_once("::") {
+            Some((file, symbol)) => (file, symbol),
+            None => ("", rest),
+        };
+
+        Self { raw, language, file, symbol }
+    }
+
+    /// 原始字符串
+    pub fn as_str(&self) -> &'a str {
+        self.raw
+    }
+
+    /// 语言前缀 (rust, swift, ts, vue, java)
+    pub fn language(&self) -> Option<&'a str> {
+        self.language
+    }
+
+    /// 文件路径
+    pub fn file(&self) -> &'a str {
+        self.file
+    }
+
+    /// 文件名 (不含目录)
+    pub fn file_name(&self) -> &'a str {
+        self.file.rsplit('/').next().unwrap_or(self.file)
+    }
+
+    /// 文件之后的符号路径: "Parent::name"
+    pub fn symbol(&self) -> &'a str {
+        self.symbol
+    }
+
+    /// 函数短名
+    pub fn short(&self) -> &'a str {
+        self.symbol.rsplit("::").next().unwrap_or(self.symbol)
+    }
+
+    /// 所属类型/模块 (如有)
+    pub fn parent(&self) -> Option<&'a str> {
+        self.symbol.rsplit_once("::").map(|(parent, _)| parent)
+    }
+
+    /// 展示格式: "Parent::name (file.rs)"
+    pub fn display(&self) -> String {
+        if self.file.is_empty() {
+            self.symbol.to_string()
+        } else {
+            format!("{} ({})", self.symbol, self.file_name())
+        }
+    }
+}
+
 /// 函数引用 - 唯一标识一个函数
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FunctionRef {
@@ -140,11 +355,24 @@ pub struct FunctionNode {
     pub file_path: String,          // 标识符 part 1
     pub line: u32,                  // 标识符 part 2
     pub name: String,               // 显示名
+    pub end_line: u32,              // 结束行
+    pub kind: String,               // 类型: "function", "method", "init"
+    pub signature: Option<String>,  // 签名行 (从函数体解析)
     pub callers: Vec<FunctionRef>,
     pub callees: Vec<FunctionRef>,
 }
 
 impl FunctionNode {
+    /// 函数跨越的行数
+    pub fn line_count(&self) -> u32 {
+        self.end_line.saturating_sub(self.line) + 1
+    }
+
+    /// 是否是方法 (可能经由 trait / 协议动态派发调用)
+    pub fn is_method(&self) -> bool {
+        self.kind == "method"
+    }
+
     /// 获取此节点的引用
     pub fn as_ref(&self) -> FunctionRef {
         FunctionRef::new(self.file_path.clone(), self.line)
@@ -173,10 +401,76 @@ impl CallHierarchyItem {
     }
 }
 
+/// 签名之前的注释/属性/注解行
+fn is_preamble_line(line: &str) -> bool {
+    ["//", "/*", "*", "#[", "#!", "@"].iter().any(|p| line.starts_with(p))
+}
+
+/// 从 hover 文本中提取函数签名
+///
+/// 优先取包含参数列表的代码块 (rust-analyzer 的第一个代码块通常是模块路径)，
+/// 没有代码块时取第一行非空文本
+pub fn extract_signature(hover: &str) -> Option<String> {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in hover.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(lines) => blocks.push(lines.join("\n").trim().to_string()),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+
+    let signature = blocks
+        .iter()
+        .find(|b| b.contains('('))
+        .or(blocks.last())
+        .cloned()
+        .or_else(|| hover.lines().map(str::trim).find(|l| !l.is_empty()).map(String::from))?;
+
+    if signature.is_empty() { None } else { Some(signature) }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_qualified_name_parse() {
+        let q = QualifiedName::parse("rust:/p/src/lib.rs::Store::open");
+        assert_eq!(q.language(), Some("rust"));
+        assert_eq!(q.file(), "/p/src/lib.rs");
+        assert_eq!(q.file_name(), "lib.rs");
+        assert_eq!(q.symbol(), "Store::open");
+        assert_eq!(q.short(), "open");
+        assert_eq!(q.parent(), Some("Store"));
+        assert_eq!(q.display(), "Store::open (lib.rs)");
+
+        let q = QualifiedName::parse("ts:/p/src/app.ts::main");
+        assert_eq!(q.language(), Some("ts"));
+        assert_eq!(q.parent(), None);
+        assert_eq!(q.display(), "main (app.ts)");
+
+        // 双冒号前缀
+        let q = QualifiedName::parse("rust::/p/a.rs::foo");
+        assert_eq!(q.language(), Some("rust"));
+        assert_eq!(q.file(), "/p/a.rs");
+        assert_eq!(q.short(), "foo");
+
+        // 无前缀 / 无分隔符
+        let q = QualifiedName::parse("/p/a.rs::foo");
+        assert_eq!(q.language(), None);
+        assert_eq!(q.file(), "/p/a.rs");
+        assert_eq!(QualifiedName::parse("single").short(), "single");
+        assert_eq!(QualifiedName::parse("foo::bar::baz").language(), None);
+        assert_eq!(QualifiedName::parse("foo::bar::baz").short(), "baz");
+        assert_eq!(QualifiedName::parse("single").display(), "single");
+    }
+
     fn make_unit(body: &str) -> CodeUnit {
         CodeUnit {
             qualified_name: "test::func".to_string(),
@@ -187,9 +481,27 @@ mod tests {
             body: body.to_string(),
             selection_line: 0,
             selection_column: 0,
+            signature: None,
         }
     }
 
+    #[test]
+    fn test_drop_empty_bodies() {
+        let mut units = vec![
+            make_unit(""),
+            make_unit("  \n\t "),
+            make_unit("{ }"),
+            make_unit("fn foo() { 42 }"),
+        ];
+        assert!(!units[0].has_body());
+        assert!(units[3].has_body());
+
+        assert_eq!(CodeUnit::drop_empty_bodies(&mut units), 3);
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].body, "fn foo() { 42 }");
+        assert_eq!(CodeUnit::drop_empty_bodies(&mut units), 0);
+    }
+
     #[test]
     fn test_content_hash_deterministic() {
         let unit = make_unit("fn foo() { 42 }");
@@ -235,6 +547,50 @@ mod tests {
         assert_eq!(unit1.structure_hash(), unit2.structure_hash());
     }
 
+    #[test]
+    fn test_cross_normalize() {
+        assert_eq!(
+            CodeUnit::cross_normalize("pub fn area(&self) -> f64 {\n    this.w\n}"),
+            "area(&self) -> f64 {\nself.w\n}"
+        );
+        // 字符串内的关键字不处理
+        assert_eq!(CodeUnit::cross_normalize(r#"let s = "let var";"#), r#"s = "let var";"#);
+        assert_eq!(CodeUnit::cross_normalize("return None"), "return nil");
+    }
+
+    /// 词集合 Jaccard 相似度，作为嵌入相似度的离线近似
+    fn token_jaccard(a: &str, b: &str) -> f64 {
+        use std::collections::HashSet;
+        let tokens = |s: &str| -> HashSet<String> {
+            s.split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_string())
+                .collect()
+        };
+        let (a, b) = (tokens(a), tokens(b));
+        a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+    }
+
+    #[test]
+    fn test_cross_normalize_eval() {
+        let rust_total = "fn total(&self) -> i32 {\n    let mut sum = 0;\n    for item in &self.items {\n        sum += item.price;\n    }\n    sum\n}";
+        let swift_total = "func total() -> Int {\n    var sum = 0\n    for item in self.items {\n        sum += item.price\n    }\n    return sum\n}";
+        let rust_parse = "fn parse_header(line: &str) -> Option<String> {\n    let trimmed = line.trim();\n    if trimmed.is_empty() { return None; }\n    Some(trimmed.to_string())\n}";
+        let swift_fetch = "func fetchUser(id: Int) -> User? {\n    let request = makeRequest(id)\n    return client.send(request)\n}";
+
+        let n = CodeUnit::cross_normalize;
+
+        // 语义等价的实现更接近
+        let before = token_jaccard(rust_total, swift_total);
+        let after = token_jaccard(&n(rust_total), &n(swift_total));
+        assert!(after > before + 0.1, "equivalent: {:.3} -> {:.3}", before, after);
+
+        // 无关的实现不会因此变得更相似
+        let before = token_jaccard(rust_parse, swift_fetch);
+        let after = token_jaccard(&n(rust_parse), &n(swift_fetch));
+        assert!(after <= before, "unrelated: {:.3} -> {:.3}", before, after);
+    }
+
     #[test]
     fn test_structure_hash_normalizes_numbers() {
         let unit1 = make_unit("let x = 42;");
@@ -267,4 +623,60 @@ mod tests {
         assert!(!normalized.contains("3.14"));
     }
 
+
+    #[test]
+    fn test_extract_signature() {
+        // rust-analyzer: 模块路径 + 签名 + 文档
+        let hover = "```rust\nmycrate::store\n```\n\n```rust\npub fn open(path: &Path) -> Result<Self>\n```\n\n---\n\nOpen the store";
+        assert_eq!(extract_signature(hover).as_deref(), Some("pub fn open(path: &Path) -> Result<Self>"));
+
+        let hover = "```typescript\nfunction add(a: number, b: number): number\n```";
+        assert_eq!(extract_signature(hover).as_deref(), Some("function add(a: number, b: number): number"));
+
+        // 纯文本
+        assert_eq!(extract_signature("\n  func run() -> Int\nmore").as_deref(), Some("func run() -> Int"));
+        assert_eq!(extract_signature("   "), None);
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        assert_eq!(
+            CodeUnit::parse_signature("/// Adds\n#[inline]\npub fn add(a: i32, b: i32) -> i32 { a + b }").as_deref(),
+            Some("pub fn add(a: i32, b: i32) -> i32")
+        );
+        // 多行参数列表合并
+        let body = "fn open(\n    path: &Path,\n    readonly: bool,\n) -> Result<Self> {\n    todo!()\n}";
+        assert_eq!(CodeUnit::parse_signature(body).as_deref(), Some("fn open(path: &Path, readonly: bool) -> Result<Self>"));
+        // 注解、无函数体
+        assert_eq!(
+            CodeUnit::parse_signature("@Override\npublic String toString() {\n  return name;\n}").as_deref(),
+            Some("public String toString()")
+        );
+        assert_eq!(CodeUnit::parse_signature("fn run(&self);").as_deref(), Some("fn run(&self)"));
+        assert_eq!(CodeUnit::parse_signature("  \n// only a comment"), None);
+    }
+
+    #[test]
+    fn test_attach_signature() {
+        let mut unit = make_unit("{ a + b }");
+        unit.attach_signature("fn add(a: i32, b: i32) -> i32".to_string(), false);
+        assert_eq!(unit.body, "{ a + b }");
+        assert!(unit.signature.is_some());
+
+        unit.attach_signature("fn add(a: i32, b: i32) -> i32".to_string(), true);
+        assert_eq!(unit.body, "fn add(a: i32, b: i32) -> i32\n{ a + b }");
+
+        // 不重复添加
+        unit.attach_signature("fn add(a: i32, b: i32) -> i32".to_string(), true);
+        assert_eq!(unit.body.matches("fn add").count(), 1);
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let code = "/// Adds numbers\nfn add(a: i32, b: i32) -> i32 {\n    // sum\n    a + b /* inline */ // trailing\n}";
+        assert_eq!(CodeUnit::strip_comments(code), "fn add(a: i32, b: i32) -> i32 {\n    a + b \n}");
+
+        // 字面量保持不变
+        assert_eq!(CodeUnit::strip_comments("let x = 42;"), "let x = 42;");
+    }
 }
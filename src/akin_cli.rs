@@ -1,17 +1,48 @@
 //! akin subcommand - code similarity detection
 
 use akin::{
-    Database, PairStatus, CodeUnitRecord, Store,
-    OllamaEmbedding, embedding_to_bytes, bytes_to_embedding,
-    VectorIndex, VectorIndexConfig,
+    Database, MinLines, PairStatus, CodeUnitRecord, SimilarPairRecord, Store, StoreError, IndexMetadata, SCHEMA_VERSION,
+    CachedEmbedder, EmbeddingCache, Embedder, EmbedRole, OllamaEmbedding, DEFAULT_RETRIES, default_model, embedding_to_bytes, bytes_to_embedding,
+    apply_length_penalty, line_count,
+    VectorIndex, VectorIndexConfig, DEFAULT_CANDIDATES, DEFAULT_CLUSTER_CANDIDATES, SimilarityHistogram, HISTOGRAM_MIN,
+    EventCoalescer, DEFAULT_DEBOUNCE_MS,
 };
-use akin::hook::get_db_path;
-use clap::Subcommand;
-use lsp::{LanguageAdapter, RustAdapter, SwiftAdapter, TypeScriptAdapter, VueAdapter, JavaAdapter, CodeUnit};
+use akin::hook::{get_db_path, get_language, find_similar_units_ann, CodeParser, HookConfig};
+use clap::{Args, Subcommand, ValueEnum};
+use futures::stream::{self, StreamExt};
+use lsp::{LanguageAdapter, attach_signatures, RustAdapter, SwiftAdapter, TypeScriptAdapter, VueAdapter, JavaAdapter, KotlinAdapter, GoAdapter, CodeUnit, IgnoreSet, QualifiedName};
 use sha2::{Sha256, Digest};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Informational line, suppressed by --quiet
+macro_rules! info {
+    ($progress:expr, $($arg:tt)*) => {
+        if !$progress.quiet { println!($($arg)*) }
+    };
+}
+
+/// Database chosen by --db; falls back to `get_db_path` (AKIN_DB_PATH or the default location)
+static DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set by --follow-symlinks; applies to every adapter's source file collection
+static FOLLOW_SYMLINKS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Args)]
+pub struct AkinArgs {
+    /// Database path (default: $AKIN_DB_PATH or ~/.vimo/akin/akin.db)
+    #[arg(long, global = true, value_name = "PATH")]
+    db: Option<PathBuf>,
+    /// Follow symbolic links when collecting source files (loops are detected)
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+    #[command(subcommand)]
+    command: AkinCommands,
+}
 
 #[derive(Subcommand)]
 pub enum AkinCommands {
@@ -19,15 +50,179 @@ pub enum AkinCommands {
     Index {
         /// Project path
         path: String,
-        /// Language (rust, swift, typescript/ts)
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
         #[arg(short, long, default_value = "rust")]
         lang: String,
-        /// Embedding model
-        #[arg(short, long, default_value = "bge-m3")]
-        model: String,
-        /// Minimum function lines
-        #[arg(long, default_value = "3")]
-        min_lines: u32,
+        /// Embedding model (default: $IRIS_EMBED_MODEL, then bge-m3)
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Embedding backend
+        #[arg(long, value_enum, default_value = "ollama")]
+        backend: EmbedBackend,
+        /// ONNX model directory with model.onnx and tokenizer.json (--backend onnx)
+        #[arg(long)]
+        model_path: Option<PathBuf>,
+        /// Minimum function lines, globally or per language (e.g. 5 or rust=5,ts=8)
+        #[arg(long, default_value = "3", value_parser = parse_min_lines)]
+        min_lines: MinLines,
+        /// Report time spent per phase
+        #[arg(long)]
+        profile: bool,
+        /// Prepend each function's LSP hover signature to its body before embedding
+        #[arg(long)]
+        signatures: bool,
+        /// Remove comments and doc comments before embedding, so scores reflect logic
+        #[arg(long)]
+        strip_comments: bool,
+        /// Save the vector index and progress every N units so an interrupted run can resume
+        #[arg(long, value_name = "N", default_value = "100")]
+        checkpoint_every: usize,
+        /// Swift: also collect test targets declared in Package.swift
+        #[arg(long)]
+        include_tests: bool,
+        /// TypeScript: also index <script> blocks of .vue/.svelte files
+        #[arg(long)]
+        sfc: bool,
+        /// Also index struct/enum/class/interface declarations (kind "type")
+        #[arg(long)]
+        include_types: bool,
+        /// Keep at most N functions per file (largest first), e.g. to cap generated code
+        #[arg(long, value_name = "N")]
+        max_functions_per_file: Option<usize>,
+        /// Store function bodies in a full-text index for `akin grep` (makes the database larger)
+        #[arg(long)]
+        store_bodies: bool,
+        /// Index database to write instead of the --db/default database (vectors go to <path>.usearch)
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+        /// Re-embed the project even if the model's dimensions or input prefix differ from the recorded ones
+        #[arg(long)]
+        force: bool,
+        /// Concurrent embedding requests (tune to the Ollama server's capacity)
+        #[arg(short = 'j', long, default_value = "4")]
+        jobs: usize,
+        /// Retries per function on transient Ollama errors (timeouts, 5xx), with exponential backoff
+        #[arg(long, default_value_t = DEFAULT_RETRIES)]
+        retries: u32,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Index every project listed in a TOML manifest into one database
+    IndexAll {
+        /// Manifest with [[project]] tables: path, lang (default rust), model, min_lines
+        manifest: PathBuf,
+        /// Embedding backend
+        #[arg(long, value_enum, default_value = "ollama")]
+        backend: EmbedBackend,
+        /// ONNX model directory with model.onnx and tokenizer.json (--backend onnx)
+        #[arg(long)]
+        model_path: Option<PathBuf>,
+        /// Prepend each function's LSP hover signature to its body before embedding
+        #[arg(long)]
+        signatures: bool,
+        /// Remove comments and doc comments before embedding, so scores reflect logic
+        #[arg(long)]
+        strip_comments: bool,
+        /// Also index struct/enum/class/interface declarations (kind "type")
+        #[arg(long)]
+        include_types: bool,
+        /// Index database to write instead of the --db/default database (vectors go to <path>.usearch)
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+        /// Re-embed projects even if the model's dimensions or input prefix differ from the recorded ones
+        #[arg(long)]
+        force: bool,
+        /// Concurrent embedding requests (tune to the Ollama server's capacity)
+        #[arg(short = 'j', long, default_value = "4")]
+        jobs: usize,
+        /// Retries per function on transient Ollama errors (timeouts, 5xx), with exponential backoff
+        #[arg(long, default_value_t = DEFAULT_RETRIES)]
+        retries: u32,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Re-index a project incrementally: embed only new/changed functions and drop deleted ones
+    Reindex {
+        /// Project path
+        path: String,
+        /// Language (default: the language the project was indexed with, then rust)
+        #[arg(short, long)]
+        lang: Option<String>,
+        /// Embedding model (default: the model the project was indexed with, then $IRIS_EMBED_MODEL, then bge-m3)
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Embedding backend
+        #[arg(long, value_enum, default_value = "ollama")]
+        backend: EmbedBackend,
+        /// ONNX model directory with model.onnx and tokenizer.json (--backend onnx)
+        #[arg(long)]
+        model_path: Option<PathBuf>,
+        /// Minimum function lines, globally or per language (e.g. 5 or rust=5,ts=8)
+        #[arg(long, default_value = "3", value_parser = parse_min_lines)]
+        min_lines: MinLines,
+        /// Prepend each function's LSP hover signature to its body before embedding
+        #[arg(long)]
+        signatures: bool,
+        /// Remove comments and doc comments before embedding (use the same setting as the original index)
+        #[arg(long)]
+        strip_comments: bool,
+        /// Swift: also collect test targets declared in Package.swift
+        #[arg(long)]
+        include_tests: bool,
+        /// TypeScript: also index <script> blocks of .vue/.svelte files
+        #[arg(long)]
+        sfc: bool,
+        /// Also index struct/enum/class/interface declarations (kind "type")
+        #[arg(long)]
+        include_types: bool,
+        /// Keep at most N functions per file (largest first), e.g. to cap generated code
+        #[arg(long, value_name = "N")]
+        max_functions_per_file: Option<usize>,
+        /// Store function bodies in a full-text index for `akin grep` (makes the database larger)
+        #[arg(long)]
+        store_bodies: bool,
+        /// Index database to update instead of the --db/default database
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+        /// Concurrent embedding requests (tune to the Ollama server's capacity)
+        #[arg(short = 'j', long, default_value = "4")]
+        jobs: usize,
+        /// Retries per function on transient Ollama errors (timeouts, 5xx), with exponential backoff
+        #[arg(long, default_value_t = DEFAULT_RETRIES)]
+        retries: u32,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Re-index a project incrementally whenever its source files change
+    Watch {
+        /// Project path
+        path: String,
+        /// Language (default: the language the project was indexed with, then rust)
+        #[arg(short, long)]
+        lang: Option<String>,
+        /// Embedding model (default: the model the project was indexed with, then $IRIS_EMBED_MODEL, then bge-m3)
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Embedding backend
+        #[arg(long, value_enum, default_value = "ollama")]
+        backend: EmbedBackend,
+        /// ONNX model directory with model.onnx and tokenizer.json (--backend onnx)
+        #[arg(long)]
+        model_path: Option<PathBuf>,
+        /// Minimum function lines, globally or per language (e.g. 5 or rust=5,ts=8)
+        #[arg(long, default_value = "3", value_parser = parse_min_lines)]
+        min_lines: MinLines,
+        /// Re-index once no file has changed for this many milliseconds
+        #[arg(long, value_name = "MS", default_value_t = DEFAULT_DEBOUNCE_MS)]
+        watch_debounce: u64,
+        /// Index database to update instead of the --db/default database
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+        /// Concurrent embedding requests (tune to the Ollama server's capacity)
+        #[arg(short = 'j', long, default_value = "4")]
+        jobs: usize,
+        #[command(flatten)]
+        output: OutputArgs,
     },
     /// Scan for similar code
     Scan {
@@ -42,22 +237,118 @@ pub enum AkinCommands {
         /// Similarity threshold
         #[arg(short, long, default_value = "0.85")]
         threshold: f32,
+        /// Minimum similarity for pairs persisted to the database (default: --threshold)
+        #[arg(long)]
+        store_threshold: Option<f32>,
+        /// Stream pairs as newline-delimited JSON while scanning
+        #[arg(long)]
+        json_stream: bool,
+        /// Don't build a missing vector index (use an existing one or brute force)
+        #[arg(long)]
+        no_index_build: bool,
+        /// Scale similarity down by relative length difference (0 = off, 1 = full)
+        #[arg(long, value_name = "WEIGHT", default_value = "0")]
+        length_penalty: f32,
+        /// Nearest neighbours fetched per function; raise if near-duplicates are missed
+        #[arg(short = 'k', long, default_value_t = DEFAULT_CANDIDATES)]
+        candidates: usize,
+        /// Print the distribution of similarity scores (0.70-1.00), regardless of --threshold
+        #[arg(long)]
+        histogram: bool,
+        /// Scan a shared index database instead of the --db/default database (vectors from <path>.usearch)
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+        /// Report scores as similarity or cosine distance (thresholds stay in similarity)
+        #[arg(long, value_enum, default_value = "similarity")]
+        metric: ScoreMetric,
+        /// Scan even if the projects were indexed with different embedding dimensions or prefixes
+        #[arg(long)]
+        force: bool,
+        /// Show why each pair matched: structure hash, line counts, cross-project
+        #[arg(long)]
+        explain: bool,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Find clusters of mutually similar functions
+    Clusters {
+        /// Similarity threshold for linking two functions
+        #[arg(short, long, default_value = "0.90")]
+        threshold: f32,
+        /// Minimum cluster size
+        #[arg(long, default_value = "3")]
+        min_size: usize,
+        /// Nearest neighbours linked per function; raise if large clusters come out split
+        #[arg(short = 'k', long, default_value_t = DEFAULT_CLUSTER_CANDIDATES)]
+        candidates: usize,
+    },
+    /// Check a file's functions against an index without indexing it
+    Similar {
+        /// Source file to check
+        file: PathBuf,
+        /// Similarity threshold
+        #[arg(short, long, default_value = "0.85")]
+        threshold: f32,
+        /// Matches reported per function
+        #[arg(long, default_value = "3")]
+        max_results: usize,
+        /// Embedding model (default: the model recorded in the index, then $IRIS_EMBED_MODEL, then bge-m3)
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Embedding backend
+        #[arg(long, value_enum, default_value = "ollama")]
+        backend: EmbedBackend,
+        /// ONNX model directory with model.onnx and tokenizer.json (--backend onnx)
+        #[arg(long)]
+        model_path: Option<PathBuf>,
+        /// Minimum function lines, globally or per language (e.g. 5 or rust=5,ts=8)
+        #[arg(long, default_value = "3", value_parser = parse_min_lines)]
+        min_lines: MinLines,
+        /// Check against a shared index database instead of the --db/default database (vectors from <path>.usearch)
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
     },
     /// Cross-project comparison (LSP mode, no database)
     Compare {
         /// Project A path
         path_a: String,
-        /// Project A language (rust, swift, typescript/ts)
+        /// Project A language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
         #[arg(long, default_value = "typescript")]
         lang_a: String,
         /// Project B path
         path_b: String,
-        /// Project B language (rust, swift, typescript/ts)
+        /// Project B language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
         #[arg(long, default_value = "typescript")]
         lang_b: String,
         /// Similarity threshold
         #[arg(short, long, default_value = "0.80")]
         threshold: f32,
+        /// Embedding model (default: $IRIS_EMBED_MODEL, then bge-m3)
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Embedding backend
+        #[arg(long, value_enum, default_value = "ollama")]
+        backend: EmbedBackend,
+        /// ONNX model directory with model.onnx and tokenizer.json (--backend onnx)
+        #[arg(long)]
+        model_path: Option<PathBuf>,
+        /// Concurrent embedding requests
+        #[arg(short = 'j', long, default_value = "4")]
+        concurrency: usize,
+        /// Save pairs to the database (both projects must be indexed)
+        #[arg(long)]
+        persist: bool,
+        /// Nearest neighbours in project B fetched per function in project A
+        #[arg(short = 'k', long, default_value_t = DEFAULT_CANDIDATES)]
+        candidates: usize,
+        /// Remove comments and doc comments before embedding
+        #[arg(long)]
+        strip_comments: bool,
+        /// Drop language-specific keywords (fn/func/let/var...) and unify this/self before embedding
+        #[arg(long)]
+        cross_normalize: bool,
+        #[command(flatten)]
+        output: OutputArgs,
     },
     /// Show project status
     Status {
@@ -66,6 +357,22 @@ pub enum AkinCommands {
     },
     /// List indexed projects
     Projects,
+    /// Remove an indexed project with its code units, pairs, groups and vectors
+    Remove {
+        /// Project path (the directory may already be deleted)
+        path: String,
+        /// Index database to remove from instead of the --db/default database
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+    },
+    /// Merge another akin database (projects, code units and vectors) into this one without re-embedding
+    Import {
+        /// Database to import from (read-only; its .usearch is used when present)
+        source: PathBuf,
+        /// Index database to import into instead of the --db/default database
+        #[arg(long, value_name = "PATH")]
+        index: Option<PathBuf>,
+    },
     /// List similar pairs
     Pairs {
         /// Filter by status (new, ignored, confirmed, redundant)
@@ -74,20 +381,144 @@ pub enum AkinCommands {
         /// Max results
         #[arg(short, long, default_value = "20")]
         limit: usize,
+        /// Only pairs involving this project
+        #[arg(short = 'P', long)]
+        project: Option<String>,
+        /// Only pairs whose units are both of this kind (function, method, type, ...)
+        #[arg(long)]
+        kind: Option<String>,
+        /// Report scores as similarity or cosine distance
+        #[arg(long, value_enum, default_value = "similarity")]
+        metric: ScoreMetric,
     },
-    /// Ignore a pair
+    /// Full-text search over function bodies stored with `index --store-bodies`
+    Grep {
+        /// Text to find, matched as a phrase of whole tokens
+        query: String,
+        /// Max results
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+        /// Only units of this project
+        #[arg(short = 'P', long)]
+        project: Option<String>,
+        /// Pass the query to SQLite FTS5 unchanged (AND/OR/NEAR, prefix*)
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Ignore a pair, or all new pairs whose files match a glob
     Ignore {
         /// Code unit A
-        unit_a: String,
+        #[arg(required_unless_present = "pattern")]
+        unit_a: Option<String>,
         /// Code unit B
-        unit_b: String,
+        #[arg(required_unless_present = "pattern")]
+        unit_b: Option<String>,
         /// Reason
         #[arg(short, long)]
         reason: Option<String>,
+        /// Glob matched against full file paths, e.g. '*/generated/*'
+        #[arg(short, long, conflicts_with_all = ["unit_a", "unit_b"])]
+        pattern: Option<String>,
+        /// With --pattern, match pairs where either file matches (default: both)
+        #[arg(long, requires = "pattern")]
+        either: bool,
+    },
+    /// Set the review status of a pair
+    Mark {
+        /// Code unit A
+        unit_a: String,
+        /// Code unit B
+        unit_b: String,
+        /// New status (new, ignored, confirmed, redundant)
+        status: String,
+    },
+    /// Reset an ignored pair to new (same as `mark <a> <b> new`)
+    Unignore {
+        /// Code unit A
+        unit_a: String,
+        /// Code unit B
+        unit_b: String,
+    },
+    /// Remove orphaned pairs and reclaim database space
+    Compact,
+    /// Check that the database and vector index agree
+    Verify {
+        /// Rebuild the vector index when drift is found
+        #[arg(long)]
+        fix: bool,
     },
     /// Group management
     #[command(subcommand)]
     Group(GroupCommands),
+    /// Manage the on-disk embedding cache (~/.vimo/akin/embcache)
+    #[command(subcommand)]
+    Cache(CacheCommands),
+}
+
+/// Output flags shared by the long-running commands
+#[derive(Args, Clone, Copy)]
+pub struct OutputArgs {
+    /// Only print final results (implies --no-progress)
+    #[arg(short, long)]
+    quiet: bool,
+    /// Don't draw the progress line (automatic when stdout is not a terminal)
+    #[arg(long)]
+    no_progress: bool,
+}
+
+/// Carriage-return progress rendering, only drawn on an interactive terminal
+#[derive(Clone, Copy)]
+struct Progress {
+    enabled: bool,
+    quiet: bool,
+}
+
+impl Progress {
+    fn new(output: OutputArgs) -> Self {
+        Self {
+            enabled: !output.quiet && !output.no_progress && std::io::stdout().is_terminal(),
+            quiet: output.quiet,
+        }
+    }
+
+    fn update(&self, line: std::fmt::Arguments) {
+        if self.enabled {
+            print!("\r{}", line);
+            let _ = std::io::stdout().flush();
+        }
+    }
+
+    /// Terminate the progress line so the next output starts on its own line
+    fn finish(&self) {
+        if self.enabled {
+            println!();
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScoreMetric {
+    /// Cosine similarity as a percentage (100% = identical)
+    Similarity,
+    /// Raw distance reported by the vector index, in the index metric (0 = identical)
+    Distance,
+}
+
+impl ScoreMetric {
+    fn format(self, similarity: f32, distance: f32) -> String {
+        match self {
+            ScoreMetric::Similarity => format!("{:.2}%", similarity * 100.0),
+            ScoreMetric::Distance => format!("{:.4}", distance),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmbedBackend {
+    /// Ollama HTTP server
+    Ollama,
+    /// Local ONNX model (requires the `onnx` feature)
+    Onnx,
 }
 
 #[derive(Subcommand)]
@@ -126,23 +557,156 @@ pub enum GroupCommands {
     },
 }
 
-pub async fn run(cmd: AkinCommands) -> anyhow::Result<()> {
-    match cmd {
-        AkinCommands::Index { path, lang, model, min_lines } => {
-            cmd_index(&path, &lang, &model, min_lines).await
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Delete every cached embedding
+    Clear,
+}
+
+pub async fn run(args: AkinArgs) -> anyhow::Result<()> {
+    if let Some(db) = args.db {
+        let _ = DB_PATH.set(db);
+    }
+    FOLLOW_SYMLINKS.store(args.follow_symlinks, Ordering::Relaxed);
+
+    match args.command {
+        AkinCommands::Index { path, lang, model, backend, model_path, min_lines, profile, signatures, strip_comments, checkpoint_every, include_tests, sfc, include_types, max_functions_per_file, store_bodies, index, force, jobs, retries, output } => {
+            let model = model.unwrap_or_else(default_model);
+            let embedder = create_embedder(backend, &model, model_path.as_deref(), retries, EmbedRole::Passage)?;
+            let opts = IndexOptions {
+                profile,
+                signatures,
+                strip_comments,
+                checkpoint_every: checkpoint_every.max(1),
+                include_tests,
+                sfc,
+                include_types,
+                max_functions_per_file,
+                store_bodies,
+                index,
+                force,
+                jobs: jobs.max(1),
+                retries,
+                prune: false,
+                progress: Progress::new(output),
+            };
+            cmd_index(&path, &lang, embedder, &min_lines, &opts).await
+        }
+        AkinCommands::IndexAll { manifest, backend, model_path, signatures, strip_comments, include_types, index, force, jobs, retries, output } => {
+            let opts = IndexOptions {
+                profile: false,
+                signatures,
+                strip_comments,
+                checkpoint_every: 100,
+                include_tests: false,
+                sfc: false,
+                include_types,
+                max_functions_per_file: None,
+                store_bodies: false,
+                index,
+                force,
+                jobs: jobs.max(1),
+                retries,
+                prune: false,
+                progress: Progress::new(output),
+            };
+            cmd_index_all(&manifest, backend, model_path.as_deref(), &opts).await
+        }
+        AkinCommands::Reindex { path, lang, model, backend, model_path, min_lines, signatures, strip_comments, include_tests, sfc, include_types, max_functions_per_file, store_bodies, index, jobs, retries, output } => {
+            let opts = IndexOptions {
+                profile: false,
+                signatures,
+                strip_comments,
+                checkpoint_every: 100,
+                include_tests,
+                sfc,
+                include_types,
+                max_functions_per_file,
+                store_bodies,
+                index,
+                force: false,
+                jobs: jobs.max(1),
+                retries,
+                prune: true,
+                progress: Progress::new(output),
+            };
+            cmd_reindex(&path, lang, model, backend, model_path.as_deref(), &min_lines, &opts).await
+        }
+        AkinCommands::Watch { path, lang, model, backend, model_path, min_lines, watch_debounce, index, jobs, output } => {
+            let opts = IndexOptions {
+                profile: false,
+                signatures: false,
+                strip_comments: false,
+                checkpoint_every: 100,
+                include_tests: false,
+                sfc: false,
+                include_types: false,
+                max_functions_per_file: None,
+                store_bodies: false,
+                index,
+                force: false,
+                jobs: jobs.max(1),
+                retries: DEFAULT_RETRIES,
+                prune: true,
+                progress: Progress::new(output),
+            };
+            let debounce = Duration::from_millis(watch_debounce);
+            cmd_watch(&path, lang, model, backend, model_path.as_deref(), &min_lines, debounce, &opts).await
+        }
+        AkinCommands::Scan { paths, all, cross_only, threshold, store_threshold, json_stream, no_index_build, length_penalty, candidates, histogram, index, metric, force, explain, output } => {
+            let opts = ScanOptions {
+                all,
+                cross_only,
+                threshold,
+                store_threshold: store_threshold.unwrap_or(threshold),
+                json_stream,
+                build_index: !no_index_build,
+                length_penalty,
+                candidates,
+                histogram,
+                index,
+                metric,
+                force,
+                explain,
+                progress: Progress::new(output),
+            };
+            cmd_scan(&paths, &opts).await
         }
-        AkinCommands::Scan { paths, all, cross_only, threshold } => {
-            cmd_scan(&paths, all, cross_only, threshold).await
+        AkinCommands::Clusters { threshold, min_size, candidates } => cmd_clusters(threshold, min_size, candidates),
+        AkinCommands::Similar { file, threshold, max_results, model, backend, model_path, min_lines, index } => {
+            let config = HookConfig { threshold, max_results, min_lines, ..HookConfig::default() };
+            cmd_similar(&file, model, backend, model_path.as_deref(), index.as_deref(), &config).await
         }
-        AkinCommands::Compare { path_a, lang_a, path_b, lang_b, threshold } => {
-            cmd_compare(&path_a, &lang_a, &path_b, &lang_b, threshold).await
+        AkinCommands::Compare { path_a, lang_a, path_b, lang_b, threshold, model, backend, model_path, concurrency, persist, candidates, strip_comments, cross_normalize, output } => {
+            let model = model.unwrap_or_else(default_model);
+            let opts = CompareOptions {
+                threshold,
+                model: &model,
+                backend,
+                model_path: model_path.as_deref(),
+                concurrency,
+                persist,
+                candidates,
+                strip_comments,
+                cross_normalize,
+                progress: Progress::new(output),
+            };
+            cmd_compare(&path_a, &lang_a, &path_b, &lang_b, &opts).await
         }
         AkinCommands::Status { path } => cmd_status(&path),
         AkinCommands::Projects => cmd_projects(),
-        AkinCommands::Pairs { status, limit } => cmd_pairs(&status, limit),
-        AkinCommands::Ignore { unit_a, unit_b, reason } => {
-            cmd_ignore(&unit_a, &unit_b, reason.as_deref())
-        }
+        AkinCommands::Remove { path, index } => cmd_remove(&path, index.as_deref()),
+        AkinCommands::Import { source, index } => cmd_import(&source, index.as_deref()),
+        AkinCommands::Pairs { status, limit, project, kind, metric } => cmd_pairs(&status, limit, project.as_deref(), kind.as_deref(), metric),
+        AkinCommands::Ignore { unit_a, unit_b, reason, pattern, either } => match pattern {
+            Some(pattern) => cmd_ignore_pattern(&pattern, either, reason.as_deref()),
+            None => cmd_ignore(&unit_a.unwrap_or_default(), &unit_b.unwrap_or_default(), reason.as_deref()),
+        },
+        AkinCommands::Grep { query, limit, project, raw } => cmd_grep(&query, limit, project.as_deref(), raw),
+        AkinCommands::Mark { unit_a, unit_b, status } => cmd_mark(&unit_a, &unit_b, &status),
+        AkinCommands::Unignore { unit_a, unit_b } => cmd_mark(&unit_a, &unit_b, "new"),
+        AkinCommands::Compact => cmd_compact(),
+        AkinCommands::Verify { fix } => cmd_verify(fix),
         AkinCommands::Group(sub) => match sub {
             GroupCommands::Create { name, reason, pattern, project } => {
                 cmd_group_create(&name, &reason, pattern.as_deref(), project.as_deref())
@@ -153,120 +717,687 @@ pub async fn run(cmd: AkinCommands) -> anyhow::Result<()> {
             GroupCommands::List { project } => cmd_group_list(project.as_deref()),
             GroupCommands::Members { group_id } => cmd_group_members(group_id),
         },
+        AkinCommands::Cache(sub) => match sub {
+            CacheCommands::Clear => cmd_cache_clear(),
+        },
     }
 }
 
+fn db_path() -> PathBuf {
+    DB_PATH.get().cloned().unwrap_or_else(get_db_path)
+}
+
 fn ensure_db() -> anyhow::Result<Database> {
-    let db_path = get_db_path();
+    let db_path = db_path();
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     Ok(Database::open(&db_path)?)
 }
 
+/// Read-only connection for query commands; doesn't contend with a running index
+fn open_db_readonly() -> anyhow::Result<Database> {
+    let db_path = db_path();
+    if !db_path.exists() {
+        return ensure_db();
+    }
+    let db = Database::open_readonly(&db_path)?;
+    // Queries expect the current schema: migrate older databases once through a writable connection
+    if db.schema_version()? < SCHEMA_VERSION {
+        drop(db);
+        Database::open(&db_path)?;
+        return Ok(Database::open_readonly(&db_path)?);
+    }
+    Ok(db)
+}
+
 fn ensure_store() -> anyhow::Result<Store> {
-    let db_path = get_db_path();
+    let db_path = db_path();
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     Ok(Store::open(&db_path)?)
 }
 
-async fn cmd_index(path: &str, lang: &str, model: &str, min_lines: u32) -> anyhow::Result<()> {
+/// Open the store at `index` if given, otherwise the default location
+fn open_store(index: Option<&Path>, create: bool) -> anyhow::Result<Store> {
+    let Some(path) = index else {
+        return ensure_store();
+    };
+
+    // Accept either the database or its .usearch sidecar
+    let db_path = if path.extension().is_some_and(|ext| ext == "usearch") {
+        path.with_extension("db")
+    } else {
+        path.to_path_buf()
+    };
+    if !db_path.exists() {
+        if !create {
+            anyhow::bail!("Index not found: {}", db_path.display());
+        }
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(Store::open(&db_path)?)
+}
+
+/// Units embedded per chunk for each concurrent request slot
+const EMBED_CHUNK_PER_JOB: usize = 4;
+
+/// Where an indexed unit's embedding comes from
+enum EmbeddingSource {
+    /// Stored with the same content and location; nothing to write
+    Unchanged,
+    /// Reused from another unit with the same content hash
+    Cached(Vec<u8>),
+    /// Needs a fresh embedding
+    Embed,
+}
+
+/// Per-phase timings collected by `index --profile`
+#[derive(Default)]
+struct IndexProfile {
+    extraction: Duration,
+    embedding: Duration,
+    embedded: usize,
+    cache_hits: usize,
+    disk_cache_hits: usize,
+    unchanged: usize,
+    insertion: Duration,
+    save: Duration,
+}
+
+impl IndexProfile {
+    fn print(&self, total: Duration) {
+        let avg_ms = if self.embedded > 0 {
+            self.embedding.as_secs_f64() * 1000.0 / self.embedded as f64
+        } else {
+            0.0
+        };
+
+        println!("\nProfile:");
+        println!("  {:<28} {:>10}", "Phase", "Time");
+        println!("  {}", "-".repeat(39));
+        println!("  {:<28} {:>9.2}s", "Function extraction", self.extraction.as_secs_f64());
+        println!("  {:<28} {:>9.2}s", "Embedding (total)", self.embedding.as_secs_f64());
+        println!("  {:<28} {:>8.1}ms", "Embedding (per unit)", avg_ms);
+        println!("  {:<28} {:>10}", "Embedded units", self.embedded);
+        println!("  {:<28} {:>10}", "Cache hits", self.cache_hits);
+        println!("  {:<28} {:>10}", "Disk cache hits", self.disk_cache_hits);
+        println!("  {:<28} {:>10}", "Unchanged (skipped)", self.unchanged);
+        println!("  {:<28} {:>9.2}s", "DB + vector index insertion", self.insertion.as_secs_f64());
+        println!("  {:<28} {:>9.2}s", "Index save", self.save.as_secs_f64());
+        println!("  {}", "-".repeat(39));
+        println!("  {:<28} {:>9.2}s", "Total", total.as_secs_f64());
+    }
+}
+
+struct IndexOptions {
+    profile: bool,
+    signatures: bool,
+    strip_comments: bool,
+    checkpoint_every: usize,
+    include_tests: bool,
+    /// TypeScript: extract functions from .vue/.svelte <script> blocks
+    sfc: bool,
+    include_types: bool,
+    max_functions_per_file: Option<usize>,
+    /// Keep function bodies in the full-text index (`akin grep`)
+    store_bodies: bool,
+    index: Option<PathBuf>,
+    force: bool,
+    jobs: usize,
+    retries: u32,
+    /// Remove stored units that were not extracted this time (deleted functions and files)
+    prune: bool,
+    progress: Progress,
+}
+
+async fn cmd_index(
+    path: &str,
+    lang: &str,
+    embedder: Box<dyn Embedder>,
+    min_lines: &MinLines,
+    opts: &IndexOptions,
+) -> anyhow::Result<()> {
+    let mut store = open_store(opts.index.as_deref(), true)?;
+    index_project(&mut store, path, lang, embedder, min_lines, None, opts).await?;
+    Ok(())
+}
+
+/// `index` with pruning; language and model default to the ones the project was indexed with
+async fn cmd_reindex(
+    path: &str,
+    lang: Option<String>,
+    model: Option<String>,
+    backend: EmbedBackend,
+    model_path: Option<&Path>,
+    min_lines: &MinLines,
+    opts: &IndexOptions,
+) -> anyhow::Result<()> {
+    let mut store = open_store(opts.index.as_deref(), true)?;
+    let project_path = PathBuf::from(path).canonicalize()?;
+    let project = store.db().get_project_by_path(&project_path.to_string_lossy())?;
+
+    let lang = lang
+        .or_else(|| project.as_ref().map(|p| p.language.clone()))
+        .unwrap_or_else(|| "rust".to_string());
+    let model = model
+        .or_else(|| project.as_ref().and_then(|p| p.embedding_model.clone()))
+        .unwrap_or_else(default_model);
+    let embedder = create_embedder(backend, &model, model_path, opts.retries, EmbedRole::Passage)?;
+    index_project(&mut store, path, &lang, embedder, min_lines, None, opts).await?;
+    Ok(())
+}
+
+/// How often `watch` checks source file modification times
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// `reindex`, then re-index the changed files after every burst of changes, coalescing events per file within `debounce`
+#[allow(clippy::too_many_arguments)]
+async fn cmd_watch(
+    path: &str,
+    lang: Option<String>,
+    model: Option<String>,
+    backend: EmbedBackend,
+    model_path: Option<&Path>,
+    min_lines: &MinLines,
+    debounce: Duration,
+    opts: &IndexOptions,
+) -> anyhow::Result<()> {
+    cmd_reindex(path, lang.clone(), model.clone(), backend, model_path, min_lines, opts).await?;
+
+    // The first pass recorded the project, so its language and model are known from here on
+    let project_path = PathBuf::from(path).canonicalize()?;
+    let project = open_store(opts.index.as_deref(), false)?
+        .db()
+        .get_project_by_path(&project_path.to_string_lossy())?;
+    let project_lang = lang
+        .or_else(|| project.as_ref().map(|p| p.language.clone()))
+        .unwrap_or_else(|| "rust".to_string());
+    let model = model
+        .or_else(|| project.as_ref().and_then(|p| p.embedding_model.clone()))
+        .unwrap_or_else(default_model);
+    println!("Watching {} for changes (Ctrl-C to stop)", project_path.display());
+
+    let mut coalescer = EventCoalescer::new(debounce);
+    let mut mtimes = source_mtimes(&project_path, &project_lang, opts)?;
+    loop {
+        tokio::time::sleep(WATCH_POLL_INTERVAL).await;
+
+        let now = Instant::now();
+        let current = source_mtimes(&project_path, &project_lang, opts)?;
+        for (file, modified) in &current {
+            if mtimes.get(file) != Some(modified) {
+                coalescer.push(file, now);
+            }
+        }
+        for file in mtimes.keys().filter(|f| !current.contains_key(*f)) {
+            coalescer.push(file, now);
+        }
+        mtimes = current;
+
+        // Files still being written stay pending until they have been quiet for the debounce window
+        let changed = coalescer.drain_ready(now);
+        if changed.is_empty() {
+            continue;
+        }
+        println!("\n{} file(s) changed, re-indexing", changed.len());
+        let changed: Vec<String> = changed.iter().map(|f| f.to_string_lossy().into_owned()).collect();
+        let result = async {
+            let mut store = open_store(opts.index.as_deref(), true)?;
+            let embedder = create_embedder(backend, &model, model_path, opts.retries, EmbedRole::Passage)?;
+            index_project(&mut store, path, &project_lang, embedder, min_lines, Some(&changed), opts).await
+        }.await;
+        if let Err(e) = result {
+            eprintln!("Re-index failed: {:#}", e);
+        }
+    }
+}
+
+/// Modification times of the files the language adapter would index
+fn source_mtimes(path: &Path, lang: &str, opts: &IndexOptions) -> anyhow::Result<HashMap<PathBuf, std::time::SystemTime>> {
+    let path = path.to_str().unwrap();
+    let follow = FOLLOW_SYMLINKS.load(Ordering::Relaxed);
+    let files = match lang {
+        "rust" => RustAdapter::new(path).with_follow_symlinks(follow).get_source_files()?,
+        "swift" => SwiftAdapter::new(path).with_include_tests(opts.include_tests).with_follow_symlinks(follow).get_source_files()?,
+        "typescript" | "ts" => TypeScriptAdapter::new(path).with_sfc(opts.sfc).with_follow_symlinks(follow).get_source_files()?,
+        "vue" => VueAdapter::new(path).with_follow_symlinks(follow).get_source_files()?,
+        "java" => JavaAdapter::new(path).with_follow_symlinks(follow).get_source_files()?,
+        "kotlin" | "kt" => KotlinAdapter::new(path).with_follow_symlinks(follow).get_source_files()?,
+        "go" => GoAdapter::new(path).with_follow_symlinks(follow).get_source_files()?,
+        _ => anyhow::bail!("Unsupported language: {}", lang),
+    };
+    Ok(files.into_iter()
+        .filter_map(|f| {
+            let modified = std::fs::metadata(&f).and_then(|m| m.modified()).ok()?;
+            Some((PathBuf::from(f), modified))
+        })
+        .collect())
+}
+
+/// Index one project into `store`, returning the number of units indexed
+///
+/// With `files`, only those files are extracted, and pruning only touches units stored for them
+async fn index_project(
+    store: &mut Store,
+    path: &str,
+    lang: &str,
+    mut embedder: Box<dyn Embedder>,
+    min_lines: &MinLines,
+    files: Option<&[String]>,
+    opts: &IndexOptions,
+) -> anyhow::Result<usize> {
+    let IndexOptions { profile, signatures, strip_comments, checkpoint_every, include_tests, sfc, include_types, max_functions_per_file, store_bodies, index: _, force, jobs, retries: _, prune, progress } = *opts;
+    let t0 = Instant::now();
+    let mut timings = IndexProfile::default();
+
     let project_path = PathBuf::from(path).canonicalize()?;
     let project_name = project_path.file_name()
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    println!("Project: {}", project_path.display());
-    println!("Language: {}", lang);
-    println!("Model: {}", model);
-    println!();
+    info!(progress, "Project: {}", project_path.display());
+    info!(progress, "Language: {}", lang);
+    let model = embedder.model_name().to_string();
+    let prefix = embedder.input_prefix().to_string();
+    let dimensions = embedder.embed("fn probe() {}").await
+        .map_err(|e| anyhow::anyhow!("Embedding model {} unavailable: {}", model, e))?
+        .len();
+    info!(progress, "Model: {} ({} dimensions)", model, dimensions);
+    // The vector index has a fixed dimension; catch a model switch before embedding anything
+    if let Err(e) = store.set_embedding_model(&model, dimensions) {
+        if let StoreError::DimensionMismatch { expected, .. } = &e {
+            anyhow::bail!(
+                "{}\nThe vector index at {} holds {}-dimension embeddings. After a model change, re-create the index: \
+                 delete it (and its .usearch / .usearch.json files) or pass --index <new path>",
+                e, store.db_path().display(), expected
+            );
+        }
+        return Err(e.into());
+    }
+    // Embeddings computed for any project or database are reused from the on-disk cache
+    let mut embedder = CachedEmbedder::new(embedder, EmbeddingCache::default()).with_dimensions(Some(dimensions));
 
-    let mut store = ensure_store()?;
     let project_id = store.db_mut().get_or_create_project(&project_name, project_path.to_str().unwrap(), lang)?;
 
-    println!("Extracting code units...");
-    let units = extract_functions_lsp(project_path.to_str().unwrap(), lang).await?;
-    println!("Found {} functions", units.len());
+    // Embeddings of different dimensions or input prefixes can't be compared; a change means re-embedding everything
+    let mut reembed_all = false;
+    if let Some(project) = store.db().get_project_by_path(project_path.to_str().unwrap())? {
+        if let Err(mismatch) = project.check_model(&model, dimensions, &prefix) {
+            if !force {
+                anyhow::bail!("{}\nRe-run with --force to re-embed the whole project with {}", mismatch, model);
+            }
+            eprintln!("WARNING: {}; re-embedding every unit (--force)", mismatch);
+            reembed_all = true;
+        }
+    }
 
-    let units: Vec<_> = units.into_iter()
-        .filter(|u| (u.range_end - u.range_start) >= min_lines)
-        .collect();
-    println!("After filter: {} functions (>= {} lines)", units.len(), min_lines);
+    info!(progress, "\nExtracting code units...");
+    let t_extract = Instant::now();
+    // Deleted files have nothing to extract; pruning drops what was stored for them
+    let existing_files: Option<Vec<String>> = files.map(|files| {
+        files.iter().filter(|f| Path::new(f).exists()).cloned().collect()
+    });
+    let units = extract_functions_lsp(project_path.to_str().unwrap(), lang, signatures, include_tests, sfc, include_types, existing_files.as_deref()).await?;
+    timings.extraction = t_extract.elapsed();
+    info!(progress, "Found {} functions", units.len());
+
+    let mut units = min_lines.filter_units(units, lang);
+    info!(progress, "After filter: {} functions (>= {} lines)", units.len(), min_lines.for_language(lang));
+    if let Some(max) = max_functions_per_file {
+        let (capped, skipped, files) = cap_units_per_file(units, max);
+        units = capped;
+        if skipped > 0 {
+            println!("Skipped {} functions in {} files over --max-functions-per-file {}", skipped, files, max);
+        }
+    }
+    if strip_comments {
+        strip_unit_comments(&mut units);
+    }
+    let empty = CodeUnit::drop_empty_bodies(&mut units);
+    if empty > 0 {
+        println!("Skipped {} units with empty bodies", empty);
+    }
 
     if units.is_empty() {
         println!("No matching functions found");
-        return Ok(());
+        // Pruning still has to drop the units stored by earlier runs
+        if !prune {
+            return Ok(0);
+        }
+    }
+
+    // Units already stored with the same content and location need no work
+    let mut stored = store.db().get_code_units_by_project(project_id)?;
+    if let Some(files) = files {
+        stored.retain(|u| files.contains(&u.file_path));
+    }
+    let stored_files: HashMap<String, String> = stored.iter()
+        .map(|u| (u.qualified_name.clone(), u.file_path.clone()))
+        .collect();
+    let existing: HashMap<String, CodeUnitRecord> = stored
+        .into_iter()
+        .filter(|u| u.embedding.is_some() && !reembed_all)
+        .map(|u| (u.qualified_name.clone(), u))
+        .collect();
+    let is_unchanged = |unit: &CodeUnit, content_hash: &str| {
+        existing.get(&unit.qualified_name).is_some_and(|u| {
+            u.content_hash == content_hash
+                && u.file_path == unit.file_path
+                && u.range_start == unit.range_start
+                && u.range_end == unit.range_end
+        })
+    };
+
+    // A leftover progress marker means the previous run was interrupted
+    if store.db().get_index_progress(project_id)?.is_some() {
+        let done = units.iter().filter(|u| is_unchanged(u, &compute_hash(&u.body))).count();
+        info!(progress, "Resuming from {}/{}", done, units.len());
+        // The saved index may predate the last units written to the database
+        store.rebuild_vector_index()?;
     }
+    store.db().set_index_progress(project_id, 0, units.len())?;
 
-    println!("\nGenerating embeddings...");
-    let mut embedder = OllamaEmbedding::new(model);
+    info!(progress, "\nGenerating embeddings ({} concurrent)...", jobs);
     let mut indexed = 0;
+    let (mut added, mut updated) = (0, 0);
+
+    // Embed a chunk at a time so up to `jobs` requests stay in flight
+    let chunk_size = jobs * EMBED_CHUNK_PER_JOB;
+    for (chunk_idx, chunk) in units.chunks(chunk_size).enumerate() {
+        // Hash and look up the whole chunk first; only cache misses go to the embedder
+        let mut prepared = Vec::with_capacity(chunk.len());
+        let mut texts = Vec::new();
+        for unit in chunk {
+            let content_hash = compute_hash(&unit.body);
+            let source = if is_unchanged(unit, &content_hash) {
+                EmbeddingSource::Unchanged
+            } else {
+                // The cache may hold embeddings from another model; only reuse ones of the right size
+                match store.db().get_embedding_by_content_hash(&content_hash).ok().flatten()
+                    .filter(|bytes| bytes.len() == dimensions * 4)
+                {
+                    Some(cached) => EmbeddingSource::Cached(cached),
+                    None => {
+                        texts.push(unit.body.as_str());
+                        EmbeddingSource::Embed
+                    }
+                }
+            };
+            prepared.push((content_hash, source));
+        }
 
-    for (i, unit) in units.iter().enumerate() {
-        print!("\r  [{}/{}] {}", i + 1, units.len(), short_name(&unit.qualified_name));
+        let t_embed = Instant::now();
+        let mut fresh = embedder.embed_each(&texts, jobs).await.into_iter();
+        timings.embedding += t_embed.elapsed();
 
-        let content_hash = compute_hash(&unit.body);
-        let structure_hash = compute_structure_hash(&unit.body);
+        for (offset, (unit, (content_hash, source))) in chunk.iter().zip(prepared).enumerate() {
+            let i = chunk_idx * chunk_size + offset;
+            progress.update(format_args!("  [{}/{}] {}", i + 1, units.len(), short_name(&unit.qualified_name)));
 
-        let embedding = if let Ok(Some(cached)) = store.db().get_embedding_by_content_hash(&content_hash) {
-            cached
-        } else {
-            match embedder.embed(&unit.body).await {
-                Ok(emb) => embedding_to_bytes(&emb),
-                Err(e) => {
-                    eprintln!("\nWarning: failed to generate embedding: {}", e);
+            if i > 0 && i.is_multiple_of(checkpoint_every) {
+                let t_save = Instant::now();
+                store.save_vector_index()?;
+                store.db().set_index_progress(project_id, i, units.len())?;
+                timings.save += t_save.elapsed();
+            }
+
+            let embedding = match source {
+                EmbeddingSource::Unchanged => {
+                    if store_bodies {
+                        store.db().upsert_code_body(&unit.qualified_name, &unit.body)?;
+                    }
+                    timings.unchanged += 1;
+                    indexed += 1;
                     continue;
                 }
-            }
-        };
+                EmbeddingSource::Cached(cached) => {
+                    timings.cache_hits += 1;
+                    cached
+                }
+                EmbeddingSource::Embed => match fresh.next() {
+                    Some(Ok(emb)) => {
+                        timings.embedded += 1;
+                        embedding_to_bytes(&emb)
+                    }
+                    Some(Err(e)) => {
+                        eprintln!("\nWarning: failed to generate embedding: {}", e);
+                        continue;
+                    }
+                    None => continue,
+                },
+            };
+            let structure_hash = compute_structure_hash(&unit.body);
+
+            let record = CodeUnitRecord {
+                qualified_name: unit.qualified_name.clone(),
+                project_id,
+                file_path: unit.file_path.clone(),
+                kind: unit.kind.clone(),
+                range_start: unit.range_start,
+                range_end: unit.range_end,
+                content_hash,
+                structure_hash,
+                embedding: Some(embedding),
+                group_id: None,
+                signature: unit.signature.clone(),
+            };
 
-        let record = CodeUnitRecord {
-            qualified_name: unit.qualified_name.clone(),
-            project_id,
-            file_path: unit.file_path.clone(),
-            kind: unit.kind.clone(),
-            range_start: unit.range_start,
-            range_end: unit.range_end,
-            content_hash,
-            structure_hash,
-            embedding: Some(embedding),
-            group_id: None,
-        };
+            let t_insert = Instant::now();
+            store.upsert_code_unit(&record)?;
+            // Without --store-bodies, drop a body stored earlier rather than keep it stale
+            if store_bodies {
+                store.db().upsert_code_body(&record.qualified_name, &unit.body)?;
+            } else {
+                store.db().delete_code_body(&record.qualified_name)?;
+            }
+            timings.insertion += t_insert.elapsed();
+            indexed += 1;
+            if stored_files.contains_key(&unit.qualified_name) {
+                updated += 1;
+            } else {
+                added += 1;
+            }
+        }
+    }
 
-        store.upsert_code_unit(&record)?;
-        indexed += 1;
+    // Units no longer extracted: whole files that were deleted, or functions removed from a file
+    let mut removed = 0;
+    if prune {
+        let current: HashSet<&str> = units.iter().map(|u| u.qualified_name.as_str()).collect();
+        let mut deleted_files = BTreeSet::new();
+        for (name, file_path) in &stored_files {
+            if current.contains(name.as_str()) {
+                continue;
+            }
+            if Path::new(file_path).exists() {
+                removed += store.remove_unit(name)? as usize;
+            } else {
+                deleted_files.insert(file_path.as_str());
+            }
+        }
+        for file_path in deleted_files {
+            removed += store.remove_units_by_file(file_path)?;
+        }
     }
 
+    let t_save = Instant::now();
     store.save_vector_index()?;
-
-    println!("\n\nIndexed: {} code units", indexed);
+    timings.save += t_save.elapsed();
+
+    progress.finish();
+    // Disk cache hits come back from the embedder like fresh embeddings
+    timings.disk_cache_hits = embedder.hits();
+    timings.embedded = timings.embedded.saturating_sub(timings.disk_cache_hits);
+    println!("\nIndexed: {} code units", indexed);
+    if prune {
+        println!("Added: {}, updated: {}, removed: {}", added, updated, removed);
+    }
+    println!("Embedding cache: {} hits", embedder.hits());
     if let Some((size, mem)) = store.vector_index_stats() {
         println!("Vector index: {} entries, {} KB", size, mem / 1024);
     }
     store.db_mut().update_project_indexed_time(project_id)?;
+    store.db().set_project_model(project_id, &model, dimensions, &prefix)?;
+    store.db().clear_index_progress(project_id)?;
+
+    if profile {
+        timings.print(t0.elapsed());
+    }
+
+    Ok(indexed)
+}
+
+/// `index-all` manifest: one `[[project]]` table per repository
+#[derive(serde::Deserialize)]
+struct IndexManifest {
+    #[serde(rename = "project", default)]
+    projects: Vec<ManifestProject>,
+}
+
+#[derive(serde::Deserialize)]
+struct ManifestProject {
+    path: String,
+    #[serde(default = "default_manifest_lang")]
+    lang: String,
+    model: Option<String>,
+    min_lines: Option<u32>,
+}
 
+fn default_manifest_lang() -> String {
+    "rust".to_string()
+}
+
+async fn cmd_index_all(manifest: &Path, backend: EmbedBackend, model_path: Option<&Path>, opts: &IndexOptions) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(manifest)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", manifest.display(), e))?;
+    let parsed: IndexManifest = toml::from_str(&content)?;
+    if parsed.projects.is_empty() {
+        anyhow::bail!("No [[project]] entries in {}", manifest.display());
+    }
+
+    // Relative project paths are resolved against the manifest's directory
+    let base = manifest.parent().unwrap_or(Path::new("."));
+
+    let t0 = Instant::now();
+    let mut store = open_store(opts.index.as_deref(), true)?;
+    let mut rows: Vec<(&ManifestProject, anyhow::Result<usize>, Duration)> = Vec::new();
+
+    for (i, project) in parsed.projects.iter().enumerate() {
+        println!("\n[{}/{}] {}", i + 1, parsed.projects.len(), project.path);
+        let t_project = Instant::now();
+        let path = base.join(&project.path);
+        let model = project.model.clone().unwrap_or_else(default_model);
+        let min_lines = MinLines::new(project.min_lines.unwrap_or(3));
+        let result = match create_embedder(backend, &model, model_path, opts.retries, EmbedRole::Passage) {
+            Ok(embedder) => index_project(&mut store, &path.to_string_lossy(), &project.lang, embedder, &min_lines, None, opts).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            eprintln!("Error: {}", e);
+        }
+        rows.push((project, result, t_project.elapsed()));
+    }
+
+    println!("\n{:<40} {:<12} {:>8} {:>9}", "Project", "Language", "Units", "Time");
+    println!("{}", "-".repeat(72));
+    let mut total_units = 0;
+    let mut failed = 0;
+    for (project, result, elapsed) in &rows {
+        let units = match result {
+            Ok(n) => {
+                total_units += n;
+                n.to_string()
+            }
+            Err(_) => {
+                failed += 1;
+                "failed".to_string()
+            }
+        };
+        println!("{:<40} {:<12} {:>8} {:>8.1}s", truncate_path(&project.path, 40), project.lang, units, elapsed.as_secs_f64());
+    }
+    println!("{}", "-".repeat(72));
+    println!("{:<40} {:<12} {:>8} {:>8.1}s", "Total", "", total_units, t0.elapsed().as_secs_f64());
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} projects failed to index", failed, rows.len());
+    }
     Ok(())
 }
 
-async fn cmd_scan(paths: &[String], all: bool, cross_only: bool, threshold: f32) -> anyhow::Result<()> {
+/// Keep the tail of a long path so the table stays aligned
+fn truncate_path(path: &str, width: usize) -> String {
+    let chars: Vec<char> = path.chars().collect();
+    if chars.len() <= width {
+        return path.to_string();
+    }
+    let tail: String = chars[chars.len() - (width - 3)..].iter().collect();
+    format!("...{}", tail)
+}
+
+struct ScanOptions {
+    all: bool,
+    cross_only: bool,
+    threshold: f32,
+    store_threshold: f32,
+    json_stream: bool,
+    build_index: bool,
+    length_penalty: f32,
+    candidates: usize,
+    histogram: bool,
+    index: Option<PathBuf>,
+    metric: ScoreMetric,
+    force: bool,
+    explain: bool,
+    progress: Progress,
+}
+
+/// Embeddings read and searched per batch by `scan`
+const SCAN_PAGE_SIZE: usize = 1024;
+
+async fn cmd_scan(paths: &[String], opts: &ScanOptions) -> anyhow::Result<()> {
+    let ScanOptions { all, cross_only, threshold, store_threshold, json_stream, build_index, length_penalty, candidates, histogram, ref index, metric, force, explain, progress } = *opts;
+
+    // In JSONL mode stdout carries only pair records; progress goes to stderr
+    macro_rules! status {
+        ($($arg:tt)*) => {
+            if json_stream { eprintln!($($arg)*) } else { println!($($arg)*) }
+        };
+    }
+    // Informational status, dropped by --quiet
+    macro_rules! detail {
+        ($($arg:tt)*) => {
+            if !progress.quiet { status!($($arg)*) }
+        };
+    }
+
     let t0 = Instant::now();
-    let store = ensure_store()?;
+    let mut store = open_store(index.as_deref(), false)?;
+    if let Some(path) = index {
+        detail!("Index: {}", path.display());
+    }
+    if build_index {
+        store.ensure_vector_index()?;
+    } else {
+        store.load_vector_index()?;
+    }
     let db = store.db();
 
     let has_vector_index = store.vector_index_stats().is_some();
     if !has_vector_index {
-        println!("Warning: vector index not initialized, using brute force (slow)");
+        status!("Warning: vector index not initialized, using brute force (slow)");
     }
 
     let project_ids: Vec<i64> = if all || paths.is_empty() {
         let projects = db.get_all_projects()?;
         if projects.is_empty() {
-            println!("No indexed projects. Run 'iris akin index <path>' first.");
+            status!("No indexed projects. Run 'iris akin index <path>' first.");
             return Ok(());
         }
-        println!("Scanning {} projects: {}", projects.len(),
+        detail!("Scanning {} projects: {}", projects.len(),
             projects.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", "));
         projects.iter().map(|p| p.id).collect()
     } else {
@@ -276,7 +1407,7 @@ async fn cmd_scan(paths: &[String], all: bool, cross_only: bool, threshold: f32)
             match db.get_project_by_path(resolved.to_str().unwrap())? {
                 Some(proj) => ids.push(proj.id),
                 None => {
-                    println!("Project not indexed: {}", resolved.display());
+                    status!("Project not indexed: {}", resolved.display());
                     return Ok(());
                 }
             }
@@ -284,86 +1415,203 @@ async fn cmd_scan(paths: &[String], all: bool, cross_only: bool, threshold: f32)
         ids
     };
 
-    let units = db.get_code_units_by_projects(Some(&project_ids))?;
-    println!("Loaded {} code units", units.len());
-
-    if units.len() < 2 {
-        println!("Not enough code units to compare");
+    let unit_count = db.count_code_units(Some(&project_ids))?;
+    if unit_count < 2 {
+        status!("Not enough code units to compare");
         return Ok(());
     }
 
-    let units_with_emb: Vec<_> = units.iter()
-        .filter_map(|u| {
-            u.embedding.as_ref()
-                .and_then(|e| bytes_to_embedding(e))
-                .map(|emb| (u, emb))
-        })
+    let selected: Vec<_> = db.get_all_projects()?
+        .into_iter()
+        .filter(|p| project_ids.contains(&p.id))
         .collect();
-    println!("Valid embeddings: {}", units_with_emb.len());
 
-    if units_with_emb.len() < 2 {
-        println!("Not enough valid embeddings");
-        return Ok(());
+    // Scores between embeddings of different dimensions or prefixes are meaningless
+    if let Some(reference) = selected.iter().find(|p| p.embedding_dimensions.is_some()) {
+        let model = reference.embedding_model.clone().unwrap_or_default();
+        let dimensions = reference.embedding_dimensions.unwrap_or_default();
+        let prefix = reference.embedding_prefix.clone().unwrap_or_default();
+        let mismatches: Vec<_> = selected.iter()
+            .filter_map(|p| p.check_model(&model, dimensions, &prefix).err())
+            .collect();
+        for mismatch in &mismatches {
+            status!("WARNING: {}", mismatch);
+        }
+        if !mismatches.is_empty() && !force {
+            anyhow::bail!("Projects were indexed with different embedding dimensions or prefixes; re-index them with one model or pass --force");
+        }
+    }
+
+    // Each project honors the .irisignore found from its root
+    let ignore_sets: HashMap<i64, IgnoreSet> = selected.iter()
+        .map(|p| (p.id, IgnoreSet::discover(Path::new(&p.root_path))))
+        .collect();
+    // Metadata only; embeddings are read a page at a time while searching
+    let (units, ignored): (Vec<_>, Vec<_>) = db.get_code_unit_metadata_by_projects(Some(&project_ids))?
+        .into_iter()
+        .partition(|u| !ignore_sets.get(&u.project_id)
+            .is_some_and(|set| set.is_ignored(&u.file_path, &u.qualified_name)));
+    let ignored: HashSet<String> = ignored.into_iter().map(|u| u.qualified_name).collect();
+    detail!("Loaded {} code units", units.len());
+    if !ignored.is_empty() {
+        detail!("Ignored by .irisignore: {}", ignored.len());
     }
 
     let name_to_project: HashMap<String, i64> = units.iter()
         .map(|u| (u.qualified_name.clone(), u.project_id))
         .collect();
-
-    let queries: Vec<(usize, &[f32])> = units_with_emb.iter()
-        .enumerate()
-        .map(|(i, (_, emb))| (i, emb.as_slice().unwrap()))
+    let unit_by_name: HashMap<&str, &CodeUnitRecord> = units.iter()
+        .map(|u| (u.qualified_name.as_str(), u))
         .collect();
 
-    print!("Searching...");
-    let k = 100;
-    let search_results = store.search_batch_parallel(&queries, k, threshold)?;
+    // The histogram covers scores below the threshold too, so search once at the lower bound
+    let search_threshold = if histogram { threshold.min(HISTOGRAM_MIN) } else { threshold };
+    // Pairs not hit by this search are converted back from their stored similarity
+    let index_metric = store.index_metadata().map(|meta| meta.metric).unwrap_or_default();
+    let mut distribution = SimilarityHistogram::default();
 
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
     let mut new_pairs: Vec<(String, String, f32)> = Vec::new();
+    let mut distances: HashMap<(String, String), f32> = HashMap::new();
     let mut seen: HashSet<(String, String)> = HashSet::new();
+    let mut embedded = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let page = db.get_embeddings_page(Some(&project_ids), after.as_deref(), SCAN_PAGE_SIZE)?;
+        let Some((last, _)) = page.last() else { break };
+        after = Some(last.clone());
+
+        let (query_names, embeddings): (Vec<String>, Vec<Vec<f32>>) = page.into_iter()
+            .filter(|(name, _)| !ignored.contains(name))
+            .filter_map(|(name, bytes)| Some((name, bytes_to_embedding(&bytes)?.to_vec())))
+            .unzip();
+        embedded += query_names.len();
+        if !json_stream {
+            progress.update(format_args!("Searching... {}/{}", embedded, units.len()));
+        }
+        let search_results = store.search_results_grouped_async(embeddings, candidates, search_threshold).await?;
 
-    for (query_idx, similar_name, similarity) in search_results {
-        let query_name = &units_with_emb[query_idx].0.qualified_name;
-        let query_project = units_with_emb[query_idx].0.project_id;
+        for (query_name, hits) in query_names.iter().zip(search_results) {
+            let query_project = name_to_project.get(query_name).copied();
 
-        if &similar_name == query_name {
-            continue;
-        }
+            for (similar_name, result) in hits {
+                let similarity = result.similarity();
+                if &similar_name == query_name || ignored.contains(&similar_name) {
+                    continue;
+                }
+
+                if cross_only {
+                    if let Some(&similar_project) = name_to_project.get(&similar_name) {
+                        if Some(similar_project) == query_project {
+                            continue;
+                        }
+                    }
+                }
 
-        if cross_only {
-            if let Some(&similar_project) = name_to_project.get(&similar_name) {
-                if similar_project == query_project {
+                let pair = if query_name < &similar_name {
+                    (query_name.clone(), similar_name.clone())
+                } else {
+                    (similar_name.clone(), query_name.clone())
+                };
+
+                let similarity = match (unit_by_name.get(query_name.as_str()), unit_by_name.get(similar_name.as_str())) {
+                    (Some(a), Some(b)) => apply_length_penalty(
+                        similarity,
+                        line_count(a.range_start, a.range_end),
+                        line_count(b.range_start, b.range_end),
+                        length_penalty,
+                    ),
+                    _ => similarity,
+                };
+
+                if !seen.insert(pair.clone()) {
+                    continue;
+                }
+                distribution.add(similarity);
+                if similarity < threshold {
                     continue;
                 }
+
+                // The penalty only adjusts the similarity; the distance stays as the index reported it
+                if json_stream {
+                    write_pair_jsonl(&mut out, &pair.0, &pair.1, similarity, result.distance, similarity >= store_threshold, metric, &unit_by_name)?;
+                }
+                distances.insert(pair.clone(), result.distance);
+                new_pairs.push((pair.0, pair.1, similarity));
             }
         }
+    }
+    if !json_stream {
+        progress.update(format_args!(""));
+    }
+    detail!("Valid embeddings: {}", embedded);
+    if embedded < 2 {
+        status!("Not enough valid embeddings");
+        return Ok(());
+    }
 
-        let pair = if query_name < &similar_name {
-            (query_name.clone(), similar_name.clone())
-        } else {
-            (similar_name.clone(), query_name.clone())
-        };
+    let (stored_pairs, display_only): (Vec<_>, Vec<_>) = new_pairs.into_iter()
+        .partition(|(_, _, similarity)| *similarity >= store_threshold);
+    db.batch_upsert_similar_pairs(&stored_pairs, Some("scan"))?;
+    drop(out);
 
-        if seen.insert(pair.clone()) {
-            new_pairs.push((pair.0, pair.1, similarity));
-        }
+    if !json_stream {
+        println!("Done: {} pairs ({:.2}s)", stored_pairs.len() + display_only.len(), t0.elapsed().as_secs_f32());
+    }
+    if histogram {
+        status!("\nSimilarity distribution ({} candidate pairs >= {:.2}):", distribution.total(), HISTOGRAM_MIN);
+        status!("{}", distribution.render(40));
+    }
+    if json_stream {
+        status!("Done: {} pairs ({:.2}s)", stored_pairs.len() + display_only.len(), t0.elapsed().as_secs_f32());
+        return Ok(());
     }
 
-    db.batch_upsert_similar_pairs(&new_pairs, Some("scan"))?;
-
-    println!("\rDone: {} pairs ({:.2}s)", new_pairs.len(), t0.elapsed().as_secs_f32());
+    if !display_only.is_empty() {
+        println!("Not stored: {} pairs below store threshold {:.0}%", display_only.len(), store_threshold * 100.0);
+    }
 
-    let pairs = db.get_similar_pairs(None, None, threshold)?;
+    let mut pairs = db.get_similar_pairs(None, None, threshold, cross_only)?;
+    pairs.retain(|p| !ignored.contains(&p.unit_a) && !ignored.contains(&p.unit_b));
 
-    let pairs: Vec<_> = if cross_only && project_ids.len() > 1 {
-        pairs.into_iter().filter(|p| {
-            let a_proj = units.iter().find(|u| u.qualified_name == p.unit_a).map(|u| u.project_id);
-            let b_proj = units.iter().find(|u| u.qualified_name == p.unit_b).map(|u| u.project_id);
-            a_proj != b_proj
-        }).collect()
-    } else {
-        pairs
-    };
+    let persisted: HashSet<(String, String)> = pairs.iter()
+        .map(|p| (p.unit_a.clone(), p.unit_b.clone()))
+        .collect();
+    for (unit_a, unit_b, similarity) in display_only {
+        if persisted.contains(&(unit_a.clone(), unit_b.clone())) {
+            continue;
+        }
+        let a = unit_by_name.get(unit_a.as_str());
+        let b = unit_by_name.get(unit_b.as_str());
+        pairs.push(SimilarPairRecord {
+            id: 0,
+            unit_a,
+            unit_b,
+            similarity,
+            status: PairStatus::New,
+            trigger_reason: None,
+            file_a: a.map(|u| u.file_path.clone()),
+            start_a: a.map(|u| u.range_start),
+            end_a: a.map(|u| u.range_end),
+            file_b: b.map(|u| u.file_path.clone()),
+            start_b: b.map(|u| u.range_start),
+            end_b: b.map(|u| u.range_end),
+            kind_a: a.map(|u| u.kind.clone()),
+            kind_b: b.map(|u| u.kind.clone()),
+            signature_a: a.and_then(|u| u.signature.clone()),
+            signature_b: b.and_then(|u| u.signature.clone()),
+            created_at: None,
+            updated_at: None,
+            status_reason: None,
+        });
+    }
+    pairs.sort_by(|a, b| {
+        b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.unit_a.cmp(&b.unit_a))
+            .then_with(|| a.unit_b.cmp(&b.unit_b))
+    });
 
     println!("\nFound {} similar pairs (threshold: {:.0}%)", pairs.len(), threshold * 100.0);
     println!("{}", "=".repeat(60));
@@ -372,9 +1620,17 @@ async fn cmd_scan(paths: &[String], all: bool, cross_only: bool, threshold: f32)
         let file_a = pair.file_a.as_ref().map(|f| Path::new(f).file_name().unwrap_or_default().to_string_lossy().to_string()).unwrap_or_default();
         let file_b = pair.file_b.as_ref().map(|f| Path::new(f).file_name().unwrap_or_default().to_string_lossy().to_string()).unwrap_or_default();
 
-        println!("\n[{}] {:.2}%", i + 1, pair.similarity * 100.0);
-        println!("  A: {}:{} {}", file_a, pair.start_a.unwrap_or(0), short_name(&pair.unit_a));
-        println!("  B: {}:{} {}", file_b, pair.start_b.unwrap_or(0), short_name(&pair.unit_b));
+        let distance = distances.get(&(pair.unit_a.clone(), pair.unit_b.clone()))
+            .copied()
+            .unwrap_or_else(|| pair.distance(index_metric));
+        println!("\n[{}] {}", i + 1, metric.format(pair.similarity, distance));
+        println!("  A: {}:{} {}", file_a, pair.start_a.unwrap_or(0), unit_label(&pair.unit_a, pair.signature_a.as_deref()));
+        println!("  B: {}:{} {}", file_b, pair.start_b.unwrap_or(0), unit_label(&pair.unit_b, pair.signature_b.as_deref()));
+        if explain {
+            if let (Some(a), Some(b)) = (unit_by_name.get(pair.unit_a.as_str()), unit_by_name.get(pair.unit_b.as_str())) {
+                println!("  Why: {}", explain_pair(a, b, pair.similarity));
+            }
+        }
     }
 
     if pairs.len() > 20 {
@@ -384,58 +1640,144 @@ async fn cmd_scan(paths: &[String], all: bool, cross_only: bool, threshold: f32)
     Ok(())
 }
 
-async fn cmd_compare(path_a: &str, lang_a: &str, path_b: &str, lang_b: &str, threshold: f32) -> anyhow::Result<()> {
+/// One-line summary of the facets behind a match
+fn explain_pair(a: &CodeUnitRecord, b: &CodeUnitRecord, similarity: f32) -> String {
+    let structure = if a.structure_hash == b.structure_hash { "same structure" } else { "different structure" };
+    let project = if a.project_id == b.project_id { "same project" } else { "cross-project" };
+    format!(
+        "similarity {:.3}, {}, {} vs {} lines, {}",
+        similarity,
+        structure,
+        line_count(a.range_start, a.range_end),
+        line_count(b.range_start, b.range_end),
+        project,
+    )
+}
+
+/// Write one similar pair as a JSONL record and flush so consumers see it immediately
+#[allow(clippy::too_many_arguments)]
+fn write_pair_jsonl(
+    out: &mut impl Write,
+    unit_a: &str,
+    unit_b: &str,
+    similarity: f32,
+    distance: f32,
+    stored: bool,
+    metric: ScoreMetric,
+    unit_by_name: &HashMap<&str, &CodeUnitRecord>,
+) -> anyhow::Result<()> {
+    #[derive(serde::Serialize)]
+    struct PairLine<'a> {
+        unit_a: &'a str,
+        unit_b: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        similarity: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        distance: Option<f32>,
+        file_a: Option<&'a str>,
+        line_a: Option<u32>,
+        file_b: Option<&'a str>,
+        line_b: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        signature_a: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        signature_b: Option<&'a str>,
+        stored: bool,
+    }
+
+    let a = unit_by_name.get(unit_a);
+    let b = unit_by_name.get(unit_b);
+    let line = PairLine {
+        unit_a,
+        unit_b,
+        similarity: (metric == ScoreMetric::Similarity).then_some(similarity),
+        distance: (metric == ScoreMetric::Distance).then_some(distance),
+        file_a: a.map(|u| u.file_path.as_str()),
+        line_a: a.map(|u| u.range_start),
+        file_b: b.map(|u| u.file_path.as_str()),
+        line_b: b.map(|u| u.range_start),
+        signature_a: a.and_then(|u| u.signature.as_deref()),
+        signature_b: b.and_then(|u| u.signature.as_deref()),
+        stored,
+    };
+    serde_json::to_writer(&mut *out, &line)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+struct CompareOptions<'a> {
+    threshold: f32,
+    model: &'a str,
+    backend: EmbedBackend,
+    model_path: Option<&'a Path>,
+    concurrency: usize,
+    persist: bool,
+    candidates: usize,
+    strip_comments: bool,
+    cross_normalize: bool,
+    progress: Progress,
+}
+
+async fn cmd_compare(path_a: &str, lang_a: &str, path_b: &str, lang_b: &str, opts: &CompareOptions<'_>) -> anyhow::Result<()> {
+    let CompareOptions { threshold, model, backend, model_path, concurrency, persist, candidates, strip_comments, cross_normalize, progress } = *opts;
     let t0 = Instant::now();
 
-    println!("Cross-project comparison (ANN):");
-    println!("  A: {} ({})", path_a, lang_a);
-    println!("  B: {} ({})", path_b, lang_b);
+    info!(progress, "Cross-project comparison (ANN):");
+    info!(progress, "  A: {} ({})", path_a, lang_a);
+    info!(progress, "  B: {} ({})", path_b, lang_b);
+    info!(progress, "  Model: {}", model);
 
-    let units_a = extract_functions_lsp(path_a, lang_a).await?;
-    println!("Project A: {} functions", units_a.len());
+    let mut units_a = extract_functions_lsp(path_a, lang_a, false, false, false, false, None).await?;
+    info!(progress, "Project A: {} functions", units_a.len());
 
-    let units_b = extract_functions_lsp(path_b, lang_b).await?;
-    println!("Project B: {} functions", units_b.len());
+    let mut units_b = extract_functions_lsp(path_b, lang_b, false, false, false, false, None).await?;
+    info!(progress, "Project B: {} functions", units_b.len());
+
+    if strip_comments {
+        strip_unit_comments(&mut units_a);
+        strip_unit_comments(&mut units_b);
+    }
+    if cross_normalize {
+        for unit in units_a.iter_mut().chain(units_b.iter_mut()) {
+            unit.body = CodeUnit::cross_normalize(&unit.body);
+        }
+    }
 
     if units_a.is_empty() || units_b.is_empty() {
         println!("At least one project has no functions");
         return Ok(());
     }
 
-    println!("\nGenerating embeddings...");
-    let mut embedder = OllamaEmbedding::new("bge-m3");
-    let mut all_embeddings: Vec<(usize, String, Vec<f32>, bool)> = Vec::new();
-
-    for (i, unit) in units_a.iter().enumerate() {
-        print!("\r  A: [{}/{}]", i + 1, units_a.len());
-        if let Ok(emb) = embedder.embed(&unit.body).await {
-            let vec: Vec<f32> = emb.as_slice().unwrap().to_vec();
-            all_embeddings.push((all_embeddings.len(), unit.qualified_name.clone(), vec, true));
+    info!(progress, "\nGenerating embeddings ({} concurrent)...", concurrency.max(1));
+    let all_embeddings = match backend {
+        EmbedBackend::Ollama => {
+            embed_projects(&OllamaEmbedding::new(model), &units_a, &units_b, concurrency, progress).await
         }
-    }
-    println!();
-
-    for (i, unit) in units_b.iter().enumerate() {
-        print!("\r  B: [{}/{}]", i + 1, units_b.len());
-        if let Ok(emb) = embedder.embed(&unit.body).await {
-            let vec: Vec<f32> = emb.as_slice().unwrap().to_vec();
-            all_embeddings.push((all_embeddings.len(), unit.qualified_name.clone(), vec, false));
+        #[cfg(feature = "onnx")]
+        EmbedBackend::Onnx => {
+            embed_projects(&load_onnx(model_path)?, &units_a, &units_b, concurrency, progress).await
         }
-    }
-    println!();
+        #[cfg(not(feature = "onnx"))]
+        EmbedBackend::Onnx => {
+            let _ = model_path;
+            anyhow::bail!(ONNX_UNAVAILABLE)
+        }
+    };
 
     if all_embeddings.len() < 2 {
         println!("Not enough valid embeddings");
         return Ok(());
     }
 
-    println!("Building ANN index...");
+    info!(progress, "Building ANN index...");
     let dimensions = all_embeddings[0].2.len();
     let config = VectorIndexConfig {
         dimensions,
         connectivity: 16,
         expansion_add: 128,
         expansion_search: 64,
+        ..Default::default()
     };
     let index = VectorIndex::new(config)?;
     index.reserve(all_embeddings.len())?;
@@ -444,8 +1786,7 @@ async fn cmd_compare(path_a: &str, lang_a: &str, path_b: &str, lang_b: &str, thr
         index.add(*idx as u64, emb)?;
     }
 
-    println!("Searching...");
-    let k = 50;
+    info!(progress, "Searching...");
     let mut cross_pairs: Vec<(String, String, f32)> = Vec::new();
     let mut seen: HashSet<(String, String)> = HashSet::new();
 
@@ -457,7 +1798,7 @@ async fn cmd_compare(path_a: &str, lang_a: &str, path_b: &str, lang_b: &str, thr
     for (_idx, name_a, emb, is_a) in &all_embeddings {
         if !*is_a { continue; }
 
-        let results = index.search_filtered(emb, k, |id| !project_a_indices.contains(&id))?;
+        let results = index.search_filtered(emb, candidates, |id| !project_a_indices.contains(&id))?;
 
         for result in results {
             let similarity = result.similarity();
@@ -476,7 +1817,11 @@ async fn cmd_compare(path_a: &str, lang_a: &str, path_b: &str, lang_b: &str, thr
         }
     }
 
-    cross_pairs.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+    cross_pairs.sort_by(|a, b| {
+        b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+            .then_with(|| a.1.cmp(&b.1))
+    });
 
     println!("\nFound {} cross-project pairs (threshold: {:.0}%, {:.2}s)",
         cross_pairs.len(), threshold * 100.0, t0.elapsed().as_secs_f32());
@@ -491,12 +1836,51 @@ async fn cmd_compare(path_a: &str, lang_a: &str, path_b: &str, lang_b: &str, thr
         println!("\n... {} more", cross_pairs.len() - 30);
     }
 
+    if persist {
+        persist_compare_pairs(path_a, path_b, &cross_pairs)?;
+    }
+
+    Ok(())
+}
+
+fn persist_compare_pairs(path_a: &str, path_b: &str, pairs: &[(String, String, f32)]) -> anyhow::Result<()> {
+    let db = ensure_db()?;
+
+    for path in [path_a, path_b] {
+        let canonical = PathBuf::from(path).canonicalize()?;
+        if db.get_project_by_path(canonical.to_str().unwrap())?.is_none() {
+            println!("\nNot persisting: {} is not indexed (run `akin index` first)", path);
+            return Ok(());
+        }
+    }
+
+    // Only keep pairs whose units both exist in the database
+    let mut known: HashMap<String, bool> = HashMap::new();
+    let mut exists = |name: &str| -> anyhow::Result<bool> {
+        if let Some(&e) = known.get(name) {
+            return Ok(e);
+        }
+        let e = db.get_code_unit(name)?.is_some();
+        known.insert(name.to_string(), e);
+        Ok(e)
+    };
+
+    let mut stored = Vec::new();
+    for (a, b, similarity) in pairs {
+        if exists(a)? && exists(b)? {
+            stored.push((a.clone(), b.clone(), *similarity));
+        }
+    }
+
+    let count = db.batch_upsert_similar_pairs(&stored, Some("compare"))?;
+    println!("\nPersisted {} pairs ({} skipped: units not in database)", count, pairs.len() - stored.len());
+
     Ok(())
 }
 
 fn cmd_status(path: &str) -> anyhow::Result<()> {
     let project_path = PathBuf::from(path).canonicalize()?;
-    let db = ensure_db()?;
+    let db = open_db_readonly()?;
 
     match db.get_project_by_path(project_path.to_str().unwrap())? {
         Some(project) => {
@@ -519,8 +1903,33 @@ fn cmd_status(path: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn cmd_remove(path: &str, index: Option<&Path>) -> anyhow::Result<()> {
+    // Abandoned projects are often already gone from disk; fall back to the path as given
+    let project_path = PathBuf::from(path).canonicalize().unwrap_or_else(|_| PathBuf::from(path));
+    let mut store = open_store(index, false)?;
+
+    match store.db().get_project_by_path(&project_path.to_string_lossy())? {
+        Some(project) => {
+            let removed = store.remove_project(project.id)?;
+            println!("Removed project {}: {} code units", project.name, removed);
+        }
+        None => println!("Project not indexed: {}", project_path.display()),
+    }
+    Ok(())
+}
+
+fn cmd_import(source: &Path, index: Option<&Path>) -> anyhow::Result<()> {
+    let mut store = open_store(index, true)?;
+    let imported = store.import_from(source)?;
+    println!("Imported {} code units from {}", imported, source.display());
+    if imported > 0 {
+        println!("Similar pairs are not imported; run `akin scan` to find them");
+    }
+    Ok(())
+}
+
 fn cmd_projects() -> anyhow::Result<()> {
-    let db = ensure_db()?;
+    let db = open_db_readonly()?;
     let projects = db.get_all_projects()?;
 
     if projects.is_empty() {
@@ -538,12 +1947,26 @@ fn cmd_projects() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn cmd_pairs(status: &str, limit: usize) -> anyhow::Result<()> {
-    let db = ensure_db()?;
-    let pair_status = PairStatus::from_str(status)
+fn cmd_pairs(status: &str, limit: usize, project: Option<&str>, kind: Option<&str>, metric: ScoreMetric) -> anyhow::Result<()> {
+    let db = open_db_readonly()?;
+    let index_metric = IndexMetadata::load(&db_path()).map(|meta| meta.metric).unwrap_or_default();
+    let pair_status = PairStatus::parse(status)
         .ok_or_else(|| anyhow::anyhow!("Invalid status: {}", status))?;
 
-    let pairs = db.get_similar_pairs(None, Some(pair_status), 0.0)?;
+    let project_id = match project {
+        Some(p) => {
+            let project_path = PathBuf::from(p).canonicalize()?;
+            let proj = db.get_project_by_path(project_path.to_str().unwrap())?
+                .ok_or_else(|| anyhow::anyhow!("Project not indexed: {}", p))?;
+            Some(proj.id)
+        }
+        None => None,
+    };
+
+    let mut pairs = db.get_similar_pairs(project_id, Some(pair_status), 0.0, false)?;
+    if let Some(kind) = kind {
+        pairs.retain(|p| p.both_kind(kind));
+    }
 
     println!("Similar pairs (status: {}):\n", status);
 
@@ -551,9 +1974,13 @@ fn cmd_pairs(status: &str, limit: usize) -> anyhow::Result<()> {
         let file_a = pair.file_a.as_ref().map(|f| Path::new(f).file_name().unwrap_or_default().to_string_lossy().to_string()).unwrap_or_default();
         let file_b = pair.file_b.as_ref().map(|f| Path::new(f).file_name().unwrap_or_default().to_string_lossy().to_string()).unwrap_or_default();
 
-        println!("[{}] {:.2}%", pair.id, pair.similarity * 100.0);
-        println!("  A: {}:{} {}", file_a, pair.start_a.unwrap_or(0), short_name(&pair.unit_a));
-        println!("  B: {}:{} {}", file_b, pair.start_b.unwrap_or(0), short_name(&pair.unit_b));
+        let first_seen = pair.created_at.as_ref().map(|t| format!("  first seen {} UTC", t)).unwrap_or_default();
+        println!("[{}] {}{}", pair.id, metric.format(pair.similarity, pair.distance(index_metric)), first_seen);
+        println!("  A: {}:{} {}", file_a, pair.start_a.unwrap_or(0), unit_label(&pair.unit_a, pair.signature_a.as_deref()));
+        println!("  B: {}:{} {}", file_b, pair.start_b.unwrap_or(0), unit_label(&pair.unit_b, pair.signature_b.as_deref()));
+        if let Some(reason) = &pair.status_reason {
+            println!("  Reason: {}", reason);
+        }
         println!();
     }
 
@@ -563,25 +1990,230 @@ fn cmd_pairs(status: &str, limit: usize) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn cmd_ignore(unit_a: &str, unit_b: &str, _reason: Option<&str>) -> anyhow::Result<()> {
-    let db = ensure_db()?;
-    let (a, b) = if unit_a < unit_b { (unit_a, unit_b) } else { (unit_b, unit_a) };
+fn cmd_clusters(threshold: f32, min_size: usize, candidates: usize) -> anyhow::Result<()> {
+    let mut store = ensure_store()?;
+    store.ensure_vector_index()?;
+    let clusters = store.cluster(candidates, threshold, min_size)?;
+
+    println!("Found {} clusters (>= {} functions, >= {:.0}%):\n", clusters.len(), min_size, threshold * 100.0);
+
+    for (i, cluster) in clusters.iter().enumerate() {
+        println!("[{}] {} functions", i + 1, cluster.len());
+        for name in cluster {
+            match store.db().get_code_unit(name)? {
+                Some(unit) => {
+                    let file = Path::new(&unit.file_path).file_name().unwrap_or_default().to_string_lossy();
+                    println!("  {}:{} {}", file, unit.range_start, short_name(name));
+                }
+                None => println!("  {}", short_name(name)),
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+async fn cmd_similar(
+    file: &Path,
+    model: Option<String>,
+    backend: EmbedBackend,
+    model_path: Option<&Path>,
+    index: Option<&Path>,
+    config: &HookConfig,
+) -> anyhow::Result<()> {
+    let file = file.canonicalize()?;
+    let file_str = file.to_string_lossy().to_string();
+    let content = std::fs::read_to_string(&file)?;
+
+    let mut store = open_store(index, false)?;
+    store.ensure_vector_index()?;
+
+    let model = model
+        .or_else(|| store.index_metadata().and_then(|meta| meta.model.clone()))
+        .unwrap_or_else(default_model);
+
+    let mut parser = CodeParser::new();
+    let mut units = parser.extract_functions(&content, &file_str, config.min_lines_for(get_language(&file_str)));
+    CodeUnit::drop_empty_bodies(&mut units);
+    if units.is_empty() {
+        println!("No functions found in {}", file.display());
+        return Ok(());
+    }
+
+    // New code is the query, indexed functions are passages
+    let mut embedder = create_embedder(backend, &model, model_path, DEFAULT_RETRIES, EmbedRole::Query)?;
+    let ignore = file.parent().map(IgnoreSet::discover).unwrap_or_default();
+    let matches = find_similar_units_ann(&store, embedder.as_mut(), &units, None, config, &ignore).await?;
+
+    if matches.is_empty() {
+        println!("No similar code found for {} functions (>= {:.0}%)", units.len(), config.threshold * 100.0);
+        return Ok(());
+    }
+
+    for m in &matches {
+        let similar_file = Path::new(&m.similar_file).file_name().unwrap_or_default().to_string_lossy();
+        println!(
+            "[{:.1}%] {}:{} {} <-> {}:{} {}",
+            m.similarity * 100.0,
+            file.file_name().unwrap_or_default().to_string_lossy(),
+            m.current_line,
+            short_name(&m.current_name),
+            similar_file,
+            m.similar_line,
+            short_name(&m.similar_name),
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_grep(query: &str, limit: usize, project: Option<&str>, raw: bool) -> anyhow::Result<()> {
+    let db = open_db_readonly()?;
+
+    let project_id = match project {
+        Some(p) => {
+            let project_path = PathBuf::from(p).canonicalize()?;
+            let proj = db.get_project_by_path(project_path.to_str().unwrap())?
+                .ok_or_else(|| anyhow::anyhow!("Project not indexed: {}", p))?;
+            Some(proj.id)
+        }
+        None => None,
+    };
 
-    let pairs = db.get_similar_pairs(None, None, 0.0)?;
-    let pair = pairs.iter().find(|p| p.unit_a == a && p.unit_b == b);
+    // A phrase query, so snippets like `foo.bar(` are not parsed as FTS5 syntax
+    let fts_query = if raw { query.to_string() } else { format!("\"{}\"", query.replace('"', "\"\"")) };
+    let matches = db.search_code_bodies(&fts_query, project_id, limit)?;
 
-    match pair {
+    if matches.is_empty() {
+        if db.count_code_bodies()? == 0 {
+            println!("No function bodies stored. Run `akin index --store-bodies` first.");
+        } else {
+            println!("No matches.");
+        }
+        return Ok(());
+    }
+
+    for m in &matches {
+        println!("{:>6.2}  {}:{}  {}", m.score, m.file_path, m.range_start, m.qualified_name);
+    }
+    Ok(())
+}
+
+fn cmd_ignore(unit_a: &str, unit_b: &str, reason: Option<&str>) -> anyhow::Result<()> {
+    let db = ensure_db()?;
+
+    match set_pair_status(&db, unit_a, unit_b, PairStatus::Ignored, reason)? {
         Some(p) => {
-            db.update_pair_status(p.id, PairStatus::Ignored)?;
             println!("Ignored pair ({:.2}%):", p.similarity * 100.0);
-            println!("  A: {}", a);
-            println!("  B: {}", b);
+            println!("  A: {}", p.unit_a);
+            println!("  B: {}", p.unit_b);
         }
         None => println!("Pair not found."),
     }
     Ok(())
 }
 
+fn cmd_mark(unit_a: &str, unit_b: &str, status: &str) -> anyhow::Result<()> {
+    let status = PairStatus::parse(status).ok_or_else(|| {
+        anyhow::anyhow!("Invalid status: {} (expected new, ignored, confirmed or redundant)", status)
+    })?;
+    let db = ensure_db()?;
+
+    match set_pair_status(&db, unit_a, unit_b, status, None)? {
+        Some(p) => {
+            println!("Pair ({:.2}%): {} -> {}", p.similarity * 100.0, p.status.as_str(), status.as_str());
+            println!("  A: {}", p.unit_a);
+            println!("  B: {}", p.unit_b);
+        }
+        None => println!("Pair not found."),
+    }
+    Ok(())
+}
+
+/// Update a pair's status; returns the pair as it was before the update
+fn set_pair_status(db: &Database, unit_a: &str, unit_b: &str, status: PairStatus, reason: Option<&str>) -> anyhow::Result<Option<SimilarPairRecord>> {
+    let (a, b) = if unit_a < unit_b { (unit_a, unit_b) } else { (unit_b, unit_a) };
+
+    let pair = db.get_similar_pairs(None, None, 0.0, false)?
+        .into_iter()
+        .find(|p| p.unit_a == a && p.unit_b == b);
+    if let Some(p) = &pair {
+        db.update_pair_status_with_reason(p.id, status, reason)?;
+    }
+    Ok(pair)
+}
+
+fn cmd_ignore_pattern(pattern: &str, either: bool, reason: Option<&str>) -> anyhow::Result<()> {
+    let db = ensure_db()?;
+    let count = db.bulk_update_pair_status_by_file(pattern, PairStatus::Ignored, either, reason)?;
+    let scope = if either { "either" } else { "both" };
+    println!("Ignored {} pairs ({} files matching '{}')", count, scope, pattern);
+    Ok(())
+}
+
+fn cmd_compact() -> anyhow::Result<()> {
+    let db = ensure_db()?;
+
+    let pruned = db.prune_orphan_pairs()?;
+    println!("Pruned {} orphaned pairs", pruned);
+
+    db.vacuum()?;
+    println!("Database compacted");
+
+    Ok(())
+}
+
+fn cmd_verify(fix: bool) -> anyhow::Result<()> {
+    let mut store = ensure_store()?;
+    let report = store.verify()?;
+
+    if !report.index_loaded {
+        println!("Vector index: not found");
+    }
+    println!("Units with embeddings: {}", report.units_with_embeddings);
+    println!("Vector index entries: {} ({} dimensions)", report.index_size, report.index_dimensions);
+
+    if report.is_consistent() {
+        println!("OK: database and vector index are consistent");
+        return Ok(());
+    }
+
+    const SHOWN: usize = 10;
+    if !report.missing_vectors.is_empty() {
+        println!("\nUnits missing from the vector index: {}", report.missing_vectors.len());
+        for name in report.missing_vectors.iter().take(SHOWN) {
+            println!("  {}", format_name(name));
+        }
+    }
+    if !report.orphan_vectors.is_empty() {
+        println!("\nVectors without a unit: {}", report.orphan_vectors.len());
+        let ids: Vec<String> = report.orphan_vectors.iter().take(SHOWN).map(|id| id.to_string()).collect();
+        println!("  ids: {}", ids.join(", "));
+    }
+    if !report.dimension_mismatches.is_empty() {
+        println!("\nEmbeddings with wrong dimensions (expected {}): {}",
+            report.index_dimensions, report.dimension_mismatches.len());
+        for (name, dims) in report.dimension_mismatches.iter().take(SHOWN) {
+            println!("  {} ({})", format_name(name), dims);
+        }
+    }
+
+    if !fix {
+        println!("\nRun with --fix to rebuild the vector index");
+        return Ok(());
+    }
+
+    if !report.dimension_mismatches.is_empty() {
+        anyhow::bail!("Cannot rebuild: re-index the projects above with the model used for the rest of the index");
+    }
+
+    println!("\nRebuilding vector index...");
+    let indexed = store.rebuild_vector_index()?;
+    println!("Rebuilt with {} embeddings", indexed);
+    Ok(())
+}
+
 fn cmd_group_create(name: &str, reason: &str, pattern: Option<&str>, project: Option<&str>) -> anyhow::Result<()> {
     let db = ensure_db()?;
     let project_path = match project {
@@ -618,7 +2250,7 @@ fn cmd_group_add(group_id: i64, qualified_names: &[String]) -> anyhow::Result<()
 }
 
 fn cmd_group_list(project: Option<&str>) -> anyhow::Result<()> {
-    let db = ensure_db()?;
+    let db = open_db_readonly()?;
 
     let groups = if let Some(p) = project {
         let project_path = PathBuf::from(p).canonicalize()?;
@@ -652,6 +2284,13 @@ fn cmd_group_list(project: Option<&str>) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn cmd_cache_clear() -> anyhow::Result<()> {
+    let cache = EmbeddingCache::default();
+    let removed = cache.clear()?;
+    println!("Removed {} cached embeddings from {}", removed, cache.dir().display());
+    Ok(())
+}
+
 fn cmd_group_members(group_id: i64) -> anyhow::Result<()> {
     let db = ensure_db()?;
     let all_units = db.get_code_units_by_projects(None)?;
@@ -674,47 +2313,162 @@ fn cmd_group_members(group_id: i64) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn extract_functions_lsp(path: &str, lang: &str) -> anyhow::Result<Vec<CodeUnit>> {
+/// Extract units from the whole project, or only from `files`
+async fn extract_functions_lsp(path: &str, lang: &str, signatures: bool, include_tests: bool, sfc: bool, include_types: bool, files: Option<&[String]>) -> anyhow::Result<Vec<CodeUnit>> {
+    let follow = FOLLOW_SYMLINKS.load(Ordering::Relaxed);
     match lang {
-        "rust" => {
-            let mut adapter = RustAdapter::new(path);
-            adapter.start().await?;
-            let units = adapter.get_functions().await?;
-            adapter.stop()?;
-            Ok(units)
-        }
+        "rust" => run_adapter(RustAdapter::new(path).with_include_types(include_types).with_follow_symlinks(follow), signatures, files).await,
         "swift" => {
-            let mut adapter = SwiftAdapter::new(path);
-            adapter.start().await?;
-            let units = adapter.get_functions().await?;
-            adapter.stop()?;
-            Ok(units)
+            let adapter = SwiftAdapter::new(path)
+                .with_include_tests(include_tests)
+                .with_include_types(include_types)
+                .with_follow_symlinks(follow);
+            run_adapter(adapter, signatures, files).await
         }
         "typescript" | "ts" => {
-            let mut adapter = TypeScriptAdapter::new(path);
-            adapter.start().await?;
-            let units = adapter.get_functions().await?;
-            adapter.stop()?;
-            Ok(units)
-        }
-        "vue" => {
-            let mut adapter = VueAdapter::new(path);
-            adapter.start().await?;
-            let units = adapter.get_functions().await?;
-            adapter.stop()?;
-            Ok(units)
-        }
-        "java" => {
-            let mut adapter = JavaAdapter::new(path);
-            adapter.start().await?;
-            let units = adapter.get_functions().await?;
-            adapter.stop()?;
-            Ok(units)
+            let adapter = TypeScriptAdapter::new(path)
+                .with_sfc(sfc)
+                .with_include_types(include_types)
+                .with_follow_symlinks(follow);
+            run_adapter(adapter, signatures, files).await
         }
+        "vue" => run_adapter(VueAdapter::new(path).with_include_types(include_types).with_follow_symlinks(follow), signatures, files).await,
+        "java" => run_adapter(JavaAdapter::new(path).with_include_types(include_types).with_follow_symlinks(follow), signatures, files).await,
+        "kotlin" | "kt" => run_adapter(KotlinAdapter::new(path).with_include_types(include_types).with_follow_symlinks(follow), signatures, files).await,
+        "go" => run_adapter(GoAdapter::new(path).with_include_types(include_types).with_follow_symlinks(follow), signatures, files).await,
         _ => anyhow::bail!("Unsupported language: {}", lang),
     }
 }
 
+/// Keep the `max` largest units of each file, preserving order otherwise.
+///
+/// Returns the kept units, the number skipped and the number of files that were capped.
+fn cap_units_per_file(units: Vec<CodeUnit>, max: usize) -> (Vec<CodeUnit>, usize, usize) {
+    let mut dropped: HashSet<usize> = HashSet::new();
+    let mut files = 0;
+    {
+        let mut by_file: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, unit) in units.iter().enumerate() {
+            by_file.entry(unit.file_path.as_str()).or_default().push(i);
+        }
+
+        for indices in by_file.values_mut().filter(|indices| indices.len() > max) {
+            indices.sort_by_key(|&i| std::cmp::Reverse(units[i].range_end.saturating_sub(units[i].range_start)));
+            dropped.extend(indices.iter().skip(max).copied());
+            files += 1;
+        }
+    }
+
+    let skipped = dropped.len();
+    let kept = units.into_iter()
+        .enumerate()
+        .filter(|(i, _)| !dropped.contains(i))
+        .map(|(_, unit)| unit)
+        .collect();
+    (kept, skipped, files)
+}
+
+/// Embed logic only: hashes and embeddings are computed from the stripped body
+fn strip_unit_comments(units: &mut [CodeUnit]) {
+    for unit in units {
+        unit.body = CodeUnit::strip_comments(&unit.body);
+    }
+}
+
+async fn run_adapter<A: LanguageAdapter>(mut adapter: A, signatures: bool, files: Option<&[String]>) -> anyhow::Result<Vec<CodeUnit>> {
+    adapter.start().await?;
+    let mut units = match files {
+        Some(files) => adapter.get_functions_in(files).await?,
+        None => adapter.get_functions().await?,
+    };
+    if signatures {
+        let attached = attach_signatures(&mut adapter, &mut units, true).await;
+        println!("Signatures: {}/{} functions", attached, units.len());
+    }
+    adapter.stop()?;
+    Ok(units)
+}
+
+#[cfg(not(feature = "onnx"))]
+const ONNX_UNAVAILABLE: &str = "ONNX backend not available: rebuild with `--features onnx`";
+
+fn create_embedder(backend: EmbedBackend, model: &str, model_path: Option<&Path>, retries: u32, role: EmbedRole) -> anyhow::Result<Box<dyn Embedder>> {
+    match backend {
+        EmbedBackend::Ollama => Ok(Box::new(OllamaEmbedding::new(model).with_retries(retries).with_role(role))),
+        #[cfg(feature = "onnx")]
+        EmbedBackend::Onnx => Ok(Box::new(load_onnx(model_path)?)),
+        #[cfg(not(feature = "onnx"))]
+        EmbedBackend::Onnx => {
+            let _ = model_path;
+            anyhow::bail!(ONNX_UNAVAILABLE)
+        }
+    }
+}
+
+#[cfg(feature = "onnx")]
+fn load_onnx(model_path: Option<&Path>) -> anyhow::Result<akin::OnnxEmbedding> {
+    let dir = model_path.ok_or_else(|| anyhow::anyhow!("--backend onnx requires --model-path <dir>"))?;
+    Ok(akin::OnnxEmbedding::load(dir)?)
+}
+
+/// Embed both projects; returns (index, name, vector, is_project_a)
+async fn embed_projects<E: Embedder + Clone + Sync>(
+    embedder: &E,
+    units_a: &[CodeUnit],
+    units_b: &[CodeUnit],
+    concurrency: usize,
+    progress: Progress,
+) -> Vec<(usize, String, Vec<f32>, bool)> {
+    let mut all_embeddings = Vec::new();
+
+    for (unit_idx, vec) in embed_concurrent(embedder, units_a, concurrency, "A", progress).await {
+        all_embeddings.push((all_embeddings.len(), units_a[unit_idx].qualified_name.clone(), vec, true));
+    }
+
+    for (unit_idx, vec) in embed_concurrent(embedder, units_b, concurrency, "B", progress).await {
+        all_embeddings.push((all_embeddings.len(), units_b[unit_idx].qualified_name.clone(), vec, false));
+    }
+
+    all_embeddings
+}
+
+/// Embed units with at most `concurrency` requests in flight.
+///
+/// Returns `(unit index, embedding)` sorted by unit index; failures are logged and skipped.
+async fn embed_concurrent<E: Embedder + Clone + Sync>(
+    embedder: &E,
+    units: &[CodeUnit],
+    concurrency: usize,
+    label: &str,
+    progress: Progress,
+) -> Vec<(usize, Vec<f32>)> {
+    let mut stream = stream::iter(units.iter().enumerate())
+        .map(|(i, unit)| {
+            let mut embedder = embedder.clone();
+            async move { (i, embedder.embed(&unit.body).await) }
+        })
+        .buffer_unordered(concurrency.max(1));
+
+    let mut results = Vec::with_capacity(units.len());
+    let mut done = 0;
+    while let Some((i, result)) = stream.next().await {
+        done += 1;
+        progress.update(format_args!("  {}: [{}/{}]", label, done, units.len()));
+        match result {
+            Ok(emb) => results.push((i, emb.to_vec())),
+            Err(e) => eprintln!("\nWarning: {}: {}", units[i].qualified_name, e),
+        }
+    }
+    progress.finish();
+
+    results.sort_by_key(|(i, _)| *i);
+    results
+}
+
+fn parse_min_lines(spec: &str) -> Result<MinLines, String> {
+    MinLines::parse(spec, 3)
+}
+
 fn compute_hash(content: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(content.as_bytes());
@@ -731,19 +2485,14 @@ fn compute_structure_hash(content: &str) -> String {
 }
 
 fn short_name(name: &str) -> String {
-    name.split("::").last().unwrap_or(name).to_string()
+    QualifiedName::parse(name).short().to_string()
+}
+
+/// Signature when one was extracted, otherwise the short name
+fn unit_label(name: &str, signature: Option<&str>) -> String {
+    signature.map(str::to_string).unwrap_or_else(|| short_name(name))
 }
 
 fn format_name(name: &str) -> String {
-    let parts: Vec<&str> = name.splitn(2, "::").collect();
-    if parts.len() == 2 {
-        let file_part = parts[0];
-        let func_part = parts[1];
-        let file_name = file_part.rsplit('/').next().unwrap_or(file_part)
-            .trim_start_matches("swift:")
-            .trim_start_matches("rust:");
-        format!("{} ({})", func_part, file_name)
-    } else {
-        name.to_string()
-    }
+    QualifiedName::parse(name).display()
 }
@@ -1,9 +1,28 @@
 //! arch subcommand - architecture analysis
 
-use arch::{ArchitectureAnalyzer, MermaidGenerator, CallDirection};
-use clap::Subcommand;
-use lsp::{LanguageAdapter, RustAdapter, SwiftAdapter, TypeScriptAdapter, VueAdapter, JavaAdapter};
+use arch::{analyze_file, analyze_project_with, AnalyzeOptions, ArchitectureAnalyzer, CallGraphExport, CallTreeNode, Direction, DotGenerator, GraphMlGenerator, MermaidGenerator, NodeSelection, CallDirection, CentralityMetric, DeadCodeSort};
+use clap::{Args, Subcommand};
+use lsp::{FunctionNode, FunctionRef, QualifiedName};
+use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by --follow-symlinks; applies to source file collection for every language
+static FOLLOW_SYMLINKS: AtomicBool = AtomicBool::new(false);
+/// Set by --sfc; TypeScript only
+static SFC: AtomicBool = AtomicBool::new(false);
+
+#[derive(Args)]
+pub struct ArchArgs {
+    /// Follow symbolic links when collecting source files (loops are detected)
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+    /// TypeScript: also analyze <script> blocks of .vue/.svelte files (their functions have no call edges)
+    #[arg(long, global = true)]
+    sfc: bool,
+    #[command(subcommand)]
+    command: ArchCommands,
+}
 
 #[derive(Subcommand)]
 pub enum ArchCommands {
@@ -11,26 +30,65 @@ pub enum ArchCommands {
     Diagram {
         /// Project path
         path: String,
-        /// Language (rust, swift, typescript/ts, vue, java)
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
         #[arg(short, long, default_value = "rust")]
         lang: String,
         /// Generate module-level diagram
-        #[arg(short, long)]
+        #[arg(short, long, visible_alias = "collapse-modules")]
         module: bool,
+        /// With --module, keep the N most-connected modules and merge the rest into one node
+        #[arg(long, value_name = "N", requires = "module")]
+        top_modules: Option<usize>,
         /// Max nodes
         #[arg(long, default_value = "100")]
         max_nodes: usize,
+        /// Output format: mermaid or dot (Graphviz)
+        #[arg(long, default_value = "mermaid")]
+        format: String,
+        /// Layout direction: TD (top-down), LR (left-right), BT, RL
+        #[arg(long, default_value = "TD")]
+        direction: String,
         /// Output file
         #[arg(short, long)]
         output: Option<String>,
+        /// Write a JSON manifest of included and truncated functions to this file
+        #[arg(long, value_name = "FILE", conflicts_with = "module")]
+        manifest: Option<String>,
+        /// Mark unreferenced functions with a red dashed border
+        #[arg(long, conflicts_with = "module")]
+        highlight_dead: bool,
+        /// Group functions into one subgraph per module (mermaid only)
+        #[arg(long, conflicts_with = "module")]
+        cluster: bool,
     },
     /// Detect dead code
     DeadCode {
         /// Project path
         path: String,
-        /// Language (rust, swift, typescript/ts, vue, java)
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
         #[arg(short, long, default_value = "rust")]
         lang: String,
+        /// Sort order: file (file then line), size (largest first), name
+        #[arg(long, default_value = "file")]
+        sort: String,
+        /// Never report methods; their callers are often hidden by trait/dynamic dispatch
+        #[arg(long)]
+        methods_as_live: bool,
+        /// Extra entry-point name pattern, matched on `_`-delimited segments (repeatable; `foo_` = prefix, `_foo` = suffix)
+        #[arg(long = "entry-pattern")]
+        entry_patterns: Vec<String>,
+        /// Report everything not reachable from the roots, including dead subgraphs and cycles
+        #[arg(long)]
+        unreachable: bool,
+        /// Root function for --unreachable (repeatable; default: detected entry points)
+        #[arg(long = "root", requires = "unreachable")]
+        roots: Vec<String>,
+        /// Group results by file (JSON: object keyed by file)
+        #[arg(long)]
+        group_by_file: bool,
+        /// Show each function's signature line instead of its name
+        #[arg(long)]
+        show_signature: bool,
         /// JSON output
         #[arg(long)]
         json: bool,
@@ -41,7 +99,7 @@ pub enum ArchCommands {
         path: String,
         /// Entry function name
         entry: String,
-        /// Language (rust, swift, typescript/ts, vue, java)
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
         #[arg(short, long, default_value = "rust")]
         lang: String,
         /// Max depth
@@ -50,142 +108,368 @@ pub enum ArchCommands {
         /// Show callers (default: callees)
         #[arg(short, long)]
         incoming: bool,
+        /// Print qualified names instead of short names
+        #[arg(long)]
+        full_names: bool,
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate call tree for a single file without indexing the whole project
+    ///
+    /// Only the file's own functions are graphed; cross-file edges may be incomplete
+    CallTreeFile {
+        /// Source file
+        file: String,
+        /// Entry function name
+        entry: String,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// Max depth
+        #[arg(short, long, default_value = "5")]
+        depth: usize,
+        /// Show callers (default: callees)
+        #[arg(short, long)]
+        incoming: bool,
+        /// Print qualified names instead of short names
+        #[arg(long)]
+        full_names: bool,
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// List functions reachable from entry points
+    Reachable {
+        /// Project path
+        path: String,
+        /// Entry function names (repeatable)
+        #[arg(long, required = true)]
+        from: Vec<String>,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// List functions NOT reachable from the entries instead
+        #[arg(short, long)]
+        unreachable: bool,
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rank the busiest functions in the call graph
+    HotPaths {
+        /// Project path
+        path: String,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// Number of functions to show
+        #[arg(long, default_value = "20")]
+        top: usize,
+        /// Centrality metric: fan (fan_in * fan_out) or betweenness (shortest call paths through the node)
+        #[arg(long, default_value = "fan")]
+        metric: String,
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Per-function fan-in, fan-out and centrality (fan_in * fan_out)
+    Metrics {
+        /// Project path
+        path: String,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// Number of functions to show
+        #[arg(long, default_value = "20")]
+        top: usize,
         /// JSON output
         #[arg(long)]
         json: bool,
     },
+    /// Find recursive and mutually recursive call chains
+    Cycles {
+        /// Project path
+        path: String,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Split functions into root, intermediate and leaf tiers
+    Layers {
+        /// Project path
+        path: String,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export the full call graph (GraphML for Gephi/Cytoscape, or JSON for scripts)
+    #[command(visible_alias = "export")]
+    Graph {
+        /// Project path
+        path: String,
+        /// Language (rust, swift, typescript/ts, vue, java, kotlin/kt, go)
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// Output format: graphml or json
+        #[arg(long, default_value = "graphml")]
+        format: String,
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 }
 
-pub async fn run(cmd: ArchCommands) -> anyhow::Result<()> {
-    match cmd {
-        ArchCommands::Diagram { path, lang, module, max_nodes, output } => {
-            cmd_diagram(&path, &lang, module, max_nodes, output.as_deref()).await
+pub async fn run(args: ArchArgs) -> anyhow::Result<()> {
+    FOLLOW_SYMLINKS.store(args.follow_symlinks, Ordering::Relaxed);
+    SFC.store(args.sfc, Ordering::Relaxed);
+
+    match args.command {
+        ArchCommands::Diagram { path, lang, module, top_modules, max_nodes, format, direction, output, manifest, highlight_dead, cluster } => {
+            let opts = DiagramOptions {
+                module,
+                top_modules,
+                max_nodes,
+                format: &format,
+                direction: &direction,
+                output: output.as_deref(),
+                manifest: manifest.as_deref(),
+                highlight_dead,
+                cluster,
+            };
+            cmd_diagram(&path, &lang, &opts).await
+        }
+        ArchCommands::DeadCode { path, lang, sort, methods_as_live, entry_patterns, unreachable, roots, group_by_file, show_signature, json } => {
+            let opts = DeadCodeOptions {
+                sort: &sort,
+                methods_as_live,
+                entry_patterns: &entry_patterns,
+                unreachable,
+                roots: &roots,
+                group_by_file,
+                show_signature,
+                json,
+            };
+            cmd_dead_code(&path, &lang, &opts).await
+        }
+        ArchCommands::CallTree { path, entry, lang, depth, incoming, full_names, json } => {
+            cmd_call_tree(&path, &entry, &lang, depth, incoming, full_names, json).await
+        }
+        ArchCommands::CallTreeFile { file, entry, lang, depth, incoming, full_names, json } => {
+            cmd_call_tree_file(&file, &entry, &lang, depth, incoming, full_names, json).await
+        }
+        ArchCommands::Reachable { path, from, lang, unreachable, json } => {
+            cmd_reachable(&path, &from, &lang, unreachable, json).await
+        }
+        ArchCommands::HotPaths { path, lang, top, metric, json } => {
+            cmd_hot_paths(&path, &lang, top, &metric, json).await
         }
-        ArchCommands::DeadCode { path, lang, json } => {
-            cmd_dead_code(&path, &lang, json).await
+        ArchCommands::Metrics { path, lang, top, json } => {
+            cmd_metrics(&path, &lang, top, json).await
         }
-        ArchCommands::CallTree { path, entry, lang, depth, incoming, json } => {
-            cmd_call_tree(&path, &entry, &lang, depth, incoming, json).await
+        ArchCommands::Cycles { path, lang, json } => {
+            cmd_cycles(&path, &lang, json).await
+        }
+        ArchCommands::Layers { path, lang, json } => {
+            cmd_layers(&path, &lang, json).await
+        }
+        ArchCommands::Graph { path, lang, format, output } => {
+            cmd_graph(&path, &lang, &format, output.as_deref()).await
         }
     }
 }
 
-async fn cmd_diagram(path: &str, lang: &str, module: bool, max_nodes: usize, output: Option<&str>) -> anyhow::Result<()> {
+async fn analyze(path: &str, lang: &str) -> anyhow::Result<ArchitectureAnalyzer> {
+    let opts = AnalyzeOptions {
+        follow_symlinks: FOLLOW_SYMLINKS.load(Ordering::Relaxed),
+        sfc: SFC.load(Ordering::Relaxed),
+    };
+    Ok(analyze_project_with(path, lang, opts).await?)
+}
+
+struct DiagramOptions<'a> {
+    module: bool,
+    top_modules: Option<usize>,
+    max_nodes: usize,
+    format: &'a str,
+    direction: &'a str,
+    output: Option<&'a str>,
+    manifest: Option<&'a str>,
+    highlight_dead: bool,
+    cluster: bool,
+}
+
+async fn cmd_diagram(path: &str, lang: &str, opts: &DiagramOptions<'_>) -> anyhow::Result<()> {
+    let DiagramOptions { module, top_modules, max_nodes, format, direction, output, manifest, highlight_dead, cluster } = *opts;
+    if !matches!(format, "mermaid" | "dot") {
+        anyhow::bail!("Unknown diagram format: {} (expected mermaid or dot)", format);
+    }
+    let direction = Direction::parse(direction)
+        .ok_or_else(|| anyhow::anyhow!("Unknown direction: {} (expected TD, LR, BT or RL)", direction))?;
+    if cluster && format != "mermaid" {
+        anyhow::bail!("--cluster is only supported with --format mermaid");
+    }
+
     let project_path = PathBuf::from(path).canonicalize()?;
     println!("Analyzing: {}", project_path.display());
 
-    let mut analyzer = ArchitectureAnalyzer::new();
-
     println!("Building call graph...");
-    match lang {
-        "rust" => {
-            let mut adapter = RustAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "swift" => {
-            let mut adapter = SwiftAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "typescript" | "ts" => {
-            let mut adapter = TypeScriptAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "vue" => {
-            let mut adapter = VueAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "java" => {
-            let mut adapter = JavaAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        _ => anyhow::bail!("Unsupported language: {}", lang),
-    }
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
 
-    let generator = MermaidGenerator::new().with_max_nodes(max_nodes);
+    let dead: HashSet<FunctionRef> = if highlight_dead {
+        analyzer.find_dead_code().into_iter().map(|node| node.as_ref()).collect()
+    } else {
+        HashSet::new()
+    };
+    let workspace = project_path.to_str().unwrap();
 
-    let mermaid = if module {
+    if module {
         println!("Generating module diagram...");
-        generator.generate_module_diagram(&analyzer, project_path.to_str().unwrap())
     } else {
         println!("Generating call graph...");
-        generator.generate_call_graph(&analyzer)
+    }
+    let diagram = if format == "dot" {
+        let generator = DotGenerator::new()
+            .with_max_nodes(max_nodes)
+            .with_top_modules(top_modules)
+            .with_direction(direction)
+            .with_dead_nodes(dead);
+        if module { generator.generate_module_diagram(&analyzer, workspace) } else { generator.generate_call_graph(&analyzer) }
+    } else {
+        let generator = MermaidGenerator::new()
+            .with_max_nodes(max_nodes)
+            .with_top_modules(top_modules)
+            .with_direction(direction)
+            .with_dead_nodes(dead)
+            .with_clustering(cluster);
+        if module { generator.generate_module_diagram(&analyzer, workspace) } else { generator.generate_call_graph_in(&analyzer, workspace) }
     };
 
     match output {
         Some(file) => {
-            std::fs::write(file, format!("```mermaid\n{}\n```\n", mermaid))?;
+            let contents = if format == "dot" {
+                format!("{}\n", diagram)
+            } else {
+                format!("```mermaid\n{}\n```\n", diagram)
+            };
+            std::fs::write(file, contents)?;
             println!("Saved to: {}", file);
         }
         None => {
-            println!("\n{}", mermaid);
+            println!("\n{}", diagram);
+        }
+    }
+
+    if !module {
+        let selection = MermaidGenerator::new().with_max_nodes(max_nodes).select_nodes(&analyzer);
+        if selection.is_truncated() {
+            println!("Diagram shows {} of {} functions (--max-nodes {})",
+                selection.included.len(), selection.total(), max_nodes);
+        }
+        if let Some(file) = manifest {
+            write_diagram_manifest(file, &selection)?;
+            println!("Manifest saved to: {}", file);
         }
     }
 
     Ok(())
 }
 
-async fn cmd_dead_code(path: &str, lang: &str, json: bool) -> anyhow::Result<()> {
+fn write_diagram_manifest(file: &str, selection: &NodeSelection) -> anyhow::Result<()> {
+    #[derive(serde::Serialize)]
+    struct ManifestNode {
+        name: String,
+        file: String,
+        line: u32,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Manifest {
+        total: usize,
+        truncated: bool,
+        included: Vec<ManifestNode>,
+        excluded: Vec<ManifestNode>,
+    }
+
+    let to_nodes = |nodes: &[(&FunctionRef, &FunctionNode)]| -> Vec<ManifestNode> {
+        nodes.iter().map(|(_, node)| ManifestNode {
+            name: node.name.clone(),
+            file: node.file_path.clone(),
+            line: node.line,
+        }).collect()
+    };
+
+    let manifest = Manifest {
+        total: selection.total(),
+        truncated: selection.is_truncated(),
+        included: to_nodes(&selection.included),
+        excluded: to_nodes(&selection.excluded),
+    };
+    std::fs::write(file, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(())
+}
+
+struct DeadCodeOptions<'a> {
+    sort: &'a str,
+    methods_as_live: bool,
+    entry_patterns: &'a [String],
+    unreachable: bool,
+    roots: &'a [String],
+    group_by_file: bool,
+    show_signature: bool,
+    json: bool,
+}
+
+async fn cmd_dead_code(path: &str, lang: &str, opts: &DeadCodeOptions<'_>) -> anyhow::Result<()> {
+    let DeadCodeOptions { sort, methods_as_live, entry_patterns, unreachable, roots, group_by_file, show_signature, json } = *opts;
+    let sort = DeadCodeSort::parse(sort)
+        .ok_or_else(|| anyhow::anyhow!("Unknown sort: {} (expected file, size or name)", sort))?;
+
     let project_path = PathBuf::from(path).canonicalize()?;
     println!("Analyzing: {}", project_path.display());
 
-    let mut analyzer = ArchitectureAnalyzer::new();
-
     println!("Building call graph...");
-    match lang {
-        "rust" => {
-            let mut adapter = RustAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "swift" => {
-            let mut adapter = SwiftAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "typescript" | "ts" => {
-            let mut adapter = TypeScriptAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "vue" => {
-            let mut adapter = VueAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "java" => {
-            let mut adapter = JavaAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        _ => anyhow::bail!("Unsupported language: {}", lang),
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?
+        .with_methods_as_live(methods_as_live)
+        .with_entry_patterns(entry_patterns.iter().cloned());
+
+    let mut found = Vec::new();
+    for root in roots {
+        match analyzer.find_function_ref(root) {
+            Some(_) => found.push(root.as_str()),
+            None => println!("Warning: function not found: {}", root),
+        }
     }
+    if !roots.is_empty() && found.is_empty() {
+        anyhow::bail!("None of the root functions were found");
+    }
+    let dead = if unreachable {
+        analyzer.find_unreachable_sorted(&found, sort)
+    } else {
+        analyzer.find_dead_code_sorted(sort)
+    };
+    let label = if unreachable { "unreachable" } else { "unreferenced" };
+
+    let root = project_path.to_str().unwrap();
+    let relative = |file: &str| -> String {
+        file.strip_prefix(root)
+            .map(|s| s.trim_start_matches('/'))
+            .unwrap_or(file)
+            .to_string()
+    };
 
-    let dead_code = analyzer.find_dead_code();
+    let display = |node: &FunctionNode| -> String {
+        match &node.signature {
+            Some(signature) if show_signature => signature.clone(),
+            _ => short_name(&node.name),
+        }
+    };
 
     if json {
         #[derive(serde::Serialize)]
@@ -193,24 +477,48 @@ async fn cmd_dead_code(path: &str, lang: &str, json: bool) -> anyhow::Result<()>
             name: String,
             file: String,
             line: u32,
+            end_line: u32,
+            kind: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            signature: Option<String>,
         }
 
-        let items: Vec<_> = dead_code.iter().map(|node| DeadCodeItem {
+        let item = |node: &FunctionNode| DeadCodeItem {
             name: node.name.clone(),
             file: node.file_path.clone(),
             line: node.line,
-        }).collect();
+            end_line: node.end_line,
+            kind: node.kind.clone(),
+            signature: node.signature.clone().filter(|_| show_signature),
+        };
 
-        println!("{}", serde_json::to_string_pretty(&items)?);
+        let output = if group_by_file {
+            let groups: BTreeMap<&str, Vec<DeadCodeItem>> = ArchitectureAnalyzer::group_by_file(dead)
+                .into_iter()
+                .map(|(file, nodes)| (file, nodes.into_iter().map(&item).collect()))
+                .collect();
+            serde_json::to_string_pretty(&groups)?
+        } else {
+            let items: Vec<_> = dead.into_iter().map(&item).collect();
+            serde_json::to_string_pretty(&items)?
+        };
+        println!("{}", output);
+    } else if group_by_file {
+        let groups = ArchitectureAnalyzer::group_by_file(dead);
+        let total: usize = groups.iter().map(|(_, nodes)| nodes.len()).sum();
+        println!("\nFound {} potentially {} functions in {} files:\n", total, label, groups.len());
+        for (file, nodes) in groups {
+            println!("{} ({})", relative(file), nodes.len());
+            for node in nodes {
+                println!("  {:>5}  {}", node.line, display(node));
+            }
+            println!();
+        }
     } else {
-        println!("\nFound {} potentially unreferenced functions:\n", dead_code.len());
-        for node in dead_code {
-            let rel_path = node.file_path
-                .strip_prefix(project_path.to_str().unwrap())
-                .map(|s| s.trim_start_matches('/'))
-                .unwrap_or(&node.file_path);
-            println!("  {}:{}", rel_path, node.line);
-            println!("    {}", short_name(&node.name));
+        println!("\nFound {} potentially {} functions:\n", dead.len(), label);
+        for node in dead {
+            println!("  {}:{}", relative(&node.file_path), node.line);
+            println!("    {}", display(node));
             println!();
         }
     }
@@ -218,57 +526,42 @@ async fn cmd_dead_code(path: &str, lang: &str, json: bool) -> anyhow::Result<()>
     Ok(())
 }
 
-async fn cmd_call_tree(path: &str, entry: &str, lang: &str, depth: usize, incoming: bool, json: bool) -> anyhow::Result<()> {
+async fn cmd_call_tree(path: &str, entry: &str, lang: &str, depth: usize, incoming: bool, full_names: bool, json: bool) -> anyhow::Result<()> {
     let project_path = PathBuf::from(path).canonicalize()?;
     println!("Analyzing: {}", project_path.display());
     println!("Entry: {}", entry);
     println!("Direction: {}", if incoming { "callers" } else { "callees" });
 
-    let mut analyzer = ArchitectureAnalyzer::new();
-
     println!("Building call graph...");
-    match lang {
-        "rust" => {
-            let mut adapter = RustAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "swift" => {
-            let mut adapter = SwiftAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "typescript" | "ts" => {
-            let mut adapter = TypeScriptAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "vue" => {
-            let mut adapter = VueAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        "java" => {
-            let mut adapter = JavaAdapter::new(project_path.to_str().unwrap());
-            adapter.start().await?;
-            analyzer.build_call_graph(&mut adapter).await
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            adapter.stop()?;
-        }
-        _ => anyhow::bail!("Unsupported language: {}", lang),
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+
+    let direction = if incoming { CallDirection::Incoming } else { CallDirection::Outgoing };
+    let tree = analyzer.get_call_tree(entry, direction, depth);
+
+    print_call_tree(&tree, entry, project_path.to_str().unwrap(), full_names, json)
+}
+
+async fn cmd_call_tree_file(file: &str, entry: &str, lang: &str, depth: usize, incoming: bool, full_names: bool, json: bool) -> anyhow::Result<()> {
+    let file_path = PathBuf::from(file).canonicalize()?;
+    if !file_path.is_file() {
+        anyhow::bail!("Not a file: {}", file_path.display());
     }
+    let root = file_path.parent().unwrap_or(&file_path).to_path_buf();
+    println!("Analyzing file: {}", file_path.display());
+    println!("Entry: {}", entry);
+    println!("Direction: {}", if incoming { "callers" } else { "callees" });
+    println!("Note: only this file is indexed; cross-file calls may be missing");
+
+    println!("Building call graph...");
+    let analyzer = analyze_file(file_path.to_str().unwrap(), lang).await?;
 
     let direction = if incoming { CallDirection::Incoming } else { CallDirection::Outgoing };
     let tree = analyzer.get_call_tree(entry, direction, depth);
 
+    print_call_tree(&tree, entry, root.to_str().unwrap(), full_names, json)
+}
+
+fn print_call_tree(tree: &[CallTreeNode], entry: &str, root: &str, full_names: bool, json: bool) -> anyhow::Result<()> {
     if tree.is_empty() {
         println!("\nFunction not found: {}", entry);
         return Ok(());
@@ -278,26 +571,336 @@ async fn cmd_call_tree(path: &str, entry: &str, lang: &str, depth: usize, incomi
         #[derive(serde::Serialize)]
         struct TreeItem {
             name: String,
+            file: String,
+            line: u32,
             depth: usize,
         }
 
         let items: Vec<_> = tree.iter().map(|n| TreeItem {
             name: n.name.clone(),
+            file: n.file_path.clone(),
+            line: n.line,
             depth: n.depth,
         }).collect();
 
         println!("{}", serde_json::to_string_pretty(&items)?);
     } else {
         println!("\nCall tree ({}):\n", entry);
-        for node in &tree {
+        for node in tree {
             let indent = "  ".repeat(node.depth);
-            println!("{}- {}", indent, short_name(&node.name));
+            let name = if full_names { node.name.clone() } else { short_name(&node.name) };
+            let rel_path = node.file_path
+                .strip_prefix(root)
+                .map(|s| s.trim_start_matches('/'))
+                .unwrap_or(&node.file_path);
+            println!("{}- {} {}:{}", indent, name, rel_path, node.line);
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_reachable(path: &str, from: &[String], lang: &str, unreachable: bool, json: bool) -> anyhow::Result<()> {
+    let project_path = PathBuf::from(path).canonicalize()?;
+    println!("Analyzing: {}", project_path.display());
+
+    println!("Building call graph...");
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+
+    let mut entries = Vec::new();
+    for name in from {
+        match analyzer.find_function_ref(name) {
+            Some(r) => entries.push(r),
+            None => println!("Warning: function not found: {}", name),
+        }
+    }
+    if entries.is_empty() {
+        anyhow::bail!("None of the entry functions were found");
+    }
+
+    let mut nodes: Vec<_> = if unreachable {
+        analyzer.unreachable_functions(&entries)
+    } else {
+        let reachable = analyzer.reachable_from(&entries);
+        analyzer.functions().iter()
+            .filter(|(k, _)| reachable.contains(*k))
+            .map(|(_, node)| node)
+            .collect()
+    };
+    nodes.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct ReachableItem {
+            name: String,
+            file: String,
+            line: u32,
+        }
+
+        let items: Vec<_> = nodes.iter().map(|node| ReachableItem {
+            name: node.name.clone(),
+            file: node.file_path.clone(),
+            line: node.line,
+        }).collect();
+
+        println!("{}", serde_json::to_string_pretty(&items)?);
+    } else {
+        let label = if unreachable { "unreachable" } else { "reachable" };
+        println!("\n{} of {} functions {} from {}:\n", nodes.len(), analyzer.functions().len(), label, from.join(", "));
+        for node in nodes {
+            let rel_path = node.file_path
+                .strip_prefix(project_path.to_str().unwrap())
+                .map(|s| s.trim_start_matches('/'))
+                .unwrap_or(&node.file_path);
+            println!("  {}:{} {}", rel_path, node.line, short_name(&node.name));
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_hot_paths(path: &str, lang: &str, top: usize, metric: &str, json: bool) -> anyhow::Result<()> {
+    let metric = CentralityMetric::parse(metric)
+        .ok_or_else(|| anyhow::anyhow!("Unknown metric: {} (expected fan or betweenness)", metric))?;
+
+    let project_path = PathBuf::from(path).canonicalize()?;
+    println!("Analyzing: {}", project_path.display());
+
+    println!("Building call graph...");
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+
+    let hot = analyzer.hot_paths(metric, top);
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct HotPathItem {
+            name: String,
+            file: String,
+            line: u32,
+            score: f64,
+            fan_in: usize,
+            fan_out: usize,
+        }
+
+        let items: Vec<_> = hot.iter().map(|(node, score)| HotPathItem {
+            name: node.name.clone(),
+            file: node.file_path.clone(),
+            line: node.line,
+            score: *score,
+            fan_in: node.callers.len(),
+            fan_out: node.callees.len(),
+        }).collect();
+
+        println!("{}", serde_json::to_string_pretty(&items)?);
+    } else {
+        println!("\nTop {} hot functions ({:?}):\n", hot.len(), metric);
+        for (i, (node, score)) in hot.iter().enumerate() {
+            let rel_path = node.file_path
+                .strip_prefix(project_path.to_str().unwrap())
+                .map(|s| s.trim_start_matches('/'))
+                .unwrap_or(&node.file_path);
+            println!("  {:>3}. [{:.1}] {}:{}", i + 1, score, rel_path, node.line);
+            println!("       {} (in: {}, out: {})", short_name(&node.name), node.callers.len(), node.callees.len());
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_metrics(path: &str, lang: &str, top: usize, json: bool) -> anyhow::Result<()> {
+    let project_path = PathBuf::from(path).canonicalize()?;
+    println!("Analyzing: {}", project_path.display());
+
+    println!("Building call graph...");
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+
+    let mut metrics = analyzer.metrics();
+    metrics.truncate(top);
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct MetricsItem {
+            name: String,
+            file: String,
+            line: u32,
+            fan_in: usize,
+            fan_out: usize,
+            centrality: usize,
+        }
+
+        let items: Vec<_> = metrics.iter().map(|m| MetricsItem {
+            name: m.node.name.clone(),
+            file: m.node.file_path.clone(),
+            line: m.node.line,
+            fan_in: m.fan_in,
+            fan_out: m.fan_out,
+            centrality: m.centrality,
+        }).collect();
+
+        println!("{}", serde_json::to_string_pretty(&items)?);
+    } else {
+        println!("\nTop {} of {} functions by centrality:\n", metrics.len(), analyzer.functions().len());
+        println!("  {:>6} {:>6} {:>10}  function", "in", "out", "centrality");
+        for m in &metrics {
+            let rel_path = m.node.file_path
+                .strip_prefix(project_path.to_str().unwrap())
+                .map(|s| s.trim_start_matches('/'))
+                .unwrap_or(&m.node.file_path);
+            println!("  {:>6} {:>6} {:>10}  {} ({}:{})",
+                m.fan_in, m.fan_out, m.centrality, short_name(&m.node.name), rel_path, m.node.line);
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_cycles(path: &str, lang: &str, json: bool) -> anyhow::Result<()> {
+    let project_path = PathBuf::from(path).canonicalize()?;
+    println!("Analyzing: {}", project_path.display());
+
+    println!("Building call graph...");
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+
+    let cycles: Vec<Vec<&FunctionNode>> = analyzer.find_cycles()
+        .iter()
+        .map(|cycle| cycle.iter().filter_map(|r| analyzer.functions().get(r)).collect())
+        .collect();
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct CycleItem {
+            name: String,
+            file: String,
+            line: u32,
+        }
+
+        let items: Vec<Vec<CycleItem>> = cycles.iter().map(|cycle| {
+            cycle.iter().map(|node| CycleItem {
+                name: node.name.clone(),
+                file: node.file_path.clone(),
+                line: node.line,
+            }).collect()
+        }).collect();
+
+        println!("{}", serde_json::to_string_pretty(&items)?);
+    } else {
+        println!("\nFound {} call cycles:\n", cycles.len());
+        for cycle in &cycles {
+            let mut names: Vec<String> = cycle.iter().map(|node| short_name(&node.name)).collect();
+            names.push(names[0].clone());
+            println!("  {}", names.join(" -> "));
+
+            let first = cycle[0];
+            let rel_path = first.file_path
+                .strip_prefix(project_path.to_str().unwrap())
+                .map(|s| s.trim_start_matches('/'))
+                .unwrap_or(&first.file_path);
+            println!("    {}:{}", rel_path, first.line);
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_layers(path: &str, lang: &str, json: bool) -> anyhow::Result<()> {
+    let project_path = PathBuf::from(path).canonicalize()?;
+    println!("Analyzing: {}", project_path.display());
+
+    println!("Building call graph...");
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+
+    let roots = analyzer.root_functions();
+    let leaves = analyzer.leaf_functions();
+    let mut intermediate: Vec<_> = analyzer.functions()
+        .values()
+        .filter(|node| !node.callers.is_empty() && !node.callees.is_empty())
+        .collect();
+    intermediate.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+
+    let root_str = project_path.to_str().unwrap();
+    let rel = |file: &str| -> String {
+        file.strip_prefix(root_str)
+            .map(|s| s.trim_start_matches('/'))
+            .unwrap_or(file)
+            .to_string()
+    };
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct LayerItem {
+            name: String,
+            file: String,
+            line: u32,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Layers {
+            roots: Vec<LayerItem>,
+            intermediate: Vec<LayerItem>,
+            leaves: Vec<LayerItem>,
+        }
+
+        let to_items = |nodes: &[&FunctionNode]| -> Vec<LayerItem> {
+            nodes.iter().map(|node| LayerItem {
+                name: node.name.clone(),
+                file: node.file_path.clone(),
+                line: node.line,
+            }).collect()
+        };
+
+        let layers = Layers {
+            roots: to_items(&roots),
+            intermediate: to_items(&intermediate),
+            leaves: to_items(&leaves),
+        };
+        println!("{}", serde_json::to_string_pretty(&layers)?);
+    } else {
+        for (title, nodes) in [("Roots", &roots), ("Intermediate", &intermediate), ("Leaves", &leaves)] {
+            println!("\n{} ({}):", title, nodes.len());
+            for node in nodes.iter() {
+                println!("  {}:{} {}", rel(&node.file_path), node.line, short_name(&node.name));
+            }
+        }
+
+        let shown = roots.len() + intermediate.len() + leaves.len();
+        let skipped = analyzer.functions().len().saturating_sub(shown);
+        if skipped > 0 {
+            println!("\n({} entry points or isolated functions not shown)", skipped);
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_graph(path: &str, lang: &str, format: &str, output: Option<&str>) -> anyhow::Result<()> {
+    if !matches!(format, "graphml" | "json") {
+        anyhow::bail!("Unknown graph format: {} (expected: graphml or json)", format);
+    }
+
+    // The graph may go to stdout, so progress goes to stderr
+    let project_path = PathBuf::from(path).canonicalize()?;
+    eprintln!("Analyzing: {}", project_path.display());
+
+    eprintln!("Building call graph...");
+    let analyzer = analyze(project_path.to_str().unwrap(), lang).await?;
+    let graph = if format == "json" {
+        serde_json::to_string_pretty(&CallGraphExport::from_analyzer(&analyzer))?
+    } else {
+        GraphMlGenerator::new().generate(&analyzer)
+    };
+
+    match output {
+        Some(file) => {
+            std::fs::write(file, format!("{}\n", graph))?;
+            eprintln!("Saved to: {}", file);
         }
+        None => println!("{}", graph),
     }
 
     Ok(())
 }
 
 fn short_name(name: &str) -> String {
-    name.split("::").last().unwrap_or(name).to_string()
+    QualifiedName::parse(name).short().to_string()
 }
@@ -16,11 +16,9 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Code similarity detection
-    #[command(subcommand)]
-    Akin(akin_cli::AkinCommands),
+    Akin(akin_cli::AkinArgs),
     /// Architecture analysis
-    #[command(subcommand)]
-    Arch(arch_cli::ArchCommands),
+    Arch(arch_cli::ArchArgs),
 }
 
 #[tokio::main]